@@ -0,0 +1,48 @@
+use std::fs;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use stract::bangs::Bangs;
+
+const NUM_BANGS: usize = 15_000;
+
+fn bangs_json() -> String {
+    let bangs: Vec<_> = (0..NUM_BANGS)
+        .map(|i| {
+            serde_json::json!({
+                "c": "Online Services",
+                "d": format!("www.example{i}.com"),
+                "r": i,
+                "s": format!("Example {i}"),
+                "sc": "Search",
+                "t": format!("ex{i}"),
+                "u": format!("https://www.example{i}.com/search?q={{{{{{s}}}}}}"),
+            })
+        })
+        .collect();
+
+    serde_json::to_string(&bangs).unwrap()
+}
+
+pub fn criterion_benchmark(c: &mut Criterion) {
+    let dir = stract::gen_temp_path();
+    fs::create_dir_all(&dir).unwrap();
+
+    let json_path = dir.join("bangs.json");
+    let compiled_path = dir.join("bangs.bin");
+
+    fs::write(&json_path, bangs_json()).unwrap();
+    Bangs::compile(&json_path, &compiled_path).unwrap();
+
+    c.bench_function("load bangs from json", |b| {
+        b.iter(|| Bangs::from_path(&json_path))
+    });
+
+    c.bench_function("load compiled bangs", |b| {
+        b.iter(|| Bangs::from_compiled(&compiled_path).unwrap())
+    });
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,87 @@
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use stract::{
+    index::Index,
+    ranking::{Signal, SignalComputer, SignalEnum},
+    searcher::NUM_RESULTS_PER_PAGE,
+};
+
+const INDEX_PATH: &str = "data/index";
+
+/// Score the documents in order while keeping track of the `k` best scores seen so far.
+/// When `use_cutoff` is set, the current k-th best score is used as the cutoff for
+/// the next document, the same way the top docs collector does it.
+fn score_docs(computer: &SignalComputer, num_docs: u32, k: usize, use_cutoff: bool) -> f64 {
+    let mut top_k: BinaryHeap<Reverse<u64>> = BinaryHeap::with_capacity(k + 1);
+
+    for doc in 0..num_docs {
+        let cutoff = if use_cutoff && top_k.len() >= k {
+            top_k.peek().map(|Reverse(score)| f64::from_bits(*score))
+        } else {
+            None
+        };
+
+        let mut signals = computer.compute_signals(doc, cutoff);
+        let score = signals
+            .by_ref()
+            .flatten()
+            .map(|computed| computed.score.coefficient * computed.score.value)
+            .sum::<f64>();
+
+        if signals.is_pruned() {
+            continue;
+        }
+
+        // the scores are non-negative, so the bit patterns are ordered the same way as the floats
+        top_k.push(Reverse(score.max(0.0).to_bits()));
+
+        if top_k.len() > k {
+            top_k.pop();
+        }
+    }
+
+    top_k
+        .into_iter()
+        .map(|Reverse(score)| f64::from_bits(score))
+        .sum()
+}
+
+pub fn criterion_benchmark(c: &mut Criterion) {
+    let index = Index::open(INDEX_PATH).unwrap();
+    let ctx = index.inverted_index.local_search_ctx();
+    let segment = ctx
+        .tv_searcher
+        .segment_readers()
+        .iter()
+        .max_by_key(|segment| segment.max_doc())
+        .expect("the index has no segments");
+
+    // compute the signals that can not be bounded first, so the rest can be skipped
+    let unbounded: Vec<_> = SignalEnum::all()
+        .filter(|signal| signal.max_value().is_none())
+        .collect();
+
+    let mut computer = SignalComputer::new(None);
+    computer.set_signal_priority(&unbounded);
+
+    for (name, use_cutoff) in [("no cutoff", false), ("top-k cutoff", true)] {
+        c.bench_function(&format!("Compute signals ({name})"), |b| {
+            b.iter(|| {
+                computer
+                    .register_segment(&ctx.tv_searcher, segment, &ctx.fastfield_reader)
+                    .unwrap();
+
+                score_docs(
+                    &computer,
+                    segment.max_doc(),
+                    NUM_RESULTS_PER_PAGE,
+                    use_cutoff,
+                )
+            })
+        });
+    }
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,396 @@
+// Stract is an open source web search engine.
+// Copyright (C) 2024 Stract ApS
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Endpoints for operating the api while it is running.
+//! The endpoints require the `admin_token` from the config.
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{self, FromRef},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::{
+    autosuggest::{Autosuggest, AutosuggestSource, SharedAutosuggest},
+    bangs::SharedBangs,
+    config::QueryLogConfig,
+};
+
+use super::State;
+
+#[derive(Clone)]
+pub struct AdminState {
+    pub token: Option<String>,
+    pub bangs_path: String,
+    pub bangs: SharedBangs,
+    pub queries_csv_path: String,
+    pub query_log: Option<QueryLogConfig>,
+    pub autosuggest: SharedAutosuggest,
+}
+
+impl FromRef<Arc<State>> for AdminState {
+    fn from_ref(state: &Arc<State>) -> Self {
+        Self {
+            token: state.config.admin_token.clone(),
+            bangs_path: state.config.bangs_path.clone(),
+            bangs: state.bangs.clone(),
+            queries_csv_path: state.config.queries_csv_path.clone(),
+            query_log: state.config.query_log.clone(),
+            autosuggest: state.autosuggest.clone(),
+        }
+    }
+}
+
+/// Check the bearer token of the request. The endpoints are hidden
+/// when no admin token is configured.
+fn authorize(token: Option<&str>, headers: &HeaderMap) -> Result<(), StatusCode> {
+    let Some(token) = token else {
+        return Err(StatusCode::NOT_FOUND);
+    };
+
+    let bearer = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+
+    if bearer == Some(token) {
+        Ok(())
+    } else {
+        Err(StatusCode::UNAUTHORIZED)
+    }
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ReloadBangsResponse {
+    pub num_bangs: usize,
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/bangs/reload",
+    responses(
+        (status = 200, description = "The bangs were reloaded", body = ReloadBangsResponse),
+        (status = 401, description = "Missing or wrong admin token"),
+        (status = 500, description = "The bangs file could not be loaded. The current bangs are kept"),
+    )
+)]
+pub async fn reload_bangs(
+    extract::State(state): extract::State<AdminState>,
+    headers: HeaderMap,
+) -> Response {
+    if let Err(status) = authorize(state.token.as_deref(), &headers) {
+        return status.into_response();
+    }
+
+    let path = state.bangs_path.clone();
+    let res = tokio::task::spawn_blocking(move || state.bangs.reload(&state.bangs_path)).await;
+
+    match res {
+        Ok(Ok(num_bangs)) => {
+            tracing::info!("reloaded {} bangs from {}", num_bangs, path);
+            Json(ReloadBangsResponse { num_bangs }).into_response()
+        }
+        Ok(Err(err)) => {
+            tracing::error!("failed to reload bangs from {}: {:?}", path, err);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("failed to reload bangs: {err}"),
+            )
+                .into_response()
+        }
+        Err(err) => {
+            tracing::error!("bang reload panicked: {:?}", err);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+pub struct ReloadAutosuggestParams {
+    /// Source to load the suggestions from. Defaults to the current source.
+    pub source: Option<AutosuggestSource>,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ReloadAutosuggestResponse {
+    pub source: AutosuggestSource,
+    pub num_suggestions: usize,
+}
+
+fn load_autosuggest(
+    source: AutosuggestSource,
+    queries_csv_path: &str,
+    query_log: Option<&QueryLogConfig>,
+) -> crate::Result<Autosuggest> {
+    match source {
+        AutosuggestSource::Csv => Autosuggest::load_csv(queries_csv_path),
+        AutosuggestSource::QueryLog => {
+            let config = query_log.ok_or_else(|| anyhow::anyhow!("no query log is configured"))?;
+            Autosuggest::from_query_log(&config.path, config.min_frequency, config.half_life())
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/autosuggest/reload",
+    params(ReloadAutosuggestParams),
+    responses(
+        (status = 200, description = "The suggestions were reloaded", body = ReloadAutosuggestResponse),
+        (status = 400, description = "The query log was requested but is not configured"),
+        (status = 401, description = "Missing or wrong admin token"),
+        (status = 500, description = "The suggestions could not be loaded. The current suggestions are kept"),
+    )
+)]
+pub async fn reload_autosuggest(
+    extract::State(state): extract::State<AdminState>,
+    extract::Query(params): extract::Query<ReloadAutosuggestParams>,
+    headers: HeaderMap,
+) -> Response {
+    if let Err(status) = authorize(state.token.as_deref(), &headers) {
+        return status.into_response();
+    }
+
+    let source = params.source.unwrap_or_else(|| state.autosuggest.source());
+
+    if source == AutosuggestSource::QueryLog && state.query_log.is_none() {
+        return (StatusCode::BAD_REQUEST, "no query log is configured").into_response();
+    }
+
+    let queries_csv_path = state.queries_csv_path.clone();
+    let query_log = state.query_log.clone();
+    let res = tokio::task::spawn_blocking(move || {
+        load_autosuggest(source, &queries_csv_path, query_log.as_ref())
+    })
+    .await;
+
+    match res {
+        Ok(Ok(autosuggest)) => {
+            let num_suggestions = autosuggest.len();
+            tracing::info!("reloaded {} suggestions from {:?}", num_suggestions, source);
+            state.autosuggest.replace(source, autosuggest);
+
+            Json(ReloadAutosuggestResponse {
+                source,
+                num_suggestions,
+            })
+            .into_response()
+        }
+        Ok(Err(err)) => {
+            tracing::error!("failed to reload suggestions from {:?}: {:?}", source, err);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("failed to reload suggestions: {err}"),
+            )
+                .into_response()
+        }
+        Err(err) => {
+            tracing::error!("autosuggest reload panicked: {:?}", err);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{body::Body, http::Request, routing::post, Router};
+    use tower::ServiceExt;
+
+    use crate::{autosuggest::QueryLog, bangs::Bangs};
+
+    use super::*;
+
+    const TOKEN: &str = "secret";
+
+    fn router(state: AdminState) -> Router {
+        Router::new()
+            .route("/admin/bangs/reload", post(reload_bangs))
+            .route("/admin/autosuggest/reload", post(reload_autosuggest))
+            .with_state(state)
+    }
+
+    fn admin_state(token: Option<&str>, bangs_path: &str) -> AdminState {
+        let queries_csv_path = crate::gen_temp_path().join("queries.csv");
+        std::fs::create_dir_all(queries_csv_path.parent().unwrap()).unwrap();
+        std::fs::write(&queries_csv_path, "query\nrust programming\n").unwrap();
+
+        AdminState {
+            token: token.map(|token| token.to_string()),
+            bangs_path: bangs_path.to_string(),
+            bangs: Bangs::from_json("[]").into(),
+            queries_csv_path: queries_csv_path.to_str().unwrap().to_string(),
+            query_log: None,
+            autosuggest: SharedAutosuggest::new(
+                AutosuggestSource::Csv,
+                Autosuggest::load_csv(&queries_csv_path).unwrap(),
+            ),
+        }
+    }
+
+    async fn reload(state: &AdminState, token: Option<&str>) -> (StatusCode, String) {
+        post_admin(state, "/admin/bangs/reload", token).await
+    }
+
+    async fn post_admin(
+        state: &AdminState,
+        uri: &str,
+        token: Option<&str>,
+    ) -> (StatusCode, String) {
+        let mut request = Request::builder().method("POST").uri(uri);
+
+        if let Some(token) = token {
+            request = request.header(header::AUTHORIZATION, format!("Bearer {token}"));
+        }
+
+        let response = router(state.clone())
+            .oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn reload_from_file() {
+        let path = crate::gen_temp_path().join("bangs.json");
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+
+        let state = admin_state(Some(TOKEN), path.to_str().unwrap());
+
+        std::fs::write(
+            &path,
+            r#"[
+                {"t": "gh", "u": "https://github.com/search?q={{{s}}}"},
+                {"t": "yt", "u": "https://www.youtube.com/results?search_query={{{s}}}"}
+            ]"#,
+        )
+        .unwrap();
+
+        let (status, _) = reload(&state, None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        let (status, _) = reload(&state, Some("wrong")).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert!(state.bangs.load().is_empty());
+
+        let (status, body) = reload(&state, Some(TOKEN)).await;
+        assert_eq!(status, StatusCode::OK);
+        let res: ReloadBangsResponse = serde_json::from_str(&body).unwrap();
+        assert_eq!(res.num_bangs, 2);
+        assert!(state.bangs.load().contains("gh"));
+
+        std::fs::write(&path, r#"[{"t": "gh""#).unwrap();
+
+        let (status, _) = reload(&state, Some(TOKEN)).await;
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(state.bangs.load().len(), 2);
+        assert!(state.bangs.load().contains("yt"));
+    }
+
+    #[tokio::test]
+    async fn disabled_without_token() {
+        let state = admin_state(None, "");
+
+        let (status, _) = reload(&state, Some(TOKEN)).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let (status, _) = post_admin(&state, "/admin/autosuggest/reload", Some(TOKEN)).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn switch_autosuggest_source() {
+        let mut state = admin_state(Some(TOKEN), "");
+        let suggestions =
+            |state: &AdminState| state.autosuggest.load().suggestions("rust").unwrap();
+
+        assert_eq!(suggestions(&state), vec!["rust programming"]);
+
+        let (status, _) = post_admin(
+            &state,
+            "/admin/autosuggest/reload?source=queryLog",
+            Some(TOKEN),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(state.autosuggest.source(), AutosuggestSource::Csv);
+
+        let log_path = crate::gen_temp_path().join("queries.log");
+        let log = QueryLog::open(&log_path, std::time::Duration::from_secs(60 * 60)).unwrap();
+        for _ in 0..3 {
+            log.append("rust lang").unwrap();
+        }
+
+        state.query_log = Some(QueryLogConfig {
+            path: log_path.to_str().unwrap().to_string(),
+            min_frequency: 0.0,
+            half_life_hours: 1,
+            compaction_interval_secs: 60,
+        });
+
+        // requests that loaded the suggestions before the switch keep their snapshot
+        let in_flight = state.autosuggest.load();
+
+        let (status, body) = post_admin(
+            &state,
+            "/admin/autosuggest/reload?source=queryLog",
+            Some(TOKEN),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let res: ReloadAutosuggestResponse = serde_json::from_str(&body).unwrap();
+        assert_eq!(res.source, AutosuggestSource::QueryLog);
+        assert_eq!(res.num_suggestions, 1);
+
+        assert_eq!(suggestions(&state), vec!["rust lang"]);
+        assert_eq!(
+            in_flight.suggestions("rust").unwrap(),
+            vec!["rust programming"]
+        );
+
+        // a periodic rebuild of another source doesn't undo the switch
+        assert!(!state.autosuggest.refresh(
+            AutosuggestSource::Csv,
+            Autosuggest::load_csv(&state.queries_csv_path).unwrap()
+        ));
+        assert_eq!(suggestions(&state), vec!["rust lang"]);
+
+        // reloading without a source keeps the current source
+        log.append("rust belt").unwrap();
+        let (status, body) = post_admin(&state, "/admin/autosuggest/reload", Some(TOKEN)).await;
+        assert_eq!(status, StatusCode::OK);
+        let res: ReloadAutosuggestResponse = serde_json::from_str(&body).unwrap();
+        assert_eq!(res.source, AutosuggestSource::QueryLog);
+        assert_eq!(res.num_suggestions, 2);
+
+        let (status, _) =
+            post_admin(&state, "/admin/autosuggest/reload?source=csv", Some(TOKEN)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(suggestions(&state), vec!["rust programming"]);
+    }
+}
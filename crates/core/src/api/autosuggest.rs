@@ -20,7 +20,7 @@ use axum::{extract, response::IntoResponse, Json};
 use serde::Serialize;
 use utoipa::{IntoParams, ToSchema};
 
-use crate::highlighted::HighlightedFragment;
+use crate::{autosuggest::NUM_SUGGESTIONS, bangs::Bangs, highlighted::HighlightedFragment};
 
 use super::State;
 
@@ -47,6 +47,46 @@ fn highlight(query: &str, suggestion: &str) -> Vec<HighlightedFragment> {
 pub struct Suggestion {
     highlighted: Vec<HighlightedFragment>,
     raw: String,
+    /// The site of the bang for bang suggestions.
+    description: Option<String>,
+}
+
+/// Suggest bang tags instead of queries when the query is a single term that starts
+/// with a bang prefix, e.g. `!yo` suggests `!youtube`. Returns `None` for other queries.
+/// A bang prefix without a tag matches every bang, so nothing is suggested until the
+/// first character of the tag is typed.
+fn bang_suggestions(bangs: &Bangs, query: &str) -> Option<Vec<Suggestion>> {
+    let prefix = query.chars().next()?;
+
+    if !bangs.prefixes().contains(&prefix) {
+        return None;
+    }
+
+    let tag = &query[prefix.len_utf8()..];
+
+    if tag.chars().any(char::is_whitespace) {
+        return None;
+    }
+
+    if tag.is_empty() {
+        return Some(Vec::new());
+    }
+
+    Some(
+        bangs
+            .by_tag_prefix(tag, NUM_SUGGESTIONS)
+            .into_iter()
+            .map(|bang| {
+                let raw = format!("{prefix}{}", bang.tag);
+
+                Suggestion {
+                    highlighted: highlight(query, &raw),
+                    raw,
+                    description: bang.site.clone(),
+                }
+            })
+            .collect(),
+    )
 }
 
 #[derive(Debug, serde::Serialize, serde::Deserialize, IntoParams)]
@@ -69,13 +109,20 @@ pub async fn route(
     extract::Query(params): extract::Query<HashMap<String, String>>,
 ) -> impl IntoResponse {
     if let Some(query) = params.get("q") {
+        if let Some(suggestions) = bang_suggestions(&state.bangs.load(), query) {
+            return Json(suggestions);
+        }
+
         let mut suggestions = Vec::new();
 
-        for suggestion in state.autosuggest.suggestions(query).unwrap() {
+        let autosuggest = state.autosuggest.load();
+
+        for suggestion in autosuggest.suggestions(query).unwrap() {
             let highlighted = highlight(query, &suggestion);
             suggestions.push(Suggestion {
                 highlighted,
                 raw: suggestion,
+                description: None,
             });
         }
 
@@ -90,7 +137,8 @@ pub async fn browser(
     extract::Query(params): extract::Query<HashMap<String, String>>,
 ) -> impl IntoResponse {
     if let Some(query) = params.get("q") {
-        Json((query.clone(), state.autosuggest.suggestions(query).unwrap()))
+        let autosuggest = state.autosuggest.load();
+        Json((query.clone(), autosuggest.suggestions(query).unwrap()))
     } else {
         Json((String::new(), Vec::new()))
     }
@@ -145,4 +193,47 @@ mod tests {
             format!("test{HIGHLIGHTED_PREFIX}{HIGHLIGHTED_POSTFIX}")
         );
     }
+
+    #[test]
+    fn bang_suggestions_for_bang_prefix() {
+        let bangs = Bangs::from_json(
+            r#"[{
+            "r": 10,
+            "s": "YOPmail",
+            "t": "yopmail",
+            "u": "https://yopmail.com/?{{{s}}}"
+        }, {
+            "r": 100,
+            "s": "YouTube",
+            "t": "youtube",
+            "u": "https://www.youtube.com/results?search_query={{{s}}}"
+        }, {
+            "s": "GitHub",
+            "t": "gh",
+            "u": "https://github.com/search?q={{{s}}}"
+        }]"#,
+        );
+
+        let suggestions = bang_suggestions(&bangs, "!yo").unwrap();
+        let suggestions: Vec<_> = suggestions
+            .iter()
+            .map(|s| (s.raw.as_str(), s.description.as_deref()))
+            .collect();
+        assert_eq!(
+            suggestions,
+            vec![("!youtube", Some("YouTube")), ("!yopmail", Some("YOPmail"))]
+        );
+
+        let suggestions = bang_suggestions(&bangs, "!yo").unwrap();
+        assert_eq!(
+            highlight_fragments(&suggestions[0].highlighted),
+            format!("!yo{HIGHLIGHTED_PREFIX}utube{HIGHLIGHTED_POSTFIX}")
+        );
+
+        assert!(bang_suggestions(&bangs, "yo").is_none());
+        assert!(bang_suggestions(&bangs, "!yo tube").is_none());
+        assert!(bang_suggestions(&bangs, "").is_none());
+        assert!(bang_suggestions(&bangs, "!").unwrap().is_empty());
+        assert!(bang_suggestions(&bangs, "!x").unwrap().is_empty());
+    }
 }
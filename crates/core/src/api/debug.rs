@@ -0,0 +1,164 @@
+// Stract is an open source web search engine.
+// Copyright (C) 2024 Stract ApS
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Endpoints for debugging why a query matches a document or not and how documents are ranked.
+
+use axum::{extract, http::StatusCode, response::IntoResponse, Json};
+use tantivy::tokenizer::{TokenStream, Tokenizer as _};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::{
+    ranking::SignalComputer,
+    schema::{text_field::TextField, TextFieldEnum},
+};
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, IntoParams)]
+pub struct TokenizeQuery {
+    /// The text to tokenize.
+    pub q: String,
+    /// Name of the text field whose tokenizer is used, e.g. `title`.
+    pub field: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PreviewToken {
+    pub text: String,
+    pub offset_from: usize,
+    pub offset_to: usize,
+    pub position: usize,
+}
+
+/// Tokenize `text` the same way it is tokenized when indexed into `field`.
+pub fn tokenize(field: TextFieldEnum, text: &str) -> Vec<PreviewToken> {
+    let mut tokenizer = field.indexing_tokenizer();
+    let mut stream = tokenizer.token_stream(text);
+
+    let mut tokens = Vec::new();
+    while let Some(token) = stream.next() {
+        tokens.push(PreviewToken {
+            text: token.text.clone(),
+            offset_from: token.offset_from,
+            offset_to: token.offset_to,
+            position: token.position,
+        });
+    }
+
+    tokens
+}
+
+#[utoipa::path(
+    get,
+    path = "/debug/tokenize",
+    params(TokenizeQuery),
+    responses(
+        (status = 200, description = "The tokens of the text when indexed into the field", body = Vec<PreviewToken>),
+        (status = 400, description = "The field does not exist"),
+    )
+)]
+pub async fn tokenize_route(
+    extract::Query(params): extract::Query<TokenizeQuery>,
+) -> impl IntoResponse {
+    match TextFieldEnum::all().find(|field| field.name() == params.field) {
+        Some(field) => Json(tokenize(field, &params.q)).into_response(),
+        None => (
+            StatusCode::BAD_REQUEST,
+            format!("unknown text field '{}'", params.field),
+        )
+            .into_response(),
+    }
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, IntoParams)]
+pub struct SignalOrderQuery {
+    /// Show the order for searches without a query, e.g. when listing the pages of a site.
+    #[serde(default)]
+    pub browse: bool,
+}
+
+/// Names of the signals in the order they are computed with the default coefficients.
+pub fn signal_order(browse: bool) -> Vec<String> {
+    let computer = if browse {
+        SignalComputer::browse()
+    } else {
+        SignalComputer::empty_query()
+    };
+
+    computer
+        .compute_order()
+        .iter()
+        .map(|signal| signal.name().to_string())
+        .collect()
+}
+
+#[utoipa::path(
+    get,
+    path = "/debug/signal-order",
+    params(SignalOrderQuery),
+    responses(
+        (status = 200, description = "The signals in the order they are computed for each document", body = Vec<String>),
+    )
+)]
+pub async fn signal_order_route(
+    extract::Query(params): extract::Query<SignalOrderQuery>,
+) -> impl IntoResponse {
+    Json(signal_order(params.browse))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::schema::text_field;
+
+    use super::*;
+
+    #[test]
+    fn stemmed() {
+        let text = "The dogs were running quickly through the gardens";
+        let tokens = tokenize(text_field::StemmedTitle.into(), text);
+
+        let texts: Vec<_> = tokens.iter().map(|token| token.text.as_str()).collect();
+        assert!(texts.contains(&"dog"));
+        assert!(texts.contains(&"run"));
+        assert!(texts.contains(&"garden"));
+
+        let run = tokens.iter().find(|token| token.text == "run").unwrap();
+        assert_eq!(&text[run.offset_from..run.offset_to], "running");
+        assert_eq!(run.position, 3);
+    }
+
+    #[test]
+    fn not_stemmed() {
+        let tokens = tokenize(text_field::Title.into(), "Running Dogs");
+
+        assert_eq!(
+            tokens,
+            vec![
+                PreviewToken {
+                    text: "running".to_string(),
+                    offset_from: 0,
+                    offset_to: 7,
+                    position: 0,
+                },
+                PreviewToken {
+                    text: "dogs".to_string(),
+                    offset_from: 8,
+                    offset_to: 12,
+                    position: 1,
+                },
+            ]
+        );
+    }
+}
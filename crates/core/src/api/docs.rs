@@ -14,7 +14,7 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use super::{autosuggest, explore, hosts, search, summarize, webgraph};
+use super::{admin, autosuggest, debug, explore, hosts, search, stats, summarize, webgraph};
 use axum::Router;
 use utoipa::{Modify, OpenApi};
 use utoipa_swagger_ui::SwaggerUi;
@@ -26,8 +26,10 @@ use utoipa_swagger_ui::SwaggerUi;
             search::widget,
             search::sidebar,
             search::spellcheck,
+            search::correct,
             webgraph::host::similar,
             webgraph::host::knows,
+            webgraph::host::related,
             webgraph::host::ingoing_hosts,
             webgraph::host::outgoing_hosts,
             webgraph::page::ingoing_pages,
@@ -36,6 +38,11 @@ use utoipa_swagger_ui::SwaggerUi;
             summarize::summarize_route,
             hosts::hosts_export_optic,
             explore::explore_export_optic,
+            debug::tokenize_route,
+            debug::signal_order_route,
+            stats::terms_route,
+            admin::reload_bangs,
+            admin::reload_autosuggest,
         ),
         components(
             schemas(
@@ -43,11 +50,21 @@ use utoipa_swagger_ui::SwaggerUi;
                 optics::HostRankings,
                 search::ApiSearchQuery,
                 search::ApiSearchResult,
+                search::SearchFormat,
                 search::WidgetQuery,
                 search::SidebarQuery,
                 search::SpellcheckQuery,
+                debug::PreviewToken,
+                crate::searcher::TermStat,
+                admin::ReloadBangsResponse,
+                admin::ReloadAutosuggestResponse,
+                crate::autosuggest::AutosuggestSource,
                 crate::searcher::WebsitesResult,
+                crate::searcher::Device,
+                crate::ranking::CoefficientProfile,
+                crate::ranking::OpticRuleOutcome,
                 crate::search_prettifier::HighlightedSpellCorrection,
+                crate::searcher::correction::QueryCorrection,
                 crate::search_prettifier::DisplayedWebpage,
                 crate::search_prettifier::DisplayedEntity,
                 crate::search_prettifier::DisplayedAnswer,
@@ -78,8 +95,12 @@ use utoipa_swagger_ui::SwaggerUi;
                 crate::widgets::thesaurus::PartOfSpeechMeaning,
 
                 crate::ranking::signal::SignalScore,
+                crate::ranking::signal::SignalVector,
                 crate::bangs::BangHit,
                 crate::bangs::Bang,
+                crate::bangs::BangParam,
+                crate::bangs::BangParamKind,
+                crate::bangs::BangParamValue,
 
                 webgraph::host::SimilarHostsParams,
                 webgraph::KnowsHost,
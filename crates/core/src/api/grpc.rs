@@ -0,0 +1,305 @@
+// Stract is an open source web search engine.
+// Copyright (C) 2024 Stract ApS
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! gRPC api for programmatic search. The requests are handled by the same
+//! [`ApiSearcher`] as the http api, so query parsing, ranking and bangs behave
+//! the same. Bangs are returned as a redirect in the response instead of
+//! being followed.
+
+use std::{net::SocketAddr, sync::Arc};
+
+use tonic::{Request, Response, Status};
+
+use crate::{
+    query::optic,
+    searcher::{self, api::ApiSearcher, live, SearchQuery, SearchResult, WebsitesResult},
+    webpage::Region,
+};
+
+use super::search::ApiSearchQuery;
+
+pub mod proto {
+    tonic::include_proto!("stract.search");
+}
+
+const MAX_NUM_RESULTS: usize = 100;
+
+impl TryFrom<proto::SearchRequest> for SearchQuery {
+    type Error = anyhow::Error;
+
+    fn try_from(req: proto::SearchRequest) -> Result<Self, Self::Error> {
+        let selected_region = req
+            .selected_region
+            .as_deref()
+            .map(Region::from_gl)
+            .transpose()?;
+
+        let mut query = SearchQuery::try_from(ApiSearchQuery {
+            query: req.query,
+            page: req.page.map(|page| page as usize),
+            num_results: req.num_results.map(|num_results| num_results as usize),
+            selected_region,
+            optic: req.optic,
+            host_rankings: None,
+            safe_search: req.safe_search,
+            return_ranking_signals: req.return_ranking_signals,
+            features: false,
+            flatten_response: true,
+            count_results: req.count_results,
+            timeout_ms: req.timeout_ms,
+            disable: None,
+            per_host_limit: None,
+            min_score: None,
+            show_filtered: false,
+            debug_optic: false,
+            normalize_scores: false,
+            lang: None,
+            model: None,
+            device: None,
+            explore_seed: None,
+            profile: None,
+            coeff: None,
+            seen: None,
+        })?;
+
+        query.num_results = query.num_results.min(MAX_NUM_RESULTS);
+
+        Ok(query)
+    }
+}
+
+impl From<WebsitesResult> for proto::Websites {
+    fn from(result: WebsitesResult) -> Self {
+        Self {
+            webpages: result
+                .webpages
+                .into_iter()
+                .map(|webpage| proto::Webpage {
+                    title: webpage.title,
+                    url: webpage.url,
+                    site: webpage.site,
+                    domain: webpage.domain,
+                    pretty_url: webpage.pretty_url,
+                    snippet: webpage.snippet.text.unhighlighted_string(),
+                    score: webpage.score,
+                    likely_has_ads: webpage.likely_has_ads,
+                    likely_has_paywall: webpage.likely_has_paywall,
+                })
+                .collect(),
+            num_hits: result.num_hits.map(|num_hits| num_hits as u64),
+            search_duration_ms: result.search_duration_ms as u64,
+            has_more_results: result.has_more_results,
+            partial: result.partial,
+        }
+    }
+}
+
+impl From<SearchResult> for proto::SearchResponse {
+    fn from(result: SearchResult) -> Self {
+        let result = match result {
+            SearchResult::Websites(websites) => {
+                proto::search_response::Result::Websites(websites.into())
+            }
+            SearchResult::Bang(hit) => proto::search_response::Result::Redirect(proto::Redirect {
+                url: hit.redirect_to.to_string(),
+                bang: hit.bang.tag,
+            }),
+        };
+
+        Self {
+            result: Some(result),
+        }
+    }
+}
+
+pub struct SearchService<S, L> {
+    searcher: Arc<ApiSearcher<S, L>>,
+}
+
+impl<S, L> SearchService<S, L>
+where
+    S: searcher::distributed::SearchClient + Send + Sync + 'static,
+    L: live::SearchClient + Send + Sync + 'static,
+{
+    pub fn new(searcher: Arc<ApiSearcher<S, L>>) -> Self {
+        Self { searcher }
+    }
+
+    pub fn into_server(self) -> proto::search_server::SearchServer<Self> {
+        proto::search_server::SearchServer::new(self)
+    }
+
+    pub async fn serve(self, addr: SocketAddr) -> Result<(), tonic::transport::Error> {
+        tonic::transport::Server::builder()
+            .add_service(self.into_server())
+            .serve(addr)
+            .await
+    }
+}
+
+#[tonic::async_trait]
+impl<S, L> proto::search_server::Search for SearchService<S, L>
+where
+    S: searcher::distributed::SearchClient + Send + Sync + 'static,
+    L: live::SearchClient + Send + Sync + 'static,
+{
+    async fn search(
+        &self,
+        request: Request<proto::SearchRequest>,
+    ) -> Result<Response<proto::SearchResponse>, Status> {
+        let req = request.into_inner();
+        tracing::debug!(?req);
+
+        let query =
+            SearchQuery::try_from(req).map_err(|err| Status::invalid_argument(err.to_string()))?;
+
+        match self.searcher.search(&query).await {
+            Ok(result) => Ok(Response::new(result.into())),
+            Err(err) if err.is::<optic::Error>() => Err(Status::invalid_argument(err.to_string())),
+            Err(err) => match err.downcast_ref() {
+                Some(err @ searcher::distributed::Error::EmptyQuery) => {
+                    Err(Status::invalid_argument(err.to_string()))
+                }
+                _ => {
+                    tracing::error!("{:?}", err);
+                    Err(Status::internal("search failed"))
+                }
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tonic::transport::{Endpoint, Server, Uri};
+
+    use crate::searcher::api::tests::{api_searcher, EmptySearcher};
+
+    use super::*;
+
+    async fn client() -> proto::search_client::SearchClient<tonic::transport::Channel> {
+        let searcher = api_searcher(EmptySearcher::default());
+
+        let (client, server) = tokio::io::duplex(1024);
+
+        tokio::spawn(async move {
+            Server::builder()
+                .add_service(SearchService::new(Arc::new(searcher)).into_server())
+                .serve_with_incoming(tokio_stream::iter(vec![Ok::<_, std::io::Error>(server)]))
+                .await
+        });
+
+        let mut client = Some(client);
+        let channel = Endpoint::try_from("http://[::]:50051")
+            .unwrap()
+            .connect_with_connector(tower::service_fn(move |_: Uri| {
+                let client = client.take();
+
+                async move {
+                    client.ok_or_else(|| {
+                        std::io::Error::new(std::io::ErrorKind::Other, "client already taken")
+                    })
+                }
+            }))
+            .await
+            .unwrap();
+
+        proto::search_client::SearchClient::new(channel)
+    }
+
+    #[tokio::test]
+    async fn websites() {
+        let mut client = client().await;
+
+        let res = client
+            .search(proto::SearchRequest {
+                query: "test".to_string(),
+                ..Default::default()
+            })
+            .await
+            .unwrap()
+            .into_inner();
+
+        match res.result {
+            Some(proto::search_response::Result::Websites(websites)) => {
+                assert!(websites.webpages.is_empty());
+                assert!(!websites.has_more_results);
+            }
+            other => panic!("expected websites, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn bang_redirect() {
+        let mut client = client().await;
+
+        let res = client
+            .search(proto::SearchRequest {
+                query: "!ty test".to_string(),
+                ..Default::default()
+            })
+            .await
+            .unwrap()
+            .into_inner();
+
+        assert_eq!(
+            res.result,
+            Some(proto::search_response::Result::Redirect(proto::Redirect {
+                url: "https://www.youtube.com/results?search_query=test".to_string(),
+                bang: "ty".to_string(),
+            }))
+        );
+    }
+
+    #[tokio::test]
+    async fn optic_over_limit() {
+        let mut client = client().await;
+
+        let status = client
+            .search(proto::SearchRequest {
+                query: "test".to_string(),
+                optic: Some(
+                    r#"
+                    Rule { Matches { Site("a.com") }, Action(Boost(1)) };
+                    Rule { Matches { Site("b.com") }, Action(Boost(1)) };
+                    Rule { Matches { Site("c.com") }, Action(Downrank(1)) };
+                    "#
+                    .to_string(),
+                ),
+                ..Default::default()
+            })
+            .await
+            .unwrap_err();
+
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+        assert_eq!(
+            status.message(),
+            "Optic has 3 ranking rules, but at most 2 are allowed"
+        );
+    }
+
+    #[tokio::test]
+    async fn empty_query() {
+        let mut client = client().await;
+
+        let status = client
+            .search(proto::SearchRequest::default())
+            .await
+            .unwrap_err();
+
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+    }
+}
@@ -23,9 +23,9 @@ use tower::limit::ConcurrencyLimitLayer;
 use tower_http::compression::CompressionLayer;
 
 use crate::{
-    autosuggest::Autosuggest,
-    bangs::Bangs,
-    config::ApiConfig,
+    autosuggest::{Autosuggest, AutosuggestSource, QueryLog, SharedAutosuggest},
+    bangs::{Bangs, SharedBangs},
+    config::{ApiConfig, QueryLogConfig},
     distributed::{
         cluster::Cluster,
         member::{Member, Service},
@@ -43,6 +43,7 @@ use anyhow::Result;
 use std::{
     net::{IpAddr, SocketAddr},
     sync::Arc,
+    time::Duration,
 };
 
 use axum::{
@@ -54,13 +55,18 @@ use axum::{
 
 use self::webgraph::RemoteWebgraph;
 
+mod admin;
 mod autosuggest;
+mod debug;
 mod docs;
 mod explore;
+#[cfg(feature = "grpc")]
+pub mod grpc;
 mod hosts;
 pub mod improvement;
 mod metrics;
 pub mod search;
+mod stats;
 mod summarize;
 pub mod user_count;
 mod webgraph;
@@ -74,9 +80,11 @@ pub struct Counters {
 
 pub struct State {
     pub config: ApiConfig,
-    pub searcher: ApiSearcher<DistributedSearcher, LiveSearcher>,
+    pub searcher: Arc<ApiSearcher<DistributedSearcher, LiveSearcher>>,
+    pub bangs: SharedBangs,
     pub remote_webgraph: RemoteWebgraph,
-    pub autosuggest: Autosuggest,
+    pub autosuggest: SharedAutosuggest,
+    pub query_log: Option<Arc<QueryLog>>,
     pub counters: Counters,
     pub summarizer: Arc<Summarizer>,
     pub improvement_queue: Option<Arc<Mutex<LeakyQueue<ImprovementEvent>>>>,
@@ -92,7 +100,7 @@ pub async fn favicon() -> impl IntoResponse {
         .unwrap()
 }
 
-fn build_router(state: Arc<State>) -> Router {
+pub fn router(state: Arc<State>) -> Router {
     let mut search = Router::new()
         .route("/beta/api/search", post(search::search))
         .route_layer(middleware::from_fn_with_state(state.clone(), search_metric))
@@ -105,6 +113,11 @@ fn build_router(state: Arc<State>) -> Router {
     Router::new()
         .merge(search)
         .route("/favicon.ico", get(favicon))
+        .route("/debug/tokenize", get(debug::tokenize_route))
+        .route("/debug/signal-order", get(debug::signal_order_route))
+        .route("/stats/terms", get(stats::terms_route))
+        .route("/admin/bangs/reload", post(admin::reload_bangs))
+        .route("/admin/autosuggest/reload", post(admin::reload_autosuggest))
         .merge(
             Router::new()
                 .route("/improvement/click", post(improvement::click))
@@ -119,11 +132,13 @@ fn build_router(state: Arc<State>) -> Router {
                 .route("/api/search/widget", post(search::widget))
                 .route("/api/search/sidebar", post(search::sidebar))
                 .route("/api/search/spellcheck", post(search::spellcheck))
+                .route("/api/correct", get(search::correct))
                 .route("/api/autosuggest", post(autosuggest::route))
                 .route("/api/autosuggest/browser", get(autosuggest::browser))
                 .route("/api/summarize", get(summarize::summarize_route))
                 .route("/api/webgraph/host/similar", post(webgraph::host::similar))
                 .route("/api/webgraph/host/knows", post(webgraph::host::knows))
+                .route("/api/webgraph/host/related", get(webgraph::host::related))
                 .route(
                     "/api/webgraph/host/ingoing",
                     post(webgraph::host::ingoing_hosts),
@@ -148,8 +163,35 @@ fn build_router(state: Arc<State>) -> Router {
         .with_state(state)
 }
 
-pub async fn router(config: &ApiConfig, counters: Counters) -> Result<Router> {
-    let autosuggest = Autosuggest::load_csv(&config.queries_csv_path)?;
+pub async fn state(config: &ApiConfig, counters: Counters) -> Result<Arc<State>> {
+    let (autosuggest, query_log) = match &config.query_log {
+        Some(log_config) => {
+            let autosuggest = SharedAutosuggest::new(
+                AutosuggestSource::QueryLog,
+                Autosuggest::from_query_log(
+                    &log_config.path,
+                    log_config.min_frequency,
+                    log_config.half_life(),
+                )?,
+            );
+            let query_log = Arc::new(QueryLog::open(&log_config.path, log_config.half_life())?);
+
+            tokio::spawn(compact_query_log_loop(
+                query_log.clone(),
+                autosuggest.clone(),
+                log_config.clone(),
+            ));
+
+            (autosuggest, Some(query_log))
+        }
+        None => (
+            SharedAutosuggest::new(
+                AutosuggestSource::Csv,
+                Autosuggest::load_csv(&config.queries_csv_path)?,
+            ),
+            None,
+        ),
+    };
 
     let lambda_model = match &config.lambda_model_path {
         Some(path) => Some(LambdaMART::open(path)?),
@@ -167,7 +209,15 @@ pub async fn router(config: &ApiConfig, counters: Counters) -> Result<Router> {
         query_store_queue
     });
 
-    let bangs = Bangs::from_path(&config.bangs_path);
+    let bangs = Bangs::open(&config.bangs_path)?.with_prefixes(config.bang_prefixes.clone());
+
+    if bangs.is_empty() {
+        tracing::warn!("no bangs loaded from {}", config.bangs_path);
+    } else {
+        tracing::info!("loaded {} bangs", bangs.len());
+    }
+
+    let bangs = SharedBangs::from(bangs);
 
     let cluster = Arc::new(
         Cluster::join(
@@ -198,14 +248,16 @@ pub async fn router(config: &ApiConfig, counters: Counters) -> Result<Router> {
             cross_encoder,
             lambda_model,
             dual_encoder_model,
-            bangs,
+            bangs.clone(),
             config.clone(),
         );
 
         Arc::new(State {
             config: config.clone(),
-            searcher,
+            searcher: Arc::new(searcher),
+            bangs,
             autosuggest,
+            query_log,
             counters,
             remote_webgraph,
             summarizer: Arc::new(Summarizer::new(
@@ -219,7 +271,40 @@ pub async fn router(config: &ApiConfig, counters: Counters) -> Result<Router> {
         })
     };
 
-    Ok(build_router(state))
+    Ok(state)
+}
+
+/// Periodically decays the counts in the query log and rebuilds
+/// the suggestions from the compacted log. The suggestions are left
+/// untouched if they have been switched to another source.
+async fn compact_query_log_loop(
+    query_log: Arc<QueryLog>,
+    autosuggest: SharedAutosuggest,
+    config: QueryLogConfig,
+) {
+    let mut interval =
+        tokio::time::interval(Duration::from_secs(config.compaction_interval_secs.max(1)));
+    interval.tick().await;
+
+    loop {
+        interval.tick().await;
+
+        let query_log = query_log.clone();
+        let config = config.clone();
+        let res = tokio::task::spawn_blocking(move || {
+            query_log.compact()?;
+            Autosuggest::from_query_log(query_log.path(), config.min_frequency, config.half_life())
+        })
+        .await;
+
+        match res {
+            Ok(Ok(new_autosuggest)) => {
+                autosuggest.refresh(AutosuggestSource::QueryLog, new_autosuggest);
+            }
+            Ok(Err(err)) => tracing::error!("failed to compact query log: {:?}", err),
+            Err(err) => tracing::error!("query log compaction panicked: {:?}", err),
+        }
+    }
 }
 
 /// Enables CORS for development where the API and frontend are on
@@ -237,6 +322,17 @@ pub fn metrics_router(registry: crate::metrics::PrometheusRegistry) -> Router {
         .with_state(Arc::new(registry))
 }
 
+/// The ip address of the client, taken from the `x-forwarded-for` header when
+/// the api is behind a proxy.
+fn client_ip(headers: &http::HeaderMap, addr: SocketAddr) -> IpAddr {
+    headers
+        .get("x-forwarded-for")
+        .and_then(|forwarded_for| forwarded_for.to_str().ok())
+        .and_then(|forwarded_for| forwarded_for.split(',').next())
+        .and_then(|client_ip| client_ip.trim().parse::<IpAddr>().ok())
+        .unwrap_or_else(|| addr.ip())
+}
+
 async fn search_metric(
     extract::State(state): extract::State<Arc<State>>,
     extract::ConnectInfo(addr): extract::ConnectInfo<SocketAddr>,
@@ -245,18 +341,7 @@ async fn search_metric(
 ) -> Response {
     // It is very important that the ip address is not stored. It is only used
     // for a probabilistic estimate of the number of unique users using a hyperloglog datastructure.
-    let mut ip = None;
-
-    if let Some(forwarded_for) = request.headers().get("x-forwarded-for") {
-        let forwarded_for = forwarded_for.to_str().unwrap_or_default();
-        if let Some(client_ip) = forwarded_for.split(',').next() {
-            if let Ok(client_ip) = client_ip.trim().parse::<IpAddr>() {
-                ip = Some(client_ip);
-            }
-        }
-    }
-
-    let ip = ip.unwrap_or_else(|| addr.ip());
+    let ip = client_ip(request.headers(), addr);
     state.counters.daily_active_users.inc(&ip).ok();
 
     let response = next.run(request).await;
@@ -14,22 +14,42 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use crate::config::defaults;
-use http::StatusCode;
-use optics::{HostRankings, Optic};
-use std::sync::Arc;
-use utoipa::ToSchema;
+use crate::config::{defaults, ModelBucketsConfig};
+use http::{HeaderMap, StatusCode};
+use optics::{
+    ast::{RankingCoeff, RankingTarget},
+    HostRankings, Optic,
+};
+use siphasher::{
+    sip::SipHasher13,
+    sip128::{Hasher128, SipHasher13 as SipHasher128},
+};
+use std::{
+    hash::Hasher,
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+};
+use utoipa::{IntoParams, ToSchema};
+use whatlang::Lang;
 
-use axum::Json;
+use axum::{body::Body, response::Response, Json};
 use axum_macros::debug_handler;
+use futures::{future, stream, Future, FutureExt, Stream, StreamExt};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
 
 use crate::{
     bangs::BangHit,
-    searcher::{self, SearchQuery, SearchResult, WebsitesResult},
+    query::optic,
+    ranking::{models::registry::ModelSelection, CoefficientProfile, SignalEnum},
+    search_prettifier::DisplayedWebpage,
+    searcher::{
+        self, correction::QueryCorrection, Device, SearchQuery, SearchResult, WebsitesResult,
+    },
     webpage::region::Region,
 };
 
-use super::State;
+use super::{client_ip, State};
 
 use axum::{extract, response::IntoResponse};
 
@@ -44,27 +64,90 @@ pub struct ApiSearchQuery {
     pub optic: Option<String>,
     pub host_rankings: Option<HostRankings>,
     pub safe_search: Option<bool>,
+    /// Return the best results found within this many milliseconds.
+    /// Capped by the server.
+    pub timeout_ms: Option<u64>,
+    /// Comma separated names of signals that are not computed for the search,
+    /// e.g. `host_centrality,bm25_title`. Unknown signals are ignored.
+    pub disable: Option<String>,
+    /// Maximum number of results from the same host before the remaining results
+    /// of the host are moved below the results of other hosts.
+    pub per_host_limit: Option<usize>,
+    /// Results with a final score below this are not returned, so a page can have
+    /// fewer results than requested.
+    pub min_score: Option<f64>,
+    /// ISO 639-3 code of the language of the query, e.g. `eng`. Query terms are then
+    /// stemmed for this language instead of the language detected from the query.
+    pub lang: Option<String>,
+    /// Name of the linear model used to rank the results. Searches without a model
+    /// are spread over the available models by client.
+    pub model: Option<String>,
+    /// Device the search is made from, e.g. `mobile`. Defaults to `desktop`.
+    pub device: Option<Device>,
+    /// Seed of a small random perturbation of the final scores. The same seed
+    /// gives the same order of the results for the same query and index.
+    pub explore_seed: Option<u64>,
+    /// Preset of signal coefficients, e.g. `freshness`.
+    pub profile: Option<CoefficientProfile>,
+    /// Comma separated coefficients of signals, e.g. `host_centrality:2,bm25_title:0.5`.
+    /// These take precedence over both the profile and the optic.
+    pub coeff: Option<String>,
+    /// Urls of results the user has recently clicked. These are ranked slightly lower
+    /// so the user sees other results. The urls are only used for this search.
+    pub seen: Option<Vec<String>>,
 
     #[serde(default = "defaults::SearchQuery::return_ranking_signals")]
     pub return_ranking_signals: bool,
 
+    /// Attach the signal vector of each result, e.g. to be used as
+    /// features in a second-stage reranker.
+    #[serde(default = "defaults::SearchQuery::return_features")]
+    pub features: bool,
+
     #[serde(default = "defaults::SearchQuery::flatten_response")]
     pub flatten_response: bool,
 
     #[serde(default = "defaults::SearchQuery::count_results")]
     pub count_results: bool,
+
+    /// Return the number of results that were removed by `Discard` rules in the optic.
+    #[serde(default = "defaults::SearchQuery::show_filtered")]
+    pub show_filtered: bool,
+
+    /// Return whether each rule of the optic was applied, or why it was left out.
+    #[serde(default = "defaults::SearchQuery::debug_optic")]
+    pub debug_optic: bool,
+
+    /// Attach the score of each result rescaled to 0.0-1.0, where the best
+    /// result has a score of 1.0.
+    #[serde(default = "defaults::SearchQuery::normalize_scores")]
+    pub normalize_scores: bool,
 }
 
 impl TryFrom<ApiSearchQuery> for SearchQuery {
     type Error = anyhow::Error;
 
     fn try_from(api: ApiSearchQuery) -> Result<Self, Self::Error> {
-        let optic = if let Some(optic) = &api.optic {
+        let mut optic = if let Some(optic) = &api.optic {
             Some(Optic::parse(optic)?)
         } else {
             None
         };
 
+        let coefficients = api
+            .coeff
+            .as_deref()
+            .map(parse_coefficients)
+            .unwrap_or_default();
+
+        // later directives win, so the explicit coefficients override those of the optic
+        if !coefficients.is_empty() {
+            optic
+                .get_or_insert_with(Optic::default)
+                .rankings
+                .extend(coefficients);
+        }
+
         let default = SearchQuery::default();
 
         Ok(SearchQuery {
@@ -75,12 +158,101 @@ impl TryFrom<ApiSearchQuery> for SearchQuery {
             optic,
             host_rankings: api.host_rankings,
             return_ranking_signals: api.return_ranking_signals,
+            return_features: api.features,
             safe_search: api.safe_search.unwrap_or(default.safe_search),
             count_results: api.count_results,
+            cursor: None,
+            timeout_ms: api.timeout_ms,
+            disabled_signals: api
+                .disable
+                .as_deref()
+                .map(parse_disabled_signals)
+                .unwrap_or_default(),
+            per_host_limit: api.per_host_limit,
+            min_score: api.min_score,
+            show_filtered: api.show_filtered,
+            lang: api.lang.as_deref().and_then(parse_lang),
+            model: api.model.map(ModelSelection::Name),
+            normalize_scores: api.normalize_scores,
+            device: api.device.unwrap_or_default(),
+            explore_seed: api.explore_seed,
+            profile: api.profile,
+            seen_urls: api.seen.unwrap_or_default(),
+            debug_optic: api.debug_optic,
+            bang_prefixes: default.bang_prefixes,
         })
     }
 }
 
+fn parse_lang(code: &str) -> Option<Lang> {
+    let lang = Lang::from_code(code.trim());
+
+    if lang.is_none() {
+        tracing::warn!("ignoring unknown language '{}'", code);
+    }
+
+    lang
+}
+
+/// A sticky bucket for the client, so the client is ranked by the same model in
+/// every search. The ip address is hashed with a keyed hash and only the index
+/// of the model is sent to the search servers.
+fn model_bucket(ip: IpAddr, config: &ModelBucketsConfig) -> u64 {
+    let mut key = SipHasher128::new();
+    key.write(config.secret.as_bytes());
+    let key = key.finish128();
+
+    let mut hasher = SipHasher13::new_with_keys(key.h1, key.h2);
+
+    match ip {
+        IpAddr::V4(ip) => hasher.write(&ip.octets()),
+        IpAddr::V6(ip) => hasher.write(&ip.octets()),
+    }
+
+    hasher.finish() % config.num_models.max(1)
+}
+
+fn parse_coefficients(coefficients: &str) -> Vec<RankingCoeff> {
+    coefficients
+        .split(',')
+        .map(str::trim)
+        .filter(|coefficient| !coefficient.is_empty())
+        .filter_map(|coefficient| {
+            let parsed = coefficient.split_once(':').and_then(|(name, value)| {
+                let value = value.trim().parse().ok()?;
+                let name = name.trim();
+                SignalEnum::from_name(name)?;
+
+                Some(RankingCoeff {
+                    target: RankingTarget::Signal(name.to_string()),
+                    value,
+                })
+            });
+
+            if parsed.is_none() {
+                tracing::warn!("ignoring invalid signal coefficient '{}'", coefficient);
+            }
+
+            parsed
+        })
+        .collect()
+}
+
+fn parse_disabled_signals(names: &str) -> Vec<SignalEnum> {
+    names
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .filter_map(|name| match SignalEnum::from_name(name) {
+            Some(signal) => Some(signal),
+            None => {
+                tracing::warn!("ignoring unknown signal '{}' in disabled signals", name);
+                None
+            }
+        })
+        .collect()
+}
+
 #[derive(Debug, serde::Serialize, serde::Deserialize, ToSchema)]
 #[serde(tag = "type", rename_all = "camelCase")]
 pub enum ApiSearchResult {
@@ -97,10 +269,143 @@ impl From<SearchResult> for ApiSearchResult {
     }
 }
 
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize, ToSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchFormat {
+    #[default]
+    Json,
+    /// One result per line as soon as it is retrieved, followed by a line with the
+    /// metadata of the result. A bang is returned as a single line.
+    Ndjson,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchParams {
+    /// Format of the response. Defaults to `json`.
+    pub format: Option<SearchFormat>,
+}
+
+/// Number of webpages buffered between the search and a client that reads slower
+/// than the webpages are retrieved.
+const NDJSON_BUFFER: usize = 16;
+
+/// Last line of a ndjson response for a search that didn't match a bang. A response
+/// without it was cut short by an error.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", rename = "metadata", rename_all = "camelCase")]
+pub struct NdjsonMetadata {
+    pub num_hits: Option<usize>,
+    pub has_more_results: bool,
+    pub partial: bool,
+    pub features_layout: Option<Vec<String>>,
+}
+
+impl From<&WebsitesResult> for NdjsonMetadata {
+    fn from(result: &WebsitesResult) -> Self {
+        Self {
+            num_hits: result.num_hits,
+            has_more_results: result.has_more_results,
+            partial: result.partial,
+            features_layout: result.features_layout.clone(),
+        }
+    }
+}
+
+/// Streams the webpages as newline delimited json as they are received, followed by a
+/// line with the metadata of the result once the search has finished. Each webpage is
+/// only serialized when the client is ready for more data, so slow clients don't cause
+/// the whole response to be buffered.
+fn ndjson_response<W, R>(webpages: W, result: R) -> Response
+where
+    W: Stream<Item = DisplayedWebpage> + Send + 'static,
+    R: Future<Output = crate::Result<SearchResult>> + Send + 'static,
+{
+    fn line<T: serde::Serialize>(item: T) -> crate::Result<Vec<u8>> {
+        let mut bytes = serde_json::to_vec(&item)?;
+        bytes.push(b'\n');
+        Ok(bytes)
+    }
+
+    let last = stream::once(result).map(|result| match result? {
+        SearchResult::Websites(result) => line(NdjsonMetadata::from(&result)),
+        SearchResult::Bang(hit) => line(ApiSearchResult::Bang(hit)),
+    });
+
+    Response::builder()
+        .header(http::header::CONTENT_TYPE, "application/x-ndjson")
+        .body(Body::from_stream(webpages.map(line).chain(last)))
+        .unwrap()
+}
+
+/// Runs the search in the background so the webpages can be sent to the client
+/// while the remaining webpages are still being retrieved.
+async fn ndjson_search(state: Arc<State>, query: SearchQuery) -> Result<Response, StatusCode> {
+    let (tx, mut rx) = mpsc::channel(NDJSON_BUFFER);
+
+    let search = tokio::spawn(async move {
+        let result = state.searcher.search_stream(&query, tx).await;
+
+        if let Ok(result) = &result {
+            log_query(&state, &query, result);
+        }
+
+        result
+    })
+    .map(|result| -> crate::Result<SearchResult> { result? });
+
+    // the search fails before any webpage is sent, so the status code
+    // can still be set when no webpage is received.
+    match rx.recv().await {
+        Some(webpage) => Ok(ndjson_response(
+            stream::iter(Some(webpage)).chain(ReceiverStream::new(rx)),
+            search,
+        )),
+        None => match search.await {
+            Ok(result) => Ok(ndjson_response(stream::empty(), future::ready(Ok(result)))),
+            Err(err) => search_error_response(err),
+        },
+    }
+}
+
+/// Only searches that return websites are logged. The query log feeds autosuggest, and
+/// bang queries would otherwise suggest bang tags (`!ty ...`) as regular queries. Bangs
+/// already have their own suggestions.
+fn log_query(state: &State, query: &SearchQuery, result: &SearchResult) {
+    if let (Some(query_log), SearchResult::Websites(_)) = (&state.query_log, result) {
+        if query.page == 0 {
+            if let Err(err) = query_log.append(&query.query) {
+                tracing::error!("failed to append to query log: {:?}", err);
+            }
+        }
+    }
+}
+
+fn search_error_response(err: anyhow::Error) -> Result<Response, StatusCode> {
+    if err.is::<optic::Error>() {
+        return Ok((StatusCode::BAD_REQUEST, err.to_string()).into_response());
+    }
+
+    match err.downcast_ref() {
+        Some(searcher::distributed::Error::EmptyQuery) => {
+            Ok(searcher::distributed::Error::EmptyQuery
+                .to_string()
+                .into_response())
+        }
+        _ => {
+            tracing::error!("{:?}", err);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
 #[debug_handler]
 #[utoipa::path(
     post,
     path = "/beta/api/search",
+    params(SearchParams),
     request_body(content = ApiSearchQuery),
     responses(
         (status = 200, description = "Search results", body = ApiSearchResult),
@@ -108,8 +413,11 @@ impl From<SearchResult> for ApiSearchResult {
 )]
 pub async fn search(
     extract::State(state): extract::State<Arc<State>>,
+    extract::ConnectInfo(addr): extract::ConnectInfo<SocketAddr>,
+    extract::Query(params): extract::Query<SearchParams>,
+    headers: HeaderMap,
     extract::Json(query): extract::Json<ApiSearchQuery>,
-) -> Result<impl IntoResponse, StatusCode> {
+) -> Result<Response, StatusCode> {
     tracing::debug!(?query);
     let flatten_result = query.flatten_response;
     let query = SearchQuery::try_from(query);
@@ -122,26 +430,28 @@ pub async fn search(
 
     query.num_results = query.num_results.min(100);
 
+    if let (None, Some(model_buckets)) = (&query.model, &state.config.model_buckets) {
+        query.model = Some(ModelSelection::Bucket(model_bucket(
+            client_ip(&headers, addr),
+            model_buckets,
+        )));
+    }
+
+    if params.format.unwrap_or_default() == SearchFormat::Ndjson {
+        return ndjson_search(state, query).await;
+    }
+
     match state.searcher.search(&query).await {
         Ok(result) => {
+            log_query(&state, &query, &result);
+
             if flatten_result {
                 Ok(Json(ApiSearchResult::from(result)).into_response())
             } else {
                 Ok(Json(result).into_response())
             }
         }
-
-        Err(err) => match err.downcast_ref() {
-            Some(searcher::distributed::Error::EmptyQuery) => {
-                Ok(searcher::distributed::Error::EmptyQuery
-                    .to_string()
-                    .into_response())
-            }
-            _ => {
-                tracing::error!("{:?}", err);
-                Err(StatusCode::INTERNAL_SERVER_ERROR)
-            }
-        },
+        Err(err) => search_error_response(err),
     }
 }
 
@@ -208,6 +518,27 @@ pub async fn spellcheck(
     Json(state.searcher.spell_check(&req.query))
 }
 
+#[derive(Debug, serde::Serialize, serde::Deserialize, IntoParams)]
+pub struct CorrectQuery {
+    pub q: String,
+}
+
+#[debug_handler]
+#[utoipa::path(
+    get,
+    path = "/beta/api/correct",
+    params(CorrectQuery),
+    responses(
+        (status = 200, description = "The query with likely misspellings corrected. The original query is returned unchanged if the correction has a low confidence.", body = QueryCorrection),
+    )
+)]
+pub async fn correct(
+    extract::State(state): extract::State<Arc<State>>,
+    extract::Query(params): extract::Query<CorrectQuery>,
+) -> impl IntoResponse {
+    Json(state.searcher.correct(&params.q).await)
+}
+
 #[derive(Debug, serde::Serialize, serde::Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct EntityImageParams {
@@ -249,3 +580,181 @@ pub async fn entity_image(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::bangs::{BangContext, Bangs};
+    use crate::inverted_index::RetrievedWebpage;
+    use crate::query::parser::parse;
+
+    use super::*;
+
+    #[test]
+    fn disabled_signals() {
+        assert_eq!(
+            parse_disabled_signals("host_centrality, bm25_title,not_a_signal,"),
+            vec![
+                SignalEnum::from(crate::ranking::HostCentrality),
+                SignalEnum::from(crate::ranking::Bm25Title),
+            ]
+        );
+    }
+
+    #[test]
+    fn coefficients() {
+        assert_eq!(
+            parse_coefficients("host_centrality:2, bm25_title:0.5,not_a_signal:1,title:x,"),
+            vec![
+                RankingCoeff {
+                    target: RankingTarget::Signal("host_centrality".to_string()),
+                    value: 2.0,
+                },
+                RankingCoeff {
+                    target: RankingTarget::Signal("bm25_title".to_string()),
+                    value: 0.5,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn bang_response() {
+        let bangs = Bangs::from_json(
+            r#"[{
+            "c": "Multimedia",
+            "t": "yt",
+            "u": "https://www.youtube.com/results?search_query={{{s}}}"
+        }]"#,
+        );
+
+        let hit = bangs
+            .get(
+                &parse("!yt rust tutorial").unwrap(),
+                &BangContext::default(),
+            )
+            .unwrap();
+
+        let res =
+            serde_json::to_value(ApiSearchResult::from(SearchResult::Bang(Box::new(hit)))).unwrap();
+
+        assert_eq!(res["type"], "bang");
+        assert_eq!(res["tag"], "yt");
+        assert_eq!(res["category"], "Multimedia");
+        assert_eq!(res["query"], "rust tutorial");
+        assert!(res["redirectTo"]
+            .as_str()
+            .unwrap()
+            .starts_with("https://www.youtube.com/results?search_query=rust"));
+    }
+
+    fn websites_result() -> WebsitesResult {
+        WebsitesResult {
+            webpages: Vec::new(),
+            num_hits: Some(5),
+            discarded_count: None,
+            search_duration_ms: 0,
+            has_more_results: true,
+            partial: false,
+            optic_rules: None,
+            features_layout: None,
+        }
+    }
+
+    fn webpage(i: usize) -> DisplayedWebpage {
+        DisplayedWebpage::from(RetrievedWebpage {
+            url: format!("https://www.{i}.com/"),
+            ..Default::default()
+        })
+    }
+
+    #[tokio::test]
+    async fn ndjson_stream() {
+        let response = ndjson_response(
+            stream::iter((0..5).map(webpage)),
+            future::ready(Ok(SearchResult::Websites(websites_result()))),
+        );
+
+        assert_eq!(
+            response.headers()[http::header::CONTENT_TYPE],
+            "application/x-ndjson"
+        );
+
+        let mut body = response.into_body().into_data_stream();
+        let mut data = Vec::new();
+        while let Some(chunk) = body.next().await {
+            data.extend_from_slice(&chunk.unwrap());
+        }
+
+        let lines: Vec<_> = std::str::from_utf8(&data).unwrap().lines().collect();
+        assert_eq!(lines.len(), 6);
+
+        for (i, line) in lines[..5].iter().enumerate() {
+            let webpage: serde_json::Value = serde_json::from_str(line).unwrap();
+            assert_eq!(webpage["url"], format!("https://www.{i}.com/"));
+        }
+
+        let metadata: NdjsonMetadata = serde_json::from_str(lines[5]).unwrap();
+        assert_eq!(metadata.num_hits, Some(5));
+        assert!(metadata.has_more_results);
+        assert!(!metadata.partial);
+
+        let metadata: serde_json::Value = serde_json::from_str(lines[5]).unwrap();
+        assert_eq!(metadata["type"], "metadata");
+    }
+
+    #[tokio::test]
+    async fn ndjson_sends_webpages_before_search_finishes() {
+        let (tx, rx) = mpsc::channel(NDJSON_BUFFER);
+        let (done_tx, done_rx) = tokio::sync::oneshot::channel();
+
+        let response = ndjson_response(
+            ReceiverStream::new(rx),
+            done_rx.map(|_| Ok(SearchResult::Websites(websites_result()))),
+        );
+
+        let mut body = response.into_body().into_data_stream();
+
+        tx.send(webpage(0)).await.unwrap();
+        let first = body.next().await.unwrap().unwrap();
+        let first: serde_json::Value = serde_json::from_slice(&first).unwrap();
+        assert_eq!(first["url"], "https://www.0.com/");
+
+        drop(tx);
+        done_tx.send(()).unwrap();
+
+        let last = body.next().await.unwrap().unwrap();
+        let last: NdjsonMetadata = serde_json::from_slice(&last).unwrap();
+        assert_eq!(last.num_hits, Some(5));
+        assert!(body.next().await.is_none());
+    }
+
+    #[test]
+    fn model_bucket_is_keyed_index() {
+        let config = |secret: &str| ModelBucketsConfig {
+            secret: secret.to_string(),
+            num_models: 3,
+        };
+
+        let ips: Vec<IpAddr> = (0..100u8)
+            .map(|i| IpAddr::from([192, 168, 0, i]))
+            .chain(["::1".parse().unwrap()])
+            .collect();
+
+        for ip in &ips {
+            let bucket = model_bucket(*ip, &config("secret"));
+
+            assert!(bucket < 3);
+            assert_eq!(bucket, model_bucket(*ip, &config("secret")));
+        }
+
+        // every model gets clients and the assignment depends on the secret
+        let buckets = |secret| {
+            ips.iter()
+                .map(|ip| model_bucket(*ip, &config(secret)))
+                .collect::<Vec<_>>()
+        };
+
+        assert!((0..3).all(|model| buckets("secret").contains(&model)));
+        assert_ne!(buckets("secret"), buckets("other secret"));
+    }
+}
@@ -0,0 +1,77 @@
+// Stract is an open source web search engine.
+// Copyright (C) 2024 Stract ApS
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Endpoints exposing collection-wide statistics about the index.
+
+use std::sync::Arc;
+
+use axum::{extract, http::StatusCode, response::IntoResponse, Json};
+use utoipa::IntoParams;
+
+use crate::{
+    schema::{text_field::TextField, TextFieldEnum},
+    searcher::TermStat,
+};
+
+use super::State;
+
+/// Number of terms returned when the request doesn't specify a limit.
+const DEFAULT_LIMIT: usize = 100;
+
+/// Upper bound on the number of terms a single request can ask for.
+const MAX_LIMIT: usize = 1000;
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, IntoParams)]
+pub struct TermStatsQuery {
+    /// Name of the text field to look up terms in, e.g. `body`.
+    pub field: String,
+    /// Only terms starting with this prefix are returned.
+    pub prefix: String,
+    /// Maximum number of terms to return (at most 1000).
+    pub limit: Option<usize>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/stats/terms",
+    params(TermStatsQuery),
+    responses(
+        (status = 200, description = "The terms with the highest document frequency across the index", body = Vec<TermStat>),
+        (status = 400, description = "The field does not exist or the prefix is empty"),
+    )
+)]
+pub async fn terms_route(
+    extract::State(state): extract::State<Arc<State>>,
+    extract::Query(params): extract::Query<TermStatsQuery>,
+) -> impl IntoResponse {
+    let Some(field) = TextFieldEnum::all().find(|field| field.name() == params.field) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            format!("unknown text field '{}'", params.field),
+        )
+            .into_response();
+    };
+
+    let prefix = params.prefix.to_lowercase();
+
+    if prefix.is_empty() {
+        return (StatusCode::BAD_REQUEST, "prefix must not be empty").into_response();
+    }
+
+    let limit = params.limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT);
+
+    Json(state.searcher.top_terms(field, &prefix, limit).await).into_response()
+}
@@ -61,6 +61,16 @@ pub mod host {
         pub top_n: usize,
     }
 
+    const DEFAULT_RELATED_HOSTS: usize = 10;
+    const MAX_RELATED_HOSTS: usize = 50;
+
+    #[derive(serde::Deserialize, IntoParams)]
+    #[serde(rename_all = "camelCase")]
+    pub struct RelatedHostsParams {
+        pub host: String,
+        pub limit: Option<usize>,
+    }
+
     #[derive(serde::Deserialize, IntoParams)]
     #[serde(rename_all = "camelCase")]
     pub struct KnowsHostParams {
@@ -121,6 +131,71 @@ pub mod host {
         }
     }
 
+    #[utoipa::path(get,
+        path = "/beta/api/webgraph/host/related",
+        params(RelatedHostsParams),
+        responses(
+            (status = 200, description = "The hosts most similar to the host based on their inbound links", body = Vec<ScoredHost>),
+            (status = 404, description = "The host is not in the webgraph"),
+        )
+    )]
+    pub async fn related(
+        extract::State(state): extract::State<Arc<State>>,
+        extract::Query(params): extract::Query<RelatedHostsParams>,
+    ) -> std::result::Result<impl IntoResponse, (StatusCode, String)> {
+        state.counters.explore_counter.inc();
+        let internal_error = || {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to find related hosts".to_string(),
+            )
+        };
+
+        let host = state
+            .remote_webgraph
+            .host(WebgraphGranularity::Host)
+            .await
+            .ok_or_else(internal_error)?;
+
+        let retry = ExponentialBackoff::from_millis(30)
+            .with_limit(Duration::from_millis(200))
+            .take(5);
+
+        let conn = sonic::service::Connection::create_with_timeout_retry(
+            host,
+            Duration::from_secs(30),
+            retry,
+        )
+        .await
+        .map_err(|_| internal_error())?;
+
+        let limit = params
+            .limit
+            .unwrap_or(DEFAULT_RELATED_HOSTS)
+            .min(MAX_RELATED_HOSTS);
+
+        match conn
+            .send_with_timeout(
+                &crate::entrypoint::webgraph_server::RelatedHosts {
+                    host: params.host.clone(),
+                    limit,
+                },
+                Duration::from_secs(60),
+            )
+            .await
+        {
+            Ok(Some(hosts)) => Ok(Json(hosts)),
+            Ok(None) => Err((
+                StatusCode::NOT_FOUND,
+                format!("The host '{}' is not in the webgraph", params.host),
+            )),
+            Err(err) => {
+                tracing::error!("Failed to send request to webgraph: {}", err);
+                Err(internal_error())
+            }
+        }
+    }
+
     #[utoipa::path(post,
         path = "/beta/api/webgraph/host/knows",
         params(KnowsHostParams),
@@ -18,14 +18,35 @@
 //! when you type something into the search bar and queries are suggested.
 //! It uses a finite state transducer (fst) to store popular queries
 //! and performs a prefix search on the fst to find suggestions.
+//!
+//! The queries can either be loaded from a static csv file or from an
+//! append-only [`QueryLog`] where each query is weighted by how often,
+//! and how recently, it has been searched for.
 
-use fst::{automaton::Str, Automaton, IntoStreamer};
+use fst::{automaton::Str, Automaton, IntoStreamer, Streamer};
 
 use crate::Result;
-use std::path::Path;
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, RwLock},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+pub const NUM_SUGGESTIONS: usize = 10;
+
+/// Weights are stored as integers in the fst, so they are scaled
+/// to keep some precision of the decayed counts.
+const WEIGHT_SCALE: f64 = 1_000.0;
+
+/// Queries whose decayed weight falls below this are dropped during compaction.
+const MIN_COMPACTED_WEIGHT: f64 = 0.01;
 
 pub struct Autosuggest {
-    queries: fst::Set<Vec<u8>>,
+    queries: fst::Map<Vec<u8>>,
 }
 
 impl Autosuggest {
@@ -41,27 +62,363 @@ impl Autosuggest {
         }
 
         queries.sort();
+        queries.dedup();
 
-        let queries = fst::Set::from_iter(queries)?;
+        let queries = fst::Map::from_iter(queries.into_iter().map(|query| (query, 0)))?;
 
         Ok(Self { queries })
     }
 
+    /// Build the suggestions from a query log. Queries are weighted by their
+    /// frequency, where each search is decayed by its age using `half_life`.
+    /// Queries with a weight below `min_frequency` are never suggested, so rare
+    /// (and potentially identifying) queries are not exposed to other users.
+    pub fn from_query_log<P: AsRef<Path>>(
+        path: P,
+        min_frequency: f64,
+        half_life: Duration,
+    ) -> Result<Self> {
+        let weights = QueryLog::weights(path.as_ref(), half_life, SystemTime::now())?;
+
+        Self::from_weights(weights, min_frequency)
+    }
+
+    fn from_weights(weights: HashMap<String, f64>, min_frequency: f64) -> Result<Self> {
+        let mut queries: Vec<_> = weights
+            .into_iter()
+            .filter(|(_, weight)| *weight >= min_frequency)
+            .map(|(query, weight)| (query, (weight * WEIGHT_SCALE).round() as u64))
+            .collect();
+
+        queries.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        let queries = fst::Map::from_iter(queries)?;
+
+        Ok(Self { queries })
+    }
+
+    /// The most popular queries starting with `query`.
     pub fn suggestions(&self, query: &str) -> Result<Vec<String>> {
         let query = query.to_ascii_lowercase();
         let q = Str::new(query.as_str()).starts_with();
 
-        Ok(self
-            .queries
-            .search(q)
-            .into_stream()
-            .into_strs()?
+        // min-heap of the best suggestions seen so far. Ties are won by
+        // the lexicographically smallest query as the fst is streamed in order.
+        let mut top = BinaryHeap::with_capacity(NUM_SUGGESTIONS + 1);
+        let mut stream = self.queries.search(q).into_stream();
+
+        while let Some((suggestion, weight)) = stream.next() {
+            if top.len() < NUM_SUGGESTIONS {
+                top.push(Reverse((weight, Reverse(suggestion.to_vec()))));
+            } else if top
+                .peek()
+                .map(|Reverse((min_weight, _))| weight > *min_weight)
+                .unwrap_or(false)
+            {
+                top.pop();
+                top.push(Reverse((weight, Reverse(suggestion.to_vec()))));
+            }
+        }
+
+        top.into_sorted_vec()
             .into_iter()
-            .take(10)
-            .collect())
+            .map(|Reverse((_, Reverse(suggestion)))| Ok(String::from_utf8(suggestion)?))
+            .collect()
     }
 
     pub fn all(&self) -> Result<Vec<String>> {
-        Ok(self.queries.into_stream().into_strs()?)
+        Ok(self.queries.stream().into_str_keys()?)
+    }
+
+    pub fn len(&self) -> usize {
+        self.queries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queries.is_empty()
+    }
+}
+
+/// Where the suggestions are loaded from.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize, utoipa::ToSchema,
+)]
+#[serde(rename_all = "camelCase")]
+pub enum AutosuggestSource {
+    Csv,
+    QueryLog,
+}
+
+/// Suggestions that can be replaced, and switched to another source,
+/// while they are in use.
+#[derive(Clone)]
+pub struct SharedAutosuggest(Arc<RwLock<(AutosuggestSource, Arc<Autosuggest>)>>);
+
+impl SharedAutosuggest {
+    pub fn new(source: AutosuggestSource, autosuggest: Autosuggest) -> Self {
+        Self(Arc::new(RwLock::new((source, Arc::new(autosuggest)))))
+    }
+
+    /// The current suggestions. Requests keep using the suggestions they started
+    /// with even if the suggestions are replaced in the meantime.
+    pub fn load(&self) -> Arc<Autosuggest> {
+        Arc::clone(&self.0.read().unwrap_or_else(|e| e.into_inner()).1)
+    }
+
+    pub fn source(&self) -> AutosuggestSource {
+        self.0.read().unwrap_or_else(|e| e.into_inner()).0
+    }
+
+    pub fn replace(&self, source: AutosuggestSource, autosuggest: Autosuggest) {
+        *self.0.write().unwrap_or_else(|e| e.into_inner()) = (source, Arc::new(autosuggest));
+    }
+
+    /// Replace the suggestions only if they are still loaded from `source`.
+    /// This prevents a periodic rebuild from undoing a switch to another source.
+    /// Returns whether the suggestions were replaced.
+    pub fn refresh(&self, source: AutosuggestSource, autosuggest: Autosuggest) -> bool {
+        let mut current = self.0.write().unwrap_or_else(|e| e.into_inner());
+
+        if current.0 != source {
+            return false;
+        }
+
+        *current = (source, Arc::new(autosuggest));
+        true
+    }
+}
+
+/// Append-only log of searched queries. Each line has the format
+/// `timestamp\tweight\tquery`, where the timestamp is in seconds since the epoch.
+///
+/// New searches are appended with a weight of 1. [`QueryLog::compact`] rewrites
+/// the log to a single line per query with the weight decayed to the time of compaction,
+/// so the log doesn't grow without bounds.
+pub struct QueryLog {
+    path: PathBuf,
+    half_life: Duration,
+    file: Mutex<File>,
+}
+
+impl QueryLog {
+    pub fn open<P: AsRef<Path>>(path: P, half_life: Duration) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let file = Self::open_append(&path)?;
+
+        Ok(Self {
+            path,
+            half_life,
+            file: Mutex::new(file),
+        })
+    }
+
+    fn open_append(path: &Path) -> Result<File> {
+        Ok(OpenOptions::new().create(true).append(true).open(path)?)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn normalize(query: &str) -> String {
+        query
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .to_ascii_lowercase()
+    }
+
+    fn timestamp(time: SystemTime) -> u64 {
+        time.duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs()
+    }
+
+    pub fn append(&self, query: &str) -> Result<()> {
+        self.append_at(query, SystemTime::now())
+    }
+
+    fn append_at(&self, query: &str, time: SystemTime) -> Result<()> {
+        let query = Self::normalize(query);
+
+        if query.is_empty() {
+            return Ok(());
+        }
+
+        let mut file = self.file.lock().unwrap_or_else(|e| e.into_inner());
+        writeln!(file, "{}\t1\t{}", Self::timestamp(time), query)?;
+
+        Ok(())
+    }
+
+    /// Decayed weight of each query in the log at time `now`.
+    fn weights(path: &Path, half_life: Duration, now: SystemTime) -> Result<HashMap<String, f64>> {
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(HashMap::new()),
+            Err(err) => return Err(err.into()),
+        };
+
+        let now = Self::timestamp(now);
+        let half_life = half_life.as_secs_f64().max(1.0);
+        let mut weights = HashMap::new();
+
+        for line in BufReader::new(file).lines() {
+            let line = line?;
+            let mut parts = line.splitn(3, '\t');
+
+            let (Some(timestamp), Some(weight), Some(query)) =
+                (parts.next(), parts.next(), parts.next())
+            else {
+                continue;
+            };
+
+            let (Ok(timestamp), Ok(weight)) = (timestamp.parse::<u64>(), weight.parse::<f64>())
+            else {
+                continue;
+            };
+
+            let age = now.saturating_sub(timestamp) as f64;
+            let decayed = weight * 0.5f64.powf(age / half_life);
+
+            *weights.entry(query.to_string()).or_insert(0.0) += decayed;
+        }
+
+        Ok(weights)
+    }
+
+    /// Rewrite the log with a single entry per query, decaying the old counts
+    /// and dropping queries whose weight has become negligible.
+    pub fn compact(&self) -> Result<()> {
+        self.compact_at(SystemTime::now())
+    }
+
+    fn compact_at(&self, now: SystemTime) -> Result<()> {
+        let mut file = self.file.lock().unwrap_or_else(|e| e.into_inner());
+        file.flush()?;
+
+        let weights = Self::weights(&self.path, self.half_life, now)?;
+
+        let mut queries: Vec<_> = weights
+            .into_iter()
+            .filter(|(_, weight)| *weight >= MIN_COMPACTED_WEIGHT)
+            .collect();
+        queries.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        let tmp_path = self.path.with_extension("compacting");
+        let timestamp = Self::timestamp(now);
+
+        {
+            let mut writer = BufWriter::new(File::create(&tmp_path)?);
+
+            for (query, weight) in queries {
+                writeln!(writer, "{timestamp}\t{weight}\t{query}")?;
+            }
+
+            writer.flush()?;
+        }
+
+        std::fs::rename(&tmp_path, &self.path)?;
+        *file = Self::open_append(&self.path)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+    fn synthetic_log(now: SystemTime) -> QueryLog {
+        let log = QueryLog::open(crate::gen_temp_path().join("queries.log"), 7 * DAY).unwrap();
+
+        for _ in 0..5 {
+            log.append_at("rust programming", now).unwrap();
+        }
+
+        for _ in 0..3 {
+            log.append_at("Rust  Lang", now).unwrap();
+        }
+
+        // popular a long time ago
+        for _ in 0..20 {
+            log.append_at("rust belt", now - 70 * DAY).unwrap();
+        }
+
+        log.append_at("rust john doe 12 main street", now).unwrap();
+        log.append_at("python", now).unwrap();
+        log.append_at("python", now).unwrap();
+
+        log
+    }
+
+    fn suggestions(
+        log: &QueryLog,
+        now: SystemTime,
+        min_frequency: f64,
+        query: &str,
+    ) -> Vec<String> {
+        let weights = QueryLog::weights(log.path(), log.half_life, now).unwrap();
+
+        Autosuggest::from_weights(weights, min_frequency)
+            .unwrap()
+            .suggestions(query)
+            .unwrap()
+    }
+
+    #[test]
+    fn frequency_and_recency() {
+        let now = SystemTime::now();
+        let log = synthetic_log(now);
+
+        assert_eq!(
+            suggestions(&log, now, 0.0, "rust"),
+            vec![
+                "rust programming",
+                "rust lang",
+                "rust john doe 12 main street",
+                "rust belt",
+            ]
+        );
+
+        assert_eq!(suggestions(&log, now, 0.0, "py"), vec!["python"]);
+    }
+
+    #[test]
+    fn min_frequency() {
+        let now = SystemTime::now();
+        let log = synthetic_log(now);
+
+        assert_eq!(
+            suggestions(&log, now, 2.0, "rust"),
+            vec!["rust programming", "rust lang"]
+        );
+        assert_eq!(suggestions(&log, now, 2.0, "py"), vec!["python"]);
+        assert!(suggestions(&log, now, 10.0, "").is_empty());
+    }
+
+    #[test]
+    fn compaction() {
+        let now = SystemTime::now();
+        let log = synthetic_log(now);
+
+        let before = suggestions(&log, now, 0.5, "");
+
+        log.compact_at(now).unwrap();
+
+        let lines = std::fs::read_to_string(log.path()).unwrap().lines().count();
+        assert_eq!(lines, 5);
+        assert_eq!(suggestions(&log, now, 0.5, ""), before);
+
+        // new searches are appended to the compacted log
+        log.append_at("python", now).unwrap();
+        assert_eq!(suggestions(&log, now, 0.5, "")[0], "rust programming");
+        assert_eq!(suggestions(&log, now, 0.5, "")[1], "python");
+
+        // old counts decay away
+        log.compact_at(now + 365 * DAY).unwrap();
+        assert!(std::fs::read_to_string(log.path()).unwrap().is_empty());
     }
 }
@@ -17,8 +17,10 @@
 use std::{
     collections::HashMap,
     fs,
+    io::Write,
     ops::{Deref, DerefMut},
     path::Path,
+    sync::{Arc, RwLock},
 };
 
 use itertools::intersperse;
@@ -26,10 +28,35 @@ use serde::{Deserialize, Serialize};
 use url::Url;
 use utoipa::ToSchema;
 
-use crate::query::parser::Term;
+use crate::{
+    query::parser::{SimpleOrPhrase, Term},
+    searcher::SearchQuery,
+    webpage::Region,
+    Result,
+};
 
 pub const BANG_PREFIXES: [char; 2] = ['!', '！'];
 
+/// Separates the words of a multi-word tag, so the bang with tag `gh_issues`
+/// is used for both `!gh_issues` and `!gh issues`.
+pub const TAG_SEPARATOR: char = '_';
+
+/// Magic bytes at the start of a compiled bangs file.
+const COMPILED_MAGIC: &[u8] = b"STRACTBANGS";
+
+/// Version of the compiled bangs format. Must be bumped whenever
+/// the serialized representation of the bangs changes.
+pub const COMPILED_VERSION: u64 = 1;
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("File is not a compiled bangs file")]
+    NotCompiled,
+
+    #[error("Compiled bangs have version {found}, but version {COMPILED_VERSION} is required. The bangs needs to be recompiled")]
+    UnsupportedVersion { found: u64 },
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct Bang {
@@ -53,6 +80,140 @@ pub struct Bang {
 
     #[serde(rename = "u")]
     pub(crate) url: String,
+
+    #[serde(rename = "p", default)]
+    pub(crate) params: Vec<BangParam>,
+}
+
+/// [`Bang`] with the full field names instead of the short names used for compact
+/// storage, so the bangs can be edited by hand.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BangReadable {
+    pub category: Option<String>,
+    pub sub_category: Option<String>,
+    pub domain: Option<String>,
+    pub ranking: Option<usize>,
+    pub site: Option<String>,
+    pub tag: String,
+    pub url: String,
+    #[serde(default)]
+    pub params: Vec<BangParamReadable>,
+}
+
+impl From<Bang> for BangReadable {
+    fn from(bang: Bang) -> Self {
+        Self {
+            category: bang.category,
+            sub_category: bang.sub_category,
+            domain: bang.domain,
+            ranking: bang.ranking,
+            site: bang.site,
+            tag: bang.tag,
+            url: bang.url,
+            params: bang
+                .params
+                .into_iter()
+                .map(BangParamReadable::from)
+                .collect(),
+        }
+    }
+}
+
+impl From<BangReadable> for Bang {
+    fn from(bang: BangReadable) -> Self {
+        Self {
+            category: bang.category,
+            sub_category: bang.sub_category,
+            domain: bang.domain,
+            ranking: bang.ranking,
+            site: bang.site,
+            tag: bang.tag,
+            url: bang.url,
+            params: bang.params.into_iter().map(BangParam::from).collect(),
+        }
+    }
+}
+
+/// A search parameter that can be passed on to a bang.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum BangParamKind {
+    /// `1` if safe search is enabled, otherwise `0`.
+    SafeSearch,
+    /// The `gl` code of the selected region. Not passed on if all regions are selected.
+    Region,
+}
+
+/// Declares that a bang accepts a search parameter,
+/// and the name of the query parameter in the bang url to pass it on as.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct BangParam {
+    #[serde(rename = "k")]
+    pub(crate) kind: BangParamKind,
+
+    #[serde(rename = "n")]
+    pub(crate) name: String,
+}
+
+/// [`BangParam`] with the full field names.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BangParamReadable {
+    pub kind: BangParamKind,
+    pub name: String,
+}
+
+impl From<BangParam> for BangParamReadable {
+    fn from(param: BangParam) -> Self {
+        Self {
+            kind: param.kind,
+            name: param.name,
+        }
+    }
+}
+
+impl From<BangParamReadable> for BangParam {
+    fn from(param: BangParamReadable) -> Self {
+        Self {
+            kind: param.kind,
+            name: param.name,
+        }
+    }
+}
+
+/// A query parameter that was appended to the redirect url of a bang.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct BangParamValue {
+    pub name: String,
+    pub value: String,
+}
+
+/// The search parameters of the request that can be passed on to the bangs.
+#[derive(Clone, Debug, Default)]
+pub struct BangContext {
+    pub safe_search: bool,
+    pub region: Option<Region>,
+}
+
+impl From<&SearchQuery> for BangContext {
+    fn from(query: &SearchQuery) -> Self {
+        Self {
+            safe_search: query.safe_search,
+            region: query.selected_region,
+        }
+    }
+}
+
+impl BangContext {
+    fn value(&self, kind: BangParamKind) -> Option<String> {
+        match kind {
+            BangParamKind::SafeSearch => Some(if self.safe_search { "1" } else { "0" }.to_string()),
+            BangParamKind::Region => self
+                .region
+                .filter(|region| *region != Region::All)
+                .map(|region| region.gl()),
+        }
+    }
 }
 
 /// Wrapper around `Url` that implements `ToSchema` for `Url`.
@@ -84,14 +245,108 @@ impl From<Url> for UrlWrapper {
 #[serde(rename_all = "camelCase")]
 pub struct BangHit {
     pub bang: Bang,
+    /// Tag of the matched bang, e.g. `yt` for `!yt`.
+    pub tag: String,
+    pub category: Option<String>,
+    /// The query without the bang.
+    pub query: String,
     pub redirect_to: UrlWrapper,
+    /// The search parameters that were appended to `redirect_to`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub params: Vec<BangParamValue>,
 }
 
+/// Priority of a source of bangs in [`Bangs::merge`].
+/// Bangs from sources with a higher priority override bangs with the same tag
+/// from sources with a lower priority.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Priority(pub u32);
+
 pub struct Bangs {
     bangs: HashMap<String, Bang>,
+    /// Tag of the highest ranked bang for each category and sub-category alias.
+    category_aliases: HashMap<String, String>,
+    /// Number of words in the longest tag.
+    max_tag_words: usize,
+    /// Characters that start a bang in the query. Defaults to [`BANG_PREFIXES`].
+    prefixes: Vec<char>,
+}
+
+/// The alias of a category is its lowercased name without whitespace,
+/// so `!video` refers to the `Video` category.
+fn category_alias(category: &str) -> String {
+    category
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Bang tags are matched against the query terms without the bang prefix, so a stray
+/// prefix is stripped from the tag. Bangs with empty tags or tags containing whitespace
+/// can never match and are skipped.
+fn validate_tag(mut bang: Bang, prefixes: &[char]) -> Option<Bang> {
+    let tag = bang.tag.trim_start_matches(prefixes);
+
+    if tag.is_empty() {
+        tracing::warn!("skipping bang with empty tag '{}'", bang.tag);
+        return None;
+    }
+
+    if tag.chars().any(char::is_whitespace) {
+        tracing::warn!("skipping bang with whitespace in tag '{}'", bang.tag);
+        return None;
+    }
+
+    if tag.len() != bang.tag.len() {
+        tracing::warn!("stripping bang prefix from tag '{}'", bang.tag);
+        bang.tag = tag.to_string();
+    }
+
+    Some(bang)
 }
 
 impl Bangs {
+    fn new(bangs: HashMap<String, Bang>) -> Self {
+        let mut top_bangs: HashMap<String, &Bang> = HashMap::new();
+
+        for bang in bangs.values() {
+            for category in [&bang.category, &bang.sub_category].into_iter().flatten() {
+                let alias = category_alias(category);
+
+                if alias.is_empty() {
+                    continue;
+                }
+
+                let top = top_bangs.entry(alias).or_insert(bang);
+
+                if (bang.ranking, std::cmp::Reverse(&bang.tag))
+                    > (top.ranking, std::cmp::Reverse(&top.tag))
+                {
+                    *top = bang;
+                }
+            }
+        }
+
+        let category_aliases = top_bangs
+            .into_iter()
+            .map(|(alias, bang)| (alias, bang.tag.clone()))
+            .collect();
+
+        let max_tag_words = bangs
+            .keys()
+            .map(|tag| tag.split(TAG_SEPARATOR).count())
+            .max()
+            .unwrap_or(1);
+
+        Self {
+            bangs,
+            category_aliases,
+            max_tag_words,
+            prefixes: BANG_PREFIXES.to_vec(),
+        }
+    }
+
     pub fn from_path<P: AsRef<Path>>(path: P) -> Self {
         let json = fs::read_to_string(path).unwrap();
 
@@ -99,50 +354,325 @@ impl Bangs {
     }
 
     pub fn from_json(json: &str) -> Self {
-        let all_bangs: Vec<Bang> = serde_json::from_str(json).unwrap();
+        Self::try_from_json(json).unwrap()
+    }
 
-        Self {
-            bangs: all_bangs
+    pub fn try_from_json(json: &str) -> Result<Self> {
+        let all_bangs: Vec<Bang> = serde_json::from_str(json)?;
+
+        Ok(Self::new(
+            all_bangs
+                .into_iter()
+                .filter_map(|bang| validate_tag(bang, &BANG_PREFIXES))
+                .map(|bang| (bang.tag.clone(), bang))
+                .collect(),
+        ))
+    }
+
+    /// Parse a json list of bangs in the readable format (see [`BangReadable`]).
+    pub fn from_readable_json(json: &str) -> Result<Self> {
+        let all_bangs: Vec<BangReadable> = serde_json::from_str(json)?;
+
+        Ok(Self::new(
+            all_bangs
                 .into_iter()
+                .map(Bang::from)
+                .filter_map(|bang| validate_tag(bang, &BANG_PREFIXES))
                 .map(|bang| (bang.tag.clone(), bang))
                 .collect(),
+        ))
+    }
+
+    /// Combine bangs from several sources, e.g. an upstream list with a curated
+    /// internal list. When several sources have a bang with the same tag, the bang from
+    /// the source with the highest priority is used. Sources with the same priority
+    /// are preferred in the order they are given.
+    pub fn merge(sources: Vec<(Priority, Vec<Bang>)>) -> Self {
+        let mut sources = sources;
+        sources.sort_by_key(|(priority, _)| std::cmp::Reverse(*priority));
+
+        let mut bangs: HashMap<String, Bang> = HashMap::new();
+        let mut priorities: HashMap<String, Priority> = HashMap::new();
+
+        for (priority, source) in sources {
+            for bang in source
+                .into_iter()
+                .filter_map(|bang| validate_tag(bang, &BANG_PREFIXES))
+            {
+                if let Some(existing) = priorities.get(&bang.tag) {
+                    tracing::info!(
+                        "bang '!{}' with priority {} is overridden by priority {}",
+                        bang.tag,
+                        priority.0,
+                        existing.0
+                    );
+                    continue;
+                }
+
+                priorities.insert(bang.tag.clone(), priority);
+                bangs.insert(bang.tag.clone(), bang);
+            }
+        }
+
+        Self::new(bangs)
+    }
+
+    /// The bangs as a json list in the readable format, sorted by tag.
+    /// The list can be converted back with [`Bangs::from_readable_json`].
+    pub fn to_readable_json(&self) -> Result<String> {
+        let mut all_bangs: Vec<BangReadable> = self
+            .bangs
+            .values()
+            .cloned()
+            .map(BangReadable::from)
+            .collect();
+
+        all_bangs.sort_by(|a, b| a.tag.cmp(&b.tag));
+
+        Ok(serde_json::to_string_pretty(&all_bangs)?)
+    }
+
+    /// Open either a json list of bangs or a file compiled with [`Bangs::compile`].
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let bytes = fs::read(path)?;
+
+        if bytes.starts_with(COMPILED_MAGIC) {
+            Self::from_compiled_bytes(&bytes)
+        } else {
+            Self::try_from_json(std::str::from_utf8(&bytes)?)
+        }
+    }
+
+    /// Parse the json list of bangs at `path_in` and store it in a compact
+    /// binary format at `path_out` that is much faster to load.
+    pub fn compile<P: AsRef<Path>, Q: AsRef<Path>>(path_in: P, path_out: Q) -> Result<()> {
+        let bangs = Self::try_from_json(&fs::read_to_string(path_in)?)?;
+
+        let mut file = fs::File::create(path_out)?;
+        file.write_all(COMPILED_MAGIC)?;
+        file.write_all(&COMPILED_VERSION.to_le_bytes())?;
+        file.write_all(&bincode::serialize(&bangs.bangs)?)?;
+        file.flush()?;
+
+        Ok(())
+    }
+
+    pub fn from_compiled<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::from_compiled_bytes(&fs::read(path)?)
+    }
+
+    fn from_compiled_bytes(bytes: &[u8]) -> Result<Self> {
+        let bytes = bytes
+            .strip_prefix(COMPILED_MAGIC)
+            .ok_or(Error::NotCompiled)?;
+
+        if bytes.len() < std::mem::size_of::<u64>() {
+            return Err(Error::NotCompiled.into());
+        }
+
+        let (version, bytes) = bytes.split_at(std::mem::size_of::<u64>());
+        let version = u64::from_le_bytes(version.try_into()?);
+
+        if version != COMPILED_VERSION {
+            return Err(Error::UnsupportedVersion { found: version }.into());
+        }
+
+        Ok(Self::new(bincode::deserialize(bytes)?))
+    }
+
+    /// Use `prefixes` instead of [`BANG_PREFIXES`] to start a bang. The tags are validated
+    /// again, so a stray prefix is stripped from the tags with the new prefixes. The query
+    /// must be parsed with [`parse_with_bang_prefixes`] using the same prefixes.
+    ///
+    /// [`parse_with_bang_prefixes`]: crate::query::parser::parse_with_bang_prefixes
+    pub fn with_prefixes(self, prefixes: Vec<char>) -> Self {
+        let bangs = self
+            .bangs
+            .into_values()
+            .filter_map(|bang| validate_tag(bang, &prefixes))
+            .map(|bang| (bang.tag.clone(), bang))
+            .collect();
+
+        Self {
+            prefixes,
+            ..Self::new(bangs)
         }
     }
 
-    pub fn get(&self, terms: &[Term]) -> Option<BangHit> {
-        for possible_bang in terms.iter().filter_map(|term| {
-            if let Term::PossibleBang(possible_bang) = term {
-                Some(possible_bang)
+    /// Characters that start a bang in the query.
+    pub fn prefixes(&self) -> &[char] {
+        &self.prefixes
+    }
+
+    /// Number of loaded bangs.
+    pub fn len(&self) -> usize {
+        self.bangs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bangs.is_empty()
+    }
+
+    /// Whether there is a bang with the tag. Category aliases are not included.
+    pub fn contains(&self, tag: &str) -> bool {
+        self.bangs.contains_key(tag)
+    }
+
+    /// The bang with the tag. Category aliases are not included.
+    pub fn get_by_tag(&self, tag: &str) -> Option<&Bang> {
+        self.bangs.get(tag)
+    }
+
+    /// All loaded bangs in arbitrary order.
+    pub fn iter(&self) -> impl Iterator<Item = &Bang> {
+        self.bangs.values()
+    }
+
+    /// The `limit` bangs with a tag starting with `prefix`, highest ranked first and ties
+    /// ordered by the tag. The prefix must not include the bang prefix. Category aliases
+    /// are not included. Only the returned bangs are sorted, as a short prefix can match
+    /// most of the bangs.
+    pub fn by_tag_prefix(&self, prefix: &str, limit: usize) -> Vec<&Bang> {
+        let order =
+            |a: &&Bang, b: &&Bang| b.ranking.cmp(&a.ranking).then_with(|| a.tag.cmp(&b.tag));
+
+        let mut bangs: Vec<_> = self
+            .bangs
+            .values()
+            .filter(|bang| bang.tag.starts_with(prefix))
+            .collect();
+
+        if bangs.len() > limit {
+            bangs.select_nth_unstable_by(limit, order);
+            bangs.truncate(limit);
+        }
+
+        bangs.sort_by(order);
+
+        bangs
+    }
+
+    /// The bang with the tag. Tags that are not bangs themselves can be a category
+    /// alias, in which case the highest ranked bang of the category is used.
+    fn lookup(&self, tag: &str) -> Option<&Bang> {
+        self.bangs.get(tag).or_else(|| {
+            self.category_aliases
+                .get(&category_alias(tag))
+                .and_then(|tag| self.bangs.get(tag))
+        })
+    }
+
+    /// The first bang in `terms` and where it redirects to. A bang followed by simple
+    /// terms matches the longest multi-word tag (see [`TAG_SEPARATOR`]) it starts, and
+    /// only the terms after the tag are searched for. An empty slice of terms has no
+    /// bang. If the bang is the only term, there is nothing to search for, so the bang
+    /// redirects to the root of the site instead of searching for an empty query.
+    pub fn get(&self, terms: &[Term], ctx: &BangContext) -> Option<BangHit> {
+        self.get_matching(terms, ctx, |_| true)
+    }
+
+    /// Like [`Bangs::get`], but only bangs in one of the `allowed_categories` are used.
+    /// Bangs from other categories are treated as literal terms of the query.
+    pub fn get_scoped(
+        &self,
+        terms: &[Term],
+        ctx: &BangContext,
+        allowed_categories: &[String],
+    ) -> Option<BangHit> {
+        let allowed: Vec<_> = allowed_categories
+            .iter()
+            .map(|category| category_alias(category))
+            .collect();
+
+        self.get_matching(terms, ctx, |bang| {
+            bang.category
+                .as_ref()
+                .is_some_and(|category| allowed.contains(&category_alias(category)))
+        })
+    }
+
+    /// The bang with the longest tag that starts with `possible_bang` and continues
+    /// with the simple terms that follow it, and the number of words in its tag.
+    fn lookup_multi_word(
+        &self,
+        possible_bang: &str,
+        following: &[Term],
+        is_allowed: impl Fn(&Bang) -> bool,
+    ) -> Option<(&Bang, usize)> {
+        let words: Vec<_> = std::iter::once(possible_bang)
+            .chain(following.iter().map_while(|term| match term {
+                Term::SimpleOrPhrase(SimpleOrPhrase::Simple(term)) => Some(term.as_str()),
+                _ => None,
+            }))
+            .take(self.max_tag_words)
+            .collect();
+
+        (1..=words.len()).rev().find_map(|num_words| {
+            let tag = words[..num_words].join(&TAG_SEPARATOR.to_string());
+
+            self.lookup(&tag)
+                .filter(|bang| is_allowed(bang))
+                .map(|bang| (bang, num_words))
+        })
+    }
+
+    fn get_matching(
+        &self,
+        terms: &[Term],
+        ctx: &BangContext,
+        is_allowed: impl Fn(&Bang) -> bool,
+    ) -> Option<BangHit> {
+        for (i, possible_bang) in terms.iter().enumerate().filter_map(|(i, term)| {
+            if let Term::PossibleBang {
+                tag: possible_bang, ..
+            } = term
+            {
+                Some((i, possible_bang))
             } else {
                 None
             }
         }) {
-            if let Some(bang) = self.bangs.get(possible_bang) {
-                let query = intersperse(
-                    terms
-                        .iter()
-                        .filter(|term| {
-                            if let Term::PossibleBang(bang) = term {
-                                bang != possible_bang
-                            } else {
-                                true
-                            }
-                        })
-                        .map(|term| term.to_string()),
-                    " ".to_string(),
-                )
-                .collect::<String>();
-
-                let query = urlencoding::encode(query.as_str()).to_string();
-                let mut url = bang.url.replace("{{{s}}}", query.as_str());
+            if let Some((bang, num_words)) =
+                self.lookup_multi_word(possible_bang, &terms[i + 1..], &is_allowed)
+            {
+                let tag_words = i + 1..i + num_words;
+                let stripped_terms: Vec<String> = terms
+                    .iter()
+                    .enumerate()
+                    .filter(|(j, term)| {
+                        if let Term::PossibleBang { tag, .. } = term {
+                            tag != possible_bang
+                        } else {
+                            !tag_words.contains(j)
+                        }
+                    })
+                    .map(|(_, term)| term.to_string())
+                    .collect();
+
+                let mut url = substitute_query(&bang.url, &stripped_terms);
 
                 if !url.contains("://") {
                     url = "http://".to_string() + url.as_str();
                 }
 
+                let mut redirect_to = Url::parse(url.as_str()).unwrap();
+
+                let params = if stripped_terms.is_empty() && bang.url.contains(PLACEHOLDER_START) {
+                    redirect_to.set_path("/");
+                    redirect_to.set_query(None);
+                    redirect_to.set_fragment(None);
+                    Vec::new()
+                } else {
+                    append_params(&mut redirect_to, &bang.params, ctx)
+                };
+
                 return Some(BangHit {
                     bang: bang.clone(),
-                    redirect_to: Url::parse(url.as_str()).unwrap().into(),
+                    tag: bang.tag.clone(),
+                    category: bang.category.clone(),
+                    query: stripped_terms.join(" "),
+                    redirect_to: redirect_to.into(),
+                    params,
                 });
             }
         }
@@ -151,10 +681,132 @@ impl Bangs {
     }
 }
 
+/// Bangs that can be replaced while searches are using them.
+#[derive(Clone)]
+pub struct SharedBangs(Arc<RwLock<Arc<Bangs>>>);
+
+impl From<Bangs> for SharedBangs {
+    fn from(bangs: Bangs) -> Self {
+        Self(Arc::new(RwLock::new(Arc::new(bangs))))
+    }
+}
+
+impl SharedBangs {
+    /// The currently loaded bangs. Searches keep using the bangs they started
+    /// with even if the bangs are replaced in the meantime.
+    pub fn load(&self) -> Arc<Bangs> {
+        Arc::clone(&self.0.read().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    pub fn replace(&self, bangs: Bangs) {
+        *self.0.write().unwrap_or_else(|e| e.into_inner()) = Arc::new(bangs);
+    }
+
+    /// Load the bangs at `path` and replace the current bangs with them.
+    /// The current bangs are kept if the file can't be loaded.
+    /// The prefixes of the current bangs are kept for the new bangs.
+    /// Returns the number of loaded bangs.
+    pub fn reload<P: AsRef<Path>>(&self, path: P) -> Result<usize> {
+        let bangs = Bangs::open(path)?.with_prefixes(self.load().prefixes().to_vec());
+        let len = bangs.len();
+
+        self.replace(bangs);
+
+        Ok(len)
+    }
+}
+
+const PLACEHOLDER_START: &str = "{{{";
+const PLACEHOLDER_END: &str = "}}}";
+
+/// The value a placeholder in the bang url should be replaced with.
+/// `{{{s}}}` is the full query while `{{{n}}}` is the n'th term (1-indexed).
+/// Numbered placeholders without a matching term are left empty.
+fn placeholder_value(placeholder: &str, terms: &[String]) -> Option<String> {
+    if placeholder == "s" {
+        return Some(intersperse(terms.iter().cloned(), " ".to_string()).collect());
+    }
+
+    let idx: usize = placeholder.parse().ok()?;
+
+    Some(
+        idx.checked_sub(1)
+            .and_then(|idx| terms.get(idx))
+            .cloned()
+            .unwrap_or_default(),
+    )
+}
+
+/// Insert the search terms into the placeholders of the bang url. The values
+/// are encoded according to where in the url the placeholder is. In the query
+/// string they are form-urlencoded, while in the path everything but
+/// unreserved characters (including slashes) is percent-encoded so each value
+/// stays within a single path segment.
+fn substitute_query(bang_url: &str, terms: &[String]) -> String {
+    let mut res = String::with_capacity(bang_url.len());
+    let mut rest = bang_url;
+
+    while let Some(start) = rest.find(PLACEHOLDER_START) {
+        let Some(len) = rest[start + PLACEHOLDER_START.len()..].find(PLACEHOLDER_END) else {
+            break;
+        };
+
+        let placeholder =
+            &rest[start + PLACEHOLDER_START.len()..start + PLACEHOLDER_START.len() + len];
+        let end = start + PLACEHOLDER_START.len() + len + PLACEHOLDER_END.len();
+
+        res.push_str(&rest[..start]);
+
+        match placeholder_value(placeholder, terms) {
+            Some(value) => {
+                if res.contains('?') {
+                    res.extend(url::form_urlencoded::byte_serialize(value.as_bytes()));
+                } else {
+                    res.push_str(&urlencoding::encode(&value));
+                }
+            }
+            None => res.push_str(&rest[start..end]),
+        }
+
+        rest = &rest[end..];
+    }
+
+    res.push_str(rest);
+
+    res
+}
+
+/// Append the accepted search parameters to `url`. Parameters that are already
+/// in the query of the bang url are left untouched.
+fn append_params(url: &mut Url, params: &[BangParam], ctx: &BangContext) -> Vec<BangParamValue> {
+    let existing: Vec<String> = url.query_pairs().map(|(key, _)| key.into_owned()).collect();
+
+    let appended: Vec<_> = params
+        .iter()
+        .filter(|param| !existing.contains(&param.name))
+        .filter_map(|param| {
+            ctx.value(param.kind).map(|value| BangParamValue {
+                name: param.name.clone(),
+                value,
+            })
+        })
+        .collect();
+
+    if !appended.is_empty() {
+        let mut query = url.query_pairs_mut();
+
+        for param in &appended {
+            query.append_pair(&param.name, &param.value);
+        }
+    }
+
+    appended
+}
+
 #[cfg(test)]
 mod tests {
 
-    use crate::query::parser::parse;
+    use crate::query::parser::{parse, parse_with_bang_prefixes};
 
     use super::*;
 
@@ -172,11 +824,13 @@ mod tests {
         }]"#,
         );
 
-        assert_eq!(bangs.get(&parse("no bangs").unwrap()), None);
-        assert_eq!(bangs.get(&parse("!no bangs").unwrap()), None);
+        let ctx = BangContext::default();
+
+        assert_eq!(bangs.get(&parse("no bangs").unwrap(), &ctx), None);
+        assert_eq!(bangs.get(&parse("!no bangs").unwrap(), &ctx), None);
 
         assert_eq!(
-            bangs.get(&parse("!ty bangs").unwrap()),
+            bangs.get(&parse("!ty bangs").unwrap(), &ctx),
             Some(BangHit {
                 bang: Bang {
                     category: Some("Multimedia".to_string()),
@@ -185,12 +839,761 @@ mod tests {
                     ranking: Some(1646),
                     site: Some("Youtube".to_string()),
                     tag: "ty".to_string(),
-                    url: "https://www.youtube.com/results?search_query={{{s}}}".to_string()
+                    url: "https://www.youtube.com/results?search_query={{{s}}}".to_string(),
+                    params: Vec::new(),
                 },
+                tag: "ty".to_string(),
+                category: Some("Multimedia".to_string()),
+                query: "bangs".to_string(),
                 redirect_to: Url::parse("https://www.youtube.com/results?search_query=bangs")
                     .unwrap()
-                    .into()
+                    .into(),
+                params: Vec::new(),
             })
         );
     }
+
+    #[test]
+    fn empty() {
+        let bangs = Bangs::from_json("[]");
+
+        assert!(bangs.is_empty());
+        assert_eq!(bangs.len(), 0);
+        assert!(!bangs.contains("yt"));
+    }
+
+    #[test]
+    fn len_and_contains() {
+        let bangs = Bangs::from_json(
+            r#"[{
+            "c": "Multimedia",
+            "sc": "Video",
+            "t": "yt",
+            "u": "https://www.youtube.com/results?search_query={{{s}}}"
+        }, {
+            "c": "Shopping",
+            "t": "a",
+            "u": "https://www.amazon.com/s?k={{{s}}}"
+        }]"#,
+        );
+
+        assert!(!bangs.is_empty());
+        assert_eq!(bangs.len(), 2);
+        assert!(bangs.contains("yt"));
+        assert!(bangs.contains("a"));
+        assert!(!bangs.contains("video"));
+        assert!(!bangs.contains("!yt"));
+    }
+
+    #[test]
+    fn iter_and_get_by_tag() {
+        let bangs = Bangs::from_json(
+            r#"[{
+            "c": "Multimedia",
+            "sc": "Video",
+            "t": "yt",
+            "u": "https://www.youtube.com/results?search_query={{{s}}}"
+        }, {
+            "c": "Shopping",
+            "t": "a",
+            "u": "https://www.amazon.com/s?k={{{s}}}"
+        }, {
+            "t": "gh",
+            "u": "https://github.com/search?q={{{s}}}"
+        }]"#,
+        );
+
+        assert_eq!(bangs.iter().count(), 3);
+
+        let mut tags: Vec<_> = bangs.iter().map(|bang| bang.tag.as_str()).collect();
+        tags.sort();
+        assert_eq!(tags, vec!["a", "gh", "yt"]);
+
+        let yt = bangs.get_by_tag("yt").unwrap();
+        assert_eq!(yt.category.as_deref(), Some("Multimedia"));
+        assert!(bangs.get_by_tag("video").is_none());
+        assert!(bangs.get_by_tag("!yt").is_none());
+
+        assert_eq!(Bangs::from_json("[]").iter().count(), 0);
+    }
+
+    #[test]
+    fn by_tag_prefix() {
+        let bangs = Bangs::from_json(
+            r#"[{
+            "r": 10,
+            "t": "yopmail",
+            "u": "https://yopmail.com/?{{{s}}}"
+        }, {
+            "r": 100,
+            "t": "youtube",
+            "u": "https://www.youtube.com/results?search_query={{{s}}}"
+        }, {
+            "t": "yo",
+            "u": "https://yo.example.com/?q={{{s}}}"
+        }, {
+            "r": 50,
+            "t": "gh",
+            "u": "https://github.com/search?q={{{s}}}"
+        }]"#,
+        );
+
+        let tags = |prefix| {
+            bangs
+                .by_tag_prefix(prefix, 10)
+                .into_iter()
+                .map(|bang| bang.tag.as_str())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(tags("yo"), vec!["youtube", "yopmail", "yo"]);
+        assert_eq!(tags("you"), vec!["youtube"]);
+        assert_eq!(tags("!yo"), Vec::<&str>::new());
+        assert_eq!(tags("x"), Vec::<&str>::new());
+        assert_eq!(tags("").len(), 4);
+
+        let top = bangs
+            .by_tag_prefix("yo", 2)
+            .into_iter()
+            .map(|bang| bang.tag.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(top, vec!["youtube", "yopmail"]);
+    }
+
+    #[test]
+    fn custom_prefix() {
+        let bangs = Bangs::from_json(
+            r#"[{
+            "t": "gh",
+            "u": "https://github.com/search?q={{{s}}}"
+        }]"#,
+        )
+        .with_prefixes(vec!['#']);
+
+        let ctx = BangContext::default();
+        let parse = |query| parse_with_bang_prefixes(query, bangs.prefixes()).unwrap();
+
+        let hit = bangs.get(&parse("#gh stract"), &ctx).unwrap();
+        assert_eq!(hit.tag, "gh");
+        assert_eq!(hit.query, "stract");
+        assert_eq!(
+            hit.redirect_to.as_str(),
+            "https://github.com/search?q=stract"
+        );
+
+        assert_eq!(bangs.get(&parse("!gh stract"), &ctx), None);
+
+        let hit = bangs.get(&parse("#gh !important"), &ctx).unwrap();
+        assert_eq!(hit.query, "!important");
+        assert_eq!(
+            hit.redirect_to.as_str(),
+            "https://github.com/search?q=%21important"
+        );
+    }
+
+    #[test]
+    fn custom_prefix_in_tag() {
+        let bangs = Bangs::from_json(
+            r##"[{
+            "t": "#gh",
+            "u": "https://github.com/search?q={{{s}}}"
+        }]"##,
+        );
+
+        assert!(bangs.contains("#gh"));
+
+        let bangs = bangs.with_prefixes(vec!['#']);
+
+        assert!(bangs.contains("gh"));
+        assert!(!bangs.contains("#gh"));
+    }
+
+    #[test]
+    fn leading_prefix_in_tag() {
+        let bangs = Bangs::from_json(
+            r#"[{
+            "t": "!gh",
+            "u": "https://github.com/search?q={{{s}}}"
+        }]"#,
+        );
+
+        assert!(bangs.contains("gh"));
+        assert!(!bangs.contains("!gh"));
+
+        let ctx = BangContext::default();
+        let hit = bangs.get(&parse("!gh stract").unwrap(), &ctx).unwrap();
+        assert_eq!(hit.tag, "gh");
+        assert_eq!(
+            hit.redirect_to.as_str(),
+            "https://github.com/search?q=stract"
+        );
+    }
+
+    #[test]
+    fn malformed_tags_are_skipped() {
+        let bangs = Bangs::from_json(
+            r#"[{
+            "t": "g h",
+            "u": "https://github.com/search?q={{{s}}}"
+        }, {
+            "t": "!",
+            "u": "https://example.com/search?q={{{s}}}"
+        }, {
+            "t": "yt",
+            "u": "https://www.youtube.com/results?search_query={{{s}}}"
+        }]"#,
+        );
+
+        assert_eq!(bangs.len(), 1);
+        assert!(bangs.contains("yt"));
+        assert!(!bangs.contains("g h"));
+
+        let ctx = BangContext::default();
+        assert_eq!(bangs.get(&parse("!g h stract").unwrap(), &ctx), None);
+    }
+
+    #[test]
+    fn merge_prefers_higher_priority() {
+        let bang = |tag: &str, url: &str| Bang {
+            category: None,
+            sub_category: None,
+            domain: None,
+            ranking: None,
+            site: None,
+            tag: tag.to_string(),
+            url: url.to_string(),
+            params: Vec::new(),
+        };
+
+        let upstream = vec![
+            bang("gh", "https://github.com/search?q={{{s}}}"),
+            bang("yt", "https://www.youtube.com/results?search_query={{{s}}}"),
+        ];
+        let internal = vec![bang("gh", "https://git.example.com/search?q={{{s}}}")];
+
+        let bangs = Bangs::merge(vec![(Priority(0), upstream), (Priority(1), internal)]);
+
+        assert_eq!(bangs.len(), 2);
+        assert!(bangs.contains("yt"));
+
+        let ctx = BangContext::default();
+        let hit = bangs.get(&parse("!gh stract").unwrap(), &ctx).unwrap();
+        assert_eq!(
+            hit.redirect_to.as_str(),
+            "https://git.example.com/search?q=stract"
+        );
+    }
+
+    #[test]
+    fn scoped_by_category() {
+        let bangs = Bangs::from_json(
+            r#"[{
+            "c": "Multimedia",
+            "r": 1646,
+            "sc": "Video",
+            "t": "yt",
+            "u": "https://www.youtube.com/results?search_query={{{s}}}"
+        }, {
+            "c": "Shopping",
+            "r": 500,
+            "sc": "Online",
+            "t": "a",
+            "u": "https://www.amazon.com/s?k={{{s}}}"
+        }]"#,
+        );
+
+        let ctx = BangContext::default();
+        let shopping = vec!["Shopping".to_string()];
+
+        assert_eq!(
+            bangs.get_scoped(&parse("!yt cats").unwrap(), &ctx, &shopping),
+            None
+        );
+        assert!(bangs.get(&parse("!yt cats").unwrap(), &ctx).is_some());
+
+        let hit = bangs
+            .get_scoped(&parse("!yt !a cats").unwrap(), &ctx, &shopping)
+            .unwrap();
+        assert_eq!(hit.tag, "a");
+        assert_eq!(hit.query, "!yt cats");
+    }
+
+    #[test]
+    fn category_alias() {
+        let bangs = Bangs::from_json(
+            r#"[{
+            "c": "Multimedia",
+            "r": 1646,
+            "sc": "Video",
+            "t": "yt",
+            "u": "https://www.youtube.com/results?search_query={{{s}}}"
+        }, {
+            "c": "Multimedia",
+            "r": 100,
+            "sc": "Video",
+            "t": "vimeo",
+            "u": "https://vimeo.com/search?q={{{s}}}"
+        }, {
+            "c": "Tech",
+            "r": 10,
+            "sc": "Programming",
+            "t": "programming",
+            "u": "https://programming.example.com/?q={{{s}}}"
+        }, {
+            "c": "Tech",
+            "r": 5000,
+            "sc": "Programming",
+            "t": "so",
+            "u": "https://stackoverflow.com/search?q={{{s}}}"
+        }]"#,
+        );
+
+        let ctx = BangContext::default();
+
+        let hit = bangs.get(&parse("!video cats").unwrap(), &ctx).unwrap();
+        assert_eq!(hit.tag, "yt");
+        assert_eq!(hit.category, Some("Multimedia".to_string()));
+        assert_eq!(hit.query, "cats");
+        assert_eq!(
+            hit.redirect_to.as_str(),
+            "https://www.youtube.com/results?search_query=cats"
+        );
+
+        let hit = bangs
+            .get(&parse("!multimedia cats").unwrap(), &ctx)
+            .unwrap();
+        assert_eq!(hit.tag, "yt");
+
+        // a bang with the tag takes precedence over the category alias
+        let hit = bangs
+            .get(&parse("!programming rust").unwrap(), &ctx)
+            .unwrap();
+        assert_eq!(hit.tag, "programming");
+
+        let hit = bangs.get(&parse("!tech rust").unwrap(), &ctx).unwrap();
+        assert_eq!(hit.tag, "so");
+
+        assert_eq!(bangs.get(&parse("!news cats").unwrap(), &ctx), None);
+    }
+
+    #[test]
+    fn compiled_round_trip() {
+        let json = r#"[{
+            "c": "Multimedia",
+            "d": "www.youtube.com",
+            "r": 1646,
+            "s": "Youtube",
+            "sc": "Video",
+            "t": "ty",
+            "u": "https://www.youtube.com/results?search_query={{{s}}}"
+        },
+        {
+            "t": "maps",
+            "u": "https://maps.example.com/{{{1}}}?q={{{s}}}",
+            "p": [
+                {"k": "region", "n": "gl"},
+                {"k": "safeSearch", "n": "safe"}
+            ]
+        }]"#;
+
+        let dir = crate::gen_temp_path();
+        fs::create_dir_all(&dir).unwrap();
+
+        let json_path = dir.join("bangs.json");
+        let compiled_path = dir.join("bangs.bin");
+        fs::write(&json_path, json).unwrap();
+
+        Bangs::compile(&json_path, &compiled_path).unwrap();
+
+        let from_json = Bangs::from_json(json);
+        let compiled = Bangs::from_compiled(&compiled_path).unwrap();
+        let opened = Bangs::open(&compiled_path).unwrap();
+
+        assert_eq!(compiled.bangs, from_json.bangs);
+        assert_eq!(opened.bangs, from_json.bangs);
+        assert_eq!(Bangs::open(&json_path).unwrap().bangs, from_json.bangs);
+
+        let ctx = BangContext {
+            safe_search: true,
+            region: Some(Region::Denmark),
+        };
+
+        for query in [
+            "no bangs",
+            "!no bangs",
+            "!ty bangs",
+            "!maps copenhagen",
+            "copenhagen !maps",
+        ] {
+            let terms = parse(query).unwrap();
+            assert_eq!(compiled.get(&terms, &ctx), from_json.get(&terms, &ctx));
+        }
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn compile_invalid_json() {
+        let dir = crate::gen_temp_path();
+        fs::create_dir_all(&dir).unwrap();
+
+        let json_path = dir.join("bangs.json");
+        let compiled_path = dir.join("bangs.bin");
+        fs::write(&json_path, "not json").unwrap();
+
+        assert!(Bangs::compile(&json_path, &compiled_path).is_err());
+        assert!(Bangs::compile(dir.join("missing.json"), &compiled_path).is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn stale_compiled_version() {
+        let dir = crate::gen_temp_path();
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("bangs.bin");
+
+        let mut bytes = COMPILED_MAGIC.to_vec();
+        bytes.extend((COMPILED_VERSION + 1).to_le_bytes());
+        bytes.extend(bincode::serialize(&HashMap::<String, Bang>::new()).unwrap());
+        fs::write(&path, bytes).unwrap();
+
+        let err = Bangs::from_compiled(&path).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref(),
+            Some(Error::UnsupportedVersion { found }) if *found == COMPILED_VERSION + 1
+        ));
+
+        fs::write(&path, "[]").unwrap();
+        assert!(matches!(
+            Bangs::from_compiled(&path).unwrap_err().downcast_ref(),
+            Some(Error::NotCompiled)
+        ));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn path_placeholder() {
+        let bangs = Bangs::from_json(
+            r#"[{
+            "t": "w",
+            "u": "https://wiki.example.com/wiki/{{{s}}}"
+        }]"#,
+        );
+
+        let hit = bangs
+            .get(&parse("!w AC/DC live").unwrap(), &BangContext::default())
+            .unwrap();
+
+        assert_eq!(
+            hit.redirect_to.as_str(),
+            "https://wiki.example.com/wiki/AC%2FDC%20live"
+        );
+    }
+
+    #[test]
+    fn query_placeholder() {
+        let bangs = Bangs::from_json(
+            r#"[{
+            "t": "ex",
+            "u": "https://www.example.com/search/{{{s}}}?q={{{s}}}"
+        }]"#,
+        );
+
+        let hit = bangs
+            .get(&parse("!ex AC/DC live").unwrap(), &BangContext::default())
+            .unwrap();
+
+        assert_eq!(
+            hit.redirect_to.as_str(),
+            "https://www.example.com/search/AC%2FDC%20live?q=AC%2FDC+live"
+        );
+    }
+
+    #[test]
+    fn numbered_placeholders() {
+        let bangs = Bangs::from_json(
+            r#"[{
+            "t": "conv",
+            "u": "https://conv.example.com/{{{1}}}?to={{{2}}}&q={{{s}}}"
+        }]"#,
+        );
+
+        let hit = bangs
+            .get(&parse("!conv usd dkk").unwrap(), &BangContext::default())
+            .unwrap();
+
+        assert_eq!(
+            hit.redirect_to.as_str(),
+            "https://conv.example.com/usd?to=dkk&q=usd+dkk"
+        );
+    }
+
+    #[test]
+    fn missing_placeholder_index() {
+        let bangs = Bangs::from_json(
+            r#"[{
+            "t": "conv",
+            "u": "https://conv.example.com/convert?from={{{1}}}&to={{{3}}}&amount={{{0}}}"
+        }]"#,
+        );
+
+        let hit = bangs
+            .get(&parse("!conv usd dkk").unwrap(), &BangContext::default())
+            .unwrap();
+
+        assert_eq!(
+            hit.redirect_to.as_str(),
+            "https://conv.example.com/convert?from=usd&to=&amount="
+        );
+    }
+
+    #[test]
+    fn bang_params() {
+        let bangs = Bangs::from_json(
+            r#"[{
+            "t": "maps",
+            "u": "https://maps.example.com/search?q={{{s}}}&gl=us",
+            "p": [
+                {"k": "region", "n": "gl"},
+                {"k": "safeSearch", "n": "safe"}
+            ]
+        }]"#,
+        );
+
+        let hit = bangs
+            .get(
+                &parse("!maps copenhagen").unwrap(),
+                &BangContext {
+                    safe_search: true,
+                    region: Some(Region::Denmark),
+                },
+            )
+            .unwrap();
+
+        assert_eq!(
+            hit.redirect_to.as_str(),
+            "https://maps.example.com/search?q=copenhagen&gl=us&safe=1"
+        );
+        assert_eq!(
+            hit.params,
+            vec![BangParamValue {
+                name: "safe".to_string(),
+                value: "1".to_string(),
+            }]
+        );
+
+        let hit = bangs
+            .get(&parse("!maps copenhagen").unwrap(), &BangContext::default())
+            .unwrap();
+
+        assert_eq!(
+            hit.redirect_to.as_str(),
+            "https://maps.example.com/search?q=copenhagen&gl=us&safe=0"
+        );
+    }
+
+    #[test]
+    fn bang_params_without_query() {
+        let bangs = Bangs::from_json(
+            r#"[{
+            "t": "maps",
+            "u": "https://maps.example.com/{{{s}}}",
+            "p": [
+                {"k": "region", "n": "gl"}
+            ]
+        }]"#,
+        );
+
+        let hit = bangs
+            .get(
+                &parse("!maps copenhagen").unwrap(),
+                &BangContext {
+                    safe_search: false,
+                    region: Some(Region::Denmark),
+                },
+            )
+            .unwrap();
+        assert_eq!(
+            hit.redirect_to.as_str(),
+            "https://maps.example.com/copenhagen?gl=dk"
+        );
+
+        let hit = bangs
+            .get(
+                &parse("!maps copenhagen").unwrap(),
+                &BangContext {
+                    safe_search: false,
+                    region: Some(Region::All),
+                },
+            )
+            .unwrap();
+        assert_eq!(
+            hit.redirect_to.as_str(),
+            "https://maps.example.com/copenhagen"
+        );
+        assert!(hit.params.is_empty());
+    }
+
+    #[test]
+    fn quoted_bang() {
+        let bangs = Bangs::from_json(
+            r#"[{
+            "t": "ty",
+            "u": "https://www.youtube.com/results?search_query={{{s}}}"
+        }]"#,
+        );
+
+        let ctx = BangContext::default();
+
+        assert_eq!(bangs.get(&parse("\"!ty bug\"").unwrap(), &ctx), None);
+        assert_eq!(bangs.get(&parse("bug \"!ty\"").unwrap(), &ctx), None);
+        assert_eq!(bangs.get(&parse("“!ty bug”").unwrap(), &ctx), None);
+
+        let hit = bangs.get(&parse("!ty \"bug\"").unwrap(), &ctx).unwrap();
+        assert_eq!(
+            hit.redirect_to.as_str(),
+            "https://www.youtube.com/results?search_query=%22bug%22"
+        );
+    }
+
+    #[test]
+    fn multi_word_tag() {
+        let bangs = Bangs::from_json(
+            r#"[{
+            "t": "gh",
+            "u": "https://github.com/search?q={{{s}}}"
+        }, {
+            "t": "gh_issues",
+            "u": "https://github.com/issues?q={{{s}}}"
+        }]"#,
+        );
+
+        let ctx = BangContext::default();
+
+        let hit = bangs
+            .get(&parse("!gh issues memory leak").unwrap(), &ctx)
+            .unwrap();
+        assert_eq!(hit.tag, "gh_issues");
+        assert_eq!(hit.query, "memory leak");
+        assert_eq!(
+            hit.redirect_to.as_str(),
+            "https://github.com/issues?q=memory+leak"
+        );
+
+        let hit = bangs
+            .get(&parse("memory leak !gh issues").unwrap(), &ctx)
+            .unwrap();
+        assert_eq!(hit.tag, "gh_issues");
+        assert_eq!(hit.query, "memory leak");
+
+        let hit = bangs
+            .get(&parse("!gh_issues memory leak").unwrap(), &ctx)
+            .unwrap();
+        assert_eq!(hit.tag, "gh_issues");
+
+        let hit = bangs.get(&parse("!gh pulls").unwrap(), &ctx).unwrap();
+        assert_eq!(hit.tag, "gh");
+        assert_eq!(hit.query, "pulls");
+
+        let hit = bangs.get(&parse("!gh \"issues\"").unwrap(), &ctx).unwrap();
+        assert_eq!(hit.tag, "gh");
+    }
+
+    #[test]
+    fn empty_terms() {
+        let bangs = Bangs::from_json(
+            r#"[{
+            "t": "ty",
+            "u": "https://www.youtube.com/results?search_query={{{s}}}"
+        }]"#,
+        );
+
+        assert_eq!(bangs.get(&[], &BangContext::default()), None);
+    }
+
+    #[test]
+    fn bang_only() {
+        let bangs = Bangs::from_json(
+            r#"[{
+            "t": "ty",
+            "u": "https://www.youtube.com/results?search_query={{{s}}}",
+            "p": [
+                {"k": "region", "n": "gl"}
+            ]
+        }, {
+            "t": "w",
+            "u": "https://wiki.example.com/wiki/{{{s}}}"
+        }, {
+            "t": "news",
+            "u": "https://news.example.com/frontpage"
+        }]"#,
+        );
+
+        let ctx = BangContext {
+            safe_search: false,
+            region: Some(Region::Denmark),
+        };
+
+        let hit = bangs.get(&parse("!ty").unwrap(), &ctx).unwrap();
+        assert_eq!(hit.query, "");
+        assert_eq!(hit.redirect_to.as_str(), "https://www.youtube.com/");
+        assert!(hit.params.is_empty());
+
+        let hit = bangs.get(&parse("!w").unwrap(), &ctx).unwrap();
+        assert_eq!(hit.redirect_to.as_str(), "https://wiki.example.com/");
+
+        // urls without placeholders don't depend on the query
+        let hit = bangs.get(&parse("!news").unwrap(), &ctx).unwrap();
+        assert_eq!(
+            hit.redirect_to.as_str(),
+            "https://news.example.com/frontpage"
+        );
+    }
+
+    #[test]
+    fn readable_round_trip() {
+        let bangs = Bangs::from_json(
+            r#"[{
+            "c": "Multimedia",
+            "d": "www.youtube.com",
+            "r": 1646,
+            "s": "Youtube",
+            "sc": "Video",
+            "t": "ty",
+            "u": "https://www.youtube.com/results?search_query={{{s}}}",
+            "p": [
+                {"k": "safeSearch", "n": "safe"}
+            ]
+        },
+        {
+            "t": "maps",
+            "u": "https://maps.example.com/{{{s}}}"
+        }]"#,
+        );
+
+        let readable = bangs.to_readable_json().unwrap();
+        assert!(readable.contains(r#""subCategory": "Video""#));
+        assert!(readable.contains(r#""category": null"#));
+
+        let parsed = Bangs::from_readable_json(&readable).unwrap();
+        assert_eq!(parsed.bangs, bangs.bangs);
+
+        let maps = &parsed.bangs["maps"];
+        assert_eq!(maps.category, None);
+        assert_eq!(maps.sub_category, None);
+        assert_eq!(maps.domain, None);
+        assert_eq!(maps.ranking, None);
+        assert_eq!(maps.site, None);
+        assert!(maps.params.is_empty());
+
+        assert_eq!(
+            parsed.bangs["ty"].params,
+            vec![BangParam {
+                kind: BangParamKind::SafeSearch,
+                name: "safe".to_string(),
+            }]
+        );
+    }
 }
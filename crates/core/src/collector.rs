@@ -14,7 +14,15 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, HashMap},
+    sync::{
+        atomic::{self, AtomicBool},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
 
 use min_max_heap::MinMaxHeap;
 use serde::{Deserialize, Serialize};
@@ -36,6 +44,18 @@ use crate::{
 
 pub type MainCollector = TweakedScoreTopCollector<InitialScoreTweaker>;
 
+/// A [`ScoreSegmentTweaker`] that can stop scoring a document as soon as it can no
+/// longer reach the score of the documents that are already collected.
+pub trait PruningScoreSegmentTweaker: ScoreSegmentTweaker<Score> {
+    /// The score of `doc`, or `None` if the score is known to be below `cutoff`.
+    fn score_with_cutoff(
+        &mut self,
+        doc: DocId,
+        score: tantivy::Score,
+        cutoff: f64,
+    ) -> Option<Score>;
+}
+
 #[derive(Clone, Debug)]
 pub struct MaxDocsConsidered {
     pub total_docs: usize,
@@ -54,6 +74,105 @@ pub struct Hashes {
 pub trait Doc: Clone {
     fn score(&self) -> f64;
     fn hashes(&self) -> Hashes;
+
+    /// Used to break ties between documents with identical scores.
+    /// Documents from more central hosts are ranked first.
+    fn host_centrality(&self) -> f64 {
+        0.0
+    }
+
+    /// Used to break ties between documents with identical scores and host centrality.
+    /// Documents with lower addresses are ranked first.
+    fn address(&self) -> Option<DocAddress> {
+        None
+    }
+}
+
+/// Position of the last document returned by a previous search.
+///
+/// Documents are ordered by descending score and ties are broken by
+/// descending host centrality and then ascending address, so the position is stable for identical queries
+/// against an unchanged index. A search given a cursor only collects the
+/// documents ranked after it instead of re-ranking everything from rank 0.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct SearchCursor {
+    pub score: f64,
+    pub host_centrality: f64,
+    pub address: DocAddress,
+}
+
+impl Default for SearchCursor {
+    /// A cursor positioned before the first result.
+    fn default() -> Self {
+        Self {
+            score: f64::INFINITY,
+            host_centrality: f64::INFINITY,
+            address: DocAddress {
+                segment: 0,
+                doc_id: 0,
+            },
+        }
+    }
+}
+
+impl SearchCursor {
+    /// Whether the document would have been ranked at or before the cursor.
+    fn has_passed(&self, score: f64, host_centrality: f64, address: DocAddress) -> bool {
+        match score
+            .total_cmp(&self.score)
+            .then_with(|| host_centrality.total_cmp(&self.host_centrality))
+        {
+            Ordering::Greater => true,
+            Ordering::Less => false,
+            Ordering::Equal => address <= self.address,
+        }
+    }
+}
+
+impl From<&WebpagePointer> for SearchCursor {
+    fn from(pointer: &WebpagePointer) -> Self {
+        Self {
+            score: pointer.score.total,
+            host_centrality: pointer.host_centrality,
+            address: pointer.address,
+        }
+    }
+}
+
+/// Point in time after which no more documents are scored. The deadline is
+/// shared between all segment collectors of a search, so it can be checked
+/// afterwards whether the results are partial.
+#[derive(Clone, Debug)]
+pub struct Deadline {
+    at: Instant,
+    reached: Arc<AtomicBool>,
+}
+
+impl Deadline {
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            at: Instant::now() + timeout,
+            reached: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Whether any collector stopped collecting documents because of the deadline.
+    pub fn is_reached(&self) -> bool {
+        self.reached.load(atomic::Ordering::Relaxed)
+    }
+
+    fn check(&self) -> bool {
+        if self.is_reached() {
+            return true;
+        }
+
+        if Instant::now() >= self.at {
+            self.reached.store(true, atomic::Ordering::Relaxed);
+            return true;
+        }
+
+        false
+    }
 }
 
 pub struct TopDocs {
@@ -63,6 +182,9 @@ pub struct TopDocs {
     fastfield_reader: fastfield_reader::FastFieldReader,
     de_rank_similar: bool,
     collector_config: CollectorConfig,
+    cursor: Option<SearchCursor>,
+    deadline: Option<Deadline>,
+    score_cutoff: bool,
 }
 
 impl TopDocs {
@@ -74,6 +196,9 @@ impl TopDocs {
             de_rank_similar: false,
             fastfield_reader,
             collector_config: CollectorConfig::default(),
+            cursor: None,
+            deadline: None,
+            score_cutoff: false,
         }
     }
 
@@ -101,6 +226,41 @@ impl TopDocs {
         self
     }
 
+    /// Only collect the documents ranked after the cursor.
+    ///
+    /// Similar documents are not de-ranked when a cursor is used, as the adjusted
+    /// scores would depend on the documents returned on the previous pages.
+    pub fn and_cursor(mut self, cursor: SearchCursor) -> Self {
+        self.cursor = Some(cursor);
+        self
+    }
+
+    /// Stop scoring documents when the deadline is reached. The documents collected
+    /// so far are still ranked as usual.
+    pub fn and_deadline(mut self, deadline: Deadline) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Once a segment has collected `top_n + offset` documents, the remaining signals of
+    /// a document are skipped as soon as it can't reach the lowest score among them.
+    ///
+    /// The cutoff is compared to the scores before similar documents are de-ranked, so
+    /// a skipped document could in rare cases have been ranked higher than a collected
+    /// document that was penalized for being similar to a higher ranked document.
+    pub fn and_score_cutoff(mut self) -> Self {
+        self.score_cutoff = true;
+        self
+    }
+
+    fn bucket_collector<T: Doc>(&self) -> BucketCollector<T> {
+        BucketCollector::new(self.top_n + self.offset, self.collector_config.clone())
+    }
+
+    fn de_rank_similar(&self) -> bool {
+        self.de_rank_similar && self.cursor.is_none()
+    }
+
     pub fn main_collector(self, score_tweaker: InitialScoreTweaker) -> MainCollector {
         TweakedScoreTopCollector::new(score_tweaker, self)
     }
@@ -122,10 +282,12 @@ impl TopDocs {
             max_docs,
             num_docs_taken: 0,
             segment_ord: segment_local_id,
-            bucket_collector: BucketCollector::new(
-                self.top_n + self.offset,
-                self.collector_config.clone(),
-            ),
+            cursor: self.cursor,
+            deadline: self.deadline.clone(),
+            top_scores: self
+                .score_cutoff
+                .then(|| TopScores::new(self.top_n + self.offset)),
+            bucket_collector: self.bucket_collector(),
         })
     }
 }
@@ -135,9 +297,63 @@ pub struct TopSegmentCollector {
     max_docs: Option<usize>,
     num_docs_taken: usize,
     segment_ord: SegmentOrdinal,
+    cursor: Option<SearchCursor>,
+    deadline: Option<Deadline>,
+    /// The best scores collected so far. Only tracked when a score cutoff is used.
+    top_scores: Option<TopScores>,
     bucket_collector: BucketCollector<SegmentDoc>,
 }
 
+/// Score that is ordered in reverse, so [`BinaryHeap`] pops the lowest score first.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct MinScore(f64);
+
+impl Eq for MinScore {}
+
+impl PartialOrd for MinScore {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for MinScore {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.0.total_cmp(&self.0)
+    }
+}
+
+/// The `k` highest scores seen so far.
+struct TopScores {
+    k: usize,
+    scores: BinaryHeap<MinScore>,
+}
+
+impl TopScores {
+    fn new(k: usize) -> Self {
+        Self {
+            k,
+            scores: BinaryHeap::with_capacity(k + 1),
+        }
+    }
+
+    fn insert(&mut self, score: f64) {
+        self.scores.push(MinScore(score));
+
+        if self.scores.len() > self.k {
+            self.scores.pop();
+        }
+    }
+
+    /// The k-th highest score, once `k` scores have been seen.
+    fn kth(&self) -> Option<f64> {
+        if self.scores.len() < self.k {
+            return None;
+        }
+
+        self.scores.peek().map(|score| score.0)
+    }
+}
+
 impl TopSegmentCollector {
     fn get_hash(&self, doc: DocId, field1: FastFieldEnum, field2: FastFieldEnum) -> Prehashed {
         let field_reader = self.fastfield_segment_reader.get_field_reader(doc);
@@ -151,7 +367,7 @@ impl TopSegmentCollector {
 }
 
 impl TopSegmentCollector {
-    fn is_done(&self) -> bool {
+    fn reached_max_docs(&self) -> bool {
         if let Some(max_docs) = &self.max_docs {
             self.num_docs_taken >= *max_docs
         } else {
@@ -159,13 +375,52 @@ impl TopSegmentCollector {
         }
     }
 
+    /// Whether no more documents should be scored.
+    fn is_done(&self) -> bool {
+        self.reached_max_docs() || self.deadline.as_ref().map_or(false, Deadline::check)
+    }
+
+    /// The score a document must reach to be among the best documents collected so far.
+    /// Documents with a lower score can't make it into the results of the segment.
+    fn cutoff(&self) -> Option<f64> {
+        self.top_scores.as_ref().and_then(TopScores::kth)
+    }
+
+    /// Count a document whose scoring was stopped by the cutoff as considered.
+    fn skip(&mut self) {
+        self.num_docs_taken += 1;
+    }
+
     fn collect(&mut self, doc: DocId, score: Score) {
-        if self.is_done() {
+        // documents that were scored before the deadline are still collected
+        if self.reached_max_docs() {
             return;
         }
 
+        let host_centrality = self
+            .fastfield_segment_reader
+            .get_f64(doc, fast_field::HostCentrality)
+            .unwrap_or_default();
+
+        if let Some(cursor) = &self.cursor {
+            let address = DocAddress {
+                segment: self.segment_ord,
+                doc_id: doc,
+            };
+
+            if cursor.has_passed(score.total, host_centrality, address) {
+                return;
+            }
+        }
+
+        // documents before the cursor are not counted, so every page considers
+        // the same number of documents.
         self.num_docs_taken += 1;
 
+        if let Some(top_scores) = &mut self.top_scores {
+            top_scores.insert(score.total);
+        }
+
         let simhash: Option<u64> = self
             .fastfield_segment_reader
             .get_field_reader(doc)
@@ -200,34 +455,44 @@ impl TopSegmentCollector {
             id: doc,
             segment: self.segment_ord,
             score,
+            host_centrality,
         });
     }
 
     fn harvest(self) -> Vec<SegmentDoc> {
-        self.bucket_collector.into_sorted_vec(true)
+        let de_rank_similar = self.cursor.is_none();
+        self.bucket_collector.into_sorted_vec(de_rank_similar)
     }
 }
 
 struct ScoredDoc<T: Doc> {
     doc: T,
     adjusted_score: f64,
+    is_duplicate: bool,
 }
 
 impl<T: Doc> PartialOrd for ScoredDoc<T> {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         Some(self.cmp(other))
     }
 }
 
 impl<T: Doc> PartialEq for ScoredDoc<T> {
     fn eq(&self, other: &Self) -> bool {
-        self.adjusted_score == other.adjusted_score
+        self.cmp(other) == Ordering::Equal
     }
 }
 
 impl<T: Doc> Ord for ScoredDoc<T> {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.adjusted_score.total_cmp(&other.adjusted_score)
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.adjusted_score
+            .total_cmp(&other.adjusted_score)
+            .then_with(|| {
+                self.doc
+                    .host_centrality()
+                    .total_cmp(&other.doc.host_centrality())
+            })
+            .then_with(|| other.doc.address().cmp(&self.doc.address()))
     }
 }
 
@@ -238,6 +503,7 @@ impl<T: Doc> From<T> for ScoredDoc<T> {
         Self {
             adjusted_score: doc.score(),
             doc,
+            is_duplicate: false,
         }
     }
 }
@@ -270,7 +536,12 @@ impl BucketCount {
                 + taken_urls_without_tld as f64 * self.config.url_without_tld_penalty
                 + taken_titles as f64 * self.config.title_penalty);
 
-        doc.adjusted_score = doc.doc.score() * adjuster;
+        let duplicate_adjuster = match self.config.duplicate_penalty {
+            Some(penalty) if doc.is_duplicate => 1.0 / (1.0 + penalty),
+            _ => 1.0,
+        };
+
+        doc.adjusted_score = doc.doc.score() * adjuster * duplicate_adjuster;
     }
 
     fn update_counts<T: Doc>(&mut self, doc: &ScoredDoc<T>) {
@@ -326,12 +597,21 @@ impl<T: Doc> BucketCollector<T> {
         let mut simhash_dups = Vec::new();
         let mut simhash = simhash::Table::default();
 
-        while let Some(best_doc) = self.documents.pop_max() {
+        while let Some(mut best_doc) = self.documents.pop_max() {
             let hashes = best_doc.doc.hashes();
 
-            if hashes.simhash != 0 && de_rank_similar {
+            if hashes.simhash != 0 && de_rank_similar && !best_doc.is_duplicate {
                 if simhash.contains(&hashes.simhash) {
-                    simhash_dups.push(best_doc.doc);
+                    // with a duplicate penalty, near-duplicates are demoted and re-ranked
+                    // among the remaining documents instead of moved to the end
+                    if self.count.config.duplicate_penalty.is_some() {
+                        best_doc.is_duplicate = true;
+                        self.count.adjust_score(&mut best_doc);
+                        self.documents.push(best_doc);
+                    } else {
+                        simhash_dups.push(best_doc.doc);
+                    }
+
                     continue;
                 }
                 simhash.insert(hashes.simhash);
@@ -361,6 +641,7 @@ pub struct SegmentDoc {
     id: DocId,
     segment: SegmentOrdinal,
     score: Score,
+    host_centrality: f64,
 }
 
 impl Doc for SegmentDoc {
@@ -371,6 +652,17 @@ impl Doc for SegmentDoc {
     fn hashes(&self) -> Hashes {
         self.hashes
     }
+
+    fn host_centrality(&self) -> f64 {
+        self.host_centrality
+    }
+
+    fn address(&self) -> Option<DocAddress> {
+        Some(DocAddress {
+            segment: self.segment,
+            doc_id: self.id,
+        })
+    }
 }
 
 pub struct TweakedScoreTopCollector<TScoreTweaker> {
@@ -397,6 +689,7 @@ impl<TScoreTweaker> TweakedScoreTopCollector<TScoreTweaker> {
 impl<TScoreTweaker> Collector for TweakedScoreTopCollector<TScoreTweaker>
 where
     TScoreTweaker: ScoreTweaker<Score> + Send + Sync,
+    TScoreTweaker::Child: PruningScoreSegmentTweaker,
 {
     type Fruit = Vec<WebpagePointer>;
 
@@ -427,10 +720,7 @@ where
         &self,
         segment_fruits: Vec<<Self::Child as tantivy::collector::SegmentCollector>::Fruit>,
     ) -> tantivy::Result<Self::Fruit> {
-        let mut collector = BucketCollector::new(
-            self.top_docs.top_n + self.top_docs.offset,
-            self.top_docs.collector_config.clone(),
-        );
+        let mut collector = self.top_docs.bucket_collector();
 
         for docs in segment_fruits {
             for doc in docs {
@@ -439,12 +729,13 @@ where
         }
 
         Ok(collector
-            .into_sorted_vec(self.top_docs.de_rank_similar)
+            .into_sorted_vec(self.top_docs.de_rank_similar())
             .into_iter()
             .skip(self.top_docs.offset)
             .map(|doc| WebpagePointer {
                 score: doc.score,
                 hashes: doc.hashes,
+                host_centrality: doc.host_centrality,
                 address: DocAddress {
                     segment: doc.segment,
                     doc_id: doc.id,
@@ -456,7 +747,7 @@ where
 
 pub struct TopTweakedScoreSegmentCollector<TSegmentScoreTweaker>
 where
-    TSegmentScoreTweaker: ScoreSegmentTweaker<Score>,
+    TSegmentScoreTweaker: PruningScoreSegmentTweaker,
 {
     segment_collector: TopSegmentCollector,
     segment_scorer: TSegmentScoreTweaker,
@@ -465,7 +756,7 @@ where
 impl<TSegmentScoreTweaker> SegmentCollector
     for TopTweakedScoreSegmentCollector<TSegmentScoreTweaker>
 where
-    TSegmentScoreTweaker: 'static + ScoreSegmentTweaker<Score>,
+    TSegmentScoreTweaker: 'static + PruningScoreSegmentTweaker,
 {
     type Fruit = Vec<SegmentDoc>;
 
@@ -474,8 +765,16 @@ where
             return;
         }
 
-        let score = self.segment_scorer.score(doc, score);
-        self.segment_collector.collect(doc, score);
+        match self.segment_collector.cutoff() {
+            Some(cutoff) => match self.segment_scorer.score_with_cutoff(doc, score, cutoff) {
+                Some(score) => self.segment_collector.collect(doc, score),
+                None => self.segment_collector.skip(),
+            },
+            None => {
+                let score = self.segment_scorer.score(doc, score);
+                self.segment_collector.collect(doc, score);
+            }
+        }
     }
 
     fn harvest(self) -> Self::Fruit {
@@ -496,6 +795,7 @@ mod tests {
                 id: doc.1,
                 score: Score { total: doc.2 },
                 segment: 0,
+                host_centrality: 0.0,
             });
         }
 
@@ -741,4 +1041,95 @@ mod tests {
             &[(5.0, 127), (3.1, 126), (3.0, 125)],
         );
     }
+
+    #[test]
+    fn deterministic_tie_breaking() {
+        let docs = [(10, 0.2), (5, 0.2), (7, 0.9)]
+            .into_iter()
+            .map(|(id, host_centrality)| SegmentDoc {
+                hashes: Hashes {
+                    site: (id as u64).into(),
+                    title: (id as u64).into(),
+                    url: (id as u64).into(),
+                    url_without_tld: (id as u64).into(),
+                    simhash: id as u64,
+                },
+                id,
+                segment: 0,
+                score: Score { total: 1.0 },
+                host_centrality,
+            })
+            .collect::<Vec<_>>();
+
+        for docs in [docs.clone(), docs.into_iter().rev().collect()] {
+            let mut collector = BucketCollector::new(10, CollectorConfig::default());
+
+            for doc in docs {
+                collector.insert(doc);
+            }
+
+            let res: Vec<DocId> = collector
+                .into_sorted_vec(true)
+                .into_iter()
+                .map(|doc| doc.id)
+                .collect();
+
+            assert_eq!(res, vec![7, 5, 10]);
+        }
+    }
+
+    #[test]
+    fn duplicate_penalty() {
+        let docs = [
+            (1, 0x0F0F_0F0F_0F0F_0F0F, 5.0),
+            (2, 0x0F0F_0F0F_0F0F_0F0E, 4.9),
+            (3, 0xF0F0_F0F0_F0F0_F0F0, 3.0),
+            (4, 0x00FF_00FF_00FF_00FF, 1.0),
+        ]
+        .into_iter()
+        .map(|(id, simhash, score)| SegmentDoc {
+            hashes: Hashes {
+                site: (id as u64).into(),
+                title: (id as u64).into(),
+                url: (id as u64).into(),
+                url_without_tld: (id as u64).into(),
+                simhash,
+            },
+            id,
+            segment: 0,
+            score: Score { total: score },
+            host_centrality: 0.0,
+        })
+        .collect::<Vec<_>>();
+
+        let ranked = |mut collector: BucketCollector<SegmentDoc>| -> Vec<DocId> {
+            for doc in docs.clone() {
+                collector.insert(doc);
+            }
+
+            collector
+                .into_sorted_vec(true)
+                .into_iter()
+                .map(|doc| doc.id)
+                .collect()
+        };
+
+        // without a penalty the near-duplicate is moved to the end
+        assert_eq!(
+            ranked(BucketCollector::new(
+                10,
+                CollectorConfig {
+                    duplicate_penalty: None,
+                    ..Default::default()
+                }
+            )),
+            vec![1, 3, 4, 2]
+        );
+
+        // with a penalty it is only demoted below the results it no longer outscores
+        assert_eq!(
+            ranked(BucketCollector::new(10, CollectorConfig::default())),
+            vec![1, 3, 2, 4]
+        );
+    }
 }
@@ -36,6 +36,14 @@ impl Collector {
     pub fn max_docs_considered() -> usize {
         250_000
     }
+
+    pub fn seen_multiplier() -> f64 {
+        0.8
+    }
+
+    pub fn duplicate_penalty() -> Option<f64> {
+        Some(1.0)
+    }
 }
 
 pub struct Api;
@@ -52,6 +60,50 @@ impl Api {
     pub fn max_concurrent_searches() -> Option<usize> {
         None
     }
+
+    pub fn max_search_timeout_ms() -> u64 {
+        5_000
+    }
+
+    pub fn bang_prefixes() -> Vec<char> {
+        crate::bangs::BANG_PREFIXES.to_vec()
+    }
+}
+
+pub struct RankingLog;
+
+impl RankingLog {
+    pub fn sample_rate() -> u64 {
+        1
+    }
+}
+
+pub struct QueryLog;
+
+impl QueryLog {
+    pub fn min_frequency() -> f64 {
+        10.0
+    }
+
+    pub fn half_life_hours() -> u64 {
+        7 * 24
+    }
+
+    pub fn compaction_interval_secs() -> u64 {
+        60 * 60
+    }
+}
+
+pub struct OpticLimits;
+
+impl OpticLimits {
+    pub fn max_rules() -> usize {
+        1_000
+    }
+
+    pub fn max_complexity() -> usize {
+        10_000
+    }
 }
 
 pub struct Snippet;
@@ -86,6 +138,18 @@ impl Snippet {
     }
 }
 
+pub struct SignalCache;
+
+impl SignalCache {
+    pub fn fetch_time_ms() -> usize {
+        1000
+    }
+
+    pub fn freshness_horizon_hours() -> usize {
+        3 * 365 * 24
+    }
+}
+
 pub struct Crawler;
 
 impl Crawler {
@@ -141,6 +205,10 @@ impl SearchQuery {
         false
     }
 
+    pub fn return_features() -> bool {
+        false
+    }
+
     pub fn safe_search() -> bool {
         false
     }
@@ -148,6 +216,18 @@ impl SearchQuery {
     pub fn count_results() -> bool {
         false
     }
+
+    pub fn show_filtered() -> bool {
+        false
+    }
+
+    pub fn debug_optic() -> bool {
+        false
+    }
+
+    pub fn normalize_scores() -> bool {
+        false
+    }
 }
 
 pub struct Correction;
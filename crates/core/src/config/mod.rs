@@ -19,10 +19,13 @@ pub mod defaults;
 use super::Result;
 use crate::distributed::member::ShardId;
 use crate::feed::scheduler::SplitId;
+use crate::ranking::bm25::{Bm25Params, Bm25VariantConfig};
 use serde::{Deserialize, Serialize};
 use std::fs::File;
 use std::io::{self, BufRead};
 use std::net::SocketAddr;
+use std::time::Duration;
+use whatlang::Lang;
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct IndexingLocalConfig {
@@ -167,6 +170,15 @@ pub struct CollectorConfig {
 
     #[serde(default = "defaults::Collector::max_docs_considered")]
     pub max_docs_considered: usize,
+
+    /// Multiplier of the final score of results the user has already seen.
+    #[serde(default = "defaults::Collector::seen_multiplier")]
+    pub seen_multiplier: f64,
+
+    /// Near-duplicates of higher ranked results get their score divided by
+    /// `1 + duplicate_penalty`. Without a penalty they are moved to the end of the results.
+    #[serde(default = "defaults::Collector::duplicate_penalty")]
+    pub duplicate_penalty: Option<f64>,
 }
 
 impl Default for CollectorConfig {
@@ -177,6 +189,8 @@ impl Default for CollectorConfig {
             url_penalty: defaults::Collector::url_penalty(),
             url_without_tld_penalty: defaults::Collector::url_without_tld_penalty(),
             max_docs_considered: defaults::Collector::max_docs_considered(),
+            seen_multiplier: defaults::Collector::seen_multiplier(),
+            duplicate_penalty: defaults::Collector::duplicate_penalty(),
         }
     }
 }
@@ -212,11 +226,17 @@ pub struct ApiConfig {
     pub queries_csv_path: String,
     pub host: SocketAddr,
     pub prometheus_host: SocketAddr,
+    /// Address to serve the gRPC search api on. The gRPC api is disabled if not set
+    /// or if stract is built without the `grpc` feature.
+    pub grpc_host: Option<SocketAddr>,
     pub crossencoder_model_path: Option<String>,
     pub lambda_model_path: Option<String>,
     pub dual_encoder_model_path: Option<String>,
     pub spell_checker_path: Option<String>,
     pub bangs_path: String,
+    /// Characters that start a bang in the query, e.g. `!` in `!gh stract`.
+    #[serde(default = "defaults::Api::bang_prefixes")]
+    pub bang_prefixes: Vec<char>,
     pub query_store_db_host: Option<String>,
     pub cluster_id: String,
     pub gossip_seed_nodes: Option<Vec<SocketAddr>>,
@@ -237,6 +257,90 @@ pub struct ApiConfig {
 
     #[serde(default = "defaults::Api::max_concurrent_searches")]
     pub max_concurrent_searches: Option<usize>,
+
+    /// Upper bound on the `timeout_ms` a search request can ask for.
+    #[serde(default = "defaults::Api::max_search_timeout_ms")]
+    pub max_search_timeout_ms: u64,
+
+    pub ranking_log: Option<RankingLogConfig>,
+
+    #[serde(default)]
+    pub optic_limits: OpticLimitsConfig,
+
+    /// Build autosuggest from the searched queries instead of `queries_csv_path`.
+    pub query_log: Option<QueryLogConfig>,
+
+    /// Token that must be sent as `Authorization: Bearer <token>` to use the
+    /// admin endpoints. The admin endpoints are disabled if not set.
+    pub admin_token: Option<String>,
+
+    /// Assign clients to the linear models of the search servers. Searches that don't
+    /// select a model by name use the default model if not set.
+    pub model_buckets: Option<ModelBucketsConfig>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ModelBucketsConfig {
+    /// Key of the hash that assigns a client to a model. Without the key,
+    /// the model of a client can't be linked back to its ip address.
+    pub secret: String,
+
+    /// Number of linear models registered on the search servers.
+    pub num_models: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OpticLimitsConfig {
+    /// Maximum number of rules in an optic that affect the ranking.
+    #[serde(default = "defaults::OpticLimits::max_rules")]
+    pub max_rules: usize,
+
+    /// Maximum number of terms, wildcards and anchors across all matchers of the ranking rules.
+    #[serde(default = "defaults::OpticLimits::max_complexity")]
+    pub max_complexity: usize,
+}
+
+impl Default for OpticLimitsConfig {
+    fn default() -> Self {
+        Self {
+            max_rules: defaults::OpticLimits::max_rules(),
+            max_complexity: defaults::OpticLimits::max_complexity(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RankingLogConfig {
+    /// File the ranking decisions are appended to as json lines.
+    pub path: String,
+
+    /// Log 1 in `sample_rate` queries.
+    #[serde(default = "defaults::RankingLog::sample_rate")]
+    pub sample_rate: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct QueryLogConfig {
+    /// File the searched queries are appended to.
+    pub path: String,
+
+    /// Queries with a decayed count below this are never suggested.
+    #[serde(default = "defaults::QueryLog::min_frequency")]
+    pub min_frequency: f64,
+
+    /// Time it takes for the count of a query to be halved.
+    #[serde(default = "defaults::QueryLog::half_life_hours")]
+    pub half_life_hours: u64,
+
+    /// How often the log is compacted and the suggestions are rebuilt.
+    #[serde(default = "defaults::QueryLog::compaction_interval_secs")]
+    pub compaction_interval_secs: u64,
+}
+
+impl QueryLogConfig {
+    pub fn half_life(&self) -> Duration {
+        Duration::from_secs(self.half_life_hours * 60 * 60)
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -279,6 +383,29 @@ impl Default for SnippetConfig {
     }
 }
 
+/// Size of the lookup tables used to score the fetch time and freshness of pages.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct SignalCacheConfig {
+    /// Fetch times up to this many milliseconds are looked up in the table.
+    /// Slower fetch times are scored by log2 sized buckets.
+    #[serde(default = "defaults::SignalCache::fetch_time_ms")]
+    pub fetch_time_ms: usize,
+
+    /// Number of hours since the last update that pages get a freshness score for.
+    /// Pages updated longer ago get a freshness score of 0.
+    #[serde(default = "defaults::SignalCache::freshness_horizon_hours")]
+    pub freshness_horizon_hours: usize,
+}
+
+impl Default for SignalCacheConfig {
+    fn default() -> Self {
+        Self {
+            fetch_time_ms: defaults::SignalCache::fetch_time_ms(),
+            freshness_horizon_hours: defaults::SignalCache::freshness_horizon_hours(),
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct SearchServerConfig {
     pub cluster_id: String,
@@ -292,6 +419,47 @@ pub struct SearchServerConfig {
     pub dual_encoder_model_path: Option<String>,
     pub host: SocketAddr,
 
+    /// Paths of additional linear models by name. Searches select one of these
+    /// models by name or by a sticky hash of the client, so the models can be compared.
+    /// Searches that select an unknown model use the model at `linear_model_path`.
+    #[serde(default)]
+    pub linear_models: HashMap<String, String>,
+
+    /// Query centrality given to hosts that are not in the webgraph.
+    /// If not set, these hosts get no query centrality.
+    pub query_centrality_fallback: Option<f64>,
+
+    /// Languages whose stopwords are removed from the query terms used for ranking.
+    /// Queries consisting only of stopwords are ranked using all their terms.
+    #[serde(default)]
+    pub ranking_stopwords: Vec<Lang>,
+
+    /// Number of (query, host) pairs whose host signals are cached across requests.
+    /// The cache is disabled if not set.
+    pub host_signal_cache_size: Option<usize>,
+
+    /// Number of optics whose compiled ranking rules are cached across requests.
+    /// The cache is disabled if not set.
+    pub optic_rule_cache_size: Option<usize>,
+
+    /// Reputation between 0.0 and 1.0 of top level domains, e.g. `xyz = 0.2`.
+    /// TLDs that are not in the table get a neutral reputation.
+    #[serde(default)]
+    pub tld_reputation: HashMap<String, f64>,
+
+    /// The k1 and b parameters of BM25, e.g. `{ k1 = 1.2, b = 0.75 }`.
+    /// Parameters that are not set use the defaults of tantivy.
+    #[serde(default)]
+    pub bm25: Bm25Params,
+
+    /// The BM25 formula used to score the text fields, e.g. `{ plus = 1.0 }` or
+    /// `{ field_weighted = { title = 2.0 } }`. Defaults to the classic BM25.
+    #[serde(default)]
+    pub bm25_variant: Bm25VariantConfig,
+
+    #[serde(default)]
+    pub signal_cache: SignalCacheConfig,
+
     #[serde(default)]
     pub collector: CollectorConfig,
 
@@ -27,6 +27,10 @@ impl ShardId {
     pub fn new(id: u64) -> Self {
         Self(id)
     }
+
+    pub fn as_u64(&self) -> u64 {
+        self.0
+    }
 }
 
 impl From<u64> for ShardId {
@@ -20,7 +20,7 @@ use anyhow::Result;
 use tokio::net::TcpListener;
 
 use crate::{
-    api::{metrics_router, router, user_count, Counters},
+    api::{metrics_router, router, state, user_count, Counters},
     config,
     metrics::Label,
 };
@@ -78,7 +78,30 @@ pub async fn run(config: config::ApiConfig) -> Result<()> {
         daily_active_users,
     };
 
-    let app = router(&config, counters).await?;
+    let state = state(&config, counters).await?;
+
+    if let Some(addr) = config.grpc_host {
+        #[cfg(feature = "grpc")]
+        {
+            tracing::info!("grpc server listening on {}", addr);
+            let service =
+                crate::api::grpc::SearchService::new(std::sync::Arc::clone(&state.searcher));
+
+            tokio::spawn(async move {
+                if let Err(err) = service.serve(addr).await {
+                    tracing::error!("grpc server failed: {:?}", err);
+                }
+            });
+        }
+
+        #[cfg(not(feature = "grpc"))]
+        tracing::warn!(
+            "grpc_host is set to {} but stract is built without the grpc feature",
+            addr
+        );
+    }
+
+    let app = router(state);
     let metrics_app = metrics_router(registry);
 
     let addr = config.host;
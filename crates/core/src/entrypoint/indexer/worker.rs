@@ -16,7 +16,6 @@
 
 use chrono::Utc;
 use std::path::Path;
-use url::Url;
 
 use tracing::debug;
 
@@ -233,41 +232,46 @@ impl IndexingWorker {
         Ok(())
     }
 
-    fn backlink_labels(&self, page: &Url) -> Vec<String> {
-        self.page_webgraph
-            .as_ref()
-            .map(|webgraph| {
-                webgraph
-                    .raw_ingoing_edges_with_labels(&Node::from(page).id())
-                    .into_iter()
-                    .map(|edge| edge.label)
-                    .filter(|label| !label.is_empty())
-                    .filter(|label| {
-                        let label = label.to_lowercase();
-                        let stopwords = [
-                            "click",
-                            "click here",
-                            "here",
-                            "link",
-                            "website",
-                            "webpage",
-                            "page",
-                            "site",
-                            "url",
-                            "web",
-                            "visit",
-                            "more",
-                            "info",
-                            "information",
-                            "read",
-                            "read more",
-                        ];
-
-                        !stopwords.contains(&label.as_str())
-                    })
-                    .collect()
+    /// The number of inbound links and their labels are both derived from the same
+    /// ingoing edges, so they are only fetched once.
+    fn set_backlinks(&self, page: &mut Webpage) {
+        let Some(webgraph) = self.page_webgraph.as_ref() else {
+            page.backlink_labels = Vec::new();
+            page.num_inbound_links = 0;
+            return;
+        };
+
+        let edges = webgraph.raw_ingoing_edges_with_labels(&Node::from(page.html.url()).id());
+
+        page.num_inbound_links = edges.len() as u64;
+        page.backlink_labels = edges
+            .into_iter()
+            .map(|edge| edge.label)
+            .filter(|label| !label.is_empty())
+            .filter(|label| {
+                let label = label.to_lowercase();
+                let stopwords = [
+                    "click",
+                    "click here",
+                    "here",
+                    "link",
+                    "website",
+                    "webpage",
+                    "page",
+                    "site",
+                    "url",
+                    "web",
+                    "visit",
+                    "more",
+                    "info",
+                    "information",
+                    "read",
+                    "read more",
+                ];
+
+                !stopwords.contains(&label.as_str())
             })
-            .unwrap_or_default()
+            .collect();
     }
 
     fn set_page_centralities(&self, page: &mut Webpage) {
@@ -389,7 +393,7 @@ impl IndexingWorker {
                 continue;
             }
 
-            prepared.backlink_labels = self.backlink_labels(prepared.html.url());
+            self.set_backlinks(&mut prepared);
 
             self.set_page_centralities(&mut prepared);
             self.set_dmoz_description(&mut prepared);
@@ -405,6 +409,8 @@ impl IndexingWorker {
                 host_centrality: prepared.host_centrality,
                 host_centrality_rank: prepared.host_centrality_rank,
                 fetch_time_ms: page.fetch_time_ms,
+                num_inbound_links: prepared.num_inbound_links,
+                byte_size: Some(page.body.len() as u64),
                 pre_computed_score: 0.0,
                 node_id: prepared.node_id,
                 dmoz_description: prepared.dmoz_description,
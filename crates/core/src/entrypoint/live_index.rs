@@ -27,7 +27,6 @@ use crate::{
     inverted_index,
     kv::rocksdb_store::RocksDbStore,
     live_index::{Index, IndexManager},
-    ranking::inbound_similarity::InboundSimilarity,
     searcher::{InitialWebsiteResult, LocalSearcher},
     sonic_service,
     webgraph::WebgraphBuilder,
@@ -48,14 +47,16 @@ pub struct SearchService {
 
 impl SearchService {
     async fn new(config: LiveIndexConfig) -> Result<Self> {
-        let inbound_similarity = InboundSimilarity::open(
-            Path::new(&config.host_centrality_store_path).join("inbound_similarity"),
-        )?;
-
         let manager = IndexManager::new(config.clone())?;
+        #[cfg_attr(not(feature = "webgraph"), allow(unused_mut))]
         let mut local_searcher = LocalSearcher::new(manager.index());
 
-        local_searcher.set_inbound_similarity(inbound_similarity);
+        #[cfg(feature = "webgraph")]
+        local_searcher.set_inbound_similarity(
+            crate::ranking::inbound_similarity::InboundSimilarity::open(
+                Path::new(&config.host_centrality_store_path).join("inbound_similarity"),
+            )?,
+        );
 
         tokio::task::spawn(manager.run());
 
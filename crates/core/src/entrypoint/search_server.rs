@@ -14,12 +14,16 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use std::{collections::HashMap, path::Path};
+use std::collections::HashMap;
+#[cfg(feature = "webgraph")]
+use std::path::Path;
 
 use serde::{Deserialize, Serialize};
 use tracing::info;
 use url::Url;
 
+#[cfg(feature = "webgraph")]
+use crate::ranking::inbound_similarity::InboundSimilarity;
 use crate::{
     config,
     distributed::{
@@ -31,10 +35,13 @@ use crate::{
     inverted_index::{self, RetrievedWebpage},
     models::dual_encoder::DualEncoder,
     ranking::{
-        inbound_similarity::InboundSimilarity,
-        models::{lambdamart::LambdaMART, linear::LinearRegression},
+        models::{lambdamart::LambdaMART, linear::LinearRegression, registry::ModelRegistry},
+        tld_reputation::TldReputation,
+    },
+    schema::{text_field::TextField, TextFieldEnum},
+    searcher::{
+        correction::TermFrequencies, InitialWebsiteResult, LocalSearcher, SearchQuery, TermStat,
     },
-    searcher::{InitialWebsiteResult, LocalSearcher, SearchQuery},
     sonic_service, Result,
 };
 
@@ -45,6 +52,8 @@ sonic_service!(
         Search,
         GetWebpage,
         GetHomepageDescriptions,
+        GetTermFrequencies,
+        GetTopTerms,
     ]
 );
 
@@ -57,21 +66,46 @@ pub struct SearchService {
 
 impl SearchService {
     async fn new(config: config::SearchServerConfig) -> Result<Self> {
-        let centrality_store = config
-            .host_centrality_store_path
-            .map(|p| InboundSimilarity::open(Path::new(&p).join("inbound_similarity")).unwrap());
         let search_index = Index::open(config.index_path)?;
 
         let mut local_searcher = LocalSearcher::new(search_index);
 
-        if let Some(centrality_store) = centrality_store {
-            local_searcher.set_inbound_similarity(centrality_store);
+        #[cfg(feature = "webgraph")]
+        {
+            if let Some(path) = &config.host_centrality_store_path {
+                local_searcher.set_inbound_similarity(
+                    InboundSimilarity::open(Path::new(path).join("inbound_similarity")).unwrap(),
+                );
+            }
+
+            local_searcher.set_query_centrality_fallback(config.query_centrality_fallback);
+
+            if let Some(size) = config.host_signal_cache_size {
+                local_searcher.set_host_signal_cache(size);
+            }
+        }
+
+        #[cfg(not(feature = "webgraph"))]
+        if config.host_centrality_store_path.is_some() {
+            tracing::warn!(
+                "host_centrality_store_path is set but stract is built without the webgraph feature"
+            );
         }
 
         if let Some(model_path) = config.linear_model_path {
             local_searcher.set_linear_model(LinearRegression::open(model_path)?);
         }
 
+        if !config.linear_models.is_empty() {
+            let mut registry = ModelRegistry::new();
+
+            for (name, model_path) in config.linear_models {
+                registry.insert(name, LinearRegression::open(model_path)?);
+            }
+
+            local_searcher.set_model_registry(registry);
+        }
+
         if let Some(model_path) = config.lambda_model_path {
             local_searcher.set_lambda_model(LambdaMART::open(model_path)?);
         }
@@ -81,6 +115,19 @@ impl SearchService {
         }
 
         local_searcher.set_collector_config(config.collector);
+        local_searcher.set_ranking_stopwords(config.ranking_stopwords);
+
+        if let Some(size) = config.optic_rule_cache_size {
+            local_searcher.set_optic_rule_cache(size);
+        }
+
+        if !config.tld_reputation.is_empty() {
+            local_searcher.set_tld_reputation(TldReputation::new(config.tld_reputation));
+        }
+
+        local_searcher.set_bm25_params(config.bm25);
+        local_searcher.set_bm25_variant(config.bm25_variant.try_into()?);
+        local_searcher.set_signal_cache_config(config.signal_cache);
         local_searcher.set_snippet_config(config.snippet);
 
         let cluster_handle = Cluster::join(
@@ -161,6 +208,36 @@ impl sonic::service::Message<SearchService> for GetHomepageDescriptions {
     }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetTermFrequencies {
+    pub query: String,
+}
+impl sonic::service::Message<SearchService> for GetTermFrequencies {
+    type Response = Option<TermFrequencies>;
+    async fn handle(self, server: &SearchService) -> Self::Response {
+        server.local_searcher.term_frequencies(&self.query).ok()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetTopTerms {
+    /// Name of the text field, e.g. `body`.
+    pub field: String,
+    pub prefix: String,
+    pub limit: usize,
+}
+impl sonic::service::Message<SearchService> for GetTopTerms {
+    type Response = Option<Vec<TermStat>>;
+    async fn handle(self, server: &SearchService) -> Self::Response {
+        let field = TextFieldEnum::all().find(|field| field.name() == self.field)?;
+
+        server
+            .local_searcher
+            .top_terms(field, &self.prefix, self.limit)
+            .ok()
+    }
+}
+
 pub async fn run(config: config::SearchServerConfig) -> Result<()> {
     let addr = config.host;
     let server = SearchService::new(config).await?.bind(addr).await.unwrap();
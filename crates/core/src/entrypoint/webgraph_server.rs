@@ -33,7 +33,7 @@ use crate::distributed::sonic::service::Message;
 use crate::ranking::inbound_similarity::InboundSimilarity;
 use crate::searcher::DistributedSearcher;
 use crate::searcher::SearchClient;
-use crate::similar_hosts::SimilarHostsFinder;
+use crate::similar_hosts::{ScoredNode, SimilarHostsFinder};
 use crate::sonic_service;
 use crate::webgraph::Compression;
 use crate::webgraph::FullEdge;
@@ -61,7 +61,13 @@ pub struct WebGraphService {
 
 sonic_service!(
     WebGraphService,
-    [SimilarHosts, Knows, IngoingLinks, OutgoingLinks]
+    [
+        SimilarHosts,
+        RelatedHosts,
+        Knows,
+        IngoingLinks,
+        OutgoingLinks
+    ]
 );
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -90,32 +96,54 @@ impl Message<WebGraphService> for SimilarHosts {
             .unwrap()
             .find_similar_hosts(sites, self.top_n);
 
-        let urls = similar_hosts
-            .iter()
-            .filter_map(|s| Url::parse(&("http://".to_string() + s.node.as_str())).ok())
-            .collect_vec();
-
-        let descriptions = server.searcher.get_homepage_descriptions(&urls).await;
-
-        let similar_hosts = similar_hosts
-            .into_iter()
-            .map(|site| {
-                let description = Url::parse(&("http://".to_string() + site.node.as_str()))
-                    .ok()
-                    .and_then(|url| descriptions.get(&url).cloned());
-
-                ScoredHost {
-                    host: site.node.as_str().to_string(),
-                    score: site.score,
-                    description,
-                }
-            })
-            .collect_vec();
-
-        similar_hosts
+        with_descriptions(server, similar_hosts).await
     }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelatedHosts {
+    pub host: String,
+    pub limit: usize,
+}
+
+impl Message<WebGraphService> for RelatedHosts {
+    /// `None` if the host is not in the graph.
+    type Response = Option<Vec<ScoredHost>>;
+
+    async fn handle(self, server: &WebGraphService) -> Self::Response {
+        let related_hosts = server
+            .similar_hosts_finder
+            .as_ref()?
+            .related_hosts(&self.host, self.limit)?;
+
+        Some(with_descriptions(server, related_hosts).await)
+    }
+}
+
+async fn with_descriptions(server: &WebGraphService, hosts: Vec<ScoredNode>) -> Vec<ScoredHost> {
+    let urls = hosts
+        .iter()
+        .filter_map(|s| Url::parse(&("http://".to_string() + s.node.as_str())).ok())
+        .collect_vec();
+
+    let descriptions = server.searcher.get_homepage_descriptions(&urls).await;
+
+    hosts
+        .into_iter()
+        .map(|site| {
+            let description = Url::parse(&("http://".to_string() + site.node.as_str()))
+                .ok()
+                .and_then(|url| descriptions.get(&url).cloned());
+
+            ScoredHost {
+                host: site.node.as_str().to_string(),
+                score: site.score,
+                description,
+            }
+        })
+        .collect_vec()
+}
+
 impl Message<WebGraphService> for Knows {
     type Response = Option<Node>;
 
@@ -67,6 +67,10 @@ where
         self.len
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
     pub fn get(&self, key: K) -> Option<&V> {
         let key = key.into_usize();
         if key >= self.inner.len() {
@@ -20,7 +20,7 @@ use tantivy::{columnar::ColumnValues, DocId, SegmentId};
 
 use crate::{
     enum_map::EnumMap,
-    schema::{fast_field::FastField, DataType, FastFieldEnum, Field},
+    schema::{fast_field::FastField, DataType, FastFieldEnum, Field, FLOAT_SCALING},
 };
 
 #[derive(Default, Clone)]
@@ -87,6 +87,42 @@ struct AllReaders {
     bytes: EnumMap<FastFieldEnum, tantivy::columnar::BytesColumn>,
 }
 
+impl AllReaders {
+    fn get_u64(&self, doc: DocId, field: FastFieldEnum) -> Option<u64> {
+        let values = &self.u64s.get(field)?.values;
+
+        if doc >= values.num_vals() {
+            return None;
+        }
+
+        Some(values.get_val(doc))
+    }
+
+    fn get_bytes(&self, doc: DocId, field: FastFieldEnum) -> Option<Vec<u8>> {
+        let reader = self.bytes.get(field)?;
+        let ords = &reader.ords().values;
+
+        if doc >= ords.num_vals() {
+            return None;
+        }
+
+        let ord = ords.get_val(doc);
+
+        if ord >= reader.num_terms() as u64 {
+            return None;
+        }
+
+        let mut bytes = Vec::new();
+        reader.ord_to_bytes(ord, &mut bytes).ok()?;
+
+        if bytes.is_empty() {
+            None
+        } else {
+            Some(bytes)
+        }
+    }
+}
+
 pub enum Value {
     U64(u64),
     Bytes(Vec<u8>),
@@ -149,32 +185,8 @@ pub struct FieldReader<'a> {
 impl<'a> FieldReader<'a> {
     pub fn get(&self, field: FastFieldEnum) -> Option<Value> {
         match field.data_type() {
-            DataType::U64 => Some(
-                self.readers
-                    .u64s
-                    .get(field)?
-                    .values
-                    .get_val(self.doc)
-                    .into(),
-            ),
-
-            DataType::Bytes => {
-                let reader = self.readers.bytes.get(field)?;
-                let ord = reader.ords().values.get_val(self.doc);
-
-                if ord > reader.num_terms() as u64 || reader.num_terms() == 0 {
-                    return None;
-                }
-
-                let mut bytes = Vec::new();
-                reader.ord_to_bytes(ord, &mut bytes).unwrap();
-
-                if bytes.is_empty() {
-                    None
-                } else {
-                    Some(bytes.into())
-                }
-            }
+            DataType::U64 => self.readers.get_u64(self.doc, field).map(Value::from),
+            DataType::Bytes => self.readers.get_bytes(self.doc, field).map(Value::from),
         }
     }
 }
@@ -190,4 +202,115 @@ impl SegmentReader {
             doc,
         }
     }
+
+    /// The value of a `u64` field for `doc`. Returns `None` if the field is not a `u64` field,
+    /// has no values in this segment or if `doc` is not in the segment.
+    pub fn get_u64(&self, doc: DocId, field: impl Into<FastFieldEnum>) -> Option<u64> {
+        let field = field.into();
+
+        match field.data_type() {
+            DataType::U64 => self.field_readers.get_u64(doc, field),
+            DataType::Bytes => None,
+        }
+    }
+
+    /// The value of a float field for `doc`. Floats are stored as `u64`s
+    /// scaled by [`FLOAT_SCALING`], so this should only be used for fields
+    /// that are indexed that way. Returns `None` in the same cases as [`SegmentReader::get_u64`].
+    pub fn get_f64(&self, doc: DocId, field: impl Into<FastFieldEnum>) -> Option<f64> {
+        self.get_u64(doc, field)
+            .map(|val| val as f64 / FLOAT_SCALING as f64)
+    }
+
+    /// The value of a bytes field for `doc`. Returns `None` if the field is not a bytes field,
+    /// the document has no (or an empty) value or if `doc` is not in the segment.
+    pub fn get_bytes(&self, doc: DocId, field: impl Into<FastFieldEnum>) -> Option<Vec<u8>> {
+        let field = field.into();
+
+        match field.data_type() {
+            DataType::Bytes => self.field_readers.get_bytes(doc, field),
+            DataType::U64 => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        schema::fast_field,
+        webpage::{Html, Webpage},
+    };
+
+    use super::*;
+
+    fn index_with_page() -> crate::inverted_index::InvertedIndex {
+        let mut index = crate::inverted_index::InvertedIndex::temporary().unwrap();
+
+        let mut webpage = Webpage::from(
+            Html::parse(
+                r#"
+                <html>
+                    <head>
+                        <title>Test website</title>
+                    </head>
+                    <body>
+                        example
+                    </body>
+                </html>
+            "#,
+                "https://www.example.com/",
+            )
+            .unwrap(),
+        );
+        webpage.host_centrality = 0.5;
+        webpage.num_inbound_links = 42;
+
+        index.insert(&webpage).unwrap();
+        index.commit().unwrap();
+
+        index
+    }
+
+    #[test]
+    fn typed_accessors() {
+        let index = index_with_page();
+        let ctx = index.local_search_ctx();
+        let segment_id = ctx.tv_searcher.segment_reader(0).segment_id();
+        let reader = ctx.fastfield_reader.get_segment(&segment_id);
+
+        assert_eq!(reader.get_u64(0, fast_field::NumInboundLinks), Some(42));
+        assert_eq!(reader.get_f64(0, fast_field::HostCentrality), Some(0.5));
+        assert_eq!(
+            reader.get_bytes(0, fast_field::Host),
+            Some(b"example.com".to_vec())
+        );
+    }
+
+    #[test]
+    fn typed_accessors_wrong_type() {
+        let index = index_with_page();
+        let ctx = index.local_search_ctx();
+        let segment_id = ctx.tv_searcher.segment_reader(0).segment_id();
+        let reader = ctx.fastfield_reader.get_segment(&segment_id);
+
+        assert_eq!(reader.get_u64(0, fast_field::Host), None);
+        assert_eq!(reader.get_f64(0, fast_field::Host), None);
+        assert_eq!(reader.get_bytes(0, fast_field::NumInboundLinks), None);
+    }
+
+    #[test]
+    fn typed_accessors_out_of_range() {
+        let index = index_with_page();
+        let ctx = index.local_search_ctx();
+        let segment_id = ctx.tv_searcher.segment_reader(0).segment_id();
+        let reader = ctx.fastfield_reader.get_segment(&segment_id);
+
+        assert_eq!(reader.get_u64(1, fast_field::NumInboundLinks), None);
+        assert_eq!(reader.get_f64(1_000, fast_field::HostCentrality), None);
+        assert_eq!(reader.get_bytes(1, fast_field::Host), None);
+        assert!(reader
+            .get_field_reader(1)
+            .get(fast_field::NumInboundLinks.into())
+            .is_none());
+    }
 }
@@ -47,23 +47,47 @@ use crate::fastfield_reader::FastFieldReader;
 use crate::ranking::initial::Score;
 
 use crate::schema::text_field::TextField;
-use crate::schema::{fast_field, text_field, FastFieldEnum, Field, TextFieldEnum};
+use crate::schema::{self, fast_field, text_field, FastFieldEnum, Field, TextFieldEnum};
 use crate::snippet::TextSnippet;
 use crate::tokenizer::{
-    BigramTokenizer, Identity, JsonField, SiteOperatorUrlTokenizer, TrigramTokenizer,
+    BigramTokenizer, CjkBigramTokenizer, Identity, JsonField, SiteOperatorUrlTokenizer,
+    TokenizerRegistry, TrigramTokenizer,
 };
 use crate::webpage::region::Region;
 
 use crate::webpage::schema_org;
 use crate::Result;
-use crate::{schema::create_schema, tokenizer::Tokenizer};
+use crate::{schema::create_schema_with_tokenizers, tokenizer::Tokenizer};
 use std::fs;
 use std::path::Path;
 use std::sync::Arc;
 
+const SCHEMA_VERSION_FILE: &str = "schema_version";
+
+fn read_schema_version(path: &Path) -> Result<u64> {
+    let path = path.join(SCHEMA_VERSION_FILE);
+
+    if !path.exists() {
+        // indexes created before the schema was versioned
+        return Ok(1);
+    }
+
+    Ok(fs::read_to_string(path)?.trim().parse()?)
+}
+
+fn write_schema_version(path: &Path) -> Result<()> {
+    fs::write(
+        path.join(SCHEMA_VERSION_FILE),
+        schema::SCHEMA_VERSION.to_string(),
+    )?;
+
+    Ok(())
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct InitialSearchResult {
     pub num_websites: Option<usize>,
+    pub discarded_count: Option<usize>,
     pub top_websites: Vec<WebpagePointer>,
 }
 
@@ -71,10 +95,11 @@ pub struct InitialSearchResult {
 pub struct WebpagePointer {
     pub score: Score,
     pub hashes: Hashes,
+    pub host_centrality: f64,
     pub address: DocAddress,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub struct DocAddress {
     pub segment: u32,
     pub doc_id: u32,
@@ -114,6 +139,9 @@ fn register_tokenizers(manager: &TokenizerManager) {
     let tokenizer = Tokenizer::Trigram(TrigramTokenizer::default());
     manager.register(tokenizer.as_str(), tokenizer);
 
+    let tokenizer = Tokenizer::CjkBigram(CjkBigramTokenizer::default());
+    manager.register(tokenizer.as_str(), tokenizer);
+
     let tokenizer = Tokenizer::SiteOperator(SiteOperatorUrlTokenizer);
     manager.register(tokenizer.as_str(), tokenizer);
 
@@ -133,11 +161,25 @@ pub struct InvertedIndex {
 
 impl InvertedIndex {
     pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
-        let schema = create_schema();
+        Self::open_with_tokenizers(path, &TokenizerRegistry::default())
+    }
 
+    /// Opens the index, creating it with the registered field tokenizers if it does not exist.
+    /// Existing indexes keep the tokenizers they were created with.
+    pub fn open_with_tokenizers<P: AsRef<Path>>(
+        path: P,
+        tokenizers: &TokenizerRegistry,
+    ) -> Result<Self> {
         let tantivy_index = if path.as_ref().exists() {
             let mmap_directory = MmapDirectory::open(&path)?;
-            tantivy::Index::open(mmap_directory)?
+            let tantivy_index = tantivy::Index::open(mmap_directory)?;
+
+            schema::check_compatibility(
+                &tantivy_index.schema(),
+                read_schema_version(path.as_ref())?,
+            )?;
+
+            tantivy_index
         } else {
             let index_settings = tantivy::IndexSettings {
                 sort_by_field: Some(tantivy::IndexSortByField {
@@ -150,11 +192,17 @@ impl InvertedIndex {
             };
 
             fs::create_dir_all(&path)?;
+            write_schema_version(path.as_ref())?;
             let mmap_directory = MmapDirectory::open(&path)?;
-            tantivy::Index::create(mmap_directory, schema.clone(), index_settings)?
+            tantivy::Index::create(
+                mmap_directory,
+                create_schema_with_tokenizers(tokenizers),
+                index_settings,
+            )?
         };
 
         register_tokenizers(tantivy_index.tokenizers());
+        let schema = tantivy_index.schema();
 
         let reader: IndexReader = tantivy_index.reader_builder().try_into()?;
 
@@ -1243,4 +1291,138 @@ mod tests {
         assert!(ranking_websites[0].title_embedding.is_some());
         assert!(ranking_websites[1].title_embedding.is_none());
     }
+
+    #[test]
+    fn open_index_missing_newer_field() {
+        let path = crate::gen_temp_path();
+        let missing = TextFieldEnum::from(text_field::TitleBigrams);
+
+        let old_schema = schema::build_schema(
+            Field::all().filter(|f| *f != Field::Text(missing)),
+            &TokenizerRegistry::default(),
+        );
+        let title = old_schema.get_field(text_field::Title.name()).unwrap();
+
+        {
+            fs::create_dir_all(&path).unwrap();
+            let tantivy_index = tantivy::Index::create(
+                MmapDirectory::open(&path).unwrap(),
+                old_schema,
+                tantivy::IndexSettings::default(),
+            )
+            .unwrap();
+            register_tokenizers(tantivy_index.tokenizers());
+
+            let mut writer: IndexWriter = tantivy_index
+                .writer_with_num_threads(1, 15_000_000)
+                .unwrap();
+            writer
+                .add_document(tantivy::doc!(title => "example website"))
+                .unwrap();
+            writer.commit().unwrap();
+        }
+
+        let index = InvertedIndex::open(&path).expect("index missing a field should open");
+        let ctx = index.local_search_ctx();
+        let query = Query::parse(
+            &ctx,
+            &SearchQuery {
+                query: "example website".to_string(),
+                ..Default::default()
+            },
+            &index,
+        )
+        .unwrap();
+
+        let mut computer = SignalComputer::new(Some(&query));
+        computer
+            .register_segment(
+                &ctx.tv_searcher,
+                ctx.tv_searcher.segment_reader(0),
+                &ctx.fastfield_reader,
+            )
+            .unwrap();
+
+        let mut segment_reader = computer.segment_reader().unwrap();
+        let text_fields = segment_reader.text_fields_mut();
+
+        assert!(text_fields.get(missing).is_none());
+        assert!(text_fields.get(text_field::Title.into()).is_some());
+    }
+
+    #[test]
+    fn reject_unsupported_schema_version() {
+        let path = crate::gen_temp_path();
+        drop(InvertedIndex::open(&path).unwrap());
+
+        fs::write(
+            path.join(SCHEMA_VERSION_FILE),
+            (schema::SCHEMA_VERSION + 1).to_string(),
+        )
+        .unwrap();
+
+        assert!(InvertedIndex::open(&path).is_err());
+    }
+
+    #[test]
+    fn registered_cjk_tokenizer() {
+        let tokenizers = TokenizerRegistry::default()
+            .with_tokenizer(text_field::Title, Tokenizer::CjkBigram(Default::default()))
+            .with_tokenizer(
+                text_field::AllBody,
+                Tokenizer::CjkBigram(Default::default()),
+            )
+            .with_tokenizer(
+                text_field::CleanBody,
+                Tokenizer::CjkBigram(Default::default()),
+            );
+
+        let mut index = InvertedIndex::open_with_tokenizers(crate::gen_temp_path(), &tokenizers)
+            .expect("Unable to open index");
+        index.prepare_writer().unwrap();
+
+        index
+            .insert(
+                &Webpage::test_parse(
+                    &format!(
+                        r#"
+                        <html>
+                            <head>
+                                <title>東京都庁</title>
+                            </head>
+                            <body>
+                                東京都庁舎は新宿区にあります {CONTENT}
+                            </body>
+                        </html>
+                    "#
+                    ),
+                    "https://www.example.com",
+                )
+                .unwrap(),
+            )
+            .expect("failed to insert webpage");
+        index.commit().expect("failed to commit index");
+
+        let ctx = index.local_search_ctx();
+        let query = Query::parse(
+            &ctx,
+            &SearchQuery {
+                query: "都庁".to_string(),
+                ..Default::default()
+            },
+            &index,
+        )
+        .expect("Failed to parse query");
+
+        let ranker = Ranker::new(
+            SignalComputer::new(Some(&query)),
+            ctx.fastfield_reader.clone(),
+            CollectorConfig::default(),
+        );
+
+        let result =
+            search(&index, &query, &ctx, ranker.collector(ctx.clone())).expect("Search failed");
+        assert_eq!(result.documents.len(), 1);
+        assert_eq!(result.documents[0].url, "https://www.example.com/");
+    }
 }
@@ -16,9 +16,11 @@
 
 use super::{DocAddress, InitialSearchResult, InvertedIndex, RetrievedWebpage, WebpagePointer};
 use itertools::Itertools;
+use std::collections::HashMap;
 use tantivy::collector::Count;
 
 use tantivy::schema::Value;
+use tantivy::tokenizer::Tokenizer as _;
 
 use tantivy::TantivyDocument;
 use url::Url;
@@ -31,8 +33,11 @@ use crate::query::shortcircuit::ShortCircuitQuery;
 use crate::query::Query;
 use crate::ranking::pipeline::RecallRankingWebpage;
 use crate::ranking::SignalComputer;
+use crate::schema::text_field::TextField;
 use crate::schema::{fast_field, text_field, FastFieldEnum, Field, TextFieldEnum};
 use crate::search_ctx::Ctx;
+use crate::searcher::correction::{self, TermFrequencies};
+use crate::searcher::TermStat;
 use crate::snippet;
 use crate::snippet::TextSnippet;
 use crate::webgraph::NodeID;
@@ -47,6 +52,11 @@ impl InvertedIndex {
         ctx: &Ctx,
         collector: MainCollector,
     ) -> Result<InitialSearchResult> {
+        let discarded_count = query
+            .discarded_query()
+            .map(|discarded| ctx.tv_searcher.search(discarded, &Count))
+            .transpose()?;
+
         if !query.count_results() {
             let mut query: Box<dyn tantivy::query::Query> = Box::new(query.clone());
 
@@ -59,6 +69,7 @@ impl InvertedIndex {
 
             return Ok(InitialSearchResult {
                 num_websites: None,
+                discarded_count,
                 top_websites: pointers,
             });
         }
@@ -68,6 +79,7 @@ impl InvertedIndex {
 
         Ok(InitialSearchResult {
             num_websites: Some(count),
+            discarded_count,
             top_websites: pointers,
         })
     }
@@ -280,4 +292,77 @@ impl InvertedIndex {
         res.pop()
             .map(|(_, doc)| self.retrieve_doc(doc.into(), &tv_searcher).unwrap())
     }
+
+    /// Tokenize `query` like the body field and find the number of documents
+    /// containing each token and their spelling candidates.
+    pub fn term_frequencies(&self, query: &str) -> Result<TermFrequencies> {
+        let tv_searcher = self.reader.searcher();
+        let field = tv_searcher
+            .schema()
+            .get_field(Field::Text(TextFieldEnum::from(text_field::CleanBody)).name())
+            .unwrap();
+
+        let mut tokens = Vec::new();
+        let mut tokenizer = text_field::CleanBody.query_tokenizer_for(tv_searcher.schema());
+        let mut token_stream = tokenizer.token_stream(query);
+        token_stream.process(&mut |token| {
+            tokens.push(token.text.clone());
+        });
+
+        let mut doc_freqs = HashMap::new();
+
+        for token in &tokens {
+            for term in std::iter::once(token.clone()).chain(correction::candidates(token)) {
+                if doc_freqs.contains_key(&term) {
+                    continue;
+                }
+
+                let freq = tv_searcher.doc_freq(&tantivy::Term::from_field_text(field, &term))?;
+
+                if freq > 0 {
+                    doc_freqs.insert(term, freq);
+                }
+            }
+        }
+
+        Ok(TermFrequencies { tokens, doc_freqs })
+    }
+
+    /// The `limit` terms of `field` that start with `prefix` and are found in the most
+    /// documents. The document frequencies are summed over all segments.
+    pub fn top_terms(
+        &self,
+        field: TextFieldEnum,
+        prefix: &str,
+        limit: usize,
+    ) -> Result<Vec<TermStat>> {
+        let tv_searcher = self.reader.searcher();
+        let tv_field = tv_searcher.schema().get_field(Field::Text(field).name())?;
+
+        let mut doc_freqs: HashMap<String, u64> = HashMap::new();
+
+        for segment_reader in tv_searcher.segment_readers() {
+            let inverted_index = segment_reader.inverted_index(tv_field)?;
+            let mut stream = inverted_index
+                .terms()
+                .range()
+                .ge(prefix.as_bytes())
+                .into_stream()?;
+
+            while stream.advance() {
+                let key = stream.key();
+
+                if !key.starts_with(prefix.as_bytes()) {
+                    break;
+                }
+
+                if let Ok(term) = std::str::from_utf8(key) {
+                    *doc_freqs.entry(term.to_string()).or_default() +=
+                        stream.value().doc_freq as u64;
+                }
+            }
+        }
+
+        Ok(TermStat::top(doc_freqs, limit))
+    }
 }
@@ -257,11 +257,12 @@ mod tests {
 
 #[macro_export]
 macro_rules! enum_dispatch_from_discriminant {
-    ($discenum:ident => $enum:ident, [$($disc:ident),*$(,)?]) => {
+    ($discenum:ident => $enum:ident, [$($(#[$meta:meta])* $disc:ident),*$(,)?]) => {
         impl From<$discenum> for $enum {
             fn from(value: $discenum) -> Self {
                 match value {
                     $(
+                    $(#[$meta])*
                     $discenum::$disc => $disc.into(),
                     )*
                 }
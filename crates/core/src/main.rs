@@ -18,6 +18,7 @@ use clap::{Parser, Subcommand};
 use serde::de::DeserializeOwned;
 use std::fs;
 use std::path::Path;
+use stract::bangs::Bangs;
 use stract::config;
 use stract::entrypoint::autosuggest_scrape::{self, Gl};
 
@@ -125,6 +126,12 @@ enum Commands {
     WebSpell {
         config_path: String,
     },
+
+    /// Compile a json list of bangs into a binary file that the api can load faster.
+    CompileBangs {
+        bangs_path: String,
+        output_path: String,
+    },
 }
 
 #[derive(Subcommand)]
@@ -400,6 +407,10 @@ fn main() -> Result<()> {
             let config: config::WebSpellConfig = load_toml_config(config_path);
             entrypoint::web_spell::run(config)?;
         }
+        Commands::CompileBangs {
+            bangs_path,
+            output_path,
+        } => Bangs::compile(bangs_path, output_path)?,
     }
 
     Ok(())
@@ -43,6 +43,10 @@ impl Counter {
     pub fn store(&self, val: u64) {
         self.0.store(val, Ordering::SeqCst);
     }
+
+    pub fn get(&self) -> u64 {
+        self.0.load(Ordering::SeqCst)
+    }
 }
 
 pub enum PrometheusMetric {
@@ -15,18 +15,21 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 use crate::{
+    collector::SearchCursor,
     inverted_index::InvertedIndex,
     query::parser::TermCompound,
-    ranking::SignalCoefficient,
+    ranking::{CoefficientProfile, SignalCoefficient, SignalEnum},
     schema::{text_field, Field},
     search_ctx::Ctx,
-    searcher::SearchQuery,
+    searcher::{Device, SearchQuery},
+    stopwords,
     webpage::{region::Region, safety_classifier},
     Result,
 };
-use optics::{HostRankings, Optic};
+use optics::{Action, HostRankings, Optic};
 use std::collections::HashMap;
 use tantivy::query::{BooleanQuery, Occur, QueryClone, TermQuery};
+use whatlang::Lang;
 
 mod const_query;
 pub mod intersection;
@@ -39,8 +42,9 @@ pub mod union;
 use parser::Term;
 
 use self::{
-    optic::AsMultipleTantivyQuery,
+    optic::{AsMultipleTantivyQuery, AsSearchableRule},
     parser::{CompoundAwareTerm, SimpleOrPhrase},
+    union::UnionQuery,
 };
 
 const MAX_SIMILAR_TERMS: usize = 10;
@@ -49,18 +53,25 @@ const MAX_SIMILAR_TERMS: usize = 10;
 pub struct Query {
     terms: Vec<Term>,
     simple_terms_text: Vec<String>,
+    ranking_terms: Vec<String>,
     tantivy_query: Box<BooleanQuery>,
+    discarded_query: Option<Box<BooleanQuery>>,
     host_rankings: HostRankings,
     offset: usize,
     region: Option<Region>,
     optics: Vec<Optic>,
     top_n: usize,
     count_results: bool,
+    cursor: Option<SearchCursor>,
+    disabled_signals: Vec<SignalEnum>,
+    lang: Option<Lang>,
+    device: Device,
+    profile: Option<CoefficientProfile>,
 }
 
 impl Query {
     pub fn parse(ctx: &Ctx, query: &SearchQuery, index: &InvertedIndex) -> Result<Query> {
-        let parsed_terms = parser::parse(&query.query)?;
+        let parsed_terms = parser::parse_with_bang_prefixes(&query.query, &query.bang_prefixes)?;
         let mut term_count = HashMap::new();
         let mut terms = Vec::new();
 
@@ -110,10 +121,42 @@ impl Query {
 
         let fields: Vec<tantivy::schema::Field> = schema.fields().map(|(field, _)| field).collect();
 
-        let mut queries: Vec<(Occur, Box<dyn tantivy::query::Query + 'static>)> = compound_terms
-            .iter()
-            .map(|term| term.as_tantivy_query(&fields))
-            .collect();
+        // consecutive site filters form a disjunction that binds tighter than
+        // the implicit conjunction between all other terms. Excluded sites are
+        // removed from the results after the included sites have been matched,
+        // so they don't split a disjunction.
+        let mut queries: Vec<(Occur, Box<dyn tantivy::query::Query + 'static>)> = Vec::new();
+        let mut sites = Vec::new();
+        let mut excluded_sites = Vec::new();
+
+        for term in &compound_terms {
+            match &term.term {
+                Term::Site(site) => {
+                    sites.push(site.as_str());
+                    continue;
+                }
+                Term::Not(subterm) if matches!(subterm.as_ref(), Term::Site(_)) => {
+                    excluded_sites.push(term);
+                    continue;
+                }
+                _ => {}
+            }
+
+            if !sites.is_empty() {
+                queries.push(Term::sites_as_tantivy_query(&sites, &schema, &fields));
+                sites.clear();
+            }
+
+            queries.push(term.as_tantivy_query(&schema, &fields, query.lang));
+        }
+
+        if !sites.is_empty() {
+            queries.push(Term::sites_as_tantivy_query(&sites, &schema, &fields));
+        }
+
+        for term in excluded_sites {
+            queries.push(term.as_tantivy_query(&schema, &fields, query.lang));
+        }
 
         if query.safe_search {
             let field = Field::Text(text_field::SafetyClassification.into());
@@ -154,6 +197,27 @@ impl Query {
             optics.push(optic.clone());
         }
 
+        let discarded_query = if query.show_filtered {
+            let discard_rules: Vec<_> = optics
+                .iter()
+                .flat_map(|optic| optic.rules.iter())
+                .filter(|rule| matches!(rule.action, Action::Discard))
+                .filter_map(|rule| rule.as_searchable_rule(&schema, &ctx.fastfield_reader))
+                .map(|(_, rule)| rule.query)
+                .collect();
+
+            if discard_rules.is_empty() {
+                None
+            } else {
+                Some(Box::new(BooleanQuery::new(vec![
+                    (Occur::Must, tantivy_query.box_clone()),
+                    (Occur::Must, UnionQuery::from(discard_rules).box_clone()),
+                ])))
+            }
+        } else {
+            None
+        };
+
         for optic in &optics {
             let mut subqueries = vec![(Occur::Must, tantivy_query.box_clone())];
             subqueries.append(&mut optic.as_multiple_tantivy(&schema, &ctx.fastfield_reader));
@@ -166,16 +230,38 @@ impl Query {
                 acc.merge_into(el.host_rankings.clone());
                 acc
             }),
+            ranking_terms: simple_terms_text.clone(),
             simple_terms_text,
             tantivy_query,
+            discarded_query,
             optics,
             offset: query.num_results * query.page,
             region: query.selected_region,
             top_n: query.num_results,
             count_results: query.count_results,
+            cursor: query.cursor,
+            disabled_signals: query.disabled_signals.clone(),
+            lang: query.lang,
+            device: query.device,
+            profile: query.profile,
         })
     }
 
+    /// Signals that should not be computed for the query.
+    pub fn disabled_signals(&self) -> &[SignalEnum] {
+        &self.disabled_signals
+    }
+
+    /// Language hint for the stemming of the query terms.
+    pub fn lang(&self) -> Option<Lang> {
+        self.lang
+    }
+
+    /// Device the search is made from.
+    pub fn device(&self) -> Device {
+        self.device
+    }
+
     pub fn count_results(&self) -> bool {
         self.count_results
     }
@@ -184,6 +270,49 @@ impl Query {
         &self.simple_terms_text
     }
 
+    /// The simple terms used for ranking. These are the same as [`Query::simple_terms`]
+    /// unless stopwords have been removed with [`Query::remove_stopwords`].
+    pub fn ranking_terms(&self) -> &[String] {
+        &self.ranking_terms
+    }
+
+    /// Remove the stopwords of `langs` from the ranking terms. Words in phrases are kept
+    /// as they are part of the exact match. If only stopwords would be left, all the
+    /// terms are kept.
+    pub fn remove_stopwords(&mut self, langs: &[Lang]) {
+        if langs.is_empty() {
+            return;
+        }
+
+        let is_stopword = |word: &str| {
+            let word = word.to_lowercase();
+            langs
+                .iter()
+                .filter_map(stopwords::get)
+                .any(|stopwords| stopwords.contains(&word))
+        };
+
+        let ranking_terms: Vec<String> = self
+            .terms
+            .iter()
+            .filter_map(|term| match term {
+                Term::SimpleOrPhrase(SimpleOrPhrase::Simple(_)) => {
+                    term.as_simple_text().filter(|text| !is_stopword(text))
+                }
+                _ => term.as_simple_text(),
+            })
+            .flat_map(|term| {
+                term.split_ascii_whitespace()
+                    .map(|s| s.to_string())
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+
+        if !ranking_terms.is_empty() {
+            self.ranking_terms = ranking_terms;
+        }
+    }
+
     pub fn terms(&self) -> &[Term] {
         &self.terms
     }
@@ -192,6 +321,12 @@ impl Query {
         &self.optics
     }
 
+    /// Query for the documents that match the terms of the query but are removed
+    /// by a `Discard` rule in the optics. Only set when `show_filtered` is requested.
+    pub fn discarded_query(&self) -> Option<&BooleanQuery> {
+        self.discarded_query.as_deref()
+    }
+
     pub fn is_empty(&self) -> bool {
         self.terms.is_empty()
     }
@@ -204,6 +339,10 @@ impl Query {
         self.offset
     }
 
+    pub fn cursor(&self) -> Option<&SearchCursor> {
+        self.cursor.as_ref()
+    }
+
     pub fn region(&self) -> Option<&Region> {
         self.region.as_ref()
     }
@@ -212,20 +351,27 @@ impl Query {
         &self.host_rankings
     }
 
+    /// The coefficients of the profile overridden by the coefficients of the optics.
     pub fn signal_coefficients(&self) -> Option<SignalCoefficient> {
-        if self.optics.is_empty() {
+        if self.optics.is_empty() && self.profile.is_none() {
             return None;
         }
 
-        Some(
-            self.optics
-                .iter()
-                .fold(SignalCoefficient::default(), |mut acc, optic| {
-                    let coeffs = SignalCoefficient::from_optic(optic);
-                    acc.merge_into(coeffs);
-                    acc
-                }),
-        )
+        let mut coefficients = self
+            .profile
+            .map(|profile| profile.coefficients())
+            .unwrap_or_default();
+
+        coefficients.override_with(self.optics.iter().fold(
+            SignalCoefficient::default(),
+            |mut acc, optic| {
+                let coeffs = SignalCoefficient::from_optic(optic);
+                acc.merge_into(coeffs);
+                acc
+            },
+        ));
+
+        Some(coefficients)
     }
 }
 
@@ -365,6 +511,30 @@ mod tests {
         );
     }
 
+    #[test]
+    fn configured_bang_prefixes() {
+        let index = empty_index();
+        let ctx = index.local_search_ctx();
+
+        let simple_terms = |bang_prefixes: Vec<char>| {
+            Query::parse(
+                &ctx,
+                &SearchQuery {
+                    query: "#gh !rust".to_string(),
+                    bang_prefixes,
+                    ..Default::default()
+                },
+                &index,
+            )
+            .expect("Failed to parse query")
+            .simple_terms()
+            .to_vec()
+        };
+
+        assert_eq!(simple_terms(vec!['!']), vec!["#gh".to_string()]);
+        assert_eq!(simple_terms(vec!['#']), vec!["!rust".to_string()]);
+    }
+
     #[test]
     fn simple_terms_phrase() {
         let index = empty_index();
@@ -385,6 +555,51 @@ mod tests {
         assert_eq!(terms, vec!["test".to_string(), "term".to_string()]);
     }
 
+    fn ranking_terms(query: &str) -> (Vec<String>, Vec<String>) {
+        let index = empty_index();
+        let ctx = index.local_search_ctx();
+
+        let mut query = Query::parse(
+            &ctx,
+            &SearchQuery {
+                query: query.to_string(),
+                ..Default::default()
+            },
+            &index,
+        )
+        .expect("Failed to parse query");
+
+        query.remove_stopwords(&[Lang::Eng]);
+
+        (
+            query.simple_terms().to_vec(),
+            query.ranking_terms().to_vec(),
+        )
+    }
+
+    #[test]
+    fn stopwords_mixed_query() {
+        let (simple, ranking) = ranking_terms("the compiler of The Rust language");
+
+        assert_eq!(
+            simple,
+            vec!["the", "compiler", "of", "The", "Rust", "language"]
+        );
+        assert_eq!(ranking, vec!["compiler", "Rust", "language"]);
+
+        // words in phrases are kept
+        let (_, ranking) = ranking_terms("\"the who\" of the guitar");
+        assert_eq!(ranking, vec!["the", "who", "guitar"]);
+    }
+
+    #[test]
+    fn stopwords_only_query() {
+        let (simple, ranking) = ranking_terms("to be or not to be");
+
+        assert_eq!(simple, vec!["to", "be", "or", "not", "to", "be"]);
+        assert_eq!(ranking, simple);
+    }
+
     #[test]
     fn not_query() {
         let mut index = Index::temporary().expect("Unable to open index");
@@ -505,6 +720,202 @@ mod tests {
         assert_eq!(result.webpages[0].url, "https://www.second.com/");
     }
 
+    #[test]
+    fn site_disjunction_query() {
+        let mut index = Index::temporary().expect("Unable to open index");
+
+        for (site, body) in [
+            ("https://www.a.com", "rust programming"),
+            ("https://www.b.com", "rust programming"),
+            ("https://www.c.com", "rust programming"),
+            ("https://www.d.com", "go programming"),
+        ] {
+            index
+                .insert(
+                    &Webpage::test_parse(
+                        &format!(
+                            r#"
+                            <html>
+                                <head>
+                                    <title>Test website</title>
+                                </head>
+                                <body>
+                                    {body}
+                                </body>
+                            </html>
+                        "#
+                        ),
+                        site,
+                    )
+                    .unwrap(),
+                )
+                .expect("failed to insert webpage");
+        }
+        index.commit().expect("failed to commit index");
+        let searcher = LocalSearcher::from(index);
+
+        let query = SearchQuery {
+            query: "site:a.com OR site:b.com rust".to_string(),
+            ..Default::default()
+        };
+        let result = searcher.search(&query).expect("Search failed");
+        let mut urls: Vec<_> = result.webpages.into_iter().map(|w| w.url).collect();
+        urls.sort();
+        assert_eq!(urls, vec!["https://www.a.com/", "https://www.b.com/"]);
+
+        let query = SearchQuery {
+            query: "rust site:a.com site:d.com".to_string(),
+            ..Default::default()
+        };
+        let result = searcher.search(&query).expect("Search failed");
+        assert_eq!(result.webpages.len(), 1);
+        assert_eq!(result.webpages[0].url, "https://www.a.com/");
+    }
+
+    #[test]
+    fn site_exclusion_query() {
+        let mut index = Index::temporary().expect("Unable to open index");
+
+        for (site, body) in [
+            ("https://www.a.com", "rust programming"),
+            ("https://blog.a.com", "rust programming"),
+            ("https://www.b.com", "rust programming"),
+            ("https://www.pinterest.com", "rust programming"),
+            ("https://www.c.com", "go programming"),
+        ] {
+            index
+                .insert(
+                    &Webpage::test_parse(
+                        &format!(
+                            r#"
+                            <html>
+                                <head>
+                                    <title>Test website</title>
+                                </head>
+                                <body>
+                                    {body}
+                                </body>
+                            </html>
+                        "#
+                        ),
+                        site,
+                    )
+                    .unwrap(),
+                )
+                .expect("failed to insert webpage");
+        }
+        index.commit().expect("failed to commit index");
+        let searcher = LocalSearcher::from(index);
+
+        let urls = |query: &str| {
+            let result = searcher
+                .search(&SearchQuery {
+                    query: query.to_string(),
+                    ..Default::default()
+                })
+                .expect("Search failed");
+
+            let mut urls: Vec<_> = result.webpages.into_iter().map(|w| w.url).collect();
+            urls.sort();
+            urls
+        };
+
+        assert_eq!(
+            urls("rust -site:pinterest.com"),
+            vec![
+                "https://blog.a.com/",
+                "https://www.a.com/",
+                "https://www.b.com/"
+            ]
+        );
+
+        assert_eq!(
+            urls("rust NOT site:pinterest.com -site:a.com"),
+            vec!["https://www.b.com/"]
+        );
+
+        assert_eq!(
+            urls("rust site:a.com -site:blog.a.com site:b.com"),
+            vec!["https://www.a.com/", "https://www.b.com/"]
+        );
+    }
+
+    #[test]
+    fn stemming_lang_hint() {
+        let mut index = Index::temporary().expect("Unable to open index");
+
+        index
+            .insert(
+                &Webpage::test_parse(
+                    r#"
+                        <html>
+                            <head>
+                                <title>Meine Katze</title>
+                            </head>
+                            <body>
+                                Die Katze schläft den ganzen Tag auf dem Sofa und spielt am Abend
+                                mit ihrem Ball. Sie ist sehr neugierig und springt gerne auf den Tisch.
+                            </body>
+                        </html>
+                    "#,
+                    "https://www.german.com",
+                )
+                .unwrap(),
+            )
+            .expect("failed to insert webpage");
+        index
+            .insert(
+                &Webpage::test_parse(
+                    r#"
+                        <html>
+                            <head>
+                                <title>Marathon training</title>
+                            </head>
+                            <body>
+                                The runners were running through the park every morning while
+                                preparing for the marathon later this year.
+                            </body>
+                        </html>
+                    "#,
+                    "https://www.english.com",
+                )
+                .unwrap(),
+            )
+            .expect("failed to insert webpage");
+        index.commit().expect("failed to commit index");
+        let searcher = LocalSearcher::from(index);
+
+        let result = searcher
+            .search(&SearchQuery {
+                query: "runs".to_string(),
+                lang: Some(Lang::Eng),
+                ..Default::default()
+            })
+            .expect("Search failed");
+        assert_eq!(result.webpages.len(), 1);
+        assert_eq!(result.webpages[0].url, "https://www.english.com/");
+
+        let result = searcher
+            .search(&SearchQuery {
+                query: "katzen".to_string(),
+                lang: Some(Lang::Deu),
+                ..Default::default()
+            })
+            .expect("Search failed");
+        assert_eq!(result.webpages.len(), 1);
+        assert_eq!(result.webpages[0].url, "https://www.german.com/");
+
+        // the english stemmer leaves 'katzen' as it is, so it doesn't match the german stem
+        let result = searcher
+            .search(&SearchQuery {
+                query: "katzen".to_string(),
+                lang: Some(Lang::Eng),
+                ..Default::default()
+            })
+            .expect("Search failed");
+        assert!(result.webpages.is_empty());
+    }
+
     #[test]
     fn title_query() {
         let mut index = Index::temporary().expect("Unable to open index");
@@ -1030,4 +1441,66 @@ mod tests {
         let result = searcher.search(&query).expect("Search failed");
         assert_eq!(result.webpages.len(), 1);
     }
+
+    #[test]
+    fn range_query() {
+        let mut index = Index::temporary().expect("Unable to open index");
+
+        for (site, num_inbound_links) in [
+            ("https://www.a.com", 5),
+            ("https://www.b.com", 50),
+            ("https://www.c.com", 500),
+        ] {
+            let mut webpage = Webpage::test_parse(
+                r#"
+                    <html>
+                        <head>
+                            <title>Test website</title>
+                        </head>
+                        <body>
+                            rust programming
+                        </body>
+                    </html>
+                "#,
+                site,
+            )
+            .unwrap();
+            webpage.num_inbound_links = num_inbound_links;
+
+            index.insert(&webpage).expect("failed to insert webpage");
+        }
+        index.commit().expect("failed to commit index");
+        let searcher = LocalSearcher::from(index);
+
+        let urls = |query: &str| {
+            let result = searcher
+                .search(&SearchQuery {
+                    query: query.to_string(),
+                    ..Default::default()
+                })
+                .expect("Search failed");
+
+            let mut urls: Vec<_> = result.webpages.into_iter().map(|w| w.url).collect();
+            urls.sort();
+            urls
+        };
+
+        assert_eq!(urls("rust inlinks:10..50"), vec!["https://www.b.com/"]);
+        assert_eq!(
+            urls("rust inlinks:50.."),
+            vec!["https://www.b.com/", "https://www.c.com/"]
+        );
+        assert_eq!(urls("rust inlinks:..5"), vec!["https://www.a.com/"]);
+        assert_eq!(
+            urls("rust -inlinks:..5"),
+            vec!["https://www.b.com/", "https://www.c.com/"]
+        );
+
+        assert!(searcher
+            .search(&SearchQuery {
+                query: "rust price:10..50".to_string(),
+                ..Default::default()
+            })
+            .is_err());
+    }
 }
@@ -15,17 +15,77 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 use itertools::Itertools;
-use optics::{Action, MatchLocation, Matching, Optic, Rule};
-use std::iter;
+use optics::{ast::Comparison, Action, MatchLocation, Matching, Optic, PatternPart, Rule};
+use std::{iter, ops::Bound};
 use tantivy::{
-    query::{BooleanQuery, Occur, QueryClone},
+    query::{BooleanQuery, Occur, QueryClone, RangeQuery},
     schema::Schema,
 };
 
-use crate::{fastfield_reader::FastFieldReader, schema::text_field};
+use crate::{
+    config::OpticLimitsConfig,
+    fastfield_reader::FastFieldReader,
+    schema::{fast_field, text_field, Field},
+};
 
 use super::{const_query::ConstQuery, pattern_query::PatternQuery, union::UnionQuery};
 
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum Error {
+    #[error("Optic has {found} ranking rules, but at most {max} are allowed")]
+    TooManyRules { found: usize, max: usize },
+
+    #[error("Optic matchers have a complexity of {found}, but at most {max} is allowed")]
+    TooComplex { found: usize, max: usize },
+}
+
+/// Whether the rule changes the score of the documents it matches. These are
+/// the rules that must be searched for in every segment when computing signals.
+pub fn is_ranking_rule(rule: &Rule) -> bool {
+    match rule.action {
+        Action::Downrank(boost) | Action::Boost(boost) | Action::ScopedBoost(boost, _) => {
+            boost != 0
+        }
+        Action::Discard => false,
+    }
+}
+
+/// Ensure the ranking rules of the optic stay within the limits. The complexity
+/// of a rule is the number of terms, wildcards and anchors across all of its matchers.
+pub fn check_limits(optic: &Optic, limits: &OpticLimitsConfig) -> Result<(), Error> {
+    let rules: Vec<_> = optic
+        .rules
+        .iter()
+        .filter(|rule| is_ranking_rule(rule))
+        .collect();
+
+    if rules.len() > limits.max_rules {
+        return Err(Error::TooManyRules {
+            found: rules.len(),
+            max: limits.max_rules,
+        });
+    }
+
+    let complexity: usize = rules
+        .iter()
+        .flat_map(|rule| rule.matches.iter().flatten())
+        .flat_map(|matching| matching.pattern.iter())
+        .map(|part| match part {
+            PatternPart::Raw(text) => text.split_whitespace().count().max(1),
+            PatternPart::Wildcard | PatternPart::Anchor => 1,
+        })
+        .sum();
+
+    if complexity > limits.max_complexity {
+        return Err(Error::TooComplex {
+            found: complexity,
+            max: limits.max_complexity,
+        });
+    }
+
+    Ok(())
+}
+
 pub trait AsTantivyQuery {
     fn as_tantivy(
         &self,
@@ -138,7 +198,7 @@ impl AsSearchableRule for Rule {
         };
 
         match &self.action {
-            Action::Boost(boost) => Some((
+            Action::Boost(boost) | Action::ScopedBoost(boost, _) => Some((
                 Occur::Should,
                 SearchableRule {
                     query: Box::new(ConstQuery::new(subquery, 1.0)),
@@ -256,6 +316,23 @@ impl AsTantivyQuery for Matching {
                 )),
                 1.0,
             )),
+            MatchLocation::InboundCount(comparison, count) => {
+                let (lo, hi) = match comparison {
+                    Comparison::Greater => (Bound::Excluded(*count), Bound::Unbounded),
+                    Comparison::Less => (Bound::Unbounded, Bound::Excluded(*count)),
+                };
+
+                Box::new(ConstQuery::new(
+                    Box::new(RangeQuery::new_u64_bounds(
+                        Field::Fast(fast_field::NumInboundLinks.into())
+                            .name()
+                            .to_string(),
+                        lo,
+                        hi,
+                    )),
+                    1.0,
+                ))
+            }
         }
     }
 }
@@ -268,16 +345,308 @@ mod tests {
     };
 
     use crate::{
-        gen_temp_path,
+        config::OpticLimitsConfig,
         index::Index,
-        ranking::inbound_similarity::InboundSimilarity,
+        ranking::{OpticRuleOutcome, SignalEnumDiscriminants},
+        search_prettifier::DisplayedWebpage,
         searcher::{LocalSearcher, SearchQuery},
-        webgraph::{Node, WebgraphWriter},
         webpage::{Html, Webpage},
     };
+    #[cfg(feature = "webgraph")]
+    use crate::{
+        gen_temp_path,
+        ranking::inbound_similarity::InboundSimilarity,
+        webgraph::{Node, WebgraphWriter},
+    };
+
+    use super::{check_limits, Error};
 
     const CONTENT: &str = "this is the best example website ever this is the best example website ever this is the best example website ever this is the best example website ever this is the best example website ever this is the best example website ever";
 
+    #[test]
+    fn rule_limits() {
+        let limits = OpticLimitsConfig {
+            max_rules: 2,
+            max_complexity: 3,
+        };
+
+        let optic = Optic::parse(
+            r#"
+            Rule { Matches { Site("a.com") }, Action(Boost(1)) };
+            Rule { Matches { Site("b.com") }, Action(Boost(0)) };
+            Rule { Matches { Site("c.com") }, Action(Discard) };
+            Rule { Matches { Url("/blog") }, Action(Downrank(1)) };
+            "#,
+        )
+        .unwrap();
+        assert_eq!(check_limits(&optic, &limits), Ok(()));
+
+        let optic = Optic::parse(
+            r#"
+            Rule { Matches { Site("a.com") }, Action(Boost(1)) };
+            Rule { Matches { Site("b.com") }, Action(Boost(1)) };
+            Rule { Matches { Site("c.com") }, Action(Downrank(1)) };
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            check_limits(&optic, &limits),
+            Err(Error::TooManyRules { found: 3, max: 2 })
+        );
+
+        let optic = Optic::parse(
+            r#"
+            Rule { Matches { Title("the best example website") }, Action(Boost(1)) };
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            check_limits(&optic, &limits),
+            Err(Error::TooComplex { found: 4, max: 3 })
+        );
+    }
+
+    #[test]
+    fn scoped_boost() {
+        let mut index = Index::temporary().expect("Unable to open index");
+
+        for (url, host_centrality) in [("https://www.a.com", 0.0), ("https://www.b.com", 1.0)] {
+            index
+                .insert(&Webpage {
+                    html: Html::parse(
+                        &format!(
+                            r#"
+                        <html>
+                            <head>
+                                <title>Example website</title>
+                            </head>
+                            <body>
+                                {CONTENT}
+                            </body>
+                        </html>
+                    "#
+                        ),
+                        url,
+                    )
+                    .unwrap(),
+                    host_centrality,
+                    fetch_time_ms: 500,
+                    ..Default::default()
+                })
+                .expect("failed to insert webpage");
+        }
+
+        index.commit().expect("failed to commit index");
+        let searcher = LocalSearcher::from(index);
+
+        let search = |optic: Option<&str>| {
+            searcher
+                .search(&SearchQuery {
+                    query: "website".to_string(),
+                    optic: optic.map(|optic| Optic::parse(optic).unwrap()),
+                    return_ranking_signals: true,
+                    ..Default::default()
+                })
+                .unwrap()
+                .webpages
+        };
+
+        let coefficient = |webpage: &DisplayedWebpage, signal: SignalEnumDiscriminants| {
+            webpage.ranking_signals.as_ref().unwrap()[&signal].coefficient
+        };
+
+        let baseline = search(None);
+        assert_eq!(baseline.len(), 2);
+        assert_eq!(baseline[0].url, "https://www.b.com/");
+        assert_eq!(baseline[1].url, "https://www.a.com/");
+
+        let title_coeff = coefficient(&baseline[1], SignalEnumDiscriminants::Bm25Title);
+        let centrality_coeff = coefficient(&baseline[1], SignalEnumDiscriminants::HostCentrality);
+
+        // a global boost scales the score of the whole document
+        let res = search(Some(
+            r#"Rule { Matches { Site("a.com") }, Action(Boost(100)) };"#,
+        ));
+        assert_eq!(res[0].url, "https://www.a.com/");
+        assert_eq!(
+            coefficient(&res[0], SignalEnumDiscriminants::Bm25Title),
+            title_coeff
+        );
+
+        // a.com has no host centrality, so boosting only that signal doesn't help it
+        let res = search(Some(
+            r#"Rule { Matches { Site("a.com") }, Action(Boost(100, Signal("host_centrality"))) };"#,
+        ));
+        assert_eq!(res[0].url, "https://www.b.com/");
+        assert_eq!(res[1].url, "https://www.a.com/");
+        assert_eq!(
+            coefficient(&res[1], SignalEnumDiscriminants::HostCentrality),
+            centrality_coeff * 101.0
+        );
+        assert_eq!(
+            coefficient(&res[1], SignalEnumDiscriminants::Bm25Title),
+            title_coeff
+        );
+        assert_eq!(
+            coefficient(&res[0], SignalEnumDiscriminants::HostCentrality),
+            centrality_coeff
+        );
+
+        // a field boost scales all the signals of the field
+        let res = search(Some(
+            r#"Rule { Matches { Site("a.com") }, Action(Boost(100, Field("title"))) };"#,
+        ));
+        let a = res.iter().find(|w| w.url == "https://www.a.com/").unwrap();
+        assert_eq!(
+            coefficient(a, SignalEnumDiscriminants::Bm25Title),
+            title_coeff * 101.0
+        );
+        assert_eq!(
+            coefficient(a, SignalEnumDiscriminants::HostCentrality),
+            centrality_coeff
+        );
+    }
+
+    #[test]
+    fn debug_optic() {
+        let mut index = Index::temporary().expect("Unable to open index");
+
+        for (url, host_centrality) in [("https://www.a.com", 0.0), ("https://www.b.com", 1.0)] {
+            index
+                .insert(&Webpage {
+                    html: Html::parse(
+                        &format!(
+                            r#"
+                        <html>
+                            <head>
+                                <title>Example website</title>
+                            </head>
+                            <body>
+                                {CONTENT}
+                            </body>
+                        </html>
+                    "#
+                        ),
+                        url,
+                    )
+                    .unwrap(),
+                    host_centrality,
+                    fetch_time_ms: 500,
+                    ..Default::default()
+                })
+                .expect("failed to insert webpage");
+        }
+
+        index.commit().expect("failed to commit index");
+        let searcher = LocalSearcher::from(index);
+
+        let optic = Optic::parse(
+            r#"
+            Rule { Matches { Site("a.com") }, Action(Boost(100)) };
+            Rule { Matches { Site("b.com") }, Action(Boost(100, Signal("not_a_signal"))) };
+        "#,
+        )
+        .unwrap();
+
+        let search = |debug_optic: bool| {
+            searcher
+                .search(&SearchQuery {
+                    query: "website".to_string(),
+                    optic: Some(optic.clone()),
+                    debug_optic,
+                    ..Default::default()
+                })
+                .unwrap()
+        };
+
+        let res = search(true);
+        assert_eq!(res.webpages[0].url, "https://www.a.com/");
+        assert_eq!(
+            res.optic_rules,
+            Some(vec![
+                OpticRuleOutcome::Applied,
+                OpticRuleOutcome::UnknownTarget
+            ])
+        );
+
+        assert_eq!(search(false).optic_rules, None);
+    }
+
+    #[test]
+    fn inbound_count() {
+        let mut index = Index::temporary().expect("Unable to open index");
+
+        for (url, num_inbound_links) in [
+            ("https://www.a.com", 5),
+            ("https://www.b.com", 50),
+            ("https://www.c.com", 500),
+        ] {
+            index
+                .insert(&Webpage {
+                    html: Html::parse(
+                        &format!(
+                            r#"
+                        <html>
+                            <head>
+                                <title>Example website</title>
+                            </head>
+                            <body>
+                                {CONTENT}
+                            </body>
+                        </html>
+                    "#
+                        ),
+                        url,
+                    )
+                    .unwrap(),
+                    num_inbound_links,
+                    fetch_time_ms: 500,
+                    ..Default::default()
+                })
+                .expect("failed to insert webpage");
+        }
+
+        index.commit().expect("failed to commit index");
+        let searcher = LocalSearcher::from(index);
+
+        let search = |optic: &str| {
+            searcher
+                .search(&SearchQuery {
+                    query: "website".to_string(),
+                    optic: Some(Optic::parse(optic).unwrap()),
+                    ..Default::default()
+                })
+                .unwrap()
+                .webpages
+                .into_iter()
+                .map(|webpage| webpage.url)
+                .collect::<Vec<_>>()
+        };
+
+        let res = search(r#"Rule { Matches { InboundCount < 10 }, Action(Discard) };"#);
+        assert_eq!(res.len(), 2);
+        assert!(!res.contains(&"https://www.a.com/".to_string()));
+
+        let res = search(r#"Rule { Matches { InboundCount > 100 }, Action(Discard) };"#);
+        assert_eq!(res.len(), 2);
+        assert!(!res.contains(&"https://www.c.com/".to_string()));
+
+        // the threshold itself is not part of the match
+        let res = search(
+            r#"
+            DiscardNonMatching;
+            Rule { Matches { InboundCount > 5, InboundCount < 500 } };
+            "#,
+        );
+        assert_eq!(res, vec!["https://www.b.com/".to_string()]);
+
+        let res = search(r#"Rule { Matches { InboundCount < 10 }, Action(Boost(100)) };"#);
+        assert_eq!(res[0], "https://www.a.com/");
+
+        let res = search(r#"Rule { Matches { InboundCount > 10 }, Action(Downrank(100)) };"#);
+        assert_eq!(res[0], "https://www.a.com/");
+    }
+
     #[test]
     fn discard_and_boost_hosts() {
         let mut index = Index::temporary().expect("Unable to open index");
@@ -613,6 +982,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "webgraph")]
     fn liked_hosts() {
         let mut index = Index::temporary().expect("Unable to open index");
 
@@ -1090,6 +1460,80 @@ mod tests {
         assert_eq!(res[0].url, "https://b.com/");
     }
 
+    #[test]
+    fn discarded_count() {
+        let mut index = Index::temporary().expect("Unable to open index");
+
+        for url in ["https://a.com", "https://b.com", "https://c.com"] {
+            index
+                .insert(&Webpage {
+                    html: Html::parse(
+                        &format!(
+                            r#"
+                        <html>
+                            <head>
+                                <title>Website</title>
+                            </head>
+                            <body>
+                                {CONTENT} {}
+                                example example example
+                            </body>
+                        </html>
+                    "#,
+                            crate::rand_words(100)
+                        ),
+                        url,
+                    )
+                    .unwrap(),
+                    fetch_time_ms: 500,
+                    ..Default::default()
+                })
+                .expect("failed to insert webpage");
+        }
+
+        index.commit().expect("failed to commit index");
+        let searcher = LocalSearcher::from(index);
+
+        let optic = Optic::parse(
+            r#"
+            Rule {
+                Matches {
+                    Site("a.com")
+                },
+                Matches {
+                    Site("b.com")
+                },
+                Action(Discard)
+            };
+        "#,
+        )
+        .unwrap();
+
+        let res = searcher
+            .search(&SearchQuery {
+                query: "example".to_string(),
+                optic: Some(optic.clone()),
+                show_filtered: true,
+                ..Default::default()
+            })
+            .unwrap();
+
+        assert_eq!(res.webpages.len(), 1);
+        assert_eq!(res.webpages[0].url, "https://c.com/");
+        assert_eq!(res.discarded_count, Some(2));
+
+        let res = searcher
+            .search(&SearchQuery {
+                query: "example".to_string(),
+                optic: Some(optic),
+                ..Default::default()
+            })
+            .unwrap();
+
+        assert_eq!(res.webpages.len(), 1);
+        assert_eq!(res.discarded_count, None);
+    }
+
     #[test]
     fn special_pattern_syntax() {
         let mut index = Index::temporary().expect("Unable to open index");
@@ -14,39 +14,51 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+use std::ops::Bound;
+
 use tantivy::{
-    query::{BooleanQuery, Occur, PhraseQuery, TermQuery},
+    query::{BooleanQuery, Occur, PhraseQuery, RangeQuery, TermQuery},
     tokenizer::Tokenizer as _,
 };
+use whatlang::Lang;
 
-use crate::{
-    bangs::BANG_PREFIXES,
-    schema::{text_field::TextField, Field, TextFieldEnum},
-};
+use crate::schema::{text_field::TextField, Field, TextFieldEnum};
 
 use super::{CompoundAwareTerm, SimpleOrPhrase, SimpleTerm, Term, TermCompound};
 
 impl CompoundAwareTerm {
+    /// Stemmed fields are searched with the stemmer of `lang` when it is given, and
+    /// otherwise with the stemmer of the language detected from the term.
     pub fn as_tantivy_query(
         &self,
+        schema: &tantivy::schema::Schema,
         fields: &[tantivy::schema::Field],
+        lang: Option<Lang>,
     ) -> (Occur, Box<dyn tantivy::query::Query + 'static>) {
         if !self.adjacent_terms.is_empty() {
             if let Term::SimpleOrPhrase(SimpleOrPhrase::Simple(simple_term)) = &self.term {
-                return simple_into_tantivy(simple_term, &self.adjacent_terms, fields);
+                return simple_into_tantivy(
+                    simple_term,
+                    &self.adjacent_terms,
+                    schema,
+                    fields,
+                    lang,
+                );
             }
         }
 
-        self.term.as_tantivy_query(fields)
+        self.term.as_tantivy_query(schema, fields, lang)
     }
 }
 
 fn simple_into_tantivy(
     term: &SimpleTerm,
     adjacent_terms: &[TermCompound],
+    schema: &tantivy::schema::Schema,
     fields: &[tantivy::schema::Field],
+    lang: Option<Lang>,
 ) -> (Occur, Box<dyn tantivy::query::Query + 'static>) {
-    let mut queries = Term::into_tantivy_simple(term, fields);
+    let mut queries = Term::into_tantivy_simple(term, schema, fields, lang);
 
     let fields = fields
         .iter()
@@ -69,7 +81,10 @@ fn simple_into_tantivy(
             .collect::<String>();
 
         for field in &fields {
-            queries.push((Occur::Should, Term::tantivy_text_query(field, &combined)))
+            queries.push((
+                Occur::Should,
+                Term::tantivy_text_query(schema, field, &combined, lang),
+            ))
         }
     }
 
@@ -78,7 +93,9 @@ fn simple_into_tantivy(
 
 fn phrase_query(
     terms: &[String],
+    schema: &tantivy::schema::Schema,
     fields: &[tantivy::schema::Field],
+    lang: Option<Lang>,
 ) -> (Occur, Box<dyn tantivy::query::Query + 'static>) {
     let mut phrases = Vec::with_capacity(fields.len());
     let phrase = terms.join(" ");
@@ -91,7 +108,7 @@ fn phrase_query(
         .filter(|(field, _)| field.is_searchable())
         .filter(|(field, _)| field.has_pos())
     {
-        let mut processed_terms = Term::process_tantivy_term(&phrase, tv_field);
+        let mut processed_terms = Term::process_tantivy_term(schema, &phrase, tv_field, lang);
 
         if processed_terms.is_empty() {
             continue;
@@ -117,22 +134,52 @@ fn phrase_query(
 }
 
 impl Term {
+    /// A group of consecutive `site:` filters where a document only needs to
+    /// match one of the sites. The group itself must match.
+    pub fn sites_as_tantivy_query(
+        sites: &[&str],
+        schema: &tantivy::schema::Schema,
+        fields: &[tantivy::schema::Field],
+    ) -> (Occur, Box<dyn tantivy::query::Query + 'static>) {
+        let sites = sites
+            .iter()
+            .map(|site| {
+                (
+                    Occur::Should,
+                    Box::new(BooleanQuery::new(Term::into_tantivy_site(
+                        site, schema, fields,
+                    ))) as Box<dyn tantivy::query::Query>,
+                )
+            })
+            .collect();
+
+        (Occur::Must, Box::new(BooleanQuery::new(sites)))
+    }
+
     fn as_tantivy_query(
         &self,
+        schema: &tantivy::schema::Schema,
         fields: &[tantivy::schema::Field],
+        lang: Option<Lang>,
     ) -> (Occur, Box<dyn tantivy::query::Query + 'static>) {
         match self {
             Term::SimpleOrPhrase(SimpleOrPhrase::Simple(term)) => {
-                simple_into_tantivy(term, &[], fields)
+                simple_into_tantivy(term, &[], schema, fields, lang)
+            }
+            Term::SimpleOrPhrase(SimpleOrPhrase::Phrase(phrase)) => {
+                phrase_query(phrase, schema, fields, lang)
             }
-            Term::SimpleOrPhrase(SimpleOrPhrase::Phrase(phrase)) => phrase_query(phrase, fields),
             Term::Not(subterm) => (
                 Occur::MustNot,
-                Box::new(BooleanQuery::new(vec![subterm.as_tantivy_query(fields)])),
+                Box::new(BooleanQuery::new(vec![
+                    subterm.as_tantivy_query(schema, fields, lang)
+                ])),
             ),
             Term::Site(site) => (
                 Occur::Must,
-                Box::new(BooleanQuery::new(Term::into_tantivy_site(site, fields))),
+                Box::new(BooleanQuery::new(Term::into_tantivy_site(
+                    site, schema, fields,
+                ))),
             ),
             Term::Title(title) => {
                 let field = fields
@@ -147,7 +194,7 @@ impl Term {
 
                 (
                     Occur::Must,
-                    Term::tantivy_text_query(field, &title.as_string()),
+                    Term::tantivy_text_query(schema, field, &title.as_string(), lang),
                 )
             }
             Term::Body(body) => {
@@ -163,7 +210,7 @@ impl Term {
 
                 (
                     Occur::Must,
-                    Term::tantivy_text_query(field, &body.as_string()),
+                    Term::tantivy_text_query(schema, field, &body.as_string(), lang),
                 )
             }
             Term::Url(url) => {
@@ -179,23 +226,37 @@ impl Term {
 
                 (
                     Occur::Must,
-                    Term::tantivy_text_query(field, &url.as_string()),
+                    Term::tantivy_text_query(schema, field, &url.as_string(), lang),
                 )
             }
-            Term::PossibleBang(text) => {
+            Term::PossibleBang { prefix, tag } => {
                 let mut term = String::new();
 
-                term.push(BANG_PREFIXES[0]);
-                term.push_str(text);
+                term.push(*prefix);
+                term.push_str(tag);
+
+                simple_into_tantivy(&term.into(), &[], schema, fields, lang)
+            }
+            Term::Range { field, lo, hi } => {
+                let bound = |bound: &Option<u64>| bound.map_or(Bound::Unbounded, Bound::Included);
 
-                simple_into_tantivy(&term.into(), &[], fields)
+                (
+                    Occur::Must,
+                    Box::new(RangeQuery::new_u64_bounds(
+                        Field::Fast(field.fast_field()).name().to_string(),
+                        bound(lo),
+                        bound(hi),
+                    )),
+                )
             }
         }
     }
 
     fn into_tantivy_simple(
         term: &SimpleTerm,
+        schema: &tantivy::schema::Schema,
         fields: &[tantivy::schema::Field],
+        lang: Option<Lang>,
     ) -> Vec<(Occur, Box<dyn tantivy::query::Query + 'static>)> {
         fields
             .iter()
@@ -207,7 +268,7 @@ impl Term {
             .map(|field| {
                 (
                     Occur::Should,
-                    Term::tantivy_text_query(field, term.as_str()),
+                    Term::tantivy_text_query(schema, field, term.as_str(), lang),
                 )
             })
             .collect()
@@ -215,6 +276,7 @@ impl Term {
 
     fn into_tantivy_site(
         term: &str,
+        schema: &tantivy::schema::Schema,
         fields: &[tantivy::schema::Field],
     ) -> Vec<(Occur, Box<dyn tantivy::query::Query + 'static>)> {
         fields
@@ -226,7 +288,7 @@ impl Term {
                 )
             })
             .map(|field| {
-                let processed_terms = Term::process_tantivy_term(term, *field);
+                let processed_terms = Term::process_tantivy_term(schema, term, *field, None);
 
                 if processed_terms.len() == 1 {
                     let term = processed_terms.first().unwrap().clone();
@@ -249,10 +311,12 @@ impl Term {
     }
 
     fn tantivy_text_query(
+        schema: &tantivy::schema::Schema,
         field: &tantivy::schema::Field,
         term: &str,
+        lang: Option<Lang>,
     ) -> Box<dyn tantivy::query::Query + 'static> {
-        let mut processed_terms = Term::process_tantivy_term(term, *field);
+        let mut processed_terms = Term::process_tantivy_term(schema, term, *field, lang);
 
         let option = Field::get(field.field_id() as usize)
             .unwrap()
@@ -282,14 +346,19 @@ impl Term {
     }
 
     fn process_tantivy_term(
+        schema: &tantivy::schema::Schema,
         term: &str,
         tantivy_field: tantivy::schema::Field,
+        lang: Option<Lang>,
     ) -> Vec<tantivy::Term> {
         match Field::get(tantivy_field.field_id() as usize) {
             Some(Field::Fast(_)) => vec![tantivy::Term::from_field_text(tantivy_field, term)],
             Some(Field::Text(text_field)) => {
                 let mut terms: Vec<tantivy::Term> = Vec::new();
-                let mut tokenizer = text_field.query_tokenizer();
+                let mut tokenizer = text_field.query_tokenizer_for(schema);
+                if let Some(lang) = lang {
+                    tokenizer = tokenizer.with_stemming_lang(lang);
+                }
                 let mut token_stream = tokenizer.token_stream(term);
                 token_stream.process(&mut |token| {
                     let term = tantivy::Term::from_field_text(tantivy_field, &token.text);
@@ -88,11 +88,17 @@ fn simple_or_phrase(input: &str) -> nom::IResult<&str, SimpleOrPhrase> {
 fn single_bang(input: &str, pref: char) -> nom::IResult<&str, Term> {
     let (input, _) = nom::character::complete::char(pref)(input)?;
     let (input, output) = until_space_or_end(input)?;
-    Ok((input, Term::PossibleBang(output.to_string())))
+    Ok((
+        input,
+        Term::PossibleBang {
+            prefix: pref,
+            tag: output.to_string(),
+        },
+    ))
 }
 
-fn bang(input: &str) -> nom::IResult<&str, Term> {
-    for pref in BANG_PREFIXES.iter() {
+fn bang<'a>(input: &'a str, prefixes: &[char]) -> nom::IResult<&'a str, Term> {
+    for pref in prefixes.iter() {
         if let Ok((input, output)) = single_bang(input, *pref) {
             return Ok((input, output));
         }
@@ -140,7 +146,84 @@ fn field_selector(input: &str) -> nom::IResult<&str, Term> {
     nom::branch::alt((site_field, title_field, body_field, url_field))(input)
 }
 
-fn not(input: &str) -> nom::IResult<&str, Term> {
+fn range_bound(input: &str) -> nom::IResult<&str, Option<u64>> {
+    let (input, output) = nom::character::complete::digit0(input)?;
+
+    if output.is_empty() {
+        return Ok((input, None));
+    }
+
+    match output.parse() {
+        Ok(bound) => Ok((input, Some(bound))),
+        Err(_) => Err(nom::Err::Error(nom::error::Error::new(
+            input,
+            nom::error::ErrorKind::Digit,
+        ))),
+    }
+}
+
+/// `field:lo..hi` syntax, where at least one of the bounds must be given.
+fn range_syntax(input: &str) -> nom::IResult<&str, (&str, Option<u64>, Option<u64>)> {
+    let (input, field) =
+        nom::bytes::complete::take_while1(|c: char| c.is_ascii_alphanumeric() || c == '_')(input)?;
+    let (input, _) = nom::character::complete::char(':')(input)?;
+    let (input, lo) = range_bound(input)?;
+    let (input, _) = nom::bytes::complete::tag("..")(input)?;
+    let (input, hi) = range_bound(input)?;
+
+    if (lo.is_none() && hi.is_none()) || !(input.is_empty() || input.starts_with(' ')) {
+        return Err(nom::Err::Error(nom::error::Error::new(
+            input,
+            nom::error::ErrorKind::Fail,
+        )));
+    }
+
+    Ok((input, (field, lo, hi)))
+}
+
+/// Ranges on fields that can't be range filtered, and empty ranges, fail the
+/// parsing of the entire query. See [`range_error`] for the error message.
+fn range(input: &str) -> nom::IResult<&str, Term> {
+    let (rest, (field, lo, hi)) = range_syntax(input)?;
+
+    let Some(field) = RangeField::from_name(field) else {
+        return Err(nom::Err::Failure(nom::error::Error::new(
+            input,
+            nom::error::ErrorKind::Verify,
+        )));
+    };
+
+    if let (Some(lo), Some(hi)) = (lo, hi) {
+        if lo > hi {
+            return Err(nom::Err::Failure(nom::error::Error::new(
+                input,
+                nom::error::ErrorKind::Verify,
+            )));
+        }
+    }
+
+    Ok((rest, Term::Range { field, lo, hi }))
+}
+
+/// Explains why the range starting at `input` was rejected by [`range`].
+fn range_error(input: &str) -> anyhow::Error {
+    let token = until_space_or_end(input)
+        .map(|(_, token)| token)
+        .unwrap_or(input);
+
+    let field = token.split(':').next().unwrap_or_default();
+
+    if RangeField::from_name(field).is_none() {
+        let fields = itertools::intersperse(RangeField::ALL.iter().map(|f| f.name()), ", ")
+            .collect::<String>();
+
+        anyhow::anyhow!("`{field}` does not support range filters. Supported fields: {fields}")
+    } else {
+        anyhow::anyhow!("the lower bound of `{token}` is larger than the upper bound")
+    }
+}
+
+fn not<'a>(input: &'a str, bang_prefixes: &[char]) -> nom::IResult<&'a str, Term> {
     // ignore double negation
     if let Ok((_, _)) = nom::bytes::complete::tag::<_, _, nom::error::Error<&str>>("--")(input) {
         return Err(nom::Err::Error(nom::error::Error::new(
@@ -150,7 +233,7 @@ fn not(input: &str) -> nom::IResult<&str, Term> {
     }
 
     let (input, _) = nom::bytes::complete::tag("-")(input)?;
-    let (input, output) = term(input)?;
+    let (input, output) = term(input, bang_prefixes)?;
     Ok((input, Term::Not(Box::new(output))))
 }
 
@@ -174,31 +257,100 @@ fn ignore_weird_quotes(input: &str) -> nom::IResult<&str, &str> {
     nom::branch::alt((guillemet, up_down_quotes, rev_guillemet, squares))(input)
 }
 
-fn term(input: &str) -> nom::IResult<&str, Term> {
+fn term<'a>(input: &'a str, bang_prefixes: &[char]) -> nom::IResult<&'a str, Term> {
     let (mut input, _) = trim_leading_whitespace(input)?;
 
     if let Ok((_, new_input)) = ignore_weird_quotes(input) {
         input = new_input;
     }
 
-    nom::branch::alt((phrase_term, bang, field_selector, not, simple_term))(input)
+    // phrases must be parsed before bangs, so a bang inside a quoted phrase
+    // (e.g. `"!ty bug"`) is literal text and not a bang.
+    nom::branch::alt((
+        phrase_term,
+        |input| bang(input, bang_prefixes),
+        field_selector,
+        range,
+        |input| not(input, bang_prefixes),
+        simple_term,
+    ))(input)
 }
 
 pub fn parse(query: &str) -> anyhow::Result<Vec<Term>> {
+    parse_with_bang_prefixes(query, &BANG_PREFIXES)
+}
+
+/// Like [`parse`], but only terms starting with one of `bang_prefixes` are
+/// parsed as [`Term::PossibleBang`]. Terms starting with any other character,
+/// including the default prefixes, are literal terms.
+pub fn parse_with_bang_prefixes(query: &str, bang_prefixes: &[char]) -> anyhow::Result<Vec<Term>> {
     if query.is_empty() || query.chars().all(char::is_whitespace) {
         return Ok(vec![]);
     }
 
-    nom::multi::many1(term)(query)
-        .map(|(_, res)| res)
-        .map_err(|e| anyhow::anyhow!("Failed to parse query: {:?}", e))
+    nom::multi::many1(|input| term(input, bang_prefixes))(query)
+        .map(|(_, res)| drop_site_disjunctions(negate_not_sites(res)))
+        .map_err(|e| match e {
+            nom::Err::Failure(e) if e.code == nom::error::ErrorKind::Verify => range_error(e.input),
+            e => anyhow::anyhow!("Failed to parse query: {:?}", e),
+        })
+}
+
+/// `NOT site:example.com` is an alternative spelling of `-site:example.com`.
+/// A `NOT` in front of any other term is kept as a regular term.
+fn negate_not_sites(terms: Vec<Term>) -> Vec<Term> {
+    let is_not = |term: &Term| match term {
+        Term::SimpleOrPhrase(SimpleOrPhrase::Simple(t)) => t.as_str() == "NOT",
+        _ => false,
+    };
+
+    let mut res: Vec<Term> = Vec::with_capacity(terms.len());
+    let mut terms = terms.into_iter().peekable();
+
+    while let Some(term) = terms.next() {
+        if is_not(&term) && matches!(terms.peek(), Some(Term::Site(_))) {
+            let site = terms.next().unwrap();
+            res.push(Term::Not(Box::new(site)));
+            continue;
+        }
+
+        res.push(term);
+    }
+
+    res
+}
+
+/// Consecutive `site:` terms are grouped into a disjunction when the query is
+/// lowered, so an `OR` between two of them is only a separator and not a
+/// search term. An `OR` anywhere else is kept as a regular term.
+fn drop_site_disjunctions(terms: Vec<Term>) -> Vec<Term> {
+    let is_or = |term: &Term| match term {
+        Term::SimpleOrPhrase(SimpleOrPhrase::Simple(t)) => t.as_str() == "OR",
+        _ => false,
+    };
+
+    let mut res: Vec<Term> = Vec::with_capacity(terms.len());
+    let mut terms = terms.into_iter().peekable();
+
+    while let Some(term) = terms.next() {
+        if is_or(&term)
+            && matches!(res.last(), Some(Term::Site(_)))
+            && matches!(terms.peek(), Some(Term::Site(_)))
+        {
+            continue;
+        }
+
+        res.push(term);
+    }
+
+    res
 }
 
 #[cfg(test)]
 mod tests {
     use proptest::prelude::*;
 
-    use super::{SimpleOrPhrase, Term};
+    use super::{RangeField, SimpleOrPhrase, Term};
 
     fn parse(input: &str) -> Vec<Term> {
         super::parse(input).unwrap()
@@ -247,6 +399,62 @@ mod tests {
         );
     }
 
+    #[test]
+    fn site_disjunction() {
+        assert_eq!(
+            parse("site:a.com OR site:b.com rust"),
+            vec![
+                Term::Site("a.com".to_string()),
+                Term::Site("b.com".to_string()),
+                Term::SimpleOrPhrase(SimpleOrPhrase::Simple("rust".to_string().into())),
+            ]
+        );
+
+        assert_eq!(
+            parse("rust OR site:a.com"),
+            vec![
+                Term::SimpleOrPhrase(SimpleOrPhrase::Simple("rust".to_string().into())),
+                Term::SimpleOrPhrase(SimpleOrPhrase::Simple("OR".to_string().into())),
+                Term::Site("a.com".to_string()),
+            ]
+        );
+
+        assert_eq!(
+            parse("site:a.com OR"),
+            vec![
+                Term::Site("a.com".to_string()),
+                Term::SimpleOrPhrase(SimpleOrPhrase::Simple("OR".to_string().into())),
+            ]
+        );
+    }
+
+    #[test]
+    fn site_exclusion() {
+        assert_eq!(
+            parse("rust -site:pinterest.com"),
+            vec![
+                Term::SimpleOrPhrase(SimpleOrPhrase::Simple("rust".to_string().into())),
+                Term::Not(Box::new(Term::Site("pinterest.com".to_string()))),
+            ]
+        );
+
+        assert_eq!(
+            parse("rust NOT site:pinterest.com"),
+            vec![
+                Term::SimpleOrPhrase(SimpleOrPhrase::Simple("rust".to_string().into())),
+                Term::Not(Box::new(Term::Site("pinterest.com".to_string()))),
+            ]
+        );
+
+        assert_eq!(
+            parse("NOT rust"),
+            vec![
+                Term::SimpleOrPhrase(SimpleOrPhrase::Simple("NOT".to_string().into())),
+                Term::SimpleOrPhrase(SimpleOrPhrase::Simple("rust".to_string().into())),
+            ]
+        );
+    }
+
     #[test]
     fn title() {
         assert_eq!(
@@ -362,6 +570,109 @@ mod tests {
         );
     }
 
+    #[test]
+    fn quoted_bang() {
+        assert_eq!(
+            parse("\"!ty bug\""),
+            vec![Term::SimpleOrPhrase(SimpleOrPhrase::Phrase(vec![
+                "!ty".to_string(),
+                "bug".to_string()
+            ]))]
+        );
+        assert_eq!(
+            parse("intitle:\"!ty\""),
+            vec![Term::Title(SimpleOrPhrase::Phrase(vec!["!ty".to_string()]))]
+        );
+        assert_eq!(
+            parse("!ty \"bug\""),
+            vec![
+                Term::PossibleBang {
+                    prefix: '!',
+                    tag: "ty".to_string()
+                },
+                Term::SimpleOrPhrase(SimpleOrPhrase::Phrase(vec!["bug".to_string()]))
+            ]
+        );
+    }
+
+    #[test]
+    fn bounded_range() {
+        assert_eq!(
+            parse("rust size:1000..50000"),
+            vec![
+                Term::SimpleOrPhrase(SimpleOrPhrase::Simple("rust".to_string().into())),
+                Term::Range {
+                    field: RangeField::Size,
+                    lo: Some(1000),
+                    hi: Some(50000)
+                }
+            ]
+        );
+        assert_eq!(
+            parse("inlinks:10..10"),
+            vec![Term::Range {
+                field: RangeField::Inlinks,
+                lo: Some(10),
+                hi: Some(10)
+            }]
+        );
+        assert_eq!(
+            parse("-updated:0..1700000000"),
+            vec![Term::Not(Box::new(Term::Range {
+                field: RangeField::Updated,
+                lo: Some(0),
+                hi: Some(1700000000)
+            }))]
+        );
+    }
+
+    #[test]
+    fn open_range() {
+        assert_eq!(
+            parse("inlinks:10.."),
+            vec![Term::Range {
+                field: RangeField::Inlinks,
+                lo: Some(10),
+                hi: None
+            }]
+        );
+        assert_eq!(
+            parse("size:..50000 rust"),
+            vec![
+                Term::Range {
+                    field: RangeField::Size,
+                    lo: None,
+                    hi: Some(50000)
+                },
+                Term::SimpleOrPhrase(SimpleOrPhrase::Simple("rust".to_string().into())),
+            ]
+        );
+        assert_eq!(parse("size:..50000")[0].to_string(), "size:..50000");
+    }
+
+    #[test]
+    fn invalid_range() {
+        let err = super::parse("price:10..50").unwrap_err().to_string();
+        assert!(err.contains("`price` does not support range filters"));
+        assert!(err.contains("size, inlinks, updated"));
+
+        let err = super::parse("rust -title:1..").unwrap_err().to_string();
+        assert!(err.contains("`title`"));
+
+        let err = super::parse("size:50..10").unwrap_err().to_string();
+        assert!(err.contains("lower bound of `size:50..10`"));
+
+        // not range syntax, so these are regular terms
+        for query in ["size:..", "size:a..b", "size:1..2..3", "2..3"] {
+            assert_eq!(
+                parse(query),
+                vec![Term::SimpleOrPhrase(SimpleOrPhrase::Simple(
+                    query.to_string().into()
+                ))]
+            );
+        }
+    }
+
     #[test]
     fn unicode() {
         let query = "🦀";
@@ -14,7 +14,7 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>
 
-use crate::bangs::BANG_PREFIXES;
+use crate::schema::{fast_field, FastFieldEnum};
 
 #[derive(Debug, Clone)]
 pub struct TermCompound {
@@ -70,6 +70,41 @@ impl std::fmt::Display for SimpleOrPhrase {
     }
 }
 
+/// Fields that can be filtered by a numeric range, e.g. `size:..50000`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RangeField {
+    /// Size of the page in bytes.
+    Size,
+    /// Number of links to the page.
+    Inlinks,
+    /// Unix timestamp of when the page was last updated.
+    Updated,
+}
+
+impl RangeField {
+    pub const ALL: [RangeField; 3] = [RangeField::Size, RangeField::Inlinks, RangeField::Updated];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|field| field.name() == name)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            RangeField::Size => "size",
+            RangeField::Inlinks => "inlinks",
+            RangeField::Updated => "updated",
+        }
+    }
+
+    pub fn fast_field(&self) -> FastFieldEnum {
+        match self {
+            RangeField::Size => fast_field::PageByteSize.into(),
+            RangeField::Inlinks => fast_field::NumInboundLinks.into(),
+            RangeField::Updated => fast_field::LastUpdated.into(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Term {
     SimpleOrPhrase(SimpleOrPhrase),
@@ -77,8 +112,19 @@ pub enum Term {
     Title(SimpleOrPhrase),
     Body(SimpleOrPhrase),
     Url(SimpleOrPhrase),
-    PossibleBang(String),
+    /// A term starting with one of the bang prefixes. The prefix is kept so the
+    /// term can be written back exactly as it was typed.
+    PossibleBang {
+        prefix: char,
+        tag: String,
+    },
     Not(Box<Term>),
+    /// `field:lo..hi` where either bound can be left out. Both bounds are inclusive.
+    Range {
+        field: RangeField,
+        lo: Option<u64>,
+        hi: Option<u64>,
+    },
 }
 
 impl std::fmt::Display for Term {
@@ -90,7 +136,22 @@ impl std::fmt::Display for Term {
             Term::Title(title) => write!(f, "intitle:{}", title),
             Term::Body(body) => write!(f, "inbody:{}", body),
             Term::Url(url) => write!(f, "inurl:{}", url),
-            Term::PossibleBang(bang) => write!(f, "{}{}", BANG_PREFIXES[0], bang),
+            Term::PossibleBang { prefix, tag } => write!(f, "{}{}", prefix, tag),
+            Term::Range { field, lo, hi } => {
+                write!(f, "{}:", field.name())?;
+
+                if let Some(lo) = lo {
+                    write!(f, "{}", lo)?;
+                }
+
+                write!(f, "..")?;
+
+                if let Some(hi) = hi {
+                    write!(f, "{}", hi)?;
+                }
+
+                Ok(())
+            }
         }
     }
 }
@@ -96,7 +96,7 @@ impl PatternQuery {
         for pattern in &patterns {
             match pattern {
                 PatternPart::Raw(text) => {
-                    let mut tokenizer = field.as_text().unwrap().indexing_tokenizer();
+                    let mut tokenizer = field.as_text().unwrap().tokenizer(schema);
                     let mut stream = tokenizer.token_stream(text);
 
                     while let Some(token) = stream.next() {
@@ -1,5 +1,7 @@
 // source: https://github.com/quickwit-oss/tantivy/blob/main/src/query/bm25.rs
 
+use std::collections::HashMap;
+
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 
@@ -7,6 +9,9 @@ use tantivy::fieldnorm::FieldNormReader;
 use tantivy::query::Explanation;
 use tantivy::{Score, Searcher, Term};
 
+use crate::enum_map::EnumMap;
+use crate::schema::{text_field::TextField, TextFieldEnum};
+
 const K1: Score = 1.2;
 const B: Score = 0.75;
 
@@ -16,23 +21,104 @@ pub(crate) fn idf(doc_freq: u64, doc_count: u64) -> Score {
     (1.0 + x).ln()
 }
 
-fn cached_tf_component(fieldnorm: u32, average_fieldnorm: Score) -> Score {
-    K1 * (1.0 - B + B * fieldnorm as Score / average_fieldnorm)
+fn cached_tf_component(fieldnorm: u32, average_fieldnorm: Score, params: &Bm25Params) -> Score {
+    params.k1 * (1.0 - params.b + params.b * fieldnorm as Score / average_fieldnorm)
 }
 
-fn compute_tf_cache(average_fieldnorm: Score) -> [Score; 256] {
+fn compute_tf_cache(average_fieldnorm: Score, params: &Bm25Params) -> [Score; 256] {
     let mut cache: [Score; 256] = [0.0; 256];
     for (fieldnorm_id, cache_mut) in cache.iter_mut().enumerate() {
         let fieldnorm = FieldNormReader::id_to_fieldnorm(fieldnorm_id as u8);
-        *cache_mut = cached_tf_component(fieldnorm, average_fieldnorm);
+        *cache_mut = cached_tf_component(fieldnorm, average_fieldnorm, params);
     }
     cache
 }
 
-#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+/// The free parameters of BM25. The defaults are the same as tantivy's.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Bm25Params {
-    pub idf: Score,
-    pub avg_fieldnorm: Score,
+    /// Term frequency saturation. Higher values let repeated occurrences of
+    /// a term count for more before the score saturates.
+    pub k1: Score,
+    /// Length normalization, from 0.0 (none) to 1.0 (full). Higher values
+    /// penalize matches in long fields more.
+    pub b: Score,
+}
+
+impl Default for Bm25Params {
+    fn default() -> Self {
+        Self { k1: K1, b: B }
+    }
+}
+
+/// The BM25 formula used to score the text fields.
+#[derive(Clone, Debug, Default)]
+pub enum Bm25Variant {
+    /// Standard BM25.
+    #[default]
+    Classic,
+    /// BM25+, which adds `delta` to the term frequency normalization of every
+    /// matching term. This lower-bounds the contribution of a matching term
+    /// so long documents are not over-penalized.
+    Plus(Score),
+    /// The term frequencies of each field are scaled by the weight of the field
+    /// before saturation. Fields without a weight have a weight of 1.
+    ///
+    /// Unlike BM25F, the fields are still length normalized and saturated
+    /// one at a time, so the weight only changes how quickly a field saturates.
+    FieldWeighted(EnumMap<TextFieldEnum, Score>),
+}
+
+/// The BM25 variant as written in the config, where the field weights
+/// are keyed by the name of the text field, e.g. `{ field_weighted = { title = 2.0 } }`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Bm25VariantConfig {
+    #[default]
+    Classic,
+    Plus(Score),
+    FieldWeighted(HashMap<String, Score>),
+}
+
+impl TryFrom<Bm25VariantConfig> for Bm25Variant {
+    type Error = anyhow::Error;
+
+    fn try_from(config: Bm25VariantConfig) -> Result<Self, Self::Error> {
+        Ok(match config {
+            Bm25VariantConfig::Classic => Bm25Variant::Classic,
+            Bm25VariantConfig::Plus(delta) => Bm25Variant::Plus(delta),
+            Bm25VariantConfig::FieldWeighted(weights) => {
+                let mut fields = EnumMap::new();
+
+                for (name, weight) in weights {
+                    let field = TextFieldEnum::all()
+                        .find(|field| field.name() == name)
+                        .ok_or_else(|| anyhow::anyhow!("unknown text field '{name}'"))?;
+
+                    fields.insert(field, weight);
+                }
+
+                Bm25Variant::FieldWeighted(fields)
+            }
+        })
+    }
+}
+
+impl Bm25Variant {
+    fn delta(&self) -> Score {
+        match self {
+            Bm25Variant::Plus(delta) => *delta,
+            Bm25Variant::Classic | Bm25Variant::FieldWeighted(_) => 0.0,
+        }
+    }
+
+    fn field_weight(&self, field: TextFieldEnum) -> Score {
+        match self {
+            Bm25Variant::FieldWeighted(weights) => weights.get(field).copied().unwrap_or(1.0),
+            Bm25Variant::Classic | Bm25Variant::Plus(_) => 1.0,
+        }
+    }
 }
 
 #[derive(Clone)]
@@ -41,7 +127,13 @@ pub struct MultiBm25Weight {
 }
 
 impl MultiBm25Weight {
-    pub fn for_terms(searcher: &Searcher, terms: &[Term]) -> tantivy::Result<Self> {
+    pub fn for_terms(
+        searcher: &Searcher,
+        terms: &[Term],
+        variant: &Bm25Variant,
+        params: &Bm25Params,
+        text_field: TextFieldEnum,
+    ) -> tantivy::Result<Self> {
         if terms.is_empty() {
             return Ok(Self {
                 weights: Vec::new(),
@@ -70,11 +162,10 @@ impl MultiBm25Weight {
 
         for term in terms {
             let term_doc_freq = searcher.doc_freq(term)?;
-            weights.push(Bm25Weight::for_one_term(
-                term_doc_freq,
-                total_num_docs,
-                average_fieldnorm,
-            ));
+            weights.push(
+                Bm25Weight::for_one_term(term_doc_freq, total_num_docs, average_fieldnorm, params)
+                    .with_variant(variant, text_field),
+            );
         }
 
         Ok(Self { weights })
@@ -97,6 +188,13 @@ impl MultiBm25Weight {
             weights: self.weights.iter().map(|w| w.boost_by(boost)).collect(),
         }
     }
+
+    /// The weights of the terms at `indices`, in the order of the indices.
+    pub fn select(&self, indices: &[usize]) -> Self {
+        Self {
+            weights: indices.iter().map(|&i| self.weights[i].clone()).collect(),
+        }
+    }
 }
 
 #[derive(Clone)]
@@ -105,6 +203,9 @@ pub struct Bm25Weight {
     weight: Score,
     cache: [Score; 256],
     average_fieldnorm: Score,
+    lower_bound: Score,
+    field_weight: Score,
+    params: Bm25Params,
 }
 
 impl Bm25Weight {
@@ -114,6 +215,20 @@ impl Bm25Weight {
             weight: self.weight * boost,
             cache: self.cache,
             average_fieldnorm: self.average_fieldnorm,
+            lower_bound: self.lower_bound * boost,
+            field_weight: self.field_weight,
+            params: self.params,
+        }
+    }
+
+    pub fn with_variant(self, variant: &Bm25Variant, text_field: TextFieldEnum) -> Bm25Weight {
+        // the weight is idf * (k1 + 1), so the delta of BM25+ is scaled by idf only.
+        let lower_bound = self.weight / (1.0 + self.params.k1) * variant.delta();
+
+        Bm25Weight {
+            lower_bound,
+            field_weight: variant.field_weight(text_field),
+            ..self
         }
     }
 
@@ -121,6 +236,7 @@ impl Bm25Weight {
         term_doc_freq: u64,
         total_num_docs: u64,
         avg_fieldnorm: Score,
+        params: &Bm25Params,
     ) -> Bm25Weight {
         let idf = idf(term_doc_freq, total_num_docs);
         let mut idf_explain =
@@ -130,27 +246,38 @@ impl Bm25Weight {
             term_doc_freq as Score,
         );
         idf_explain.add_const("N, total number of docs", total_num_docs as Score);
-        Bm25Weight::new(idf_explain, avg_fieldnorm)
+        Bm25Weight::new(idf_explain, avg_fieldnorm, params)
     }
 
-    pub(crate) fn new(idf_explain: Explanation, average_fieldnorm: Score) -> Bm25Weight {
-        let weight = idf_explain.value() * (1.0 + K1);
+    pub(crate) fn new(
+        idf_explain: Explanation,
+        average_fieldnorm: Score,
+        params: &Bm25Params,
+    ) -> Bm25Weight {
+        let weight = idf_explain.value() * (1.0 + params.k1);
         Bm25Weight {
             idf_explain,
             weight,
-            cache: compute_tf_cache(average_fieldnorm),
+            cache: compute_tf_cache(average_fieldnorm, params),
             average_fieldnorm,
+            lower_bound: 0.0,
+            field_weight: 1.0,
+            params: *params,
         }
     }
 
     #[inline]
     pub fn score(&self, fieldnorm_id: u8, term_freq: u32) -> Score {
-        self.weight * self.tf_factor(fieldnorm_id, term_freq)
+        if term_freq == 0 {
+            return 0.0;
+        }
+
+        self.weight * self.tf_factor(fieldnorm_id, term_freq) + self.lower_bound
     }
 
     #[inline]
     pub(crate) fn tf_factor(&self, fieldnorm_id: u8, term_freq: u32) -> Score {
-        let term_freq = term_freq as Score;
+        let term_freq = self.field_weight * term_freq as Score;
         let norm = self.cache[fieldnorm_id as usize];
         term_freq / (term_freq + norm)
     }
@@ -161,8 +288,8 @@ impl Bm25Weight {
         let score = self.score(fieldnorm_id, term_freq);
 
         let norm = self.cache[fieldnorm_id as usize];
+        let right_factor = self.tf_factor(fieldnorm_id, term_freq);
         let term_freq = term_freq as Score;
-        let right_factor = term_freq / (term_freq + norm);
 
         let mut tf_explanation = Explanation::new(
             "freq / (freq + k1 * (1 - b + b * dl / avgdl))",
@@ -170,8 +297,8 @@ impl Bm25Weight {
         );
 
         tf_explanation.add_const("freq, occurrences of term within document", term_freq);
-        tf_explanation.add_const("k1, term saturation parameter", K1);
-        tf_explanation.add_const("b, length normalization parameter", B);
+        tf_explanation.add_const("k1, term saturation parameter", self.params.k1);
+        tf_explanation.add_const("b, length normalization parameter", self.params.b);
         tf_explanation.add_const(
             "dl, length of field",
             FieldNormReader::id_to_fieldnorm(fieldnorm_id) as Score,
@@ -179,9 +306,21 @@ impl Bm25Weight {
         tf_explanation.add_const("avgdl, average length of field", self.average_fieldnorm);
 
         let mut explanation = Explanation::new("TermQuery, product of...", score);
-        explanation.add_detail(Explanation::new("(K1+1)", K1 + 1.0));
+        explanation.add_detail(Explanation::new("(K1+1)", self.params.k1 + 1.0));
         explanation.add_detail(self.idf_explain.clone());
         explanation.add_detail(tf_explanation);
+
+        if self.field_weight != 1.0 {
+            explanation.add_const("field weight", self.field_weight);
+        }
+
+        if self.lower_bound != 0.0 {
+            explanation.add_const(
+                "idf * delta, lower bound of matching term",
+                self.lower_bound,
+            );
+        }
+
         explanation
     }
 }
@@ -197,8 +336,8 @@ mod tests {
         // 'end' appears in a smalle subset (20)
         let weight = MultiBm25Weight {
             weights: vec![
-                Bm25Weight::for_one_term(98, 100, 1.0),
-                Bm25Weight::for_one_term(20, 100, 1.0),
+                Bm25Weight::for_one_term(98, 100, 1.0, &Bm25Params::default()),
+                Bm25Weight::for_one_term(20, 100, 1.0, &Bm25Params::default()),
             ],
         };
 
@@ -209,4 +348,100 @@ mod tests {
         let high_end = weight.score(vec![(0, 8), (0, 13)].into_iter());
         assert!(high_end > high_the);
     }
+
+    fn long_short_scores_with_params(variant: &Bm25Variant, params: &Bm25Params) -> (Score, Score) {
+        let weight = Bm25Weight::for_one_term(10, 100, 100.0, params)
+            .with_variant(variant, crate::schema::text_field::CleanBody.into());
+
+        let short = weight.score(FieldNormReader::fieldnorm_to_id(50), 2);
+        let long = weight.score(FieldNormReader::fieldnorm_to_id(5_000), 2);
+
+        (short, long)
+    }
+
+    fn long_short_scores(variant: &Bm25Variant) -> (Score, Score) {
+        long_short_scores_with_params(variant, &Bm25Params::default())
+    }
+
+    #[test]
+    fn bm25_plus_long_documents() {
+        let (classic_short, classic_long) = long_short_scores(&Bm25Variant::Classic);
+        let (plus_short, plus_long) = long_short_scores(&Bm25Variant::Plus(1.0));
+
+        assert!(classic_short > classic_long);
+        assert!(plus_short > plus_long);
+
+        assert!(plus_long > classic_long);
+        assert!(plus_long / plus_short > classic_long / classic_short);
+
+        // the lower bound only applies to matching terms
+        let weight = Bm25Weight::for_one_term(10, 100, 100.0, &Bm25Params::default()).with_variant(
+            &Bm25Variant::Plus(1.0),
+            crate::schema::text_field::CleanBody.into(),
+        );
+        assert_eq!(weight.score(FieldNormReader::fieldnorm_to_id(50), 0), 0.0);
+    }
+
+    #[test]
+    fn field_weighted_term_frequency() {
+        let mut weights = EnumMap::new();
+        weights.insert(crate::schema::text_field::CleanBody.into(), 2.0);
+
+        let (classic_short, _) = long_short_scores(&Bm25Variant::Classic);
+        let (weighted_short, _) = long_short_scores(&Bm25Variant::FieldWeighted(weights));
+
+        assert!(weighted_short > classic_short);
+    }
+
+    #[test]
+    fn variant_from_config() {
+        let config: Bm25VariantConfig =
+            serde_json::from_str(r#"{"field_weighted": {"body": 2.0}}"#).unwrap();
+        let variant = Bm25Variant::try_from(config).unwrap();
+        assert_eq!(
+            variant.field_weight(crate::schema::text_field::CleanBody.into()),
+            2.0
+        );
+        assert_eq!(
+            variant.field_weight(crate::schema::text_field::Title.into()),
+            1.0
+        );
+
+        let config: Bm25VariantConfig = serde_json::from_str(r#"{"plus": 1.0}"#).unwrap();
+        assert_eq!(Bm25Variant::try_from(config).unwrap().delta(), 1.0);
+
+        let config: Bm25VariantConfig = serde_json::from_str(r#""classic""#).unwrap();
+        assert_eq!(config, Bm25VariantConfig::default());
+
+        let config = Bm25VariantConfig::FieldWeighted([("missing".to_string(), 2.0)].into());
+        assert!(Bm25Variant::try_from(config).is_err());
+    }
+
+    #[test]
+    fn length_normalization() {
+        let (short, long) = long_short_scores(&Bm25Variant::Classic);
+
+        let (strict_short, strict_long) =
+            long_short_scores_with_params(&Bm25Variant::Classic, &Bm25Params { k1: K1, b: 1.0 });
+        let (lenient_short, lenient_long) =
+            long_short_scores_with_params(&Bm25Variant::Classic, &Bm25Params { k1: K1, b: 0.0 });
+
+        // a higher b penalizes the long document more
+        assert!(strict_long < long);
+        assert!(strict_long / strict_short < long / short);
+
+        // without length normalization the document length doesn't matter
+        assert_eq!(lenient_short, lenient_long);
+        assert!(lenient_long > long);
+    }
+
+    #[test]
+    fn default_params() {
+        let params = Bm25Params::default();
+        assert_eq!(params.k1, 1.2);
+        assert_eq!(params.b, 0.75);
+
+        let params: Bm25Params = serde_json::from_str(r#"{"b": 0.5}"#).unwrap();
+        assert_eq!(params, Bm25Params { k1: 1.2, b: 0.5 });
+    }
 }
@@ -0,0 +1,160 @@
+// Stract is an open source web search engine.
+// Copyright (C) 2024 Stract ApS
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Cache of the resolution from host names to the [`NodeID`]s used by the
+//! inbound similarity and query centrality scorers. Hosts that are not in the
+//! webgraph are cached as well, but only for a limited time so hosts that are
+//! added to the graph later are eventually resolved.
+
+use std::{
+    sync::{Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    metrics::Counter,
+    webgraph::{Node, NodeID},
+};
+
+use super::lru::Lru;
+
+#[derive(Clone, Copy)]
+enum Resolved {
+    Known(NodeID),
+    Unknown { resolved_at: Instant },
+}
+
+pub struct HostResolver {
+    hosts: Mutex<Lru<String, Resolved>>,
+    unknown_ttl: Duration,
+    hits: Counter,
+    misses: Counter,
+}
+
+impl HostResolver {
+    pub fn new(capacity: usize, unknown_ttl: Duration) -> Self {
+        Self {
+            hosts: Mutex::new(Lru::new(capacity)),
+            unknown_ttl,
+            hits: Counter::default(),
+            misses: Counter::default(),
+        }
+    }
+
+    fn hosts(&self) -> MutexGuard<'_, Lru<String, Resolved>> {
+        self.hosts.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Resolve `host` to its node id, or `None` if `is_known` says the node
+    /// is not in the webgraph.
+    pub fn resolve(&self, host: &str, is_known: impl FnOnce(&NodeID) -> bool) -> Option<NodeID> {
+        let cached = self.hosts().get(&host.to_string());
+
+        match cached {
+            Some(Resolved::Known(node)) => {
+                self.hits.inc();
+                return Some(node);
+            }
+            Some(Resolved::Unknown { resolved_at }) if resolved_at.elapsed() < self.unknown_ttl => {
+                self.hits.inc();
+                return None;
+            }
+            _ => {}
+        }
+
+        self.misses.inc();
+        let node = Node::from(host.to_string()).into_host().id();
+
+        let resolved = if is_known(&node) {
+            Resolved::Known(node)
+        } else {
+            Resolved::Unknown {
+                resolved_at: Instant::now(),
+            }
+        };
+
+        self.hosts().insert(host.to_string(), resolved);
+
+        match resolved {
+            Resolved::Known(node) => Some(node),
+            Resolved::Unknown { .. } => None,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.hosts().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn hits(&self) -> &Counter {
+        &self.hits
+    }
+
+    pub fn misses(&self) -> &Counter {
+        &self.misses
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+
+    #[test]
+    fn repeated_lookups_hit_cache() {
+        let resolver = HostResolver::new(10, Duration::from_secs(60));
+        let num_resolved = Cell::new(0);
+
+        let is_known = |_: &NodeID| {
+            num_resolved.set(num_resolved.get() + 1);
+            true
+        };
+
+        for _ in 0..3 {
+            assert_eq!(
+                resolver.resolve("a.com", is_known),
+                Some(Node::from("a.com").id())
+            );
+        }
+
+        assert_eq!(num_resolved.get(), 1);
+        assert_eq!(resolver.hits().get(), 2);
+        assert_eq!(resolver.misses().get(), 1);
+        assert_eq!(resolver.len(), 1);
+    }
+
+    #[test]
+    fn unknown_hosts_expire() {
+        let resolver = HostResolver::new(10, Duration::from_secs(60));
+
+        assert_eq!(resolver.resolve("a.com", |_| false), None);
+        assert_eq!(resolver.resolve("a.com", |_| true), None);
+        assert_eq!(resolver.hits().get(), 1);
+
+        let resolver = HostResolver::new(10, Duration::ZERO);
+
+        assert_eq!(resolver.resolve("a.com", |_| false), None);
+        assert_eq!(
+            resolver.resolve("a.com", |_| true),
+            Some(Node::from("a.com").id())
+        );
+        assert_eq!(resolver.misses().get(), 2);
+    }
+}
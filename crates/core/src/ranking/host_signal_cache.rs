@@ -0,0 +1,223 @@
+// Stract is an open source web search engine.
+// Copyright (C) 2024 Stract ApS
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Cache of the host level signals across requests. Host signals like the inbound
+//! similarity and query centrality only depend on the query and the host, so popular
+//! queries can reuse the scores computed by earlier requests instead of invoking the
+//! scorers again for every result.
+
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    sync::{Mutex, MutexGuard},
+};
+
+use crate::{metrics::Counter, searcher::SearchQuery, webgraph::NodeID};
+
+use super::lru::Lru;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HostSignal {
+    InboundSimilarity,
+    QueryCentrality,
+}
+
+/// Identifies the parts of a search query that the host signals depend on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct QueryFingerprint(u64);
+
+impl QueryFingerprint {
+    pub fn new(query: &SearchQuery) -> Self {
+        let mut hasher = DefaultHasher::new();
+
+        query.query.hash(&mut hasher);
+        query.selected_region.hash(&mut hasher);
+        query.safe_search.hash(&mut hasher);
+        serde_json::to_vec(&query.optic)
+            .unwrap_or_default()
+            .hash(&mut hasher);
+        serde_json::to_vec(&query.host_rankings)
+            .unwrap_or_default()
+            .hash(&mut hasher);
+
+        Self(hasher.finish())
+    }
+}
+
+type Key = (QueryFingerprint, NodeID, HostSignal);
+
+pub struct HostSignalCache {
+    scores: Mutex<Lru<Key, Option<f64>>>,
+    hits: Counter,
+    misses: Counter,
+}
+
+impl HostSignalCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            scores: Mutex::new(Lru::new(capacity)),
+            hits: Counter::default(),
+            misses: Counter::default(),
+        }
+    }
+
+    fn scores(&self) -> MutexGuard<'_, Lru<Key, Option<f64>>> {
+        self.scores.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Get the cached score of the host signal or compute and cache it.
+    /// The cache is not locked while the score is computed.
+    pub fn get_or_compute(
+        &self,
+        query: QueryFingerprint,
+        host: NodeID,
+        signal: HostSignal,
+        compute: impl FnOnce() -> Option<f64>,
+    ) -> Option<f64> {
+        let key = (query, host, signal);
+
+        if let Some(score) = self.scores().get(&key) {
+            self.hits.inc();
+            return score;
+        }
+
+        self.misses.inc();
+        let score = compute();
+        self.scores().insert(key, score);
+
+        score
+    }
+
+    /// Remove all cached scores. This must be called whenever the webgraph
+    /// or similarity model the scores were computed from changes.
+    pub fn invalidate(&self) {
+        self.scores().clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.scores().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn hits(&self) -> &Counter {
+        &self.hits
+    }
+
+    pub fn misses(&self) -> &Counter {
+        &self.misses
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use crate::webgraph::Node;
+
+    use super::*;
+
+    fn fingerprint(query: &str) -> QueryFingerprint {
+        QueryFingerprint::new(&SearchQuery {
+            query: query.to_string(),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn reuses_scores() {
+        let cache = HostSignalCache::new(10);
+        let host = Node::from("a.com").id();
+        let num_computed = Cell::new(0);
+
+        let compute = || {
+            num_computed.set(num_computed.get() + 1);
+            Some(0.5)
+        };
+
+        for _ in 0..3 {
+            let score = cache.get_or_compute(
+                fingerprint("test"),
+                host,
+                HostSignal::InboundSimilarity,
+                compute,
+            );
+            assert_eq!(score, Some(0.5));
+        }
+
+        assert_eq!(num_computed.get(), 1);
+        assert_eq!(cache.hits().get(), 2);
+        assert_eq!(cache.misses().get(), 1);
+
+        cache.get_or_compute(
+            fingerprint("other"),
+            host,
+            HostSignal::InboundSimilarity,
+            compute,
+        );
+        cache.get_or_compute(
+            fingerprint("test"),
+            host,
+            HostSignal::QueryCentrality,
+            compute,
+        );
+
+        assert_eq!(num_computed.get(), 3);
+        assert_eq!(cache.len(), 3);
+    }
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let cache = HostSignalCache::new(2);
+        let query = fingerprint("test");
+        let a = Node::from("a.com").id();
+        let b = Node::from("b.com").id();
+        let c = Node::from("c.com").id();
+
+        cache.get_or_compute(query, a, HostSignal::InboundSimilarity, || Some(1.0));
+        cache.get_or_compute(query, b, HostSignal::InboundSimilarity, || Some(2.0));
+        cache.get_or_compute(query, a, HostSignal::InboundSimilarity, || None);
+        cache.get_or_compute(query, c, HostSignal::InboundSimilarity, || Some(3.0));
+
+        assert_eq!(cache.len(), 2);
+        assert_eq!(
+            cache.get_or_compute(query, a, HostSignal::InboundSimilarity, || None),
+            Some(1.0)
+        );
+        assert_eq!(
+            cache.get_or_compute(query, b, HostSignal::InboundSimilarity, || None),
+            None
+        );
+    }
+
+    #[test]
+    fn invalidate() {
+        let cache = HostSignalCache::new(10);
+        let query = fingerprint("test");
+        let host = Node::from("a.com").id();
+
+        cache.get_or_compute(query, host, HostSignal::QueryCentrality, || Some(1.0));
+        cache.invalidate();
+
+        assert!(cache.is_empty());
+        assert_eq!(
+            cache.get_or_compute(query, host, HostSignal::QueryCentrality, || Some(2.0)),
+            Some(2.0)
+        );
+    }
+}
@@ -135,6 +135,34 @@ impl Scorer {
         score
     }
 
+    /// Whether the node has any inbound links in the graph
+    /// the similarities were built from.
+    pub fn knows_about(&self, node: &NodeID) -> bool {
+        self.vectors.contains(node)
+    }
+
+    /// The similarity of `host` to each of the liked and disliked hosts that make up
+    /// its score. Similarities to disliked hosts are negated, so the unnormalized score
+    /// is the number of disliked hosts plus the sum of the vector.
+    /// Returns `None` if the host has no inbound links.
+    ///
+    /// This is meant for inspecting the scores and doesn't use the score cache.
+    pub fn similarity_vector(&self, host: &NodeID) -> Option<Vec<(NodeID, f32)>> {
+        let vec = self.vectors.get(host)?;
+
+        Some(
+            self.liked
+                .iter()
+                .map(|liked| (liked.node, liked.sim(host, vec) as f32))
+                .chain(
+                    self.disliked
+                        .iter()
+                        .map(|disliked| (disliked.node, -disliked.sim(host, vec) as f32)),
+                )
+                .collect(),
+        )
+    }
+
     pub fn set_self_score(&mut self, self_score: f64) {
         for scorer in self.liked.iter_mut() {
             scorer.set_self_score(self_score);
@@ -338,14 +366,18 @@ impl InboundSimilarity {
 
 #[cfg(test)]
 mod tests {
+    #[cfg(feature = "webgraph")]
     use optics::HostRankings;
 
     use crate::{
         gen_temp_path,
+        webgraph::{Node, WebgraphWriter},
+    };
+    #[cfg(feature = "webgraph")]
+    use crate::{
         index::Index,
         rand_words,
         searcher::{LocalSearcher, SearchQuery},
-        webgraph::{Node, WebgraphWriter},
         webpage::{Html, Webpage},
     };
 
@@ -381,6 +413,49 @@ mod tests {
     }
 
     #[test]
+    fn similarity_vector() {
+        let mut wrt = WebgraphWriter::new(
+            gen_temp_path(),
+            crate::executor::Executor::single_thread(),
+            crate::webgraph::Compression::default(),
+        );
+
+        wrt.insert(Node::from("z.com"), Node::from("a.com"), String::new());
+        wrt.insert(Node::from("z.com"), Node::from("b.com"), String::new());
+        wrt.insert(Node::from("x.com"), Node::from("b.com"), String::new());
+        wrt.insert(Node::from("y.com"), Node::from("c.com"), String::new());
+
+        let graph = wrt.finalize();
+
+        let inbound = InboundSimilarity::build(&graph);
+
+        let a = Node::from("a.com").id();
+        let b = Node::from("b.com").id();
+        let c = Node::from("c.com").id();
+
+        let mut scorer = inbound.scorer(&[a], &[c], false);
+
+        // a.com and b.com share 1 of their 1 and 2 inbound hosts,
+        // while c.com shares none with b.com.
+        let vector = scorer.similarity_vector(&b).unwrap();
+        assert_eq!(vector.len(), 2);
+
+        assert_eq!(vector[0].0, a);
+        assert!((vector[0].1 - 1.0 / 2.0f32.sqrt()).abs() < 1e-6);
+
+        assert_eq!(vector[1].0, c);
+        assert_eq!(vector[1].1, 0.0);
+
+        let sum: f32 = vector.iter().map(|(_, sim)| sim).sum();
+        assert!((scorer.score(&b) as f32 - (1.0 + sum)).abs() < 1e-6);
+
+        assert!(scorer
+            .similarity_vector(&Node::from("z.com").id())
+            .is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "webgraph")]
     fn it_ranks_search_results() {
         let mut wrt = WebgraphWriter::new(
             crate::gen_temp_path(),
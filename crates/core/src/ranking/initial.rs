@@ -14,8 +14,8 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+use crate::collector::PruningScoreSegmentTweaker;
 use crate::fastfield_reader::FastFieldReader;
-use chrono::Utc;
 use serde::{Deserialize, Serialize};
 use tantivy::collector::{ScoreSegmentTweaker, ScoreTweaker};
 use tantivy::{DocId, SegmentReader};
@@ -54,9 +54,6 @@ impl ScoreTweaker<Score> for InitialScoreTweaker {
     fn segment_tweaker(&self, segment_reader: &SegmentReader) -> tantivy::Result<Self::Child> {
         let mut computer = self.computer.clone();
 
-        let current_timestamp = Utc::now().timestamp() as usize;
-        computer.set_current_timestamp(current_timestamp);
-
         computer
             .register_segment(&self.tv_searcher, segment_reader, &self.fastfield_reader)
             .unwrap();
@@ -74,19 +71,59 @@ pub struct Score {
     pub total: f64,
 }
 
-impl ScoreSegmentTweaker<Score> for InitialSegmentScoreTweaker {
-    fn score(&mut self, doc: DocId, _score: tantivy::Score) -> Score {
-        let mut total = self
-            .computer
-            .compute_signals(doc)
+impl InitialSegmentScoreTweaker {
+    fn score_doc(&mut self, doc: DocId, cutoff: Option<f64>) -> Option<Score> {
+        let boosts = self.computer.boosts(doc);
+
+        // the cutoff is compared to the signals before the optic boosts are applied.
+        // A boost of the whole document scales the cutoff, but scoped boosts can
+        // increase any signal, so documents with scoped boosts are always fully scored.
+        let cutoff = match &boosts {
+            Some(boosts) if boosts.has_signal_boosts() => None,
+            Some(boosts) if boosts.total > 0.0 => cutoff.map(|cutoff| cutoff / boosts.total),
+            Some(_) => None,
+            None => cutoff,
+        };
+
+        let mut signals = self.computer.compute_signals(doc, cutoff);
+
+        let mut total = signals
+            .by_ref()
             .flatten()
-            .map(|computed| computed.score.coefficient * computed.score.value)
+            .map(|mut computed| {
+                if let Some(boosts) = &boosts {
+                    boosts.apply(&mut computed);
+                }
+
+                computed.score.coefficient * computed.score.value
+            })
             .sum();
 
-        if let Some(boost) = self.computer.boosts(doc) {
-            total *= boost;
+        if signals.is_pruned() {
+            return None;
+        }
+
+        if let Some(boosts) = boosts {
+            total *= boosts.total;
         }
 
-        Score { total }
+        Some(Score { total })
+    }
+}
+
+impl ScoreSegmentTweaker<Score> for InitialSegmentScoreTweaker {
+    fn score(&mut self, doc: DocId, _score: tantivy::Score) -> Score {
+        self.score_doc(doc, None).unwrap()
+    }
+}
+
+impl PruningScoreSegmentTweaker for InitialSegmentScoreTweaker {
+    fn score_with_cutoff(
+        &mut self,
+        doc: DocId,
+        _score: tantivy::Score,
+        cutoff: f64,
+    ) -> Option<Score> {
+        self.score_doc(doc, Some(cutoff))
     }
 }
@@ -16,18 +16,25 @@
 
 pub mod bitvec_similarity;
 pub mod bm25;
+pub mod host_resolver;
+#[cfg(feature = "webgraph")]
+pub mod host_signal_cache;
 pub mod inbound_similarity;
 pub mod initial;
+mod lru;
 pub mod models;
+pub mod optic_rule_cache;
 pub mod optics;
 pub mod pipeline;
+#[cfg(feature = "webgraph")]
 pub mod query_centrality;
 pub mod signal;
+pub mod tld_reputation;
 
 use initial::InitialScoreTweaker;
 
 use crate::{
-    collector::{MainCollector, MaxDocsConsidered, TopDocs},
+    collector::{Deadline, MainCollector, MaxDocsConsidered, SearchCursor, TopDocs},
     config::CollectorConfig,
     fastfield_reader::FastFieldReader,
     search_ctx::Ctx,
@@ -44,6 +51,8 @@ pub struct Ranker {
     fastfield_reader: FastFieldReader,
     de_rank_similar: bool,
     num_results: Option<usize>,
+    cursor: Option<SearchCursor>,
+    deadline: Option<Deadline>,
     collector_config: CollectorConfig,
 }
 
@@ -60,6 +69,8 @@ impl Ranker {
             de_rank_similar: true,
             fastfield_reader,
             num_results: None,
+            cursor: None,
+            deadline: None,
             collector_config,
         }
     }
@@ -82,6 +93,16 @@ impl Ranker {
         self
     }
 
+    pub fn with_cursor(mut self, cursor: SearchCursor) -> Self {
+        self.cursor = Some(cursor);
+        self
+    }
+
+    pub fn with_deadline(mut self, deadline: Deadline) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
     pub fn de_rank_similar(&mut self, de_rank_similar: bool) {
         self.de_rank_similar = de_rank_similar;
     }
@@ -91,7 +112,14 @@ impl Ranker {
     }
 
     pub fn collector(&self, ctx: Ctx) -> MainCollector {
-        let computer = self.computer();
+        let mut computer = self.computer();
+
+        // the bounded signals are computed last, so they can be skipped for documents
+        // that can't reach the top results.
+        let unbounded: Vec<_> = SignalEnum::all()
+            .filter(|signal| signal.max_value().is_none())
+            .collect();
+        computer.set_signal_priority(&unbounded);
 
         let score_tweaker =
             InitialScoreTweaker::new(ctx.tv_searcher, computer, self.fastfield_reader.clone());
@@ -99,7 +127,8 @@ impl Ranker {
         let mut collector = TopDocs::with_limit(
             self.num_results.unwrap_or(NUM_RESULTS_PER_PAGE),
             self.fastfield_reader.clone(),
-        );
+        )
+        .and_score_cutoff();
 
         if self.de_rank_similar {
             collector = collector.and_de_rank_similar()
@@ -113,11 +142,20 @@ impl Ranker {
             collector = collector.and_max_docs(max_docs.clone());
         }
 
+        if let Some(cursor) = self.cursor {
+            collector = collector.and_cursor(cursor);
+        }
+
+        if let Some(deadline) = &self.deadline {
+            collector = collector.and_deadline(deadline.clone());
+        }
+
         collector = collector.and_collector_config(self.collector_config.clone());
 
         collector.main_collector(score_tweaker)
     }
 
+    #[cfg(feature = "webgraph")]
     pub fn set_query_centrality(&mut self, query_centrality: query_centrality::Scorer) {
         self.computer.set_query_centrality(query_centrality);
     }
@@ -126,20 +164,33 @@ impl Ranker {
 #[cfg(test)]
 mod tests {
 
-    use std::path::Path;
+    use std::{collections::HashMap, path::Path, sync::Arc, time::Duration};
 
     use optics::{
         ast::{RankingCoeff, RankingTarget},
         Optic,
     };
+    use tantivy::collector::{ScoreSegmentTweaker, ScoreTweaker};
 
     use crate::{
-        config::{IndexingDualEncoderConfig, IndexingLocalConfig, WarcSource},
+        collector::{Deadline, PruningScoreSegmentTweaker, TopDocs, TweakedScoreTopCollector},
+        config::{CollectorConfig, IndexingDualEncoderConfig, IndexingLocalConfig, WarcSource},
         entrypoint::indexer::IndexingWorker,
+        enum_map::EnumMap,
         index::Index,
         models::dual_encoder::DualEncoder,
-        searcher::{LocalSearcher, SearchQuery},
-        webpage::{Html, Webpage},
+        query::Query,
+        searcher::{Device, LocalSearcher, SearchQuery},
+        webpage::{region::RegionCount, Html, Region, Webpage},
+    };
+
+    use super::initial::{InitialScoreTweaker, InitialSegmentScoreTweaker, Score};
+    use super::models::linear::LinearRegression;
+    use super::{
+        AnchorTextMatch, Bm25Title, CoefficientProfile, ContentFreshness, ContentLength,
+        HasStructuredData, HomepageBoost, LinkDensity, MajorityRegion, Proximity, Readability,
+        Signal, SignalComputer, SignalEnum, SignalEnumDiscriminants, SignalVector, TermCoverage,
+        TldReputation,
     };
 
     const CONTENT: &str = "this is the best example website ever this is the best example website ever this is the best example website ever this is the best example website ever this is the best example website ever this is the best example website ever";
@@ -211,6 +262,88 @@ mod tests {
         assert_eq!(result.webpages[1].url, "https://www.a.com/");
     }
 
+    #[test]
+    fn near_duplicates_moved_to_end_by_default() {
+        let mut index = Index::temporary().expect("Unable to open index");
+
+        // a.com and b.com have the same content, so b.com is a near-duplicate of a.com
+        let duplicate_content = crate::rand_words(100);
+
+        for (url, body, host_centrality) in [
+            ("https://www.a.com", duplicate_content.as_str(), 6.0),
+            ("https://www.b.com", duplicate_content.as_str(), 4.0),
+            ("https://www.c.com", "", 0.0),
+        ] {
+            index
+                .insert(&Webpage {
+                    html: Html::parse(
+                        &format!(
+                            r#"
+                        <html>
+                            <head>
+                                <title>Example website</title>
+                            </head>
+                            <body>
+                                {CONTENT} {body}
+                            </body>
+                        </html>
+                    "#
+                        ),
+                        url,
+                    )
+                    .unwrap(),
+                    host_centrality,
+                    fetch_time_ms: 500,
+                    ..Default::default()
+                })
+                .expect("failed to insert webpage");
+        }
+
+        index.commit().expect("failed to commit index");
+        let mut searcher = LocalSearcher::new(index);
+        searcher.set_collector_config(CollectorConfig {
+            duplicate_penalty: None,
+            ..Default::default()
+        });
+
+        let urls = |searcher: &LocalSearcher<_>| {
+            searcher
+                .search(&SearchQuery {
+                    query: "example".to_string(),
+                    ..Default::default()
+                })
+                .expect("Search failed")
+                .webpages
+                .into_iter()
+                .map(|webpage| webpage.url)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            urls(&searcher),
+            vec![
+                "https://www.a.com/",
+                "https://www.c.com/",
+                "https://www.b.com/"
+            ]
+        );
+
+        // with a duplicate penalty, the near-duplicate is only demoted
+        searcher.set_collector_config(CollectorConfig {
+            duplicate_penalty: Some(0.1),
+            ..Default::default()
+        });
+
+        assert_eq!(
+            urls(&searcher),
+            vec![
+                "https://www.a.com/",
+                "https://www.b.com/",
+                "https://www.c.com/"
+            ]
+        );
+    }
+
     #[test]
     fn page_centrality_ranking() {
         let mut index = Index::temporary().expect("Unable to open index");
@@ -353,6 +486,149 @@ mod tests {
         assert_eq!(result.webpages[0].url, "https://www.new.com/");
     }
 
+    #[test]
+    fn optic_raises_freshness_coefficient() {
+        let mut index = Index::temporary().expect("Unable to open index");
+        let now = chrono::Utc::now();
+
+        for (url, updated, host_centrality) in [
+            (
+                "https://www.old.com",
+                now - chrono::Duration::days(5 * 365),
+                1.0,
+            ),
+            ("https://www.new.com", now - chrono::Duration::days(1), 0.0),
+        ] {
+            index
+                .insert(&Webpage {
+                    html: Html::parse(
+                        &format!(
+                            r#"
+                        <html>
+                            <head>
+                                <title>Title</title>
+                                <meta property="og:updated_time" content="{}" />
+                            </head>
+                            <body>
+                                {CONTENT} {}
+                            </body>
+                        </html>
+                    "#,
+                            updated.to_rfc3339(),
+                            crate::rand_words(100),
+                        ),
+                        url,
+                    )
+                    .unwrap(),
+                    host_centrality,
+                    fetch_time_ms: 500,
+                    ..Default::default()
+                })
+                .expect("failed to insert webpage");
+        }
+
+        index.commit().expect("failed to commit index");
+        let searcher = LocalSearcher::from(index);
+
+        let result = searcher
+            .search(&SearchQuery {
+                query: "title".to_string(),
+                ..Default::default()
+            })
+            .expect("Search failed");
+
+        assert_eq!(result.webpages[0].url, "https://www.old.com/");
+
+        let result = searcher
+            .search(&SearchQuery {
+                query: "title".to_string(),
+                optic: Some(
+                    Optic::parse(r#"Ranking(Signal("content_freshness"), 1000000);"#).unwrap(),
+                ),
+                ..Default::default()
+            })
+            .expect("Search failed");
+
+        assert_eq!(result.webpages[0].url, "https://www.new.com/");
+    }
+
+    #[test]
+    fn coefficient_profiles() {
+        let mut index = Index::temporary().expect("Unable to open index");
+        let now = chrono::Utc::now();
+
+        for (url, updated, host_centrality) in [
+            (
+                "https://www.old.com",
+                now - chrono::Duration::days(5 * 365),
+                1.0,
+            ),
+            ("https://www.new.com", now - chrono::Duration::hours(1), 0.0),
+        ] {
+            index
+                .insert(&Webpage {
+                    html: Html::parse(
+                        &format!(
+                            r#"
+                        <html>
+                            <head>
+                                <title>Title</title>
+                                <meta property="og:updated_time" content="{}" />
+                            </head>
+                            <body>
+                                {CONTENT} {}
+                            </body>
+                        </html>
+                    "#,
+                            updated.to_rfc3339(),
+                            crate::rand_words(100),
+                        ),
+                        url,
+                    )
+                    .unwrap(),
+                    host_centrality,
+                    fetch_time_ms: 500,
+                    ..Default::default()
+                })
+                .expect("failed to insert webpage");
+        }
+
+        index.commit().expect("failed to commit index");
+        let searcher = LocalSearcher::from(index);
+
+        let top = |profile: CoefficientProfile, optic: Option<Optic>| {
+            searcher
+                .search(&SearchQuery {
+                    query: "title".to_string(),
+                    profile: Some(profile),
+                    optic,
+                    ..Default::default()
+                })
+                .expect("Search failed")
+                .webpages[0]
+                .url
+                .clone()
+        };
+
+        assert_eq!(
+            top(CoefficientProfile::Freshness, None),
+            "https://www.new.com/"
+        );
+        assert_eq!(
+            top(CoefficientProfile::Authority, None),
+            "https://www.old.com/"
+        );
+
+        // explicit coefficients take precedence over the profile
+        assert_eq!(
+            top(
+                CoefficientProfile::Authority,
+                Some(Optic::parse(r#"Ranking(Signal("update_timestamp"), 1000);"#).unwrap()),
+            ),
+            "https://www.new.com/"
+        );
+    }
+
     #[test]
     fn derank_trackers() {
         let mut index = Index::temporary().expect("Unable to open index");
@@ -773,129 +1049,1733 @@ mod tests {
         assert_eq!(result.webpages[2].url, "https://www.third.com/one/two123");
     }
 
-    fn setup_worker(data_path: &Path) -> IndexingWorker {
-        IndexingWorker::new(IndexingLocalConfig {
-            host_centrality_store_path: crate::gen_temp_path().to_str().unwrap().to_string(),
-            page_centrality_store_path: None,
-            page_webgraph_path: None,
-            topics_path: None,
-            safety_classifier_path: None,
-            dual_encoder: Some(IndexingDualEncoderConfig {
-                model_path: data_path.to_str().unwrap().to_string(),
-                page_centrality_rank_threshold: None,
-            }),
-            output_path: crate::gen_temp_path().to_str().unwrap().to_string(),
-            limit_warc_files: None,
-            skip_warc_files: None,
-            warc_source: WarcSource::Local(crate::config::LocalConfig {
-                folder: crate::gen_temp_path().to_str().unwrap().to_string(),
-                names: vec!["".to_string()],
-            }),
-            host_centrality_threshold: None,
-            minimum_clean_words: None,
-            batch_size: 10,
-        })
-    }
-
     #[test]
-    fn title_embeddings() {
-        let data_path = Path::new("../../data/summarizer/dual_encoder");
-        if !data_path.exists() {
-            // Skip the test if the test data is not available
-            return;
+    fn domain_name_match() {
+        let mut index = Index::temporary().expect("Unable to open index");
+
+        for url in [
+            "https://www.example.net",
+            "https://wikipedia.example.com",
+            "https://www.wikipedia.org",
+        ] {
+            index
+                .insert(&Webpage {
+                    html: Html::parse(
+                        &format!(
+                            r#"
+                        <html>
+                            <head>
+                                <title>Test website</title>
+                            </head>
+                            <body>
+                                wikipedia {CONTENT} {}
+                            </body>
+                        </html>
+                    "#,
+                            crate::rand_words(100)
+                        ),
+                        url,
+                    )
+                    .unwrap(),
+                    host_centrality: 1.0,
+                    fetch_time_ms: 500,
+                    ..Default::default()
+                })
+                .expect("failed to insert webpage");
         }
 
-        let worker = setup_worker(data_path);
+        index.commit().expect("failed to commit index");
+        let searcher = LocalSearcher::new(index);
 
-        let mut index = Index::temporary().expect("Unable to open index");
+        let result = searcher
+            .search(&SearchQuery {
+                query: "wikipedia".to_string(),
+                optic: Some(Optic {
+                    rankings: vec![RankingCoeff {
+                        target: RankingTarget::Signal("domain_name_match".to_string()),
+                        value: 100_000.0,
+                    }],
+                    ..Default::default()
+                }),
+                ..Default::default()
+            })
+            .expect("Search failed");
 
-        let mut pages = vec![
-            Webpage::test_parse(
-                &format!(
-                    r#"
-                <html>
-                    <head>
-                        <title>Homemade Heart Brownie Recipe</title>
-                    </head>
-                    <body>
-                        best chocolate cake {CONTENT} {}
-                    </body>
-                </html>
-            "#,
-                    crate::rand_words(100)
-                ),
-                "https://www.a.com/",
-            )
-            .unwrap(),
-            Webpage::test_parse(
-                &format!(
-                    r#"
-                <html>
-                    <head>
-                        <title>How To Best Use an iMac as a Monitor for a PC</title>
-                    </head>
-                    <body>
-                        best chocolate cake {CONTENT} {}
-                    </body>
-                </html>
-            "#,
-                    crate::rand_words(100)
-                ),
-                "https://www.b.com/",
-            )
-            .unwrap(),
-        ];
+        assert_eq!(result.webpages.len(), 3);
+        assert_eq!(result.webpages[0].url, "https://www.wikipedia.org/");
+        assert_eq!(result.webpages[1].url, "https://wikipedia.example.com/");
+        assert_eq!(result.webpages[2].url, "https://www.example.net/");
+    }
 
-        worker.set_title_embeddings(&mut pages);
-        assert!(pages.iter().all(|p| p.title_embedding.is_some()));
+    #[test]
+    fn url_term_match() {
+        let mut index = Index::temporary().expect("Unable to open index");
 
-        for page in pages {
-            index.insert(&page).expect("failed to insert webpage");
+        for url in [
+            "https://www.a.com/cooking/recipes",
+            "https://www.b.com/rust_guide",
+            "https://www.c.com/best-rust-books.html",
+        ] {
+            index
+                .insert(&Webpage {
+                    html: Html::parse(
+                        &format!(
+                            r#"
+                        <html>
+                            <head>
+                                <title>Test website</title>
+                            </head>
+                            <body>
+                                rust books {CONTENT} {}
+                            </body>
+                        </html>
+                    "#,
+                            crate::rand_words(100)
+                        ),
+                        url,
+                    )
+                    .unwrap(),
+                    host_centrality: 1.0,
+                    fetch_time_ms: 500,
+                    ..Default::default()
+                })
+                .expect("failed to insert webpage");
         }
 
         index.commit().expect("failed to commit index");
-
-        let mut searcher = LocalSearcher::new(index);
-        searcher
-            .set_dual_encoder(DualEncoder::open(data_path).expect("failed to open dual encoder"));
+        let searcher = LocalSearcher::new(index);
 
         let result = searcher
             .search(&SearchQuery {
-                query: "best chocolate cake".to_string(),
+                query: "rust books".to_string(),
                 optic: Some(Optic {
                     rankings: vec![RankingCoeff {
-                        target: RankingTarget::Signal("title_embedding_similarity".to_string()),
+                        target: RankingTarget::Signal("url_term_match".to_string()),
                         value: 100_000.0,
                     }],
                     ..Default::default()
                 }),
+                return_ranking_signals: true,
                 ..Default::default()
             })
             .expect("Search failed");
 
-        assert_eq!(result.webpages.len(), 2);
-        assert_eq!(result.webpages[0].url, "https://www.a.com/");
+        assert_eq!(result.webpages.len(), 3);
+        assert_eq!(
+            result.webpages[0].url,
+            "https://www.c.com/best-rust-books.html"
+        );
+        assert_eq!(result.webpages[1].url, "https://www.b.com/rust_guide");
+        assert_eq!(result.webpages[2].url, "https://www.a.com/cooking/recipes");
+
+        let url_term_match: Vec<_> = result
+            .webpages
+            .iter()
+            .map(|webpage| {
+                webpage
+                    .ranking_signals
+                    .as_ref()
+                    .unwrap()
+                    .get(&SignalEnumDiscriminants::UrlTermMatch)
+                    .map_or(0.0, |score| score.value)
+            })
+            .collect();
+
+        assert_eq!(url_term_match, vec![1.0, 0.5, 0.0]);
     }
 
     #[test]
-    fn keyword_embeddings() {
-        let data_path = Path::new("../../data/summarizer/dual_encoder");
-        if !data_path.exists() {
-            // Skip the test if the test data is not available
-            return;
-        }
-
-        let worker = setup_worker(data_path);
-
-        let mut index = Index::temporary().expect("Unable to open index");
+    fn content_freshness() {
+        let now = chrono::Utc::now();
+
+        let page = |updated: Option<chrono::DateTime<chrono::Utc>>, days_since_crawl: i64| {
+            let meta = updated
+                .map(|updated| {
+                    format!(
+                        r#"<meta property="og:updated_time" content="{}" />"#,
+                        updated.to_rfc3339()
+                    )
+                })
+                .unwrap_or_default();
 
-        let mut a = Webpage::test_parse(
-            &format!(
-                r#"
-                <html>
-                    <head>
-                        <title>Homemade Heart Brownie Recipe</title>
-                    </head>
+            Webpage {
+                html: Html::parse(
+                    &format!(
+                        r#"
+                    <html>
+                        <head>
+                            <title>Test website</title>
+                            {meta}
+                        </head>
+                        <body>
+                            {CONTENT}
+                        </body>
+                    </html>
+                "#
+                    ),
+                    "https://www.example.com",
+                )
+                .unwrap(),
+                inserted_at: now - chrono::Duration::days(days_since_crawl),
+                ..Default::default()
+            }
+        };
+
+        let mut computer = SignalComputer::new(None);
+        computer.set_current_timestamp(now.timestamp() as usize);
+        let cache = computer.update_time_cache().to_vec();
+
+        // content date present
+        let with_content_date = page(Some(now - chrono::Duration::days(2)), 100);
+        assert_eq!(
+            ContentFreshness.precompute(&with_content_date, &computer),
+            Some(cache[2 * 24])
+        );
+
+        // crawl time only
+        let crawl_only = page(None, 100);
+        assert_eq!(
+            ContentFreshness.precompute(&crawl_only, &computer),
+            Some(cache[100 * 24])
+        );
+
+        // content dated after the current time of the computer
+        let mut skewed = SignalComputer::new(None);
+        skewed.set_current_timestamp((now - chrono::Duration::days(10)).timestamp() as usize);
+        let future_dated = page(Some(now - chrono::Duration::days(1)), 1);
+        assert_eq!(
+            ContentFreshness.precompute(&future_dated, &skewed),
+            Some(cache[1])
+        );
+
+        let mut index = Index::temporary().expect("Unable to open index");
+        index.insert(&crawl_only).unwrap();
+        index.commit().unwrap();
+
+        let ctx = index.inverted_index.local_search_ctx();
+        computer
+            .register_segment(
+                &ctx.tv_searcher,
+                ctx.tv_searcher.segment_reader(0),
+                &ctx.fastfield_reader,
+            )
+            .unwrap();
+
+        assert_eq!(
+            ContentFreshness.compute(0, &computer),
+            Some(cache[100 * 24])
+        );
+    }
+
+    #[test]
+    fn majority_region() {
+        let page = |text: &str| Webpage {
+            html: Html::parse(
+                &format!(
+                    r#"
+                <html>
+                    <head>
+                        <title>Test website</title>
+                    </head>
+                    <body>
+                        {text}
+                    </body>
+                </html>
+            "#
+                ),
+                "https://www.example.com",
+            )
+            .unwrap(),
+            ..Default::default()
+        };
+
+        let english = page("This is a long text written in English about the weather in London and how people ride their bikes to work every day.");
+        let danish = page("Dette er en lang tekst skrevet på dansk om vejret i København og hvordan folk cykler på arbejde hver eneste dag.");
+
+        let mut region_count = RegionCount::open(crate::gen_temp_path().join("region_count.json"));
+        region_count.increment(&Region::US);
+        region_count.increment(&Region::US);
+        region_count.increment(&Region::Denmark);
+        region_count.commit();
+
+        let mut computer = SignalComputer::new(None);
+        computer.set_region_count(region_count.clone());
+
+        assert_eq!(MajorityRegion.precompute(&english, &computer), Some(1.0));
+        assert_eq!(MajorityRegion.precompute(&danish, &computer), Some(0.0));
+
+        let index = Index::temporary().expect("Unable to open index");
+        let ctx = index.inverted_index.local_search_ctx();
+        let query = Query::parse(
+            &ctx,
+            &SearchQuery {
+                query: "test".to_string(),
+                selected_region: Some(Region::Denmark),
+                ..Default::default()
+            },
+            &index.inverted_index,
+        )
+        .unwrap();
+
+        let mut computer = SignalComputer::new(Some(&query));
+        computer.set_region_count(region_count);
+
+        assert_eq!(MajorityRegion.precompute(&english, &computer), None);
+        assert_eq!(MajorityRegion.precompute(&danish, &computer), None);
+    }
+
+    #[test]
+    fn inbound_link_count_ranking() {
+        let mut index = Index::temporary().expect("Unable to open index");
+
+        for (url, num_inbound_links) in [
+            ("https://www.a.com", 0),
+            ("https://www.b.com", 100_000),
+            ("https://www.c.com", 10),
+            ("https://www.d.com", 1_000),
+        ] {
+            index
+                .insert(&Webpage {
+                    html: Html::parse(
+                        &format!(
+                            r#"
+                        <html>
+                            <head>
+                                <title>Test website</title>
+                            </head>
+                            <body>
+                                {CONTENT} {}
+                            </body>
+                        </html>
+                    "#,
+                            crate::rand_words(100)
+                        ),
+                        url,
+                    )
+                    .unwrap(),
+                    host_centrality: 1.0,
+                    fetch_time_ms: 500,
+                    num_inbound_links,
+                    ..Default::default()
+                })
+                .expect("failed to insert webpage");
+        }
+
+        index.commit().expect("failed to commit index");
+        let searcher = LocalSearcher::new(index);
+
+        let result = searcher
+            .search(&SearchQuery {
+                query: "example".to_string(),
+                optic: Some(Optic {
+                    rankings: vec![RankingCoeff {
+                        target: RankingTarget::Signal("inbound_link_count".to_string()),
+                        value: 100_000.0,
+                    }],
+                    ..Default::default()
+                }),
+                ..Default::default()
+            })
+            .expect("Search failed");
+
+        assert_eq!(result.webpages.len(), 4);
+        assert_eq!(result.webpages[0].url, "https://www.b.com/");
+        assert_eq!(result.webpages[1].url, "https://www.d.com/");
+        assert_eq!(result.webpages[2].url, "https://www.c.com/");
+        assert_eq!(result.webpages[3].url, "https://www.a.com/");
+    }
+
+    #[test]
+    fn page_byte_size() {
+        let mut index = Index::temporary().expect("Unable to open index");
+
+        let sizes = [
+            None,
+            Some(1_000),
+            Some(100_000),
+            Some(1_000_000),
+            Some(100_000_000),
+        ];
+
+        for (i, byte_size) in sizes.iter().enumerate() {
+            index
+                .insert(&Webpage {
+                    html: Html::parse(
+                        &format!(
+                            r#"
+                        <html>
+                            <head>
+                                <title>Test website</title>
+                            </head>
+                            <body>
+                                {CONTENT}
+                            </body>
+                        </html>
+                    "#
+                        ),
+                        &format!("https://www.{i}.com"),
+                    )
+                    .unwrap(),
+                    byte_size: *byte_size,
+                    ..Default::default()
+                })
+                .expect("failed to insert webpage");
+        }
+
+        index.commit().expect("failed to commit index");
+
+        let ctx = index.inverted_index.local_search_ctx();
+        let mut computer = SignalComputer::new(None);
+        computer
+            .register_segment(
+                &ctx.tv_searcher,
+                ctx.tv_searcher.segment_reader(0),
+                &ctx.fastfield_reader,
+            )
+            .unwrap();
+
+        let mut scores = vec![0.0; sizes.len()];
+        for doc in 0..sizes.len() as u32 {
+            let host = computer
+                .segment_reader()
+                .unwrap()
+                .fastfield_reader()
+                .get_bytes(doc, crate::schema::fast_field::Host)
+                .unwrap();
+            let i: usize = String::from_utf8(host)
+                .unwrap()
+                .trim_end_matches(".com")
+                .parse()
+                .unwrap();
+
+            scores[i] = super::PageByteSize.compute(doc, &computer).unwrap();
+        }
+
+        for (byte_size, score) in sizes.iter().zip(&scores) {
+            let webpage = Webpage {
+                byte_size: *byte_size,
+                ..Default::default()
+            };
+
+            assert_eq!(
+                super::PageByteSize.precompute(&webpage, &computer),
+                Some(*score)
+            );
+        }
+
+        let [missing, small, medium, large, huge] = scores[..] else {
+            unreachable!()
+        };
+
+        assert_eq!(small, 1.0);
+        assert!(medium < small);
+        assert!(large < medium);
+        assert_eq!(huge, 0.0);
+
+        // missing size data is neither rewarded nor fully penalized
+        assert!(missing > huge);
+        assert!(missing < small);
+    }
+
+    #[test]
+    fn concurrent_segment_scoring() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<SignalComputer>();
+
+        let mut index = Index::temporary().expect("Unable to open index");
+
+        for segment in 0..2 {
+            for i in 0..10 {
+                index
+                    .insert(&Webpage {
+                        html: Html::parse(
+                            &format!(
+                                r#"
+                            <html>
+                                <head>
+                                    <title>Website {segment} {i}</title>
+                                </head>
+                                <body>
+                                    {CONTENT} {}
+                                </body>
+                            </html>
+                        "#,
+                                crate::rand_words(100)
+                            ),
+                            &format!("https://www.{segment}-{i}.com"),
+                        )
+                        .unwrap(),
+                        host_centrality: i as f64,
+                        fetch_time_ms: 100 * i,
+                        ..Default::default()
+                    })
+                    .expect("failed to insert webpage");
+            }
+
+            index.commit().expect("failed to commit index");
+        }
+
+        assert_eq!(index.inverted_index.num_segments(), 2);
+
+        let ctx = index.inverted_index.local_search_ctx();
+        let query = Query::parse(
+            &ctx,
+            &SearchQuery {
+                query: "example website".to_string(),
+                ..Default::default()
+            },
+            &index.inverted_index,
+        )
+        .unwrap();
+
+        let computer = SignalComputer::new(Some(&query));
+
+        let score_segment = |mut computer: SignalComputer, segment: &tantivy::SegmentReader| {
+            computer
+                .register_segment(&ctx.tv_searcher, segment, &ctx.fastfield_reader)
+                .unwrap();
+
+            (0..segment.max_doc())
+                .map(|doc| {
+                    computer
+                        .compute_signals(doc, None)
+                        .flatten()
+                        .map(|computed| (computed.signal, computed.score.value))
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let segments = ctx.tv_searcher.segment_readers();
+
+        let sequential: Vec<_> = segments
+            .iter()
+            .map(|segment| score_segment(computer.clone(), segment))
+            .collect();
+
+        let concurrent: Vec<_> = std::thread::scope(|s| {
+            let handles: Vec<_> = segments
+                .iter()
+                .map(|segment| {
+                    let computer = computer.clone();
+                    s.spawn(move || score_segment(computer, segment))
+                })
+                .collect();
+
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+
+        assert!(sequential.iter().all(|scores| !scores.is_empty()));
+        assert_eq!(sequential, concurrent);
+    }
+
+    #[test]
+    fn term_coverage() {
+        let mut index = Index::temporary().expect("Unable to open index");
+
+        for (url, body) in [
+            ("https://www.a.com", "alpha beta gamma"),
+            ("https://www.b.com", "alpha beta alpha beta"),
+            ("https://www.c.com", "delta epsilon"),
+        ] {
+            index
+                .insert(&Webpage {
+                    html: Html::parse(
+                        &format!(
+                            r#"
+                        <html>
+                            <head>
+                                <title>Test website</title>
+                            </head>
+                            <body>
+                                {body}
+                            </body>
+                        </html>
+                    "#
+                        ),
+                        url,
+                    )
+                    .unwrap(),
+                    ..Default::default()
+                })
+                .expect("failed to insert webpage");
+        }
+
+        index.commit().expect("failed to commit index");
+
+        let ctx = index.inverted_index.local_search_ctx();
+        let query = Query::parse(
+            &ctx,
+            &SearchQuery {
+                query: "alpha beta gamma alpha".to_string(),
+                ..Default::default()
+            },
+            &index.inverted_index,
+        )
+        .unwrap();
+
+        let mut computer = SignalComputer::new(Some(&query));
+        let segment = ctx.tv_searcher.segment_reader(0);
+        computer
+            .register_segment(&ctx.tv_searcher, segment, &ctx.fastfield_reader)
+            .unwrap();
+
+        let mut coverage: Vec<_> = (0..segment.max_doc())
+            .map(|doc| TermCoverage.compute(doc, &computer).unwrap())
+            .collect();
+        coverage.sort_by(|a, b| a.total_cmp(b));
+
+        assert_eq!(coverage, vec![0.0, 2.0 / 3.0, 1.0]);
+    }
+
+    #[test]
+    fn proximity() {
+        let mut index = Index::temporary().expect("Unable to open index");
+
+        for (url, body) in [
+            ("https://www.a.com", "alpha beta delta delta delta delta"),
+            ("https://www.b.com", "alpha delta delta delta delta beta"),
+            ("https://www.c.com", "alpha delta delta delta delta delta"),
+        ] {
+            index
+                .insert(&Webpage {
+                    html: Html::parse(
+                        &format!(
+                            r#"
+                        <html>
+                            <head>
+                                <title>Test website</title>
+                            </head>
+                            <body>
+                                {body}
+                            </body>
+                        </html>
+                    "#
+                        ),
+                        url,
+                    )
+                    .unwrap(),
+                    ..Default::default()
+                })
+                .expect("failed to insert webpage");
+        }
+
+        index.commit().expect("failed to commit index");
+
+        let ctx = index.inverted_index.local_search_ctx();
+        let query = Query::parse(
+            &ctx,
+            &SearchQuery {
+                query: "alpha beta".to_string(),
+                ..Default::default()
+            },
+            &index.inverted_index,
+        )
+        .unwrap();
+
+        let mut computer = SignalComputer::new(Some(&query));
+        let segment = ctx.tv_searcher.segment_reader(0);
+        computer
+            .register_segment(&ctx.tv_searcher, segment, &ctx.fastfield_reader)
+            .unwrap();
+
+        let mut proximity: Vec<_> = (0..segment.max_doc())
+            .map(|doc| Proximity.compute(doc, &computer).unwrap())
+            .collect();
+        proximity.sort_by(|a, b| a.total_cmp(b));
+
+        assert_eq!(proximity[0], 0.0);
+        assert!(proximity[1] > 0.0);
+        assert!(proximity[1] < proximity[2]);
+        assert_eq!(proximity[2], 1.0);
+    }
+
+    #[test]
+    fn content_length() {
+        let mut index = Index::temporary().expect("Unable to open index");
+
+        for (url, body) in [
+            ("https://www.a.com", "rust".to_string()),
+            (
+                "https://www.b.com",
+                format!("rust {}", crate::rand_words(800)),
+            ),
+            (
+                "https://www.c.com",
+                format!("rust {}", crate::rand_words(20_000)),
+            ),
+        ] {
+            index
+                .insert(&Webpage {
+                    html: Html::parse(
+                        &format!(
+                            r#"
+                        <html>
+                            <head>
+                                <title>Test website</title>
+                            </head>
+                            <body>
+                                {body}
+                            </body>
+                        </html>
+                    "#
+                        ),
+                        url,
+                    )
+                    .unwrap(),
+                    ..Default::default()
+                })
+                .expect("failed to insert webpage");
+        }
+
+        index.commit().expect("failed to commit index");
+
+        let ctx = index.inverted_index.local_search_ctx();
+        let query = Query::parse(
+            &ctx,
+            &SearchQuery {
+                query: "rust".to_string(),
+                ..Default::default()
+            },
+            &index.inverted_index,
+        )
+        .unwrap();
+
+        let mut computer = SignalComputer::new(Some(&query));
+        let segment = ctx.tv_searcher.segment_reader(0);
+        computer
+            .register_segment(&ctx.tv_searcher, segment, &ctx.fastfield_reader)
+            .unwrap();
+
+        let field = ctx
+            .tv_searcher
+            .schema()
+            .get_field(
+                crate::schema::Field::Text(crate::schema::text_field::CleanBody.into()).name(),
+            )
+            .unwrap();
+        let fieldnorms = segment.get_fieldnorms_reader(field).unwrap();
+
+        let mut lengths: Vec<_> = (0..segment.max_doc())
+            .map(|doc| {
+                (
+                    fieldnorms.fieldnorm(doc),
+                    ContentLength.compute(doc, &computer).unwrap(),
+                )
+            })
+            .collect();
+        lengths.sort_by_key(|(num_tokens, _)| *num_tokens);
+
+        let (thin, normal, huge) = (lengths[0].1, lengths[1].1, lengths[2].1);
+
+        assert!(thin < 0.01);
+        assert!(normal > 0.9);
+        assert!(huge < 0.5);
+        assert!(huge > thin);
+    }
+
+    #[test]
+    fn tld_reputation() {
+        let mut index = Index::temporary().expect("Unable to open index");
+
+        for url in [
+            "https://www.example.org",
+            "https://www.example.xyz",
+            "https://www.example.dev",
+        ] {
+            index
+                .insert(&Webpage {
+                    html: Html::parse(
+                        &format!(
+                            r#"
+                        <html>
+                            <head>
+                                <title>Test website</title>
+                            </head>
+                            <body>
+                                {CONTENT}
+                            </body>
+                        </html>
+                    "#
+                        ),
+                        url,
+                    )
+                    .unwrap(),
+                    ..Default::default()
+                })
+                .expect("failed to insert webpage");
+        }
+
+        index.commit().expect("failed to commit index");
+
+        let table = Arc::new(super::tld_reputation::TldReputation::new([
+            ("org".to_string(), 0.9),
+            ("xyz".to_string(), 0.1),
+        ]));
+
+        let ctx = index.inverted_index.local_search_ctx();
+        let mut computer = SignalComputer::new(None);
+        computer.set_tld_reputation(table);
+        let segment = ctx.tv_searcher.segment_reader(0);
+        computer
+            .register_segment(&ctx.tv_searcher, segment, &ctx.fastfield_reader)
+            .unwrap();
+
+        let scores: HashMap<_, _> = (0..segment.max_doc())
+            .map(|doc| {
+                let host = computer
+                    .segment_reader()
+                    .unwrap()
+                    .fastfield_reader()
+                    .get_bytes(doc, crate::schema::fast_field::Host)
+                    .and_then(|host| String::from_utf8(host).ok())
+                    .unwrap();
+
+                (host, TldReputation.compute(doc, &computer).unwrap())
+            })
+            .collect();
+
+        assert_eq!(scores["example.org"], 0.9);
+        assert_eq!(scores["example.xyz"], 0.1);
+        assert_eq!(
+            scores["example.dev"],
+            super::tld_reputation::NEUTRAL_REPUTATION
+        );
+
+        let webpage = Webpage {
+            html: Html::parse("<html></html>", "https://www.example.xyz").unwrap(),
+            ..Default::default()
+        };
+        assert_eq!(TldReputation.precompute(&webpage, &computer), Some(0.1));
+    }
+
+    #[test]
+    fn link_density() {
+        let mut index = Index::temporary().expect("Unable to open index");
+
+        let links: String = (0..30)
+            .map(|i| format!(r#"<a href="https://www.example.com/{i}">link</a> "#))
+            .collect();
+
+        let prose = Webpage {
+            html: Html::parse(
+                &format!(
+                    r#"
+                    <html>
+                        <head>
+                            <title>Test website</title>
+                        </head>
+                        <body>
+                            <p>{}</p>
+                            <a href="https://www.example.com/">example</a>
+                        </body>
+                    </html>
+                "#,
+                    crate::rand_words(300)
+                ),
+                "https://www.prose.com",
+            )
+            .unwrap(),
+            ..Default::default()
+        };
+
+        let link_heavy = Webpage {
+            html: Html::parse(
+                &format!(
+                    r#"
+                    <html>
+                        <head>
+                            <title>Test website</title>
+                        </head>
+                        <body>
+                            <p>{links}</p>
+                        </body>
+                    </html>
+                "#
+                ),
+                "https://www.links.com",
+            )
+            .unwrap(),
+            ..Default::default()
+        };
+
+        let computer = SignalComputer::new(None);
+        assert_eq!(LinkDensity.precompute(&prose, &computer), Some(1.0));
+        assert_eq!(LinkDensity.precompute(&link_heavy, &computer), Some(0.0));
+
+        index.insert(&prose).expect("failed to insert webpage");
+        index.insert(&link_heavy).expect("failed to insert webpage");
+        index.commit().expect("failed to commit index");
+
+        let ctx = index.inverted_index.local_search_ctx();
+        let mut computer = SignalComputer::new(None);
+        let segment = ctx.tv_searcher.segment_reader(0);
+        computer
+            .register_segment(&ctx.tv_searcher, segment, &ctx.fastfield_reader)
+            .unwrap();
+
+        let mut scores: Vec<_> = (0..segment.max_doc())
+            .map(|doc| LinkDensity.compute(doc, &computer).unwrap())
+            .collect();
+        scores.sort_by(|a, b| a.total_cmp(b));
+
+        assert_eq!(scores, vec![0.0, 1.0]);
+
+        // documents without a link density are neither rewarded nor penalized
+        assert_eq!(LinkDensity.compute(segment.max_doc(), &computer), Some(0.5));
+    }
+
+    #[test]
+    fn anchor_text_match() {
+        let mut index = Index::temporary().expect("Unable to open index");
+
+        for (url, body, backlink_labels) in [
+            (
+                "https://www.a.com",
+                "delta epsilon",
+                vec!["alpha beta".to_string(), "gamma".to_string()],
+            ),
+            (
+                "https://www.b.com",
+                "delta epsilon",
+                vec!["beta".to_string()],
+            ),
+            ("https://www.c.com", "alpha beta gamma", vec![]),
+        ] {
+            index
+                .insert(&Webpage {
+                    html: Html::parse(
+                        &format!(
+                            r#"
+                        <html>
+                            <head>
+                                <title>Test website</title>
+                            </head>
+                            <body>
+                                {body}
+                            </body>
+                        </html>
+                    "#
+                        ),
+                        url,
+                    )
+                    .unwrap(),
+                    backlink_labels,
+                    ..Default::default()
+                })
+                .expect("failed to insert webpage");
+        }
+
+        index.commit().expect("failed to commit index");
+
+        let ctx = index.inverted_index.local_search_ctx();
+        let query = Query::parse(
+            &ctx,
+            &SearchQuery {
+                query: "alpha beta gamma".to_string(),
+                ..Default::default()
+            },
+            &index.inverted_index,
+        )
+        .unwrap();
+
+        let mut computer = SignalComputer::new(Some(&query));
+        let segment = ctx.tv_searcher.segment_reader(0);
+        computer
+            .register_segment(&ctx.tv_searcher, segment, &ctx.fastfield_reader)
+            .unwrap();
+
+        let mut scores: Vec<_> = (0..segment.max_doc())
+            .map(|doc| {
+                (
+                    AnchorTextMatch.compute(doc, &computer).unwrap(),
+                    TermCoverage.compute(doc, &computer).unwrap(),
+                )
+            })
+            .collect();
+        scores.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+
+        // the body of c.com matches the query, but none of its anchors do
+        assert_eq!(scores, vec![(0.0, 1.0), (1.0 / 3.0, 0.0), (1.0, 0.0)]);
+    }
+
+    #[test]
+    fn signal_priority() {
+        let mut index = Index::temporary().expect("Unable to open index");
+
+        index
+            .insert(&Webpage {
+                html: Html::parse(
+                    &format!(
+                        r#"
+                    <html>
+                        <head>
+                            <title>Example website</title>
+                        </head>
+                        <body>
+                            {CONTENT}
+                        </body>
+                    </html>
+                "#
+                    ),
+                    "https://www.example.com",
+                )
+                .unwrap(),
+                host_centrality: 1.0,
+                fetch_time_ms: 500,
+                ..Default::default()
+            })
+            .expect("failed to insert webpage");
+        index.commit().expect("failed to commit index");
+
+        let ctx = index.inverted_index.local_search_ctx();
+        let query = Query::parse(
+            &ctx,
+            &SearchQuery {
+                query: "example".to_string(),
+                ..Default::default()
+            },
+            &index.inverted_index,
+        )
+        .unwrap();
+
+        let computed_order = |computer: &mut SignalComputer| {
+            computer
+                .register_segment(
+                    &ctx.tv_searcher,
+                    ctx.tv_searcher.segment_reader(0),
+                    &ctx.fastfield_reader,
+                )
+                .unwrap();
+
+            let signals: Vec<_> = computer
+                .compute_signals(0, None)
+                .flatten()
+                .map(|computed| computed.signal)
+                .collect();
+
+            let pos = |signal: SignalEnum| signals.iter().position(|s| *s == signal).unwrap();
+
+            (
+                signals[0],
+                pos(super::FetchTimeMs.into()),
+                pos(super::Bm25Title.into()),
+                pos(super::HostCentrality.into()),
+            )
+        };
+
+        let mut computer = SignalComputer::new(Some(&query));
+        let (_, fetch_time, title, host_centrality) = computed_order(&mut computer);
+        assert!(title < host_centrality);
+        assert!(host_centrality < fetch_time);
+
+        let mut computer = SignalComputer::new(Some(&query));
+        computer.set_signal_priority(&[
+            super::FetchTimeMs.into(),
+            super::Bm25Title.into(),
+            super::HostCentrality.into(),
+        ]);
+        let (first, fetch_time, title, host_centrality) = computed_order(&mut computer);
+        assert_eq!(first, super::FetchTimeMs.into());
+        assert!(fetch_time < title);
+        assert!(title < host_centrality);
+    }
+
+    #[test]
+    fn disabled_signals() {
+        let mut index = Index::temporary().expect("Unable to open index");
+
+        index
+            .insert(&Webpage {
+                html: Html::parse(
+                    &format!(
+                        r#"
+                    <html>
+                        <head>
+                            <title>Example website</title>
+                        </head>
+                        <body>
+                            {CONTENT}
+                        </body>
+                    </html>
+                "#
+                    ),
+                    "https://www.example.com",
+                )
+                .unwrap(),
+                host_centrality: 1.0,
+                fetch_time_ms: 500,
+                ..Default::default()
+            })
+            .expect("failed to insert webpage");
+        index.commit().expect("failed to commit index");
+
+        let disabled: Vec<SignalEnum> = vec![super::HostCentrality.into(), super::Bm25Title.into()];
+
+        let ctx = index.inverted_index.local_search_ctx();
+        let query = Query::parse(
+            &ctx,
+            &SearchQuery {
+                query: "example".to_string(),
+                disabled_signals: disabled.clone(),
+                ..Default::default()
+            },
+            &index.inverted_index,
+        )
+        .unwrap();
+
+        let mut computer = SignalComputer::new(Some(&query));
+        computer
+            .register_segment(
+                &ctx.tv_searcher,
+                ctx.tv_searcher.segment_reader(0),
+                &ctx.fastfield_reader,
+            )
+            .unwrap();
+
+        let computed: Vec<_> = computer
+            .compute_signals(0, None)
+            .flatten()
+            .map(|computed| computed.signal)
+            .collect();
+
+        assert!(computed.contains(&super::FetchTimeMs.into()));
+        for signal in &disabled {
+            assert!(!computed.contains(signal));
+            assert_eq!(computer.coefficient(signal), 0.0);
+        }
+
+        let searcher = LocalSearcher::from(index);
+
+        let ranking_signals = |disabled_signals: Vec<SignalEnum>| {
+            let res = searcher
+                .search(&SearchQuery {
+                    query: "example".to_string(),
+                    return_ranking_signals: true,
+                    disabled_signals,
+                    ..Default::default()
+                })
+                .unwrap();
+
+            assert_eq!(res.webpages.len(), 1);
+            res.webpages[0].ranking_signals.clone().unwrap()
+        };
+
+        let enabled = ranking_signals(Vec::new());
+        let without = ranking_signals(disabled.clone());
+
+        for signal in disabled {
+            assert!(enabled.contains_key(&signal.into()));
+            assert!(!without.contains_key(&signal.into()));
+        }
+    }
+
+    #[test]
+    fn signal_cutoff() {
+        let mut index = Index::temporary().expect("Unable to open index");
+
+        index
+            .insert(&Webpage {
+                html: Html::parse(
+                    &format!(
+                        r#"
+                    <html>
+                        <head>
+                            <title>Example website</title>
+                        </head>
+                        <body>
+                            {CONTENT}
+                        </body>
+                    </html>
+                "#
+                    ),
+                    "https://www.example.com",
+                )
+                .unwrap(),
+                host_centrality: 1.0,
+                fetch_time_ms: 500,
+                ..Default::default()
+            })
+            .expect("failed to insert webpage");
+        index.commit().expect("failed to commit index");
+
+        let ctx = index.inverted_index.local_search_ctx();
+        let query = Query::parse(
+            &ctx,
+            &SearchQuery {
+                query: "example".to_string(),
+                ..Default::default()
+            },
+            &index.inverted_index,
+        )
+        .unwrap();
+
+        // the bounded signals are computed last, so they can be skipped
+        let unbounded: Vec<_> = SignalEnum::all()
+            .filter(|signal| signal.max_value().is_none())
+            .collect();
+
+        let mut computer = SignalComputer::new(Some(&query));
+        computer.set_signal_priority(&unbounded);
+        computer
+            .register_segment(
+                &ctx.tv_searcher,
+                ctx.tv_searcher.segment_reader(0),
+                &ctx.fastfield_reader,
+            )
+            .unwrap();
+
+        let compute = |cutoff: Option<f64>| {
+            let mut signals = computer.compute_signals(0, cutoff);
+            let computed: Vec<_> = signals.by_ref().flatten().map(|c| c.signal).collect();
+
+            (computed, signals.is_pruned())
+        };
+
+        let (all, pruned) = compute(None);
+        assert!(!pruned);
+        assert!(all.contains(&super::FetchTimeMs.into()));
+
+        let (low_cutoff, pruned) = compute(Some(0.0));
+        assert!(!pruned);
+        assert_eq!(low_cutoff, all);
+
+        let (high_cutoff, pruned) = compute(Some(1_000_000.0));
+        assert!(pruned);
+        assert!(high_cutoff.len() < all.len());
+        assert!(!high_cutoff.contains(&super::FetchTimeMs.into()));
+        assert!(high_cutoff
+            .iter()
+            .all(|signal| signal.max_value().is_none()));
+    }
+
+    #[test]
+    fn signal_vector_features() {
+        let mut index = Index::temporary().expect("Unable to open index");
+
+        index
+            .insert(&Webpage {
+                html: Html::parse(
+                    &format!(
+                        r#"
+                    <html>
+                        <head>
+                            <title>Example website</title>
+                        </head>
+                        <body>
+                            {CONTENT}
+                        </body>
+                    </html>
+                "#
+                    ),
+                    "https://www.example.com",
+                )
+                .unwrap(),
+                host_centrality: 1.0,
+                fetch_time_ms: 500,
+                ..Default::default()
+            })
+            .expect("failed to insert webpage");
+        index.commit().expect("failed to commit index");
+
+        let ctx = index.inverted_index.local_search_ctx();
+        let query = Query::parse(
+            &ctx,
+            &SearchQuery {
+                query: "example".to_string(),
+                ..Default::default()
+            },
+            &index.inverted_index,
+        )
+        .unwrap();
+
+        let mut computer = SignalComputer::new(Some(&query));
+        computer
+            .register_segment(
+                &ctx.tv_searcher,
+                ctx.tv_searcher.segment_reader(0),
+                &ctx.fastfield_reader,
+            )
+            .unwrap();
+
+        let mut signals = EnumMap::new();
+        for computed in computer.compute_signals(0, None).flatten() {
+            signals.insert(computed.signal, computed.score);
+        }
+
+        let features = SignalVector::from(&signals);
+        assert_eq!(features.len(), SignalEnum::num_variants());
+
+        for (signal, value) in SignalVector::layout().zip(features.as_slice()) {
+            let expected = signals
+                .get(signal.into())
+                .map(|score| score.value)
+                .unwrap_or(0.0);
+            assert_eq!(*value, expected);
+        }
+
+        let searcher = LocalSearcher::from(index);
+
+        let res = searcher
+            .search(&SearchQuery {
+                query: "example".to_string(),
+                return_ranking_signals: true,
+                return_features: true,
+                ..Default::default()
+            })
+            .unwrap();
+
+        assert_eq!(res.webpages.len(), 1);
+        assert_eq!(res.features_layout, Some(SignalVector::layout_names()));
+
+        let features = res.webpages[0].features.as_ref().unwrap();
+        let ranking_signals = res.webpages[0].ranking_signals.as_ref().unwrap();
+        assert_eq!(features.len(), SignalEnum::num_variants());
+
+        for (signal, value) in SignalVector::layout().zip(features.as_slice()) {
+            let expected = ranking_signals
+                .get(&signal)
+                .map(|score| score.value)
+                .unwrap_or(0.0);
+            assert_eq!(*value, expected);
+        }
+    }
+
+    struct SlowScoreTweaker {
+        inner: InitialScoreTweaker,
+        delay: Duration,
+    }
+
+    impl ScoreTweaker<Score> for SlowScoreTweaker {
+        type Child = SlowSegmentScoreTweaker;
+
+        fn segment_tweaker(
+            &self,
+            segment_reader: &tantivy::SegmentReader,
+        ) -> tantivy::Result<Self::Child> {
+            Ok(SlowSegmentScoreTweaker {
+                inner: self.inner.segment_tweaker(segment_reader)?,
+                delay: self.delay,
+            })
+        }
+    }
+
+    struct SlowSegmentScoreTweaker {
+        inner: InitialSegmentScoreTweaker,
+        delay: Duration,
+    }
+
+    impl ScoreSegmentTweaker<Score> for SlowSegmentScoreTweaker {
+        fn score(&mut self, doc: tantivy::DocId, score: tantivy::Score) -> Score {
+            std::thread::sleep(self.delay);
+            self.inner.score(doc, score)
+        }
+    }
+
+    impl PruningScoreSegmentTweaker for SlowSegmentScoreTweaker {
+        fn score_with_cutoff(
+            &mut self,
+            doc: tantivy::DocId,
+            score: tantivy::Score,
+            cutoff: f64,
+        ) -> Option<Score> {
+            std::thread::sleep(self.delay);
+            self.inner.score_with_cutoff(doc, score, cutoff)
+        }
+    }
+
+    /// Records the documents whose scoring was stopped by the cutoff.
+    struct PruneRecorder {
+        inner: InitialScoreTweaker,
+        pruned: Arc<std::sync::Mutex<Vec<tantivy::DocId>>>,
+    }
+
+    impl ScoreTweaker<Score> for PruneRecorder {
+        type Child = PruneRecorderSegment;
+
+        fn segment_tweaker(
+            &self,
+            segment_reader: &tantivy::SegmentReader,
+        ) -> tantivy::Result<Self::Child> {
+            Ok(PruneRecorderSegment {
+                inner: self.inner.segment_tweaker(segment_reader)?,
+                pruned: self.pruned.clone(),
+            })
+        }
+    }
+
+    struct PruneRecorderSegment {
+        inner: InitialSegmentScoreTweaker,
+        pruned: Arc<std::sync::Mutex<Vec<tantivy::DocId>>>,
+    }
+
+    impl ScoreSegmentTweaker<Score> for PruneRecorderSegment {
+        fn score(&mut self, doc: tantivy::DocId, score: tantivy::Score) -> Score {
+            self.inner.score(doc, score)
+        }
+    }
+
+    impl PruningScoreSegmentTweaker for PruneRecorderSegment {
+        fn score_with_cutoff(
+            &mut self,
+            doc: tantivy::DocId,
+            score: tantivy::Score,
+            cutoff: f64,
+        ) -> Option<Score> {
+            let res = self.inner.score_with_cutoff(doc, score, cutoff);
+
+            if res.is_none() {
+                self.pruned.lock().unwrap().push(doc);
+            }
+
+            res
+        }
+    }
+
+    #[test]
+    fn pruned_docs_not_in_top_results() {
+        let mut index = Index::temporary().expect("Unable to open index");
+
+        // the documents with the query in the title are scored first, so the
+        // cutoff is known when the documents that only match in the body are scored.
+        let titles = (0..3)
+            .map(|i| format!("Example website {i}"))
+            .chain((0..5).map(|i| format!("Website {i}")));
+
+        for (i, title) in titles.enumerate() {
+            index
+                .insert(&Webpage {
+                    html: Html::parse(
+                        &format!(
+                            r#"
+                        <html>
+                            <head>
+                                <title>{title}</title>
+                            </head>
+                            <body>
+                                {CONTENT}
+                            </body>
+                        </html>
+                    "#
+                        ),
+                        &format!("https://www.{i}.com"),
+                    )
+                    .unwrap(),
+                    host_centrality: (i % 3) as f64 / 3.0,
+                    fetch_time_ms: 500,
+                    ..Default::default()
+                })
+                .expect("failed to insert webpage");
+        }
+        index.commit().expect("failed to commit index");
+
+        let ctx = index.inverted_index.local_search_ctx();
+        let query = Query::parse(
+            &ctx,
+            &SearchQuery {
+                query: "example".to_string(),
+                ..Default::default()
+            },
+            &index.inverted_index,
+        )
+        .unwrap();
+
+        // only the title bm25 and the bounded host centrality contribute to the score
+        let mut weights = EnumMap::new();
+        for signal in SignalEnum::all() {
+            weights.insert(signal, 0.0);
+        }
+        weights.insert(super::Bm25Title.into(), 1.0);
+        weights.insert(super::HostCentrality.into(), 0.001);
+
+        let mut computer = SignalComputer::new(Some(&query));
+        computer.set_linear_model(Arc::new(LinearRegression { weights }));
+        computer.set_signal_priority(&[super::Bm25Title.into()]);
+
+        let search = |top_docs: TopDocs| {
+            let pruned = Arc::new(std::sync::Mutex::new(Vec::new()));
+            let collector = TweakedScoreTopCollector::new(
+                PruneRecorder {
+                    inner: InitialScoreTweaker::new(
+                        ctx.tv_searcher.clone(),
+                        computer.clone(),
+                        ctx.fastfield_reader.clone(),
+                    ),
+                    pruned: pruned.clone(),
+                },
+                top_docs,
+            );
+
+            let res: Vec<_> = ctx
+                .tv_searcher
+                .search(&query, &collector)
+                .unwrap()
+                .into_iter()
+                .map(|pointer| pointer.address)
+                .collect();
+
+            let pruned = pruned.lock().unwrap().clone();
+            (res, pruned)
+        };
+
+        let (all, pruned) = search(TopDocs::with_limit(2, ctx.fastfield_reader.clone()));
+        assert!(pruned.is_empty());
+
+        let (with_cutoff, pruned) =
+            search(TopDocs::with_limit(2, ctx.fastfield_reader.clone()).and_score_cutoff());
+
+        assert_eq!(with_cutoff, all);
+        assert!(!pruned.is_empty());
+        assert!(with_cutoff
+            .iter()
+            .all(|address| !pruned.contains(&address.doc_id)));
+
+        // the documents that only match in the body can't reach the top results
+        let (all, _) = search(TopDocs::with_limit(8, ctx.fastfield_reader.clone()));
+        for doc in pruned {
+            let rank = all
+                .iter()
+                .position(|address| address.doc_id == doc)
+                .unwrap();
+            assert!(rank >= 2);
+        }
+    }
+
+    #[test]
+    fn deadline_returns_partial_results() {
+        let mut index = Index::temporary().expect("Unable to open index");
+
+        for (url, host_centrality) in [
+            ("https://www.a.com", 1.0),
+            ("https://www.b.com", 0.5),
+            ("https://www.c.com", 0.1),
+        ] {
+            index
+                .insert(&Webpage {
+                    html: Html::parse(
+                        &format!(
+                            r#"
+                        <html>
+                            <head>
+                                <title>Example website</title>
+                            </head>
+                            <body>
+                                {CONTENT}
+                            </body>
+                        </html>
+                    "#
+                        ),
+                        url,
+                    )
+                    .unwrap(),
+                    host_centrality,
+                    fetch_time_ms: 500,
+                    ..Default::default()
+                })
+                .expect("failed to insert webpage");
+        }
+        index.commit().expect("failed to commit index");
+
+        let ctx = index.inverted_index.local_search_ctx();
+        let query = Query::parse(
+            &ctx,
+            &SearchQuery {
+                query: "example".to_string(),
+                ..Default::default()
+            },
+            &index.inverted_index,
+        )
+        .unwrap();
+
+        // every document takes 100ms to score, so the deadline is hit before
+        // all three documents are scored.
+        let deadline = Deadline::new(Duration::from_millis(150));
+        let collector = TweakedScoreTopCollector::new(
+            SlowScoreTweaker {
+                inner: InitialScoreTweaker::new(
+                    ctx.tv_searcher.clone(),
+                    SignalComputer::new(Some(&query)),
+                    ctx.fastfield_reader.clone(),
+                ),
+                delay: Duration::from_millis(100),
+            },
+            TopDocs::with_limit(10, ctx.fastfield_reader.clone()).and_deadline(deadline.clone()),
+        );
+
+        let res = ctx.tv_searcher.search(&query, &collector).unwrap();
+
+        assert!(deadline.is_reached());
+        assert!(!res.is_empty());
+        assert!(res.len() < 3);
+        assert!(res
+            .windows(2)
+            .all(|pair| pair[0].score.total >= pair[1].score.total));
+
+        let searcher = LocalSearcher::from(index);
+
+        let res = searcher
+            .search(&SearchQuery {
+                query: "example".to_string(),
+                timeout_ms: Some(0),
+                ..Default::default()
+            })
+            .unwrap();
+        assert!(res.partial);
+        assert!(res.webpages.is_empty());
+
+        let res = searcher
+            .search(&SearchQuery {
+                query: "example".to_string(),
+                timeout_ms: Some(60_000),
+                ..Default::default()
+            })
+            .unwrap();
+        assert!(!res.partial);
+        assert_eq!(res.webpages.len(), 3);
+    }
+
+    #[test]
+    fn fetch_time_score() {
+        let computer = SignalComputer::new(None);
+
+        assert_eq!(computer.fetch_time_score(0.0), 1.0);
+        assert_eq!(computer.fetch_time_score(999.0), 1.0 / 1000.0);
+
+        let slow = computer.fetch_time_score(5_000.0);
+        assert!(slow > 0.0);
+        assert!(slow < computer.fetch_time_score(999.0));
+        assert!(slow < computer.fetch_time_score(1_000.0));
+        assert!(slow > computer.fetch_time_score(20_000.0));
+
+        // fetch times in the same bucket get the same score
+        assert_eq!(slow, computer.fetch_time_score(4_500.0));
+
+        assert_eq!(computer.fetch_time_score(-1.0), 1.0);
+    }
+
+    fn setup_worker(data_path: &Path) -> IndexingWorker {
+        IndexingWorker::new(IndexingLocalConfig {
+            host_centrality_store_path: crate::gen_temp_path().to_str().unwrap().to_string(),
+            page_centrality_store_path: None,
+            page_webgraph_path: None,
+            topics_path: None,
+            safety_classifier_path: None,
+            dual_encoder: Some(IndexingDualEncoderConfig {
+                model_path: data_path.to_str().unwrap().to_string(),
+                page_centrality_rank_threshold: None,
+            }),
+            output_path: crate::gen_temp_path().to_str().unwrap().to_string(),
+            limit_warc_files: None,
+            skip_warc_files: None,
+            warc_source: WarcSource::Local(crate::config::LocalConfig {
+                folder: crate::gen_temp_path().to_str().unwrap().to_string(),
+                names: vec!["".to_string()],
+            }),
+            host_centrality_threshold: None,
+            minimum_clean_words: None,
+            batch_size: 10,
+        })
+    }
+
+    #[test]
+    fn title_embeddings() {
+        let data_path = Path::new("../../data/summarizer/dual_encoder");
+        if !data_path.exists() {
+            // Skip the test if the test data is not available
+            return;
+        }
+
+        let worker = setup_worker(data_path);
+
+        let mut index = Index::temporary().expect("Unable to open index");
+
+        let mut pages = vec![
+            Webpage::test_parse(
+                &format!(
+                    r#"
+                <html>
+                    <head>
+                        <title>Homemade Heart Brownie Recipe</title>
+                    </head>
+                    <body>
+                        best chocolate cake {CONTENT} {}
+                    </body>
+                </html>
+            "#,
+                    crate::rand_words(100)
+                ),
+                "https://www.a.com/",
+            )
+            .unwrap(),
+            Webpage::test_parse(
+                &format!(
+                    r#"
+                <html>
+                    <head>
+                        <title>How To Best Use an iMac as a Monitor for a PC</title>
+                    </head>
+                    <body>
+                        best chocolate cake {CONTENT} {}
+                    </body>
+                </html>
+            "#,
+                    crate::rand_words(100)
+                ),
+                "https://www.b.com/",
+            )
+            .unwrap(),
+        ];
+
+        worker.set_title_embeddings(&mut pages);
+        assert!(pages.iter().all(|p| p.title_embedding.is_some()));
+
+        for page in pages {
+            index.insert(&page).expect("failed to insert webpage");
+        }
+
+        index.commit().expect("failed to commit index");
+
+        let mut searcher = LocalSearcher::new(index);
+        searcher
+            .set_dual_encoder(DualEncoder::open(data_path).expect("failed to open dual encoder"));
+
+        let result = searcher
+            .search(&SearchQuery {
+                query: "best chocolate cake".to_string(),
+                optic: Some(Optic {
+                    rankings: vec![RankingCoeff {
+                        target: RankingTarget::Signal("title_embedding_similarity".to_string()),
+                        value: 100_000.0,
+                    }],
+                    ..Default::default()
+                }),
+                ..Default::default()
+            })
+            .expect("Search failed");
+
+        assert_eq!(result.webpages.len(), 2);
+        assert_eq!(result.webpages[0].url, "https://www.a.com/");
+    }
+
+    #[test]
+    fn keyword_embeddings() {
+        let data_path = Path::new("../../data/summarizer/dual_encoder");
+        if !data_path.exists() {
+            // Skip the test if the test data is not available
+            return;
+        }
+
+        let worker = setup_worker(data_path);
+
+        let mut index = Index::temporary().expect("Unable to open index");
+
+        let mut a = Webpage::test_parse(
+            &format!(
+                r#"
+                <html>
+                    <head>
+                        <title>Homemade Heart Brownie Recipe</title>
+                    </head>
                     <body>
                         best chocolate cake {CONTENT} {}
                     </body>
@@ -966,4 +2846,525 @@ mod tests {
         assert_eq!(result.webpages.len(), 2);
         assert_eq!(result.webpages[0].url, "https://www.a.com/");
     }
+
+    #[test]
+    fn mobile_friendly() {
+        let mut index = Index::temporary().expect("Unable to open index");
+
+        for (url, head) in [
+            (
+                "https://www.mobile.com",
+                r#"<meta name="viewport" content="width=device-width, initial-scale=1">"#,
+            ),
+            ("https://www.desktop.com", ""),
+        ] {
+            index
+                .insert(&Webpage {
+                    html: Html::parse(
+                        &format!(
+                            r#"
+                    <html>
+                        <head>
+                            <title>Example website</title>
+                            {head}
+                        </head>
+                        <body>
+                            {CONTENT}
+                        </body>
+                    </html>
+                "#
+                        ),
+                        url,
+                    )
+                    .unwrap(),
+                    ..Default::default()
+                })
+                .expect("failed to insert webpage");
+        }
+        index.commit().expect("failed to commit index");
+
+        let searcher = LocalSearcher::from(index);
+
+        let search = |device: Device| {
+            searcher
+                .search(&SearchQuery {
+                    query: "example".to_string(),
+                    return_ranking_signals: true,
+                    device,
+                    ..Default::default()
+                })
+                .unwrap()
+                .webpages
+        };
+
+        let signal = SignalEnumDiscriminants::MobileFriendly;
+
+        let mobile = search(Device::Mobile);
+        assert_eq!(mobile.len(), 2);
+        assert_eq!(mobile[0].url, "https://www.mobile.com/");
+        assert!(mobile[0].score.unwrap() > mobile[1].score.unwrap());
+
+        let score = |webpage: &crate::search_prettifier::DisplayedWebpage| {
+            webpage.ranking_signals.as_ref().unwrap()[&signal].value
+        };
+        assert_eq!(score(&mobile[0]), 1.0);
+        assert_eq!(score(&mobile[1]), 0.0);
+
+        // the signal is not computed for desktop searches
+        let desktop = search(Device::Desktop);
+        assert_eq!(desktop.len(), 2);
+        for webpage in &desktop {
+            assert!(!webpage
+                .ranking_signals
+                .as_ref()
+                .unwrap()
+                .contains_key(&signal));
+        }
+    }
+
+    #[test]
+    fn has_structured_data() {
+        let mut index = Index::temporary().expect("Unable to open index");
+
+        let structured = Webpage {
+            html: Html::parse(
+                r#"
+                    <html>
+                        <head>
+                            <title>Test website</title>
+                            <script type="application/ld+json">
+                                {"@context": "https://schema.org", "@type": "Article", "headline": "Test"}
+                            </script>
+                        </head>
+                        <body>
+                            test
+                        </body>
+                    </html>
+                "#,
+                "https://www.structured.com",
+            )
+            .unwrap(),
+            ..Default::default()
+        };
+
+        let plain = Webpage {
+            html: Html::parse(
+                r#"
+                    <html>
+                        <head>
+                            <title>Test website</title>
+                        </head>
+                        <body>
+                            test
+                        </body>
+                    </html>
+                "#,
+                "https://www.plain.com",
+            )
+            .unwrap(),
+            ..Default::default()
+        };
+
+        let computer = SignalComputer::new(None);
+        assert_eq!(
+            HasStructuredData.precompute(&structured, &computer),
+            Some(1.0)
+        );
+        assert_eq!(HasStructuredData.precompute(&plain, &computer), Some(0.0));
+
+        index.insert(&structured).expect("failed to insert webpage");
+        index.insert(&plain).expect("failed to insert webpage");
+        index.commit().expect("failed to commit index");
+
+        let ctx = index.inverted_index.local_search_ctx();
+        let mut computer = SignalComputer::new(None);
+        let segment = ctx.tv_searcher.segment_reader(0);
+        computer
+            .register_segment(&ctx.tv_searcher, segment, &ctx.fastfield_reader)
+            .unwrap();
+
+        let mut scores: Vec<_> = (0..segment.max_doc())
+            .map(|doc| HasStructuredData.compute(doc, &computer).unwrap())
+            .collect();
+        scores.sort_by(|a, b| a.total_cmp(b));
+
+        assert_eq!(scores, vec![0.0, 1.0]);
+
+        let searcher = LocalSearcher::from(index);
+        let result = searcher
+            .search(&SearchQuery {
+                query: "test".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+
+        assert_eq!(result.webpages.len(), 2);
+        assert_eq!(result.webpages[0].url, "https://www.structured.com/");
+    }
+
+    #[test]
+    fn homepage_boost() {
+        let mut index = Index::temporary().expect("Unable to open index");
+
+        for url in [
+            "https://www.github.com",
+            "https://www.github.com/features/actions",
+            "https://www.example.com",
+        ] {
+            index
+                .insert(&Webpage {
+                    html: Html::parse(
+                        &format!(
+                            r#"
+                        <html>
+                            <head>
+                                <title>Test website</title>
+                            </head>
+                            <body>
+                                how to use github actions {CONTENT} {}
+                            </body>
+                        </html>
+                    "#,
+                            crate::rand_words(100)
+                        ),
+                        url,
+                    )
+                    .unwrap(),
+                    host_centrality: 1.0,
+                    fetch_time_ms: 500,
+                    ..Default::default()
+                })
+                .expect("failed to insert webpage");
+        }
+
+        index.commit().expect("failed to commit index");
+
+        let ctx = index.inverted_index.local_search_ctx();
+        let homepage = Webpage {
+            html: Html::parse("", "https://www.github.com").unwrap(),
+            ..Default::default()
+        };
+        let subpage = Webpage {
+            html: Html::parse("", "https://www.github.com/features/actions").unwrap(),
+            ..Default::default()
+        };
+
+        for (query, expected) in [("github", 1.0), ("how to use github actions", 0.0)] {
+            let query = Query::parse(
+                &ctx,
+                &SearchQuery {
+                    query: query.to_string(),
+                    ..Default::default()
+                },
+                &index.inverted_index,
+            )
+            .unwrap();
+            let computer = SignalComputer::new(Some(&query));
+
+            assert_eq!(
+                HomepageBoost.precompute(&homepage, &computer),
+                Some(expected)
+            );
+            assert_eq!(HomepageBoost.precompute(&subpage, &computer), Some(0.0));
+        }
+
+        let searcher = LocalSearcher::new(index);
+        let search = |query: &str| {
+            searcher
+                .search(&SearchQuery {
+                    query: query.to_string(),
+                    optic: Some(Optic {
+                        rankings: vec![RankingCoeff {
+                            target: RankingTarget::Signal("homepage_boost".to_string()),
+                            value: 100_000.0,
+                        }],
+                        ..Default::default()
+                    }),
+                    return_ranking_signals: true,
+                    ..Default::default()
+                })
+                .expect("Search failed")
+        };
+        let homepage_boost = |webpage: &crate::search_prettifier::DisplayedWebpage| {
+            webpage
+                .ranking_signals
+                .as_ref()
+                .unwrap()
+                .get(&SignalEnumDiscriminants::HomepageBoost)
+                .map_or(0.0, |score| score.value)
+        };
+
+        // navigational query
+        let result = search("github");
+        assert_eq!(result.webpages.len(), 3);
+        assert_eq!(result.webpages[0].url, "https://www.github.com/");
+        assert_eq!(homepage_boost(&result.webpages[0]), 1.0);
+        assert!(result.webpages[1..]
+            .iter()
+            .all(|webpage| homepage_boost(webpage) == 0.0));
+
+        // informational query
+        let result = search("how to use github actions");
+        assert_eq!(result.webpages.len(), 3);
+        assert!(result
+            .webpages
+            .iter()
+            .all(|webpage| homepage_boost(webpage) == 0.0));
+    }
+
+    #[test]
+    fn heading_match() {
+        let mut index = Index::temporary().expect("Unable to open index");
+
+        for (url, heading) in [
+            ("https://www.a.com", "<h1>Tutorial</h1>"),
+            ("https://www.b.com", ""),
+        ] {
+            index
+                .insert(&Webpage {
+                    html: Html::parse(
+                        &format!(
+                            r#"
+                        <html>
+                            <head>
+                                <title>Tutorial website</title>
+                            </head>
+                            <body>
+                                {heading}
+                                <p>{CONTENT} {}</p>
+                            </body>
+                        </html>
+                    "#,
+                            crate::rand_words(100)
+                        ),
+                        url,
+                    )
+                    .unwrap(),
+                    host_centrality: 1.0,
+                    fetch_time_ms: 500,
+                    ..Default::default()
+                })
+                .expect("failed to insert webpage");
+        }
+
+        index.commit().expect("failed to commit index");
+
+        let searcher = LocalSearcher::new(index);
+        let res = searcher
+            .search(&SearchQuery {
+                query: "tutorial".to_string(),
+                optic: Some(Optic {
+                    rankings: vec![RankingCoeff {
+                        target: RankingTarget::Signal("heading_match".to_string()),
+                        value: 100_000.0,
+                    }],
+                    ..Default::default()
+                }),
+                return_ranking_signals: true,
+                ..Default::default()
+            })
+            .expect("Search failed")
+            .webpages;
+
+        let heading_match = |webpage: &crate::search_prettifier::DisplayedWebpage| {
+            webpage
+                .ranking_signals
+                .as_ref()
+                .unwrap()
+                .get(&SignalEnumDiscriminants::HeadingMatch)
+                .map_or(0.0, |score| score.value)
+        };
+
+        assert_eq!(res.len(), 2);
+        assert_eq!(res[0].url, "https://www.a.com/");
+        assert_eq!(heading_match(&res[0]), 1.0);
+        assert_eq!(heading_match(&res[1]), 0.0);
+    }
+
+    #[test]
+    fn keyword_stuffing() {
+        let mut index = Index::temporary().expect("Unable to open index");
+
+        for (url, title) in [
+            ("https://www.a.com", "Rust cheap rust rust rust rust rust"),
+            ("https://www.b.com", "The Rust programming language book"),
+        ] {
+            index
+                .insert(&Webpage {
+                    html: Html::parse(
+                        &format!(
+                            r#"
+                        <html>
+                            <head>
+                                <title>{title}</title>
+                            </head>
+                            <body>
+                                <p>{CONTENT} {}</p>
+                            </body>
+                        </html>
+                    "#,
+                            crate::rand_words(100)
+                        ),
+                        url,
+                    )
+                    .unwrap(),
+                    host_centrality: 1.0,
+                    fetch_time_ms: 500,
+                    ..Default::default()
+                })
+                .expect("failed to insert webpage");
+        }
+
+        index.commit().expect("failed to commit index");
+
+        let searcher = LocalSearcher::new(index);
+        let res = searcher
+            .search(&SearchQuery {
+                query: "rust".to_string(),
+                optic: Some(Optic {
+                    rankings: vec![RankingCoeff {
+                        target: RankingTarget::Signal("keyword_stuffing".to_string()),
+                        value: 100_000.0,
+                    }],
+                    ..Default::default()
+                }),
+                return_ranking_signals: true,
+                ..Default::default()
+            })
+            .expect("Search failed")
+            .webpages;
+
+        let keyword_stuffing = |webpage: &crate::search_prettifier::DisplayedWebpage| {
+            webpage
+                .ranking_signals
+                .as_ref()
+                .unwrap()
+                .get(&SignalEnumDiscriminants::KeywordStuffing)
+                .map_or(0.0, |score| score.value)
+        };
+
+        assert_eq!(res.len(), 2);
+        assert_eq!(res[0].url, "https://www.b.com/");
+        assert_eq!(keyword_stuffing(&res[0]), 1.0);
+        assert_eq!(keyword_stuffing(&res[1]), 0.0);
+    }
+
+    #[test]
+    fn keyword_stuffing_does_not_dampen_title() {
+        let mut index = Index::temporary().expect("Unable to open index");
+
+        index
+            .insert(&Webpage {
+                html: Html::parse(
+                    &format!(
+                        r#"
+                    <html>
+                        <head>
+                            <title>The Rust programming language book</title>
+                        </head>
+                        <body>
+                            <p>{CONTENT} {}</p>
+                        </body>
+                    </html>
+                "#,
+                        crate::rand_words(100)
+                    ),
+                    "https://www.a.com",
+                )
+                .unwrap(),
+                host_centrality: 1.0,
+                fetch_time_ms: 500,
+                ..Default::default()
+            })
+            .expect("failed to insert webpage");
+
+        index.commit().expect("failed to commit index");
+
+        let searcher = LocalSearcher::new(index);
+        let res = searcher
+            .search(&SearchQuery {
+                query: "rust".to_string(),
+                return_ranking_signals: true,
+                ..Default::default()
+            })
+            .expect("Search failed")
+            .webpages;
+
+        assert_eq!(res.len(), 1);
+
+        let signals = res[0].ranking_signals.as_ref().unwrap();
+
+        assert!(signals[&SignalEnumDiscriminants::KeywordStuffing].value > 0.0);
+        assert_eq!(
+            signals[&SignalEnumDiscriminants::Bm25Title].coefficient,
+            Bm25Title.default_coefficient()
+        );
+    }
+
+    #[test]
+    fn readability() {
+        let mut index = Index::temporary().expect("Unable to open index");
+
+        let page = |body: String, url: &str| Webpage {
+            html: Html::parse(
+                &format!(
+                    r#"
+                    <html>
+                        <head>
+                            <title>Test website</title>
+                        </head>
+                        <body>
+                            <p>{body}</p>
+                        </body>
+                    </html>
+                "#
+                ),
+                url,
+            )
+            .unwrap(),
+            ..Default::default()
+        };
+
+        let low = page("This is it. ".repeat(50), "https://www.low.com");
+        let medium = page(
+            "This is a sentence with exactly fifteen words in it so that it reads well. "
+                .repeat(10),
+            "https://www.medium.com",
+        );
+        let high = page(format!("{CONTENT} {CONTENT}"), "https://www.high.com");
+
+        let computer = SignalComputer::new(None);
+        let low_score = Readability.precompute(&low, &computer).unwrap();
+        let medium_score = Readability.precompute(&medium, &computer).unwrap();
+        let high_score = Readability.precompute(&high, &computer).unwrap();
+
+        assert_eq!(medium_score, 1.0);
+        assert!(low_score < 0.1);
+        assert!(high_score < 0.1);
+
+        index.insert(&low).expect("failed to insert webpage");
+        index.insert(&medium).expect("failed to insert webpage");
+        index.insert(&high).expect("failed to insert webpage");
+        index.commit().expect("failed to commit index");
+
+        let ctx = index.inverted_index.local_search_ctx();
+        let mut computer = SignalComputer::new(None);
+        let segment = ctx.tv_searcher.segment_reader(0);
+        computer
+            .register_segment(&ctx.tv_searcher, segment, &ctx.fastfield_reader)
+            .unwrap();
+
+        let mut scores: Vec<_> = (0..segment.max_doc())
+            .map(|doc| Readability.compute(doc, &computer).unwrap())
+            .collect();
+        scores.sort_by(|a, b| a.total_cmp(b));
+
+        assert_eq!(scores.len(), 3);
+        assert!(scores[0] < 0.1);
+        assert!(scores[1] < 0.1);
+        assert_eq!(scores[2], 1.0);
+
+        // documents without a readability are neither rewarded nor penalized
+        assert_eq!(Readability.compute(segment.max_doc(), &computer), Some(0.5));
+    }
 }
@@ -64,10 +64,18 @@ impl AsValue for f64 {
     }
 }
 
+/// A feature the model splits on. Signals that are not compiled into this build
+/// (see [`signal::UNAVAILABLE_SIGNALS`]) are treated like signals without a value.
+#[derive(Debug, Clone, Copy)]
+enum Feature {
+    Signal(SignalEnum),
+    Unavailable,
+}
+
 #[derive(Debug)]
 struct Node {
     threshold: f64,
-    feature: Option<SignalEnum>,
+    feature: Option<Feature>,
     leaf_value: f64,
     left: Option<NodeOrLeaf>,
     right: Option<NodeOrLeaf>,
@@ -76,7 +84,12 @@ struct Node {
 impl Node {
     fn next<V: AsValue>(&self, features: &EnumMap<SignalEnum, V>) -> Option<&NodeOrLeaf> {
         self.feature.and_then(|feature| {
-            let value = features.get(feature).map(|v| v.as_value()).unwrap_or(0.0);
+            let value = match feature {
+                Feature::Signal(signal) => features.get(signal).map(|v| v.as_value()),
+                Feature::Unavailable => None,
+            }
+            .unwrap_or(0.0);
+
             if value <= self.threshold {
                 self.left.as_ref()
             } else {
@@ -208,7 +221,7 @@ impl Tree {
 }
 
 struct Header {
-    features: Vec<SignalEnum>,
+    features: Vec<Feature>,
 }
 
 impl Header {
@@ -219,7 +232,13 @@ impl Header {
             if let Some((key, value)) = lin.split_once('=') {
                 if key == "feature_names" {
                     for name in value.split(' ') {
-                        features.push(SignalEnum::from(SignalEnumDiscriminants::from_str(name)?));
+                        let feature = if signal::UNAVAILABLE_SIGNALS.contains(&name) {
+                            Feature::Unavailable
+                        } else {
+                            Feature::Signal(SignalEnumDiscriminants::from_str(name)?.into())
+                        };
+
+                        features.push(feature);
                     }
                 }
             }
@@ -344,6 +363,7 @@ mod tests {
         features.insert(ranking::signal::IdfSumUrl.into(), 57.07925033569336);
         features.insert(ranking::signal::FetchTimeMs.into(), 0.023255813953488372);
         features.insert(ranking::signal::HostCentrality.into(), 0.017958538);
+        #[cfg(feature = "webgraph")]
         features.insert(ranking::signal::InboundSimilarity.into(), 0.0);
         features.insert(ranking::signal::IsHomepage.into(), 0.0);
         features.insert(ranking::signal::PageCentrality.into(), 0.008253236);
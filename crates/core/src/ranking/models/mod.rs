@@ -1,3 +1,4 @@
 pub mod cross_encoder;
 pub mod lambdamart;
 pub mod linear;
+pub mod registry;
@@ -0,0 +1,132 @@
+// Stract is an open source web search engine.
+// Copyright (C) 2023 Stract ApS
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Linear models registered by name, so different models can be compared
+//! against each other on live traffic.
+
+use std::{collections::BTreeMap, sync::Arc};
+
+use serde::{Deserialize, Serialize};
+
+use super::linear::LinearRegression;
+
+/// How the linear model of a search is chosen from the [`ModelRegistry`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ModelSelection {
+    /// The model with this name.
+    Name(String),
+    /// Index of the model among the registered models sorted by name. The api assigns
+    /// clients to a bucket with a keyed hash, so the same client always selects the same
+    /// model as long as the registered models don't change.
+    Bucket(u64),
+}
+
+#[derive(Debug, Default)]
+pub struct ModelRegistry {
+    models: BTreeMap<String, Arc<LinearRegression>>,
+}
+
+impl ModelRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, name: impl Into<String>, model: LinearRegression) {
+        self.models.insert(name.into(), Arc::new(model));
+    }
+
+    pub fn get(&self, name: &str) -> Option<Arc<LinearRegression>> {
+        self.models.get(name).cloned()
+    }
+
+    pub fn len(&self) -> usize {
+        self.models.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.models.is_empty()
+    }
+
+    /// The model to use for the search. Buckets are spread evenly over the models
+    /// sorted by name. Returns `None` if no model is registered with the selected name.
+    pub fn select(&self, selection: &ModelSelection) -> Option<Arc<LinearRegression>> {
+        match selection {
+            ModelSelection::Name(name) => {
+                let model = self.get(name);
+
+                if model.is_none() {
+                    tracing::warn!("no linear model named '{}'", name);
+                }
+
+                model
+            }
+            ModelSelection::Bucket(bucket) => {
+                if self.models.is_empty() {
+                    return None;
+                }
+
+                let idx = (*bucket % self.models.len() as u64) as usize;
+                self.models.values().nth(idx).cloned()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{enum_map::EnumMap, ranking::signal::HostCentrality};
+
+    use super::*;
+
+    fn model(host_centrality: f64) -> LinearRegression {
+        let mut weights = EnumMap::new();
+        weights.insert(HostCentrality.into(), host_centrality);
+
+        LinearRegression { weights }
+    }
+
+    #[test]
+    fn sticky_buckets() {
+        let mut registry = ModelRegistry::new();
+        registry.insert("a", model(1.0));
+        registry.insert("b", model(2.0));
+
+        let weight = |selection: &ModelSelection| {
+            registry
+                .select(selection)
+                .unwrap()
+                .weights
+                .get(HostCentrality.into())
+                .copied()
+        };
+
+        assert_eq!(weight(&ModelSelection::Name("b".to_string())), Some(2.0));
+        assert_eq!(weight(&ModelSelection::Bucket(0)), Some(1.0));
+        assert_eq!(weight(&ModelSelection::Bucket(1)), Some(2.0));
+        assert_eq!(weight(&ModelSelection::Bucket(41)), Some(2.0));
+        assert_eq!(
+            weight(&ModelSelection::Bucket(41)),
+            weight(&ModelSelection::Bucket(41))
+        );
+
+        assert!(registry
+            .select(&ModelSelection::Name("c".to_string()))
+            .is_none());
+        assert!(ModelRegistry::new()
+            .select(&ModelSelection::Bucket(1))
+            .is_none());
+    }
+}
@@ -0,0 +1,122 @@
+// Stract is an open source web search engine.
+// Copyright (C) 2024 Stract ApS
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Cache of the compiled optic rules across requests. Compiling the ranking rules of
+//! an optic into tantivy queries only depends on the rules and the index, so requests
+//! with a popular optic can reuse the rules compiled by earlier requests. Only the
+//! per-segment scorers are created for every request.
+
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use crate::{metrics::Counter, ranking::SignalEnum};
+
+use super::lru::Lru;
+
+/// An optic rule compiled into a query that can be reused for every segment.
+pub struct CompiledRule {
+    pub query: Box<dyn tantivy::query::Query>,
+    pub boost: f64,
+    /// The signals a scoped boost applies to.
+    /// Empty if the boost applies to the score of the whole document.
+    pub signals: Vec<SignalEnum>,
+}
+
+/// Identifies the ranking rules of an optic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct OpticFingerprint(u64);
+
+impl OpticFingerprint {
+    pub fn new(rules: &[optics::Rule]) -> Self {
+        let mut hasher = DefaultHasher::new();
+
+        serde_json::to_vec(rules)
+            .unwrap_or_default()
+            .hash(&mut hasher);
+
+        Self(hasher.finish())
+    }
+}
+
+/// The compiled rules and the searcher generation they were compiled for.
+type Entry = (u64, Arc<[CompiledRule]>);
+
+pub struct OpticRuleCache {
+    rules: Mutex<Lru<OpticFingerprint, Entry>>,
+    hits: Counter,
+    misses: Counter,
+}
+
+impl OpticRuleCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            rules: Mutex::new(Lru::new(capacity)),
+            hits: Counter::default(),
+            misses: Counter::default(),
+        }
+    }
+
+    fn rules(&self) -> MutexGuard<'_, Lru<OpticFingerprint, Entry>> {
+        self.rules.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Get the cached rules of the optic or compile and cache them. The compiled
+    /// rules refer to the segments of the searcher, so rules compiled for another
+    /// searcher generation are compiled again.
+    pub fn get_or_compile(
+        &self,
+        optic: OpticFingerprint,
+        generation: u64,
+        compile: impl FnOnce() -> Vec<CompiledRule>,
+    ) -> Arc<[CompiledRule]> {
+        if let Some((cached_generation, rules)) = self.rules().get(&optic) {
+            if cached_generation == generation {
+                self.hits.inc();
+                return rules;
+            }
+        }
+
+        self.misses.inc();
+        let rules: Arc<[CompiledRule]> = compile().into();
+        self.rules().insert(optic, (generation, Arc::clone(&rules)));
+
+        rules
+    }
+
+    /// Remove all cached rules.
+    pub fn invalidate(&self) {
+        self.rules().clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.rules().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn hits(&self) -> &Counter {
+        &self.hits
+    }
+
+    pub fn misses(&self) -> &Counter {
+        &self.misses
+    }
+}
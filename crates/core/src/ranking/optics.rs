@@ -14,7 +14,7 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-#[cfg(test)]
+#[cfg(all(test, feature = "webgraph"))]
 mod tests {
     use optics::{
         ast::{RankingCoeff, RankingTarget},
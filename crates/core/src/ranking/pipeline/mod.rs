@@ -14,13 +14,18 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use std::sync::Arc;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
 
 use crate::{
     collector::{self, BucketCollector},
     config::CollectorConfig,
     models::dual_encoder::DualEncoder,
+    prehashed::{self, Prehashed},
     searcher::SearchQuery,
+    webgraph::NodeID,
 };
 
 use super::{
@@ -38,6 +43,11 @@ pub trait RankableWebpage: collector::Doc + Send + Sync {
     fn set_score(&mut self, score: f64);
     fn boost(&self) -> Option<f64>;
 
+    /// The host of the webpage in the webgraph, used to diversify the results by host.
+    fn host_id(&self) -> Option<NodeID> {
+        None
+    }
+
     fn boost_score(&mut self) {
         if let Some(boost) = self.boost() {
             if boost != 0.0 {
@@ -48,6 +58,49 @@ pub trait RankableWebpage: collector::Doc + Send + Sync {
     }
 }
 
+/// Maximum relative change of a score when exploring alternative orderings.
+const EXPLORE_JITTER: f64 = 0.1;
+
+/// A factor in `[1 - EXPLORE_JITTER, 1 + EXPLORE_JITTER]` that only depends on the seed and
+/// the url of the webpage, so the same seed always perturbs the same webpage in the same way.
+fn explore_factor(seed: u64, url: Prehashed) -> f64 {
+    let mut bytes = seed.to_le_bytes().to_vec();
+    bytes.extend_from_slice(&url.0.to_le_bytes());
+
+    // the 53 most significant bits fill the mantissa of an f64 in [0, 1)
+    let unit = (prehashed::hash(bytes).0 >> 75) as f64 / (1u64 << 53) as f64;
+
+    1.0 + EXPLORE_JITTER * (2.0 * unit - 1.0)
+}
+
+/// Move the results of hosts that already have `per_host_limit` results ranked above them
+/// to the end of the list. The demoted results keep their relative order and are not removed,
+/// so they can still show up on later pages. Results without a known host are never demoted.
+fn diversify_by_host<T: RankableWebpage>(websites: Vec<T>, per_host_limit: usize) -> Vec<T> {
+    let mut host_count: HashMap<NodeID, usize> = HashMap::new();
+    let mut res = Vec::with_capacity(websites.len());
+    let mut demoted = Vec::new();
+
+    for website in websites {
+        match website.host_id() {
+            Some(host) => {
+                let count = host_count.entry(host).or_default();
+                *count += 1;
+
+                if *count > per_host_limit {
+                    demoted.push(website);
+                } else {
+                    res.push(website);
+                }
+            }
+            None => res.push(website),
+        }
+    }
+
+    res.append(&mut demoted);
+    res
+}
+
 impl lambdamart::AsValue for SignalScore {
     fn as_value(&self) -> f64 {
         self.value
@@ -58,6 +111,10 @@ struct RankingStage<T> {
     scorer: Box<dyn Scorer<T>>,
     stage_top_n: usize,
     derank_similar: bool,
+    /// Only set for the final stage, so the scores are perturbed once.
+    explore_seed: Option<u64>,
+    /// Hashes of the urls the user has already seen. Only set for the final stage.
+    seen: HashSet<Prehashed>,
 }
 
 impl<T: RankableWebpage> RankingStage<T> {
@@ -66,29 +123,57 @@ impl<T: RankableWebpage> RankingStage<T> {
         websites: Vec<T>,
         top_n: usize,
         offset: usize,
+        per_host_limit: Option<usize>,
+        min_score: Option<f64>,
         collector_config: CollectorConfig,
     ) -> Vec<T> {
+        // which results are demoted by the per host limit depends on the results
+        // ranked above them, so the offset is applied after the full list is diversified.
+        let (skip_before, skip_after) = if per_host_limit.is_some() {
+            (0, offset)
+        } else {
+            (offset, 0)
+        };
+
         let mut websites = websites
             .into_iter()
-            .skip(offset)
-            .take(self.stage_top_n.max(top_n))
+            .skip(skip_before)
+            .take(self.stage_top_n.max(top_n) + skip_after)
             .collect::<Vec<_>>();
 
         self.scorer.score(&mut websites);
 
+        let seen_multiplier = collector_config.seen_multiplier;
         let mut collector =
             BucketCollector::new(self.stage_top_n.max(top_n) + offset, collector_config);
 
         for mut website in websites {
             website.boost_score();
+
+            if self.seen.contains(&website.hashes().url) {
+                let score = website.score() * seen_multiplier;
+                website.set_score(score);
+            }
+
+            if let Some(seed) = self.explore_seed {
+                let score = website.score() * explore_factor(seed, website.hashes().url);
+                website.set_score(score);
+            }
+
             collector.insert(website);
         }
 
-        collector
-            .into_sorted_vec(self.derank_similar)
-            .into_iter()
-            .take(top_n)
-            .collect()
+        let mut websites = collector.into_sorted_vec(self.derank_similar);
+
+        if let Some(min_score) = min_score {
+            websites.retain(|website| website.score() >= min_score);
+        }
+
+        if let Some(per_host_limit) = per_host_limit {
+            websites = diversify_by_host(websites, per_host_limit);
+        }
+
+        websites.into_iter().skip(skip_after).take(top_n).collect()
     }
 
     fn set_query_info(&mut self, query: &SearchQuery) {
@@ -100,6 +185,8 @@ pub struct RankingPipeline<T> {
     stage: RankingStage<T>,
     page: usize,
     pub top_n: usize,
+    per_host_limit: Option<usize>,
+    min_score: Option<f64>,
     collector_config: CollectorConfig,
 }
 
@@ -117,12 +204,16 @@ impl RankingPipeline<crate::searcher::api::ScoredWebpagePointer> {
             )),
             stage_top_n,
             derank_similar: true,
+            explore_seed: None,
+            seen: HashSet::new(),
         };
 
         Self {
             stage: last_stage,
             page: 0,
             top_n: 0,
+            per_host_limit: None,
+            min_score: None,
             collector_config,
         }
     }
@@ -147,6 +238,8 @@ impl<T: RankableWebpage> RankingPipeline<T> {
         self.stage.set_query_info(query);
         self.page = query.page;
         self.top_n = query.num_results;
+        self.per_host_limit = query.per_host_limit;
+        self.min_score = query.min_score;
 
         query.num_results = self.collector_top_n();
         query.page = 0;
@@ -156,19 +249,32 @@ impl<T: RankableWebpage> RankingPipeline<T> {
         self.top_n * self.page
     }
 
+    /// Rank the websites and return the websites of the current page. Websites with a final
+    /// score below the minimum score of the query are removed, so the page can have fewer results.
     pub fn apply(self, websites: Vec<T>) -> Vec<T> {
         if websites.len() <= 1 {
-            return websites;
+            let min_score = self.min_score.unwrap_or(f64::NEG_INFINITY);
+
+            return websites
+                .into_iter()
+                .filter(|website| website.score() >= min_score)
+                .collect();
         }
 
         self.stage.apply(
             websites,
             self.top_n,
             self.offset(),
+            self.per_host_limit,
+            self.min_score,
             self.collector_config.clone(),
         )
     }
 
+    pub fn min_score(&self) -> Option<f64> {
+        self.min_score
+    }
+
     pub fn collector_top_n(&self) -> usize {
         (self.initial_top_n().max(self.top_n) + self.top_n * self.page) + 1
     }
@@ -213,6 +319,7 @@ mod tests {
                             url_without_tld: Prehashed(0),
                             simhash: 0,
                         },
+                        host_centrality: 0.0,
                         address: DocAddress {
                             segment: 0,
                             doc_id: i as u32,
@@ -222,6 +329,7 @@ mod tests {
                     optic_boost: None,
                     title_embedding: None,
                     keyword_embedding: None,
+                    host_id: None,
                     score: 1.0 / i as f64,
                 }
             })
@@ -336,4 +444,112 @@ mod tests {
             prev = res;
         }
     }
+
+    #[test]
+    fn per_host_limit() {
+        let num_results = 8;
+        let pipeline = RankingPipeline::<RecallRankingWebpage>::recall_stage(
+            &mut SearchQuery {
+                num_results,
+                per_host_limit: Some(2),
+                ..Default::default()
+            },
+            None,
+            None,
+            CollectorConfig::default(),
+            num_results,
+        );
+
+        // the five best results are from the same host
+        let hosts = [0, 0, 0, 0, 0, 1, 2, 3, 4];
+        let sample: Vec<_> = sample_websites(pipeline.collector_top_n())
+            .into_iter()
+            .zip(hosts)
+            .map(|(mut website, host)| {
+                website.host_id = Some(NodeID::from(host as u64));
+                website
+            })
+            .collect();
+
+        let res: Vec<_> = pipeline
+            .apply(sample)
+            .into_iter()
+            .map(|w| w.pointer.address.doc_id)
+            .collect();
+
+        assert_eq!(res, vec![0, 1, 5, 6, 7, 2, 3, 4]);
+    }
+
+    #[test]
+    fn per_host_limit_second_page() {
+        let num_results = 4;
+        let page = |page| {
+            let pipeline = RankingPipeline::<RecallRankingWebpage>::recall_stage(
+                &mut SearchQuery {
+                    page,
+                    num_results,
+                    per_host_limit: Some(1),
+                    ..Default::default()
+                },
+                None,
+                None,
+                CollectorConfig::default(),
+                20,
+            );
+
+            // the three best results are from the same host, the rest are from different hosts
+            let sample: Vec<_> = sample_websites(pipeline.collector_top_n())
+                .into_iter()
+                .enumerate()
+                .map(|(i, mut website)| {
+                    website.host_id = Some(NodeID::from(i.saturating_sub(2) as u64));
+                    website
+                })
+                .collect();
+
+            pipeline
+                .apply(sample)
+                .into_iter()
+                .map(|w| w.pointer.address.doc_id)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(page(0), vec![0, 3, 4, 5]);
+        assert_eq!(page(1), vec![6, 7, 8, 9]);
+    }
+
+    #[test]
+    fn min_score() {
+        let num_results = 4;
+        let pipeline = |min_score| {
+            RankingPipeline::<RecallRankingWebpage>::recall_stage(
+                &mut SearchQuery {
+                    num_results,
+                    min_score,
+                    ..Default::default()
+                },
+                None,
+                None,
+                CollectorConfig::default(),
+                num_results,
+            )
+        };
+
+        let unfiltered = pipeline(None);
+        let sample = sample_websites(unfiltered.collector_top_n());
+        let res = unfiltered.apply(sample.clone());
+
+        assert_eq!(res.len(), num_results);
+        let weakest = res[num_results - 1].score;
+        let second_weakest = res[num_results - 2].score;
+        assert!(weakest < second_weakest);
+
+        let res: Vec<_> = pipeline(Some((weakest + second_weakest) / 2.0))
+            .apply(sample)
+            .into_iter()
+            .map(|w| w.pointer.address.doc_id)
+            .collect();
+
+        assert_eq!(res, vec![0, 1, 2]);
+    }
 }
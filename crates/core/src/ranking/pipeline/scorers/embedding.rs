@@ -160,7 +160,7 @@ impl<W: RankableWebpage, E: EmbeddingSignal<W>> Scorer<W> for EmbeddingScorer<W,
 
     fn set_query_info(&mut self, query: &SearchQuery) {
         self.query = Some(query.query.clone());
-        self.signal_coefficients = query.optic.as_ref().map(SignalCoefficient::from_optic);
+        self.signal_coefficients = query.signal_coefficients();
     }
 }
 
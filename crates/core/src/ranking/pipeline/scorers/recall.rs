@@ -97,7 +97,7 @@ impl Scorer<RecallRankingWebpage> for Recall<RecallRankingWebpage> {
 
     fn set_query_info(&mut self, query: &SearchQuery) {
         self.sub_scorers.set_query_info(query);
-        self.signal_coefficients = query.optic.as_ref().map(SignalCoefficient::from_optic);
+        self.signal_coefficients = query.signal_coefficients();
     }
 }
 
@@ -115,6 +115,6 @@ impl Scorer<ScoredWebpagePointer> for Recall<ScoredWebpagePointer> {
     }
 
     fn set_query_info(&mut self, query: &SearchQuery) {
-        self.signal_coefficients = query.optic.as_ref().map(SignalCoefficient::from_optic);
+        self.signal_coefficients = query.signal_coefficients();
     }
 }
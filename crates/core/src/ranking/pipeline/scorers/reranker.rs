@@ -113,6 +113,6 @@ impl<M: CrossEncoder> Scorer<PrecisionRankingWebpage> for ReRanker<M> {
 
     fn set_query_info(&mut self, query: &SearchQuery) {
         self.query = Some(query.clone());
-        self.signal_coefficients = query.optic.as_ref().map(SignalCoefficient::from_optic);
+        self.signal_coefficients = query.signal_coefficients();
     }
 }
@@ -14,14 +14,16 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use std::sync::Arc;
+use std::{collections::HashSet, sync::Arc};
 
 use serde::{Deserialize, Serialize};
+use url::Url;
 
 use crate::{
     collector,
     config::CollectorConfig,
-    inverted_index::RetrievedWebpage,
+    inverted_index::{DocAddress, RetrievedWebpage},
+    prehashed,
     ranking::{
         models::{cross_encoder::CrossEncoder, lambdamart::LambdaMART},
         pipeline::{
@@ -30,6 +32,7 @@ use crate::{
         },
     },
     searcher::SearchQuery,
+    webgraph::NodeID,
     Result,
 };
 
@@ -49,6 +52,14 @@ impl collector::Doc for PrecisionRankingWebpage {
     fn hashes(&self) -> collector::Hashes {
         self.ranking.pointer.hashes
     }
+
+    fn host_centrality(&self) -> f64 {
+        self.ranking.pointer.host_centrality
+    }
+
+    fn address(&self) -> Option<DocAddress> {
+        Some(self.ranking.pointer.address)
+    }
 }
 
 impl RankableWebpage for PrecisionRankingWebpage {
@@ -59,6 +70,10 @@ impl RankableWebpage for PrecisionRankingWebpage {
     fn boost(&self) -> Option<f64> {
         self.ranking.optic_boost
     }
+
+    fn host_id(&self) -> Option<NodeID> {
+        self.ranking.host_id
+    }
 }
 
 impl PrecisionRankingWebpage {
@@ -91,12 +106,16 @@ impl RankingPipeline<PrecisionRankingWebpage> {
             scorer,
             stage_top_n: top_n_considered,
             derank_similar: true,
+            explore_seed: None,
+            seen: HashSet::new(),
         };
 
         Ok(Self {
             stage,
             page: 0,
             top_n: 0,
+            per_host_limit: None,
+            min_score: None,
             collector_config,
         })
     }
@@ -111,6 +130,13 @@ impl RankingPipeline<PrecisionRankingWebpage> {
         let mut pipeline =
             Self::create_reranking_stage(crossencoder, lambda, collector_config, top_n_considered)?;
         pipeline.set_query_info(query);
+        pipeline.stage.explore_seed = query.explore_seed;
+        pipeline.stage.seen = query
+            .seen_urls
+            .iter()
+            .filter_map(|url| Url::parse(url).ok())
+            .map(|url| prehashed::hash(url.as_str()))
+            .collect();
 
         Ok(pipeline)
     }
@@ -14,7 +14,7 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use std::sync::Arc;
+use std::{collections::HashSet, sync::Arc};
 
 use serde::{Deserialize, Serialize};
 
@@ -23,7 +23,7 @@ use crate::{
     config::CollectorConfig,
     enum_map::EnumMap,
     fastfield_reader,
-    inverted_index::WebpagePointer,
+    inverted_index::{DocAddress, WebpagePointer},
     models::dual_encoder::DualEncoder,
     ranking::{
         models::lambdamart::LambdaMART,
@@ -32,6 +32,7 @@ use crate::{
     },
     schema::fast_field,
     searcher::SearchQuery,
+    webgraph::NodeID,
 };
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -50,6 +51,7 @@ pub struct RecallRankingWebpage {
     pub optic_boost: Option<f64>,
     pub title_embedding: Option<StoredEmbeddings>,
     pub keyword_embedding: Option<StoredEmbeddings>,
+    pub host_id: Option<NodeID>,
     pub score: f64,
 }
 
@@ -69,6 +71,12 @@ impl RecallRankingWebpage {
             .get(fast_field::KeywordEmbeddings.into())
             .and_then(|v| v.into());
 
+        let host_id: Option<NodeID> = fastfields
+            .get(fast_field::HostNodeID.into())
+            .and_then(|v| v.as_u64())
+            .filter(|id| *id != u64::MAX)
+            .map(|id| id.into());
+
         let mut res = RecallRankingWebpage {
             signals: EnumMap::new(),
             score: pointer.score.total,
@@ -76,16 +84,26 @@ impl RecallRankingWebpage {
             pointer: pointer.clone(),
             title_embedding: title_embedding.map(StoredEmbeddings),
             keyword_embedding: keyword_embedding.map(StoredEmbeddings),
+            host_id,
         };
 
-        for computed_signal in computer.compute_signals(pointer.address.doc_id).flatten() {
+        let boosts = computer.boosts(pointer.address.doc_id);
+
+        // the signals are the features of the later ranking stages, so all
+        // of them are computed even though the document is already in the top results.
+        for mut computed_signal in computer
+            .compute_signals(pointer.address.doc_id, None)
+            .flatten()
+        {
+            if let Some(boosts) = &boosts {
+                boosts.apply(&mut computed_signal);
+            }
+
             res.signals
                 .insert(computed_signal.signal, computed_signal.score);
         }
 
-        if let Some(boost) = computer.boosts(pointer.address.doc_id) {
-            res.optic_boost = Some(boost);
-        }
+        res.optic_boost = boosts.map(|boosts| boosts.total);
 
         res
     }
@@ -98,6 +116,10 @@ impl RankableWebpage for RecallRankingWebpage {
     fn boost(&self) -> Option<f64> {
         self.optic_boost
     }
+
+    fn host_id(&self) -> Option<NodeID> {
+        self.host_id
+    }
 }
 
 impl collector::Doc for RecallRankingWebpage {
@@ -108,6 +130,14 @@ impl collector::Doc for RecallRankingWebpage {
     fn hashes(&self) -> collector::Hashes {
         self.pointer.hashes
     }
+
+    fn host_centrality(&self) -> f64 {
+        self.pointer.host_centrality
+    }
+
+    fn address(&self) -> Option<DocAddress> {
+        Some(self.pointer.address)
+    }
 }
 
 impl RankingPipeline<RecallRankingWebpage> {
@@ -124,12 +154,16 @@ impl RankingPipeline<RecallRankingWebpage> {
             )) as Box<dyn Scorer<RecallRankingWebpage>>,
             stage_top_n,
             derank_similar: true,
+            explore_seed: None,
+            seen: HashSet::new(),
         };
 
         Self {
             stage: last_stage,
             page: 0,
             top_n: 0,
+            per_host_limit: None,
+            min_score: None,
             collector_config,
         }
     }
@@ -18,9 +18,26 @@ use crate::webgraph::NodeID;
 
 use super::inbound_similarity;
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Score {
+    /// Centrality computed from the inbound links of the host.
+    Computed(f64),
+    /// The host is not in the webgraph, so the configured fallback is used.
+    Fallback(f64),
+}
+
+impl Score {
+    pub fn value(&self) -> f64 {
+        match self {
+            Score::Computed(score) | Score::Fallback(score) => *score,
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct Scorer {
     inbound_centrality: inbound_similarity::Scorer,
+    fallback: Option<f64>,
 }
 
 impl Scorer {
@@ -28,10 +45,62 @@ impl Scorer {
         let mut inbound_centrality = inbound_centrality;
         inbound_centrality.set_self_score(0.0);
 
-        Self { inbound_centrality }
+        Self {
+            inbound_centrality,
+            fallback: None,
+        }
+    }
+
+    /// Score hosts that are not in the webgraph with `fallback`. Without a
+    /// fallback these hosts get no query centrality at all.
+    pub fn with_fallback(mut self, fallback: Option<f64>) -> Self {
+        self.fallback = fallback;
+        self
+    }
+
+    pub fn score(&mut self, node: NodeID) -> Option<Score> {
+        if self.inbound_centrality.knows_about(&node) {
+            Some(Score::Computed(self.inbound_centrality.score(&node)))
+        } else {
+            self.fallback.map(Score::Fallback)
+        }
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        gen_temp_path,
+        ranking::inbound_similarity::InboundSimilarity,
+        webgraph::{Node, WebgraphWriter},
+    };
+
+    use super::*;
+
+    #[test]
+    fn fallback_for_unknown_hosts() {
+        let mut wrt = WebgraphWriter::new(
+            gen_temp_path(),
+            crate::executor::Executor::single_thread(),
+            crate::webgraph::Compression::default(),
+        );
+
+        wrt.insert(Node::from("a.com"), Node::from("b.com"), String::new());
+        wrt.insert(Node::from("c.com"), Node::from("d.com"), String::new());
+
+        let graph = wrt.finalize();
+        let inbound = InboundSimilarity::build(&graph);
+
+        let known = Node::from("d.com").id();
+        let unknown = Node::from("new.com").id();
+
+        let mut scorer = Scorer::new(inbound.scorer(&[Node::from("b.com").id()], &[], false));
+        assert!(matches!(scorer.score(known), Some(Score::Computed(_))));
+        assert_eq!(scorer.score(unknown), None);
 
-    pub fn score(&mut self, node: NodeID) -> f64 {
-        self.inbound_centrality.score(&node)
+        let mut scorer = Scorer::new(inbound.scorer(&[Node::from("b.com").id()], &[], false))
+            .with_fallback(Some(0.0));
+        assert!(matches!(scorer.score(known), Some(Score::Computed(_))));
+        assert_eq!(scorer.score(unknown), Some(Score::Fallback(0.0)));
     }
 }
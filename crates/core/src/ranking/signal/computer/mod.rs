@@ -14,18 +14,21 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use crate::query::optic::AsSearchableRule;
+use crate::config::SignalCacheConfig;
+use crate::query::optic::{self, AsSearchableRule};
 use crate::query::Query;
 use crate::schema::text_field::TextField;
+use crate::searcher::Device;
+#[cfg(feature = "webgraph")]
+use crate::webgraph::NodeID;
 use crate::Result;
-use crate::{
-    enum_map::EnumMap, fastfield_reader, schema::TextFieldEnum, webgraph::NodeID, webpage::Webpage,
-};
+use crate::{enum_map::EnumMap, fastfield_reader, schema::TextFieldEnum, webpage::Webpage};
 
-use std::cell::RefCell;
-
-use std::sync::Arc;
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex, MutexGuard};
 
+use itertools::Itertools;
+use optics::ast::BoostTarget;
 use tantivy::fieldnorm::FieldNormReader;
 use tantivy::postings::SegmentPostings;
 use tantivy::query::{Query as _, Scorer};
@@ -36,25 +39,214 @@ use tantivy::DocSet;
 
 use crate::webpage::region::RegionCount;
 
-use crate::ranking::bm25::MultiBm25Weight;
+use crate::ranking::bm25::{Bm25Params, Bm25Variant, MultiBm25Weight};
+#[cfg(feature = "webgraph")]
+use crate::ranking::host_signal_cache::{HostSignal, HostSignalCache, QueryFingerprint};
 use crate::ranking::models::linear::LinearRegression;
+use crate::ranking::optic_rule_cache::{CompiledRule, OpticFingerprint, OpticRuleCache};
+use crate::ranking::tld_reputation::TldReputation;
+#[cfg(feature = "webgraph")]
 use crate::ranking::{inbound_similarity, query_centrality};
 
-use super::{ComputedSignal, Signal, SignalCoefficient, SignalEnum, SignalScore};
+use super::{ComputedSignal, MobileFriendly, Signal, SignalCoefficient, SignalEnum, SignalScore};
 
 mod order;
-pub use order::SignalComputeOrder;
+pub use order::{ComputedSignals, SignalComputeOrder};
 
 #[derive(Clone)]
 pub struct TextFieldData {
     pub(super) postings: Vec<SegmentPostings>,
+    /// The term of each of the postings.
+    pub(super) terms: Vec<tantivy::Term>,
     pub(super) weight: MultiBm25Weight,
     pub(super) fieldnorm_reader: FieldNormReader,
+    /// Number of distinct query terms for the field, including the terms
+    /// that are not in the index.
+    pub(super) num_query_terms: usize,
+}
+
+impl TextFieldData {
+    /// The query terms that occur in `doc` and their term frequencies. The postings
+    /// only move forward, so documents must be requested in increasing order.
+    pub fn matched_terms(&mut self, doc: DocId) -> Vec<(String, u32)> {
+        self.postings
+            .iter_mut()
+            .zip_eq(&self.terms)
+            .filter_map(|(posting, term)| {
+                if posting.doc() == doc || (posting.doc() < doc && posting.seek(doc) == doc) {
+                    let text = term.value().as_str()?.to_string();
+                    Some((text, posting.term_freq()))
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Whether any of the query terms occur in `doc`. The postings
+    /// only move forward, so documents must be requested in increasing order.
+    fn contains_doc(&mut self, doc: DocId) -> bool {
+        self.postings.iter_mut().any(|posting| {
+            posting.doc() == doc || (posting.doc() < doc && posting.seek(doc) == doc)
+        })
+    }
+}
+
+/// The term weights of each text field and the searcher generation they were computed for.
+type TermWeightCache = Arc<Mutex<Option<(u64, Arc<EnumMap<TextFieldEnum, FieldTermWeights>>)>>>;
+
+/// The tokenized query terms of a text field and their weights over the whole collection.
+struct FieldTermWeights {
+    terms: Vec<tantivy::Term>,
+    weight: MultiBm25Weight,
 }
 
 pub struct RuleBoost {
     docset: Box<dyn Scorer>,
     boost: f64,
+    /// The signals a scoped boost applies to.
+    /// Empty if the boost applies to the score of the whole document.
+    signals: Vec<SignalEnum>,
+}
+
+/// The optic boosts that apply to a single document.
+#[derive(Debug, Clone)]
+pub struct Boosts {
+    /// Multiplier for the score of the whole document.
+    pub total: f64,
+    /// Multipliers for the contributions of the signals targeted by scoped boosts.
+    signals: EnumMap<SignalEnum, f64>,
+}
+
+impl Boosts {
+    /// Whether any of the signals are scaled by a scoped boost.
+    pub fn has_signal_boosts(&self) -> bool {
+        !self.signals.is_empty()
+    }
+
+    /// Scale the contribution of the signal by its scoped boost.
+    pub fn apply(&self, computed: &mut ComputedSignal) {
+        if let Some(boost) = self.signals.get(computed.signal) {
+            computed.score.coefficient *= boost;
+        }
+    }
+}
+
+/// The signals a scoped boost targets. Unknown signals and fields,
+/// as well as fields without any signals, don't target anything.
+fn boost_target_signals(target: &BoostTarget) -> Vec<SignalEnum> {
+    match target {
+        BoostTarget::Signal(name) => SignalEnum::from_name(name).into_iter().collect(),
+        BoostTarget::Field(name) => TextFieldEnum::all()
+            .find(|field| field.name() == name)
+            .map(|field| {
+                SignalEnum::all()
+                    .filter(|signal| signal.as_textfield() == Some(field))
+                    .collect()
+            })
+            .unwrap_or_default(),
+    }
+}
+
+/// Whether an optic rule is applied to the search, or why it is left out.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize, utoipa::ToSchema,
+)]
+#[serde(rename_all = "camelCase")]
+pub enum OpticRuleOutcome {
+    Applied,
+    /// The rule has no matchers, so it can't match any documents.
+    NoMatchers,
+    /// The boost of the rule is zero, so it doesn't change any scores.
+    ZeroBoost,
+    /// The scoped boost of the rule targets an unknown signal or field.
+    UnknownTarget,
+}
+
+/// Why a signal was left out when computing the signals of a document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SkipReason {
+    /// No segment has been registered with the signal computer.
+    NoSegment,
+    /// The search has no query terms, so the text signals can't be computed.
+    NoQuery,
+    /// The postings of the field are not loaded for the query.
+    MissingField,
+    /// None of the query terms occur in the field of the document.
+    NoMatchingTerms,
+    /// A document with a higher docid has already been scored in the segment,
+    /// so the postings have moved past the document.
+    OutOfOrderDoc,
+    /// The coefficient of the signal is zero, e.g. because the query disabled it.
+    ZeroCoefficient,
+    /// The signal has no value for the document, e.g. because the data it is
+    /// computed from is not available.
+    NoValue,
+}
+
+/// The result of computing a single signal for a document.
+#[derive(Debug, Clone, Copy)]
+pub enum SignalOutcome {
+    Computed(ComputedSignal),
+    Skipped {
+        signal: SignalEnum,
+        reason: SkipReason,
+    },
+}
+
+/// A [`SignalOutcome`] together with the length of the text field the signal is computed
+/// from, which helps explain why bm25 scores a long document low.
+#[derive(Debug, Clone, Copy)]
+pub struct SignalExplanation {
+    pub outcome: SignalOutcome,
+    /// See [`SignalComputer::fieldnorm`]. `None` for signals that aren't computed from a text field.
+    pub fieldnorm: Option<u32>,
+}
+
+impl OpticRuleOutcome {
+    pub fn of(rule: &optics::Rule) -> Self {
+        if rule.matches.iter().all(|and_rule| and_rule.is_empty()) {
+            return OpticRuleOutcome::NoMatchers;
+        }
+
+        match &rule.action {
+            optics::Action::Discard => OpticRuleOutcome::Applied,
+            optics::Action::Boost(0)
+            | optics::Action::Downrank(0)
+            | optics::Action::ScopedBoost(0, _) => OpticRuleOutcome::ZeroBoost,
+            optics::Action::ScopedBoost(_, target) if boost_target_signals(target).is_empty() => {
+                OpticRuleOutcome::UnknownTarget
+            }
+            _ => OpticRuleOutcome::Applied,
+        }
+    }
+
+    pub fn is_applied(&self) -> bool {
+        matches!(self, OpticRuleOutcome::Applied)
+    }
+}
+
+/// Source of the current unix timestamp the freshness signals are computed relative to.
+pub trait Clock {
+    fn now(&self) -> usize;
+}
+
+impl<F> Clock for F
+where
+    F: Fn() -> usize,
+{
+    fn now(&self) -> usize {
+        self()
+    }
+}
+
+/// The system time.
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> usize {
+        chrono::Utc::now().timestamp().max(0) as usize
+    }
 }
 
 pub struct OpticBoosts {
@@ -65,6 +257,9 @@ pub struct SegmentReader {
     text_fields: EnumMap<TextFieldEnum, TextFieldData>,
     optic_boosts: OpticBoosts,
     fastfield_reader: Arc<fastfield_reader::SegmentReader>,
+    /// The largest docid the signals have been computed for in the segment.
+    max_scored_doc: Option<DocId>,
+    max_doc: DocId,
 }
 
 impl SegmentReader {
@@ -75,57 +270,129 @@ impl SegmentReader {
     pub fn fastfield_reader(&self) -> &fastfield_reader::SegmentReader {
         &self.fastfield_reader
     }
+
+    /// The query terms that occur in each text field of `doc` and their term frequencies.
+    pub fn matched_terms(&mut self, doc: DocId) -> Vec<(TextFieldEnum, String, u32)> {
+        let fields: Vec<_> = self.text_fields.keys().collect();
+        let mut res = Vec::new();
+
+        for field in fields {
+            if let Some(data) = self.text_fields.get_mut(field) {
+                res.extend(
+                    data.matched_terms(doc)
+                        .into_iter()
+                        .map(|(term, freq)| (field, term, freq)),
+                );
+            }
+        }
+
+        res
+    }
+
+    /// The number of tokens in `field` of `doc`, decoded from the fieldnorm. Lengths
+    /// above 40 tokens are approximate. `None` if the field isn't loaded for the query
+    /// or `doc` isn't in the segment.
+    pub fn fieldnorm(&self, doc: DocId, field: TextFieldEnum) -> Option<u32> {
+        if doc >= self.max_doc {
+            return None;
+        }
+
+        self.text_fields
+            .get(field)
+            .map(|data| data.fieldnorm_reader.fieldnorm(doc))
+    }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct QueryData {
     simple_terms: Vec<String>,
     optic_rules: Vec<optics::Rule>,
     selected_region: Option<crate::webpage::Region>,
+    lang: Option<whatlang::Lang>,
 }
 impl QueryData {
+    pub fn simple_terms(&self) -> &[String] {
+        &self.simple_terms
+    }
+
     pub fn selected_region(&self) -> Option<crate::webpage::Region> {
         self.selected_region
     }
 }
 
+/// Computes the ranking signals for the documents of a single segment at a time.
+///
+/// The computer is `Send + Sync`, but it is meant to be used by one thread at a time.
+/// To score segments concurrently, each worker should get its own clone and register
+/// its segment on that clone. Cloning is cheap: the lookup caches are immutable and shared,
+/// while the segment reader is dropped and the stateful scorers (which cache their
+/// results internally) are copied. The only state shared between clones is the bm25 weights
+/// of the query terms, which are computed once by whichever clone registers a segment first.
 pub struct SignalComputer {
     query_data: Option<QueryData>,
     query_signal_coefficients: Option<SignalCoefficient>,
-    segment_reader: Option<RefCell<SegmentReader>>,
-    inbound_similarity: Option<RefCell<inbound_similarity::Scorer>>,
-    fetch_time_ms_cache: Vec<f64>,
-    update_time_cache: Vec<f64>,
-    query_centrality: Option<RefCell<query_centrality::Scorer>>,
+    /// Coefficients of the precomputed score. See [`SignalComputer::precompute_coefficient`].
+    precompute_coefficients: Option<SignalCoefficient>,
+    /// Signals disabled by the query. These are never computed.
+    disabled_signals: Vec<SignalEnum>,
+    segment_reader: Option<Mutex<SegmentReader>>,
+    #[cfg(feature = "webgraph")]
+    inbound_similarity: Option<Mutex<inbound_similarity::Scorer>>,
+    fetch_time_ms_cache: Arc<[f64]>,
+    update_time_cache: Arc<[f64]>,
+    inbound_link_count_cache: Arc<[f64]>,
+    #[cfg(feature = "webgraph")]
+    query_centrality: Option<Mutex<query_centrality::Scorer>>,
+    #[cfg(feature = "webgraph")]
+    host_signal_cache: Option<(Arc<HostSignalCache>, QueryFingerprint)>,
+    optic_rule_cache: Option<Arc<OpticRuleCache>>,
     region_count: Option<Arc<RegionCount>>,
+    tld_reputation: Option<Arc<TldReputation>>,
     current_timestamp: Option<usize>,
     linear_regression: Option<Arc<LinearRegression>>,
+    bm25_variant: Bm25Variant,
+    bm25_params: Bm25Params,
+    term_weights: TermWeightCache,
     order: SignalComputeOrder,
 }
 
 impl Clone for SignalComputer {
     fn clone(&self) -> Self {
+        #[cfg(feature = "webgraph")]
         let inbound_similarity = self
             .inbound_similarity
             .as_ref()
-            .map(|scorer| RefCell::new(scorer.borrow().clone()));
+            .map(|scorer| Mutex::new(lock(scorer).clone()));
 
+        #[cfg(feature = "webgraph")]
         let query_centrality = self
             .query_centrality
             .as_ref()
-            .map(|scorer| RefCell::new(scorer.borrow().clone()));
+            .map(|scorer| Mutex::new(lock(scorer).clone()));
 
         Self {
             query_data: self.query_data.clone(),
             query_signal_coefficients: self.query_signal_coefficients.clone(),
+            precompute_coefficients: self.precompute_coefficients.clone(),
+            disabled_signals: self.disabled_signals.clone(),
             segment_reader: None,
+            #[cfg(feature = "webgraph")]
             inbound_similarity,
             fetch_time_ms_cache: self.fetch_time_ms_cache.clone(),
             update_time_cache: self.update_time_cache.clone(),
+            inbound_link_count_cache: self.inbound_link_count_cache.clone(),
+            #[cfg(feature = "webgraph")]
             query_centrality,
+            #[cfg(feature = "webgraph")]
+            host_signal_cache: self.host_signal_cache.clone(),
+            optic_rule_cache: self.optic_rule_cache.clone(),
             region_count: self.region_count.clone(),
+            tld_reputation: self.tld_reputation.clone(),
             current_timestamp: self.current_timestamp,
             linear_regression: self.linear_regression.clone(),
+            bm25_variant: self.bm25_variant.clone(),
+            bm25_params: self.bm25_params,
+            term_weights: Arc::clone(&self.term_weights),
             order: self.order.clone(),
         }
     }
@@ -149,108 +416,215 @@ impl std::fmt::Debug for SignalComputer {
 impl SignalComputer {
     pub fn new(query: Option<&Query>) -> Self {
         let query_signal_coefficients = query.as_ref().and_then(|q| q.signal_coefficients());
+        let mut disabled_signals = query
+            .as_ref()
+            .map(|q| q.disabled_signals().to_vec())
+            .unwrap_or_default();
 
-        let fetch_time_ms_cache: Vec<_> = (0..1000)
-            .map(|fetch_time| 1.0 / (fetch_time as f64 + 1.0))
-            .collect();
+        // mobile friendliness is irrelevant for searches from desktop devices
+        if query.map(|q| q.device()) != Some(Device::Mobile) {
+            disabled_signals.push(MobileFriendly.into());
+        }
+
+        let cache_config = SignalCacheConfig::default();
+        let fetch_time_ms_cache = fetch_time_ms_cache(cache_config.fetch_time_ms);
+        let update_time_cache = update_time_cache(cache_config.freshness_horizon_hours);
 
-        let update_time_cache = (0..(3 * 365 * 24))
-            .map(|hours_since_update| 1.0 / ((hours_since_update as f64 + 1.0).log2()))
+        let max_inbound_links = 10_000;
+        let inbound_link_count_cache = (0..max_inbound_links)
+            .map(|num_links| {
+                (num_links as f64 + 1.0).log2() / (max_inbound_links as f64 + 1.0).log2()
+            })
             .collect();
 
         let query = query.as_ref().map(|q| QueryData {
-            simple_terms: q.simple_terms().to_vec(),
+            simple_terms: q.ranking_terms().to_vec(),
             optic_rules: q
                 .optics()
                 .iter()
                 .flat_map(|o| o.rules.iter())
-                .filter(|rule| match rule.action {
-                    optics::Action::Downrank(b) | optics::Action::Boost(b) => b != 0,
-                    optics::Action::Discard => false,
-                })
+                .filter(|rule| optic::is_ranking_rule(rule))
                 .cloned()
                 .collect(),
             selected_region: q.region().cloned(),
+            lang: q.lang(),
         });
 
         let mut s = Self {
             segment_reader: None,
+            #[cfg(feature = "webgraph")]
             inbound_similarity: None,
             query_signal_coefficients,
+            precompute_coefficients: None,
+            disabled_signals,
             fetch_time_ms_cache,
             update_time_cache,
+            inbound_link_count_cache,
+            #[cfg(feature = "webgraph")]
             query_centrality: None,
+            #[cfg(feature = "webgraph")]
+            host_signal_cache: None,
+            optic_rule_cache: None,
             region_count: None,
+            tld_reputation: None,
             current_timestamp: None,
             linear_regression: None,
+            bm25_variant: Bm25Variant::default(),
+            bm25_params: Bm25Params::default(),
+            term_weights: TermWeightCache::default(),
             query_data: query,
             order: SignalComputeOrder::empty(),
         };
 
         s.order = SignalComputeOrder::new(&s);
-        s.set_current_timestamp(chrono::Utc::now().timestamp() as usize);
 
         s
     }
 
+    /// Like [`SignalComputer::new`], but with the current timestamp taken from `clock`.
+    /// Without a timestamp, the freshness signals give every page a neutral score.
+    pub fn new_with_clock(query: Option<&Query>, clock: &impl Clock) -> Self {
+        let mut s = Self::new(query);
+        s.set_current_timestamp(clock.now());
+        s
+    }
+
+    /// Signal computer for ranking documents without a query, e.g. when listing the pages
+    /// of a site. Only the query independent signals like centrality, freshness and region
+    /// are computed in browse mode.
+    pub fn browse() -> Self {
+        Self::new_with_clock(None, &SystemClock)
+    }
+
+    /// Signal computer for a query without terms. Unlike [`SignalComputer::browse`], the
+    /// query dependent signals are computed, so the computer has the same signals as the
+    /// computer of a regular query with the default coefficients.
+    pub fn empty_query() -> Self {
+        let mut s = Self::new(None);
+        s.query_data = Some(QueryData::default());
+        s.order = SignalComputeOrder::new(&s);
+        s
+    }
+
+    /// Whether the computer has no query (see [`SignalComputer::browse`]).
+    pub fn is_browse(&self) -> bool {
+        self.query_data.is_none()
+    }
+
+    /// The query terms and their bm25 weights for each text field. The weights only depend
+    /// on the collection statistics of the searcher, so they are computed when the first
+    /// segment is registered and reused for the remaining segments.
+    fn term_weights(
+        &self,
+        tv_searcher: &tantivy::Searcher,
+        query: &QueryData,
+    ) -> Result<Arc<EnumMap<TextFieldEnum, FieldTermWeights>>> {
+        let generation = tv_searcher.generation().generation_id();
+        let mut cache = lock(&self.term_weights);
+
+        if let Some((cached_generation, weights)) = cache.as_ref() {
+            if *cached_generation == generation {
+                return Ok(Arc::clone(weights));
+            }
+        }
+
+        let schema = tv_searcher.schema();
+        let simple_query =
+            itertools::intersperse(query.simple_terms.iter().map(|s| s.as_str()), " ")
+                .collect::<String>();
+
+        let mut weights = EnumMap::new();
+
+        for signal in SignalEnum::all() {
+            if let Some(text_field) = signal.as_textfield() {
+                if weights.contains_key(text_field) {
+                    continue;
+                }
+
+                let Ok(tv_field) = schema.get_field(text_field.name()) else {
+                    warn_missing_field(text_field);
+                    continue;
+                };
+
+                let mut terms = Vec::new();
+                let mut tokenizer = text_field.tokenizer(schema);
+                if let Some(lang) = query.lang {
+                    tokenizer = tokenizer.with_stemming_lang(lang);
+                }
+                let mut stream = tokenizer.token_stream(&simple_query);
+
+                while let Some(token) = stream.next() {
+                    let term = tantivy::Term::from_field_text(tv_field, &token.text);
+                    terms.push(term);
+                }
+
+                if terms.is_empty() {
+                    continue;
+                }
+
+                let weight = MultiBm25Weight::for_terms(
+                    tv_searcher,
+                    &terms,
+                    &self.bm25_variant,
+                    &self.bm25_params,
+                    text_field,
+                )?;
+
+                weights.insert(text_field, FieldTermWeights { terms, weight });
+            }
+        }
+
+        let weights = Arc::new(weights);
+        *cache = Some((generation, Arc::clone(&weights)));
+
+        Ok(weights)
+    }
+
     fn prepare_textfields(
         &self,
         tv_searcher: &tantivy::Searcher,
         segment_reader: &tantivy::SegmentReader,
     ) -> Result<EnumMap<TextFieldEnum, TextFieldData>> {
         let mut text_fields = EnumMap::new();
-        let schema = tv_searcher.schema();
 
-        if let Some(query) = &self.query_data {
-            if !query.simple_terms.is_empty() {
-                for signal in SignalEnum::all() {
-                    if let Some(text_field) = signal.as_textfield() {
-                        let tv_field = schema.get_field(text_field.name()).unwrap();
-                        let simple_query = itertools::intersperse(
-                            query.simple_terms.iter().map(|s| s.as_str()),
-                            " ",
-                        )
-                        .collect::<String>();
+        let Some(query) = &self.query_data else {
+            return Ok(text_fields);
+        };
 
-                        let mut terms = Vec::new();
-                        let mut tokenizer = text_field.indexing_tokenizer();
-                        let mut stream = tokenizer.token_stream(&simple_query);
+        if query.simple_terms.is_empty() {
+            return Ok(text_fields);
+        }
 
-                        while let Some(token) = stream.next() {
-                            let term = tantivy::Term::from_field_text(tv_field, &token.text);
-                            terms.push(term);
-                        }
+        let term_weights = self.term_weights(tv_searcher, query)?;
 
-                        if terms.is_empty() {
-                            continue;
-                        }
+        for text_field in term_weights.keys() {
+            let field_weights = term_weights.get(text_field).unwrap();
+            let tv_field = field_weights.terms[0].field();
 
-                        let fieldnorm_reader = segment_reader.get_fieldnorms_reader(tv_field)?;
-                        let inverted_index = segment_reader.inverted_index(tv_field)?;
-
-                        let mut matching_terms = Vec::with_capacity(terms.len());
-                        let mut postings = Vec::with_capacity(terms.len());
-                        for term in &terms {
-                            if let Some(p) =
-                                inverted_index.read_postings(term, text_field.record_option())?
-                            {
-                                postings.push(p);
-                                matching_terms.push(term.clone());
-                            }
-                        }
-                        let weight = MultiBm25Weight::for_terms(tv_searcher, &matching_terms)?;
-
-                        text_fields.insert(
-                            text_field,
-                            TextFieldData {
-                                postings,
-                                weight,
-                                fieldnorm_reader,
-                            },
-                        );
-                    }
+            let fieldnorm_reader = segment_reader.get_fieldnorms_reader(tv_field)?;
+            let inverted_index = segment_reader.inverted_index(tv_field)?;
+
+            let mut matching = Vec::with_capacity(field_weights.terms.len());
+            let mut matching_terms = Vec::with_capacity(field_weights.terms.len());
+            let mut postings = Vec::with_capacity(field_weights.terms.len());
+            for (i, term) in field_weights.terms.iter().enumerate() {
+                if let Some(p) = inverted_index.read_postings(term, text_field.record_option())? {
+                    postings.push(p);
+                    matching.push(i);
+                    matching_terms.push(term.clone());
                 }
             }
+
+            text_fields.insert(
+                text_field,
+                TextFieldData {
+                    postings,
+                    terms: matching_terms,
+                    weight: field_weights.weight.select(&matching),
+                    fieldnorm_reader,
+                    num_query_terms: field_weights.terms.iter().unique().count(),
+                },
+            );
         }
 
         Ok(text_fields)
@@ -262,29 +636,60 @@ impl SignalComputer {
         segment_reader: &tantivy::SegmentReader,
         fastfield_reader: &fastfield_reader::FastFieldReader,
     ) -> Vec<RuleBoost> {
-        let mut optic_rule_boosts = Vec::new();
+        let Some(query) = &self.query_data else {
+            return Vec::new();
+        };
 
-        if let Some(query) = &self.query_data {
-            optic_rule_boosts = query
+        if query.optic_rules.is_empty() {
+            return Vec::new();
+        }
+
+        let compile = || {
+            query
                 .optic_rules
                 .iter()
-                .filter_map(|rule| rule.as_searchable_rule(tv_searcher.schema(), fastfield_reader))
-                .map(|(_, rule)| RuleBoost {
-                    docset: rule
-                        .query
-                        .weight(tantivy::query::EnableScoring::Enabled {
-                            searcher: tv_searcher,
-                            statistics_provider: tv_searcher,
+                .filter(|rule| OpticRuleOutcome::of(rule).is_applied())
+                .filter_map(|rule| {
+                    let signals = match &rule.action {
+                        optics::Action::ScopedBoost(_, target) => boost_target_signals(target),
+                        _ => Vec::new(),
+                    };
+
+                    rule.as_searchable_rule(tv_searcher.schema(), fastfield_reader)
+                        .map(|(_, rule)| CompiledRule {
+                            query: rule.query,
+                            boost: rule.boost,
+                            signals,
                         })
-                        .unwrap()
-                        .scorer(segment_reader, 0.0)
-                        .unwrap(),
-                    boost: rule.boost,
                 })
-                .collect();
-        }
+                .collect()
+        };
+
+        let rules: Arc<[CompiledRule]> = match &self.optic_rule_cache {
+            Some(cache) => cache.get_or_compile(
+                OpticFingerprint::new(&query.optic_rules),
+                tv_searcher.generation().generation_id(),
+                compile,
+            ),
+            None => compile().into(),
+        };
 
-        optic_rule_boosts
+        rules
+            .iter()
+            .map(|rule| RuleBoost {
+                docset: rule
+                    .query
+                    .weight(tantivy::query::EnableScoring::Enabled {
+                        searcher: tv_searcher,
+                        statistics_provider: tv_searcher,
+                    })
+                    .unwrap()
+                    .scorer(segment_reader, 0.0)
+                    .unwrap(),
+                boost: rule.boost,
+                signals: rule.signals.clone(),
+            })
+            .collect()
     }
 
     pub fn register_segment(
@@ -297,50 +702,139 @@ impl SignalComputer {
         let text_fields = self.prepare_textfields(tv_searcher, segment_reader)?;
         let optic_rule_boosts = self.prepare_optic(tv_searcher, segment_reader, fastfield_reader);
 
-        self.segment_reader = Some(RefCell::new(SegmentReader {
+        self.segment_reader = Some(Mutex::new(SegmentReader {
             text_fields,
             fastfield_reader: fastfield_segment_reader,
             optic_boosts: OpticBoosts {
                 rules: optic_rule_boosts,
             },
+            max_scored_doc: None,
+            max_doc: segment_reader.max_doc(),
         }));
 
+        // the coefficients are final once the segments are being registered
+        let mut order = std::mem::replace(&mut self.order, SignalComputeOrder::empty());
+        order.update_score_bounds(self);
+        self.order = order;
+
         Ok(())
     }
 
+    #[cfg(feature = "webgraph")]
     pub fn set_query_centrality(&mut self, query_centrality: query_centrality::Scorer) {
-        self.query_centrality = Some(RefCell::new(query_centrality));
+        self.query_centrality = Some(Mutex::new(query_centrality));
     }
 
+    #[cfg(feature = "webgraph")]
     pub fn set_inbound_similarity(&mut self, scorer: inbound_similarity::Scorer) {
         let mut scorer = scorer;
         scorer.set_default_if_precalculated(true);
 
-        self.inbound_similarity = Some(RefCell::new(scorer));
+        self.inbound_similarity = Some(Mutex::new(scorer));
+    }
+
+    /// Reuse the host signals computed by earlier requests with the same query fingerprint.
+    #[cfg(feature = "webgraph")]
+    pub fn set_host_signal_cache(&mut self, cache: Arc<HostSignalCache>, query: QueryFingerprint) {
+        self.host_signal_cache = Some((cache, query));
+    }
+
+    /// Reuse the optic rules compiled by earlier requests with the same optic.
+    pub fn set_optic_rule_cache(&mut self, cache: Arc<OpticRuleCache>) {
+        self.optic_rule_cache = Some(cache);
     }
 
     pub fn set_region_count(&mut self, region_count: RegionCount) {
         self.region_count = Some(Arc::new(region_count));
     }
 
+    pub fn set_tld_reputation(&mut self, tld_reputation: Arc<TldReputation>) {
+        self.tld_reputation = Some(tld_reputation);
+    }
+
     pub fn set_current_timestamp(&mut self, current_timestamp: usize) {
         self.current_timestamp = Some(current_timestamp);
     }
 
+    /// Resize the fetch time and freshness lookup tables. A table is only rebuilt
+    /// when its size changes.
+    pub fn set_cache_config(&mut self, config: SignalCacheConfig) {
+        if self.fetch_time_ms_cache.len() != config.fetch_time_ms.max(1) {
+            self.fetch_time_ms_cache = fetch_time_ms_cache(config.fetch_time_ms);
+        }
+
+        if self.update_time_cache.len() != config.freshness_horizon_hours {
+            self.update_time_cache = update_time_cache(config.freshness_horizon_hours);
+        }
+    }
+
+    /// Use `coefficients` for the precomputed score instead of the coefficients of the
+    /// linear model. Signals without a coefficient in the set use their default coefficient.
+    pub fn set_precompute_coefficients(&mut self, coefficients: SignalCoefficient) {
+        self.precompute_coefficients = Some(coefficients);
+    }
+
     pub fn set_linear_model(&mut self, linear_model: Arc<LinearRegression>) {
         self.linear_regression = Some(linear_model);
     }
 
+    /// The signals in the order they are computed for each document.
+    pub fn compute_order(&self) -> &[SignalEnum] {
+        self.order.signals()
+    }
+
+    /// Compute the signals in `order` before the other signals.
+    /// See [`SignalComputeOrder::with_priority`].
+    pub fn set_signal_priority(&mut self, order: &[SignalEnum]) {
+        let current = std::mem::replace(&mut self.order, SignalComputeOrder::empty());
+        self.order = current.with_priority(order);
+    }
+
+    /// Set the BM25 variant used by the text signals. This must be set before
+    /// the segments are registered.
+    pub fn set_bm25_variant(&mut self, variant: Bm25Variant) {
+        self.bm25_variant = variant;
+        self.term_weights = TermWeightCache::default();
+    }
+
+    /// Set the k1 and b parameters of BM25 used by the text signals. This must be
+    /// set before the segments are registered.
+    pub fn set_bm25_params(&mut self, params: Bm25Params) {
+        self.bm25_params = params;
+        self.term_weights = TermWeightCache::default();
+    }
+
+    #[cfg(feature = "webgraph")]
+    fn cached_host_signal(
+        &self,
+        host_id: NodeID,
+        signal: HostSignal,
+        compute: impl FnOnce() -> Option<f64>,
+    ) -> Option<f64> {
+        match &self.host_signal_cache {
+            Some((cache, query)) => cache.get_or_compute(*query, host_id, signal, compute),
+            None => compute(),
+        }
+    }
+
+    #[cfg(feature = "webgraph")]
     pub fn query_centrality(&self, host_id: NodeID) -> Option<f64> {
-        self.query_centrality
-            .as_ref()
-            .map(|scorer| scorer.borrow_mut().score(host_id))
+        let scorer = self.query_centrality.as_ref()?;
+
+        self.cached_host_signal(host_id, HostSignal::QueryCentrality, || {
+            lock(scorer).score(host_id).map(|score| score.value())
+        })
     }
 
+    #[cfg(feature = "webgraph")]
     pub fn inbound_similarity(&self, host_id: NodeID) -> f64 {
         self.inbound_similarity
             .as_ref()
-            .map(|scorer| scorer.borrow_mut().score(&host_id))
+            .and_then(|scorer| {
+                self.cached_host_signal(host_id, HostSignal::InboundSimilarity, || {
+                    Some(lock(scorer).score(&host_id))
+                })
+            })
             .unwrap_or_default()
     }
 
@@ -351,22 +845,145 @@ impl SignalComputer {
     /// scores calculated for their text related signals. The wrong ranking will most likely
     /// be returned.
     /// This function also assumes that the segment reader has been set.
-    pub fn compute_signals(&self, doc: DocId) -> impl Iterator<Item = Option<ComputedSignal>> + '_ {
-        self.order.compute(doc, self)
+    ///
+    /// If a `cutoff` is given, the remaining signals are skipped as soon as the document can
+    /// no longer reach a score of `cutoff`, in which case the returned signals are marked as pruned.
+    /// The cutoff is compared to the sum of the signals before any optic boosts are applied.
+    pub fn compute_signals(&self, doc: DocId, cutoff: Option<f64>) -> ComputedSignals<'_> {
+        if let Some(mut segment_reader) = self.segment_reader() {
+            segment_reader.max_scored_doc = Some(
+                segment_reader
+                    .max_scored_doc
+                    .map_or(doc, |max_doc| max_doc.max(doc)),
+            );
+        }
+
+        self.order.compute(doc, self, cutoff)
+    }
+
+    /// Like [`SignalComputer::compute_signals`], but every signal in the compute order
+    /// is reported, together with the reason if it was skipped. Text signals of a field
+    /// where none of the query terms occur in the document are reported as skipped,
+    /// even though [`SignalComputer::compute_signals`] gives them a value of 0.
+    ///
+    /// This is meant for debugging and is slower than [`SignalComputer::compute_signals`].
+    /// The documents must still be requested in ascending order of docid.
+    pub fn compute_signals_detailed(
+        &self,
+        doc: DocId,
+    ) -> impl Iterator<Item = SignalExplanation> + '_ {
+        let text_skips = self.text_skip_reasons(doc);
+        let mut computed = EnumMap::new();
+
+        if self.segment_reader.is_some() {
+            for signal in self.compute_signals(doc, None).flatten() {
+                computed.insert(signal.signal, signal);
+            }
+        }
+
+        self.order.signals().iter().map(move |signal| {
+            let reason = if self.segment_reader.is_none() {
+                Some(SkipReason::NoSegment)
+            } else if let Some(reason) = signal
+                .as_textfield()
+                .and_then(|field| text_skips.get(field))
+            {
+                Some(*reason)
+            } else if computed.contains_key(*signal) {
+                None
+            } else if self.coefficient(signal) <= 0.0 {
+                Some(SkipReason::ZeroCoefficient)
+            } else {
+                Some(SkipReason::NoValue)
+            };
+
+            let outcome = match reason {
+                Some(reason) => SignalOutcome::Skipped {
+                    signal: *signal,
+                    reason,
+                },
+                None => SignalOutcome::Computed(*computed.get(*signal).unwrap()),
+            };
+
+            SignalExplanation {
+                outcome,
+                fieldnorm: signal
+                    .as_textfield()
+                    .and_then(|field| self.fieldnorm(doc, field)),
+            }
+        })
     }
 
-    pub fn boosts(&mut self, doc: DocId) -> Option<f64> {
+    /// Why the text signals of each field in the compute order would be skipped for `doc`.
+    /// Fields where at least one of the query terms occur in the document are left out.
+    /// This must be called before the signals of `doc` are computed, as the postings
+    /// have moved past the document afterwards.
+    fn text_skip_reasons(&self, doc: DocId) -> EnumMap<TextFieldEnum, SkipReason> {
+        let mut reasons = EnumMap::new();
+
+        let Some(mut segment_reader) = self.segment_reader() else {
+            return reasons;
+        };
+
+        let has_query = self
+            .query_data
+            .as_ref()
+            .is_some_and(|query| !query.simple_terms.is_empty());
+        let out_of_order = segment_reader
+            .max_scored_doc
+            .is_some_and(|max_doc| doc < max_doc);
+
+        let fields: Vec<_> = self
+            .order
+            .signals()
+            .iter()
+            .filter_map(|signal| signal.as_textfield())
+            .unique()
+            .collect();
+
+        for field in fields {
+            let reason = if !has_query {
+                Some(SkipReason::NoQuery)
+            } else if out_of_order {
+                Some(SkipReason::OutOfOrderDoc)
+            } else {
+                match segment_reader.text_fields_mut().get_mut(field) {
+                    None => Some(SkipReason::MissingField),
+                    Some(data) if !data.contains_doc(doc) => Some(SkipReason::NoMatchingTerms),
+                    Some(_) => None,
+                }
+            };
+
+            if let Some(reason) = reason {
+                reasons.insert(field, reason);
+            }
+        }
+
+        reasons
+    }
+
+    /// The optic boosts of the document. Boosts without a target are combined into
+    /// a multiplier for the whole score, while scoped boosts are combined per signal.
+    pub fn boosts(&mut self, doc: DocId) -> Option<Boosts> {
         self.segment_reader.as_ref().map(|segment_reader| {
             let mut downrank = 0.0;
             let mut boost = 0.0;
+            let mut signals = EnumMap::new();
 
-            for rule in &mut segment_reader.borrow_mut().optic_boosts.rules {
+            for rule in &mut lock(segment_reader).optic_boosts.rules {
                 if rule.docset.doc() > doc {
                     continue;
                 }
 
                 if rule.docset.doc() == doc || rule.docset.seek(doc) == doc {
-                    if rule.boost < 0.0 {
+                    if !rule.signals.is_empty() {
+                        for signal in &rule.signals {
+                            match signals.get_mut(*signal) {
+                                Some(signal_boost) => *signal_boost += rule.boost,
+                                None => signals.insert(*signal, 1.0 + rule.boost),
+                            }
+                        }
+                    } else if rule.boost < 0.0 {
                         downrank += rule.boost.abs();
                     } else {
                         boost += rule.boost;
@@ -374,36 +991,76 @@ impl SignalComputer {
                 }
             }
 
-            if downrank > boost {
+            let total = if downrank > boost {
                 let diff = downrank - boost;
                 1.0 / (1.0 + diff)
             } else {
                 boost - downrank + 1.0
-            }
+            };
+
+            Boosts { total, signals }
         })
     }
 
+    /// The part of the score of the webpage that can be computed at index time.
+    /// Only the query independent signals contribute, weighted by their
+    /// [`SignalComputer::precompute_coefficient`]. Signals with a coefficient of zero
+    /// don't contribute and are not computed.
     pub fn precompute_score(&self, webpage: &Webpage) -> f64 {
+        self.precompute_score_with(|signal| signal.precompute(webpage, self))
+    }
+
+    fn precompute_score_with(&self, mut precompute: impl FnMut(SignalEnum) -> Option<f64>) -> f64 {
         SignalEnum::all()
             .filter_map(|signal| {
-                signal
-                    .precompute(webpage, self)
-                    .map(|value| ComputedSignal {
-                        signal,
-                        score: SignalScore {
-                            coefficient: self.coefficient(&signal),
-                            value,
-                        },
-                    })
+                let coefficient = self.precompute_coefficient(&signal);
+
+                if coefficient == 0.0 {
+                    return None;
+                }
+
+                precompute(signal).map(|value| ComputedSignal {
+                    signal,
+                    score: SignalScore {
+                        coefficient,
+                        value: signal.clamp_value(value),
+                    },
+                })
             })
             .map(|computed| computed.score.coefficient * computed.score.value)
             .sum()
     }
 
+    /// The coefficient of the signal in the precomputed score. The precomputed score is
+    /// stored in the index and shared by all queries, so query dependent signals have a
+    /// coefficient of 0 and the coefficients of the query are not used.
+    pub fn precompute_coefficient(&self, signal: &SignalEnum) -> f64 {
+        if signal.is_query_dependent() {
+            return 0.0;
+        }
+
+        self.precompute_coefficients
+            .as_ref()
+            .map(|coefficients| coefficients.get(signal))
+            .or_else(|| {
+                self.linear_regression
+                    .as_ref()
+                    .and_then(|model| model.weights.get(*signal).copied())
+            })
+            .unwrap_or(signal.default_coefficient())
+    }
+
+    /// The coefficient of the signal. Signals disabled by the query have a coefficient
+    /// of 0, so they are skipped instead of computed. The coefficients set by the optics
+    /// of the query take precedence over the ranking model for the signals they name.
     pub fn coefficient(&self, signal: &SignalEnum) -> f64 {
+        if self.disabled_signals.contains(signal) {
+            return 0.0;
+        }
+
         self.query_signal_coefficients
             .as_ref()
-            .map(|coefficients| coefficients.get(signal))
+            .and_then(|coefficients| coefficients.try_get(signal))
             .or_else(|| {
                 self.linear_regression
                     .as_ref()
@@ -412,14 +1069,46 @@ impl SignalComputer {
             .unwrap_or(signal.default_coefficient())
     }
 
-    pub fn segment_reader(&self) -> Option<&RefCell<SegmentReader>> {
-        self.segment_reader.as_ref()
+    pub fn segment_reader(&self) -> Option<MutexGuard<'_, SegmentReader>> {
+        self.segment_reader.as_ref().map(lock)
+    }
+
+    /// The query terms that occur in `doc` of the registered segment, the text fields they
+    /// occur in and their term frequencies. This is only computed when requested, e.g. for
+    /// the top results, from the postings already loaded for the signals.
+    pub fn matched_terms(&self, doc: DocId) -> Vec<(TextFieldEnum, String, u32)> {
+        self.segment_reader()
+            .map(|mut segment_reader| segment_reader.matched_terms(doc))
+            .unwrap_or_default()
+    }
+
+    /// The length of `field` in `doc` of the registered segment. Useful when debugging
+    /// why bm25 scores a long document low.
+    pub fn fieldnorm(&self, doc: DocId, field: TextFieldEnum) -> Option<u32> {
+        self.segment_reader()?.fieldnorm(doc, field)
     }
 
     pub fn fetch_time_ms_cache(&self) -> &[f64] {
         &self.fetch_time_ms_cache
     }
 
+    /// Score a page fetch time. Fetch times covered by the cache are looked up directly,
+    /// while slower fetch times are grouped into log2 sized buckets that continue the
+    /// curve of the cache, so slow pages are still differentiated from each other.
+    pub fn fetch_time_score(&self, fetch_time_ms: f64) -> f64 {
+        let cache = self.fetch_time_ms_cache();
+        let fetch_time_ms = fetch_time_ms.max(0.0);
+
+        if fetch_time_ms < cache.len() as f64 {
+            return cache[fetch_time_ms as usize];
+        }
+
+        let bucket = (fetch_time_ms / cache.len() as f64).log2().floor();
+        let bucket_start = cache.len() as f64 * bucket.exp2();
+
+        1.0 / (bucket_start + 1.0)
+    }
+
     pub fn current_timestamp(&self) -> Option<usize> {
         self.current_timestamp
     }
@@ -428,11 +1117,697 @@ impl SignalComputer {
         &self.update_time_cache
     }
 
+    /// Freshness score of a page updated `hours_since_update` hours ago.
+    /// Pages updated at or beyond the freshness horizon get a score of 0.
+    pub fn update_time_score(&self, hours_since_update: usize) -> f64 {
+        self.update_time_cache
+            .get(hours_since_update)
+            .copied()
+            .unwrap_or(0.0)
+    }
+
+    pub fn inbound_link_count_cache(&self) -> &[f64] {
+        &self.inbound_link_count_cache
+    }
+
     pub fn region_count(&self) -> Option<&RegionCount> {
         self.region_count.as_deref()
     }
 
+    pub fn tld_reputation(&self) -> Option<&TldReputation> {
+        self.tld_reputation.as_deref()
+    }
+
     pub fn query_data(&self) -> Option<&QueryData> {
         self.query_data.as_ref()
     }
 }
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Fetch time scores of the first `size` milliseconds. The cache always has at least
+/// one entry, so slower fetch times can be bucketed relative to its length.
+fn fetch_time_ms_cache(size: usize) -> Arc<[f64]> {
+    (0..size.max(1))
+        .map(|fetch_time| 1.0 / (fetch_time as f64 + 1.0))
+        .collect()
+}
+
+/// Freshness scores of the first `horizon_hours` hours since a page was updated.
+fn update_time_cache(horizon_hours: usize) -> Arc<[f64]> {
+    (0..horizon_hours)
+        .map(|hours_since_update| 1.0 / ((hours_since_update as f64 + 1.0).log2()))
+        .collect()
+}
+
+/// Indexes created before a text field was added to the schema will not have it.
+/// The signals using the field are skipped, and a warning is logged the first time
+/// each missing field is encountered.
+fn warn_missing_field(field: TextFieldEnum) {
+    static WARNED: once_cell::sync::Lazy<Mutex<HashSet<TextFieldEnum>>> =
+        once_cell::sync::Lazy::new(|| Mutex::new(HashSet::new()));
+
+    if lock(&WARNED).insert(field) {
+        tracing::warn!(
+            "text field '{}' is missing from the index schema. Signals using it will be skipped",
+            field.name()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        index::Index,
+        ranking::signal::{
+            Bm25Title, ContentFreshness, HostCentrality, PageCentrality, TermCoverage,
+            UpdateTimestamp,
+        },
+        searcher::SearchQuery,
+        webpage::{Html, Webpage},
+    };
+
+    use super::*;
+
+    #[test]
+    fn precompute_skips_zero_coefficients() {
+        let mut weights = EnumMap::new();
+        for signal in SignalEnum::all() {
+            weights.insert(signal, 1.0);
+        }
+        weights.insert(HostCentrality.into(), 0.0);
+
+        let mut computer = SignalComputer::new(None);
+        computer.set_linear_model(Arc::new(LinearRegression { weights }));
+
+        let mut precomputed = Vec::new();
+        let score = computer.precompute_score_with(|signal| {
+            precomputed.push(signal);
+            Some(1.0)
+        });
+
+        let num_query_independent = SignalEnum::all()
+            .filter(|signal| !signal.is_query_dependent())
+            .count();
+
+        assert!(!precomputed.contains(&HostCentrality.into()));
+        assert!(precomputed.contains(&PageCentrality.into()));
+        assert_eq!(score, (num_query_independent - 1) as f64);
+    }
+
+    #[test]
+    fn precompute_excludes_query_dependent_signals() {
+        let mut weights = EnumMap::new();
+        for signal in SignalEnum::all() {
+            weights.insert(signal, 1.0);
+        }
+
+        let mut computer = SignalComputer::new(None);
+        computer.set_linear_model(Arc::new(LinearRegression { weights }));
+
+        let mut precomputed = Vec::new();
+        computer.precompute_score_with(|signal| {
+            precomputed.push(signal);
+            Some(1.0)
+        });
+
+        assert!(!precomputed.is_empty());
+        assert!(precomputed
+            .iter()
+            .all(|signal| !signal.is_query_dependent()));
+        assert!(!precomputed.contains(&Bm25Title.into()));
+        assert!(!precomputed.contains(&TermCoverage.into()));
+        assert_eq!(computer.precompute_coefficient(&Bm25Title.into()), 0.0);
+        assert_eq!(computer.coefficient(&Bm25Title.into()), 1.0);
+
+        // the dedicated coefficients replace the coefficients of the linear model
+        computer.set_precompute_coefficients(SignalCoefficient::new(
+            [(HostCentrality.into(), 2.0), (Bm25Title.into(), 2.0)].into_iter(),
+        ));
+
+        assert_eq!(computer.precompute_coefficient(&HostCentrality.into()), 2.0);
+        assert_eq!(computer.precompute_coefficient(&Bm25Title.into()), 0.0);
+        assert_eq!(
+            computer.precompute_coefficient(&PageCentrality.into()),
+            PageCentrality.default_coefficient()
+        );
+        assert_eq!(computer.coefficient(&HostCentrality.into()), 1.0);
+    }
+
+    #[test]
+    fn optic_coefficients_override_named_signals() {
+        let index = Index::temporary().expect("Unable to open index");
+        let ctx = index.inverted_index.local_search_ctx();
+
+        let query = Query::parse(
+            &ctx,
+            &SearchQuery {
+                query: "test".to_string(),
+                optic: Some(
+                    optics::Optic::parse(r#"Ranking(Signal("content_freshness"), 2.0);"#).unwrap(),
+                ),
+                ..Default::default()
+            },
+            &index.inverted_index,
+        )
+        .unwrap();
+
+        let mut weights = EnumMap::new();
+        for signal in SignalEnum::all() {
+            weights.insert(signal, 1.0);
+        }
+
+        let mut computer = SignalComputer::new(Some(&query));
+        computer.set_linear_model(Arc::new(LinearRegression { weights }));
+
+        assert_eq!(computer.coefficient(&ContentFreshness.into()), 2.0);
+        assert_eq!(computer.coefficient(&HostCentrality.into()), 1.0);
+    }
+
+    #[test]
+    fn compute_order_has_every_enabled_signal_once() {
+        let computer = SignalComputer::empty_query();
+        let order = computer.compute_order();
+
+        for signal in SignalEnum::all() {
+            let count = order.iter().filter(|s| **s == signal).count();
+
+            if computer.coefficient(&signal) == 0.0 {
+                assert_eq!(count, 0, "{signal:?} is disabled");
+            } else {
+                assert_eq!(count, 1, "{signal:?} is enabled");
+            }
+        }
+
+        let browse = SignalComputer::browse();
+        assert!(!browse.compute_order().is_empty());
+        assert!(browse
+            .compute_order()
+            .iter()
+            .all(|signal| !signal.is_query_dependent()));
+    }
+
+    #[test]
+    fn clamp_out_of_range_values() {
+        let mut index = Index::temporary().expect("Unable to open index");
+
+        let webpage = Webpage {
+            html: Html::parse(
+                &format!(
+                    "<html><head><title>Title</title></head><body>{}</body></html>",
+                    crate::rand_words(100)
+                ),
+                "https://www.a.com",
+            )
+            .unwrap(),
+            host_centrality: 1_000_000.0,
+            ..Default::default()
+        };
+
+        index.insert(&webpage).unwrap();
+        index.commit().unwrap();
+
+        let ctx = index.inverted_index.local_search_ctx();
+        let mut computer = SignalComputer::new(None);
+
+        let mut weights = EnumMap::new();
+        for signal in SignalEnum::all() {
+            weights.insert(signal, 0.0);
+        }
+        weights.insert(HostCentrality.into(), 1.0);
+        computer.set_linear_model(Arc::new(LinearRegression { weights }));
+
+        assert_eq!(computer.precompute_score(&webpage), 1.0);
+
+        computer
+            .register_segment(
+                &ctx.tv_searcher,
+                ctx.tv_searcher.segment_reader(0),
+                &ctx.fastfield_reader,
+            )
+            .unwrap();
+
+        let host_centrality = computer
+            .compute_signals(0, None)
+            .flatten()
+            .find(|computed| computed.signal == HostCentrality.into())
+            .unwrap();
+        assert_eq!(host_centrality.score.value, 1.0);
+
+        let unbounded: SignalEnum = Bm25Title.into();
+        assert!(unbounded.value_range().is_none());
+        assert_eq!(unbounded.clamp_value(1_000_000.0), 1_000_000.0);
+    }
+
+    #[test]
+    fn browse_mode() {
+        let mut index = Index::temporary().expect("Unable to open index");
+
+        for (url, host_centrality) in [("https://www.a.com", 1.0), ("https://www.b.com", 0.0)] {
+            index
+                .insert(&Webpage {
+                    html: Html::parse(
+                        &format!(
+                            "<html><head><title>Title</title></head><body>{}</body></html>",
+                            crate::rand_words(100)
+                        ),
+                        url,
+                    )
+                    .unwrap(),
+                    host_centrality,
+                    ..Default::default()
+                })
+                .unwrap();
+        }
+        index.commit().unwrap();
+
+        let ctx = index.inverted_index.local_search_ctx();
+        let mut computer = SignalComputer::browse();
+        assert!(computer.is_browse());
+
+        computer
+            .register_segment(
+                &ctx.tv_searcher,
+                ctx.tv_searcher.segment_reader(0),
+                &ctx.fastfield_reader,
+            )
+            .unwrap();
+
+        let mut scores: Vec<(f64, f64)> = (0..2)
+            .map(|doc| {
+                let computed: Vec<_> = computer.compute_signals(doc, None).flatten().collect();
+
+                assert!(computed
+                    .iter()
+                    .all(|computed| !computed.signal.is_query_dependent()));
+
+                let host_centrality = computed
+                    .iter()
+                    .find(|computed| computed.signal == HostCentrality.into())
+                    .map(|computed| computed.score.value)
+                    .unwrap();
+
+                let score = computed
+                    .iter()
+                    .map(|computed| computed.score.coefficient * computed.score.value)
+                    .sum();
+
+                (host_centrality, score)
+            })
+            .collect();
+
+        scores.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        assert_eq!(scores[0].0, 0.0);
+        assert_eq!(scores[1].0, 1.0);
+        assert!(scores[1].1 > scores[0].1);
+    }
+
+    #[test]
+    fn term_weights_shared_across_segments() {
+        let mut index = Index::temporary().expect("Unable to open index");
+
+        for (segment, title) in ["example website", "website"].into_iter().enumerate() {
+            for i in 0..5 {
+                index
+                    .insert(&Webpage {
+                        html: Html::parse(
+                            &format!(
+                                "<html><head><title>{title} {i}</title></head><body>{}</body></html>",
+                                crate::rand_words(100)
+                            ),
+                            &format!("https://www.{segment}-{i}.com"),
+                        )
+                        .unwrap(),
+                        ..Default::default()
+                    })
+                    .unwrap();
+            }
+
+            index.commit().unwrap();
+        }
+
+        assert_eq!(index.inverted_index.num_segments(), 2);
+
+        let ctx = index.inverted_index.local_search_ctx();
+        let query = Query::parse(
+            &ctx,
+            &SearchQuery {
+                query: "example website".to_string(),
+                ..Default::default()
+            },
+            &index.inverted_index,
+        )
+        .unwrap();
+
+        let mut computer = SignalComputer::new(Some(&query));
+        let mut num_matching_terms = Vec::new();
+
+        for segment in ctx.tv_searcher.segment_readers() {
+            computer
+                .register_segment(&ctx.tv_searcher, segment, &ctx.fastfield_reader)
+                .unwrap();
+
+            let mut seg_reader = computer.segment_reader().unwrap();
+            let text_fields = seg_reader.text_fields_mut();
+
+            for field in text_fields.keys().collect::<Vec<_>>() {
+                let data = text_fields.get(field).unwrap();
+
+                // weights computed for the segment alone must match the shared weights
+                let expected = MultiBm25Weight::for_terms(
+                    &ctx.tv_searcher,
+                    &data.terms,
+                    &Bm25Variant::default(),
+                    &Bm25Params::default(),
+                    field,
+                )
+                .unwrap();
+
+                assert_eq!(
+                    data.weight.idf().collect::<Vec<_>>(),
+                    expected.idf().collect::<Vec<_>>()
+                );
+            }
+
+            num_matching_terms.push(
+                text_fields
+                    .get(crate::schema::text_field::Title.into())
+                    .unwrap()
+                    .terms
+                    .len(),
+            );
+        }
+
+        num_matching_terms.sort();
+        assert_eq!(num_matching_terms, vec![1, 2]);
+        assert!(lock(&computer.term_weights).is_some());
+    }
+
+    #[test]
+    fn matched_terms() {
+        let mut index = Index::temporary().expect("Unable to open index");
+
+        index
+            .insert(&Webpage {
+                html: Html::parse(
+                    r#"
+                    <html>
+                        <head>
+                            <title>Rust rust programming</title>
+                        </head>
+                        <body>
+                            Rust is a systems language.
+                        </body>
+                    </html>
+                "#,
+                    "https://www.a.com",
+                )
+                .unwrap(),
+                ..Default::default()
+            })
+            .unwrap();
+        index.commit().unwrap();
+
+        let ctx = index.inverted_index.local_search_ctx();
+        let query = Query::parse(
+            &ctx,
+            &SearchQuery {
+                query: "rust programming missing".to_string(),
+                ..Default::default()
+            },
+            &index.inverted_index,
+        )
+        .unwrap();
+
+        let mut computer = SignalComputer::new(Some(&query));
+        computer
+            .register_segment(
+                &ctx.tv_searcher,
+                ctx.tv_searcher.segment_reader(0),
+                &ctx.fastfield_reader,
+            )
+            .unwrap();
+
+        let matched = computer.matched_terms(0);
+
+        let mut title: Vec<_> = matched
+            .iter()
+            .filter(|(field, _, _)| *field == crate::schema::text_field::Title.into())
+            .map(|(_, term, freq)| (term.as_str(), *freq))
+            .collect();
+        title.sort();
+        assert_eq!(title, vec![("programming", 1), ("rust", 2)]);
+
+        let body: Vec<_> = matched
+            .iter()
+            .filter(|(field, _, _)| *field == crate::schema::text_field::CleanBody.into())
+            .map(|(_, term, freq)| (term.as_str(), *freq))
+            .collect();
+        assert_eq!(body, vec![("rust", 1)]);
+
+        assert!(matched.iter().all(|(_, term, _)| term != "missing"));
+    }
+
+    #[test]
+    fn detailed_skip_reasons() {
+        let mut index = Index::temporary().expect("Unable to open index");
+
+        index
+            .insert(&Webpage {
+                html: Html::parse(
+                    r#"
+                    <html>
+                        <head>
+                            <title>Rust programming</title>
+                        </head>
+                        <body>
+                            Rust is a systems language.
+                        </body>
+                    </html>
+                "#,
+                    "https://www.a.com",
+                )
+                .unwrap(),
+                ..Default::default()
+            })
+            .unwrap();
+        index.commit().unwrap();
+
+        let ctx = index.inverted_index.local_search_ctx();
+
+        let title_outcome = |query: &str| {
+            let query = Query::parse(
+                &ctx,
+                &SearchQuery {
+                    query: query.to_string(),
+                    ..Default::default()
+                },
+                &index.inverted_index,
+            )
+            .unwrap();
+
+            let mut computer = SignalComputer::new(Some(&query));
+            computer
+                .register_segment(
+                    &ctx.tv_searcher,
+                    ctx.tv_searcher.segment_reader(0),
+                    &ctx.fastfield_reader,
+                )
+                .unwrap();
+
+            let explanation = computer
+                .compute_signals_detailed(0)
+                .find(|explanation| match &explanation.outcome {
+                    SignalOutcome::Computed(computed) => computed.signal == Bm25Title.into(),
+                    SignalOutcome::Skipped { signal, .. } => *signal == Bm25Title.into(),
+                })
+                .unwrap();
+
+            explanation.outcome
+        };
+
+        assert!(matches!(
+            title_outcome("rust"),
+            SignalOutcome::Computed(computed) if computed.score.value > 0.0
+        ));
+        assert!(matches!(
+            title_outcome("python"),
+            SignalOutcome::Skipped {
+                reason: SkipReason::NoMatchingTerms,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn fieldnorm() {
+        let mut index = Index::temporary().expect("Unable to open index");
+
+        for (title, url) in [
+            ("rust", "https://www.a.com"),
+            ("rust programming language guide", "https://www.b.com"),
+        ] {
+            index
+                .insert(&Webpage {
+                    html: Html::parse(
+                        &format!(
+                            r#"
+                    <html>
+                        <head>
+                            <title>{title}</title>
+                        </head>
+                        <body>
+                            Rust is a systems language.
+                        </body>
+                    </html>
+                "#
+                        ),
+                        url,
+                    )
+                    .unwrap(),
+                    ..Default::default()
+                })
+                .unwrap();
+        }
+        index.commit().unwrap();
+
+        let ctx = index.inverted_index.local_search_ctx();
+        let query = Query::parse(
+            &ctx,
+            &SearchQuery {
+                query: "rust".to_string(),
+                ..Default::default()
+            },
+            &index.inverted_index,
+        )
+        .unwrap();
+
+        let mut computer = SignalComputer::new(Some(&query));
+        computer
+            .register_segment(
+                &ctx.tv_searcher,
+                ctx.tv_searcher.segment_reader(0),
+                &ctx.fastfield_reader,
+            )
+            .unwrap();
+
+        let title = crate::schema::text_field::Title.into();
+        let body = crate::schema::text_field::CleanBody.into();
+
+        assert_eq!(computer.fieldnorm(0, title), Some(1));
+        assert_eq!(computer.fieldnorm(1, title), Some(4));
+        assert_eq!(computer.fieldnorm(0, body), computer.fieldnorm(1, body));
+        assert_eq!(computer.fieldnorm(2, title), None);
+
+        let title_explanation = computer
+            .compute_signals_detailed(1)
+            .find(|explanation| match &explanation.outcome {
+                SignalOutcome::Computed(computed) => computed.signal == Bm25Title.into(),
+                SignalOutcome::Skipped { signal, .. } => *signal == Bm25Title.into(),
+            })
+            .unwrap();
+        assert_eq!(title_explanation.fieldnorm, Some(4));
+
+        let mut computer = SignalComputer::new(None);
+        computer
+            .register_segment(
+                &ctx.tv_searcher,
+                ctx.tv_searcher.segment_reader(0),
+                &ctx.fastfield_reader,
+            )
+            .unwrap();
+
+        assert_eq!(computer.fieldnorm(0, title), None);
+    }
+
+    #[test]
+    fn pinned_clock_freshness() {
+        let now = chrono::DateTime::parse_from_rfc3339("2024-03-01T12:00:00Z")
+            .unwrap()
+            .with_timezone(&chrono::Utc);
+        let updated = now - chrono::Duration::days(2);
+
+        let webpage = Webpage {
+            html: Html::parse(
+                &format!(
+                    r#"
+                    <html>
+                        <head>
+                            <title>Title</title>
+                            <meta property="og:updated_time" content="{}" />
+                        </head>
+                        <body>{}</body>
+                    </html>
+                "#,
+                    updated.to_rfc3339(),
+                    crate::rand_words(100)
+                ),
+                "https://www.a.com",
+            )
+            .unwrap(),
+            inserted_at: updated,
+            ..Default::default()
+        };
+
+        let clock = || now.timestamp() as usize;
+
+        let computer = SignalComputer::new_with_clock(None, &clock);
+        let expected = computer.update_time_cache()[2 * 24];
+
+        for _ in 0..2 {
+            let computer = SignalComputer::new_with_clock(None, &clock);
+            assert_eq!(
+                UpdateTimestamp.precompute(&webpage, &computer),
+                Some(expected)
+            );
+            assert_eq!(
+                ContentFreshness.precompute(&webpage, &computer),
+                Some(expected)
+            );
+        }
+
+        // without a clock the pages get a neutral score
+        let computer = SignalComputer::new(None);
+        assert_eq!(computer.current_timestamp(), None);
+        assert_eq!(UpdateTimestamp.precompute(&webpage, &computer), Some(0.0));
+        assert_eq!(ContentFreshness.precompute(&webpage, &computer), None);
+    }
+
+    #[test]
+    fn configured_cache_bounds() {
+        let mut computer = SignalComputer::new(None);
+        computer.set_cache_config(SignalCacheConfig {
+            fetch_time_ms: 10,
+            freshness_horizon_hours: 5,
+        });
+
+        assert_eq!(computer.fetch_time_ms_cache().len(), 10);
+        assert_eq!(computer.update_time_cache().len(), 5);
+
+        assert_eq!(computer.fetch_time_score(9.0), 1.0 / 10.0);
+        assert_eq!(computer.fetch_time_score(10.0), 1.0 / 11.0);
+        assert_eq!(computer.fetch_time_score(25.0), 1.0 / 21.0);
+        assert_eq!(computer.fetch_time_score(-1.0), 1.0);
+
+        assert_eq!(computer.update_time_score(4), 1.0 / 5.0_f64.log2());
+        assert_eq!(computer.update_time_score(5), 0.0);
+        assert_eq!(computer.update_time_score(1000), 0.0);
+    }
+
+    #[test]
+    fn empty_fetch_time_cache() {
+        let mut computer = SignalComputer::new(None);
+        computer.set_cache_config(SignalCacheConfig {
+            fetch_time_ms: 0,
+            freshness_horizon_hours: 0,
+        });
+
+        assert_eq!(computer.fetch_time_ms_cache().len(), 1);
+        assert_eq!(computer.fetch_time_score(0.0), 1.0);
+        assert!(computer.fetch_time_score(1000.0).is_finite());
+        assert_eq!(computer.update_time_score(0), 0.0);
+    }
+}
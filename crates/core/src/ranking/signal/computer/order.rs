@@ -14,6 +14,7 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+use itertools::Either;
 use tantivy::DocId;
 
 use crate::{
@@ -24,17 +25,73 @@ use crate::{
 
 use super::SignalComputer;
 
+#[derive(Clone)]
+enum ComputeStep {
+    /// The signals of a text field and its ngram fields. These are always
+    /// computed together so the ngrams can be dampened.
+    Text(NGramComputeOrder),
+    Signal(SignalEnum),
+}
+
+impl ComputeStep {
+    fn signals(&self) -> impl Iterator<Item = &SignalEnum> {
+        match self {
+            ComputeStep::Text(ngram) => Either::Left(ngram.signals.iter().map(|(_, s)| s)),
+            ComputeStep::Signal(signal) => Either::Right(std::iter::once(signal)),
+        }
+    }
+
+    /// Upper bound of the score the step can add to a document.
+    /// Signal values are assumed to be non-negative.
+    fn max_score(&self, signal_computer: &SignalComputer) -> f64 {
+        self.signals()
+            .map(|signal| {
+                let coefficient = signal_computer.coefficient(signal);
+
+                if coefficient <= 0.0 {
+                    0.0
+                } else {
+                    signal
+                        .max_value()
+                        .map_or(f64::INFINITY, |max| coefficient * max)
+                }
+            })
+            .sum()
+    }
+}
+
+/// The order in which the signals are computed for a document.
+///
+/// By default the text signals are computed first, grouped by their field,
+/// followed by the remaining signals. The order can be changed with
+/// [`SignalComputeOrder::with_priority`] so that cheap and discriminating signals
+/// are computed first, which lets a cutoff skip the remaining signals earlier.
 #[derive(Clone)]
 pub struct SignalComputeOrder {
-    text_signals: EnumMap<TextFieldEnum, NGramComputeOrder>,
-    other_signals: Vec<SignalEnum>,
+    steps: Vec<ComputeStep>,
+    /// The signals of the steps in the order they are computed.
+    signals: Vec<SignalEnum>,
+    /// `max_remaining[i]` is the largest score the steps from `i` and onwards can add to a document.
+    /// Empty until [`SignalComputeOrder::update_score_bounds`] has been called.
+    max_remaining: Vec<f64>,
 }
 
 impl SignalComputeOrder {
     pub fn empty() -> Self {
+        Self::from_steps(Vec::new())
+    }
+
+    fn from_steps(steps: Vec<ComputeStep>) -> Self {
+        let signals = steps
+            .iter()
+            .flat_map(|step| step.signals())
+            .copied()
+            .collect();
+
         Self {
-            text_signals: EnumMap::new(),
-            other_signals: Vec::new(),
+            steps,
+            signals,
+            max_remaining: Vec::new(),
         }
     }
 
@@ -47,6 +104,10 @@ impl SignalComputeOrder {
                 continue;
             }
 
+            if signal_computer.is_browse() && signal.is_query_dependent() {
+                continue;
+            }
+
             if let Some(text_field) = signal.as_textfield() {
                 let mono = text_field.monogram_field();
 
@@ -61,42 +122,176 @@ impl SignalComputeOrder {
             }
         }
 
-        Self {
-            text_signals,
-            other_signals,
+        let steps = text_signals
+            .values()
+            .cloned()
+            .map(ComputeStep::Text)
+            .chain(other_signals.into_iter().map(ComputeStep::Signal))
+            .collect();
+
+        Self::from_steps(steps)
+    }
+
+    /// Compute the signals in `order` before all other signals.
+    ///
+    /// A text signal moves the signals of its entire field, as the ngrams of a field
+    /// are always computed together. Signals that are not computed
+    /// (e.g. because their coefficient is 0) are ignored.
+    pub fn with_priority(mut self, order: &[SignalEnum]) -> Self {
+        let mut steps = Vec::with_capacity(self.steps.len());
+
+        for signal in order {
+            if let Some(pos) = self
+                .steps
+                .iter()
+                .position(|step| step.signals().any(|s| s == signal))
+            {
+                steps.push(self.steps.remove(pos));
+            }
+        }
+
+        steps.append(&mut self.steps);
+
+        Self::from_steps(steps)
+    }
+
+    /// The signals in the order they are computed. Signals that are not computed,
+    /// e.g. because their coefficient is 0, are not included.
+    pub fn signals(&self) -> &[SignalEnum] {
+        &self.signals
+    }
+
+    /// Calculate the largest possible score of the remaining signals after each step.
+    /// The bounds depend on the coefficients of the signal computer, so this must be
+    /// called after the coefficients are final.
+    pub fn update_score_bounds(&mut self, signal_computer: &SignalComputer) {
+        let mut max_remaining = vec![0.0; self.steps.len() + 1];
+
+        for (i, step) in self.steps.iter().enumerate().rev() {
+            max_remaining[i] = max_remaining[i + 1] + step.max_score(signal_computer);
         }
+
+        self.max_remaining = max_remaining;
     }
 
+    /// Compute the signals for `doc`.
+    ///
+    /// If a `cutoff` is given, the remaining signals are skipped once the weighted sum
+    /// of the signals computed so far plus the largest possible score of the remaining signals
+    /// is below the cutoff. The document is then marked as pruned (see [`ComputedSignals::is_pruned`]).
     pub fn compute<'a>(
         &'a self,
         doc: DocId,
         signal_computer: &'a SignalComputer,
-    ) -> impl Iterator<Item = Option<ComputedSignal>> + 'a {
-        self.text_signals
-            .values()
-            .flat_map(move |ngram| ngram.compute(doc, signal_computer))
-            .map(Some)
-            .chain(
-                self.other_signals
-                    .iter()
-                    .filter_map(|signal| {
-                        let coefficient = signal_computer.coefficient(signal);
-
-                        if coefficient > 0.0 {
-                            Some((signal, coefficient))
-                        } else {
-                            None
+        cutoff: Option<f64>,
+    ) -> ComputedSignals<'a> {
+        ComputedSignals {
+            order: self,
+            signal_computer,
+            doc,
+            cutoff,
+            next_step: 0,
+            current_text: None,
+            score: 0.0,
+            pruned: false,
+        }
+    }
+}
+
+pub struct ComputedSignals<'a> {
+    order: &'a SignalComputeOrder,
+    signal_computer: &'a SignalComputer,
+    doc: DocId,
+    cutoff: Option<f64>,
+    next_step: usize,
+    current_text: Option<NGramSignals<'a>>,
+    score: f64,
+    pruned: bool,
+}
+
+impl<'a> ComputedSignals<'a> {
+    /// Whether the remaining signals were skipped because the document
+    /// could not reach the cutoff.
+    pub fn is_pruned(&self) -> bool {
+        self.pruned
+    }
+
+    fn can_reach_cutoff(&self) -> bool {
+        match self.cutoff {
+            Some(cutoff) => {
+                let max_remaining = self
+                    .order
+                    .max_remaining
+                    .get(self.next_step)
+                    .copied()
+                    .unwrap_or(f64::INFINITY);
+
+                self.score + max_remaining >= cutoff
+            }
+            None => true,
+        }
+    }
+
+    fn add_score(&mut self, computed: &Option<ComputedSignal>) {
+        if let Some(computed) = computed {
+            self.score += computed.score.coefficient * computed.score.value;
+        }
+    }
+}
+
+impl<'a> Iterator for ComputedSignals<'a> {
+    type Item = Option<ComputedSignal>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(text) = &mut self.current_text {
+                match text.next() {
+                    Some(computed) => {
+                        let computed = Some(computed);
+                        self.add_score(&computed);
+                        return Some(computed);
+                    }
+                    None => self.current_text = None,
+                }
+            }
+
+            let order = self.order;
+            let step = order.steps.get(self.next_step)?;
+
+            if !self.can_reach_cutoff() {
+                self.pruned = true;
+                self.next_step = order.steps.len();
+                return None;
+            }
+
+            self.next_step += 1;
+
+            match step {
+                ComputeStep::Text(ngram) => {
+                    self.current_text = Some(ngram.compute(self.doc, self.signal_computer));
+                }
+                ComputeStep::Signal(signal) => {
+                    let coefficient = self.signal_computer.coefficient(signal);
+
+                    if coefficient <= 0.0 {
+                        continue;
+                    }
+
+                    let computed = signal.compute(self.doc, self.signal_computer).map(|value| {
+                        ComputedSignal {
+                            signal: *signal,
+                            score: SignalScore {
+                                coefficient,
+                                value: signal.clamp_value(value),
+                            },
                         }
-                    })
-                    .map(move |(signal, coefficient)| {
-                        signal
-                            .compute(doc, signal_computer)
-                            .map(|value| ComputedSignal {
-                                signal: *signal,
-                                score: SignalScore { coefficient, value },
-                            })
-                    }),
-            )
+                    });
+
+                    self.add_score(&computed);
+                    return Some(computed);
+                }
+            }
+        }
     }
 }
 
@@ -119,36 +314,44 @@ impl NGramComputeOrder {
         self.signals.sort_unstable_by(|(a, _), (b, _)| b.cmp(a));
     }
 
-    fn compute<'a>(
-        &'a self,
-        doc: DocId,
-        signal_computer: &'a SignalComputer,
-    ) -> impl Iterator<Item = ComputedSignal> + 'a {
-        let mut hits = 0;
+    fn compute<'a>(&'a self, doc: DocId, signal_computer: &'a SignalComputer) -> NGramSignals<'a> {
+        NGramSignals {
+            signals: self.signals.iter(),
+            doc,
+            signal_computer,
+            hits: 0,
+        }
+    }
+}
 
-        self.signals
-            .iter()
-            .map(|(_, s)| s)
-            .filter_map(move |signal| {
-                signal
-                    .compute(doc, signal_computer)
-                    .map(|value| {
-                        let coefficient = signal_computer.coefficient(signal);
+struct NGramSignals<'a> {
+    signals: std::slice::Iter<'a, (usize, SignalEnum)>,
+    doc: DocId,
+    signal_computer: &'a SignalComputer,
+    hits: i32,
+}
 
-                        ComputedSignal {
-                            signal: *signal,
-                            score: SignalScore { coefficient, value },
-                        }
-                    })
-                    .map(|mut c| {
-                        c.score.coefficient *= NGRAM_DAMPENING.powi(hits);
+impl<'a> Iterator for NGramSignals<'a> {
+    type Item = ComputedSignal;
 
-                        if c.score.value > 0.0 {
-                            hits += 1;
-                        }
+    fn next(&mut self) -> Option<Self::Item> {
+        for (_, signal) in self.signals.by_ref() {
+            if let Some(value) = signal.compute(self.doc, self.signal_computer) {
+                let value = signal.clamp_value(value);
+                let coefficient =
+                    self.signal_computer.coefficient(signal) * NGRAM_DAMPENING.powi(self.hits);
 
-                        c
-                    })
-            })
+                if value > 0.0 {
+                    self.hits += 1;
+                }
+
+                return Some(ComputedSignal {
+                    signal: *signal,
+                    score: SignalScore { coefficient, value },
+                });
+            }
+        }
+
+        None
     }
 }
@@ -26,11 +26,16 @@ use utoipa::ToSchema;
 mod computer;
 mod non_text;
 mod prelude;
+mod profile;
 mod text;
 
-pub use computer::SignalComputer;
+pub use computer::{
+    Boosts, Clock, OpticRuleOutcome, SignalComputer, SignalExplanation, SignalOutcome, SkipReason,
+    SystemClock,
+};
 pub use non_text::*;
 pub use prelude::*;
+pub use profile::CoefficientProfile;
 pub use text::*;
 
 #[derive(Debug, Error)]
@@ -49,6 +54,41 @@ impl FromStr for SignalEnumDiscriminants {
     }
 }
 
+/// Names of the signals behind a cargo feature that is disabled in this build. Models
+/// trained with these signals can still be loaded, but the signals never have a value.
+#[cfg(feature = "webgraph")]
+pub const UNAVAILABLE_SIGNALS: &[&str] = &[];
+#[cfg(not(feature = "webgraph"))]
+pub const UNAVAILABLE_SIGNALS: &[&str] = &["query_centrality", "inbound_similarity"];
+
+/// The name of each signal in the order of [`SignalEnum::all`].
+static SIGNAL_NAMES: once_cell::sync::Lazy<Vec<String>> = once_cell::sync::Lazy::new(|| {
+    SignalEnum::all()
+        .map(|signal| {
+            serde_json::to_value(SignalEnumDiscriminants::from(signal))
+                .ok()
+                .and_then(|name| name.as_str().map(str::to_string))
+                .unwrap()
+        })
+        .collect()
+});
+
+impl SignalEnum {
+    /// The canonical name of the signal, e.g. `host_centrality`. The name is the
+    /// snake case version of the variant name and is what optics, the search api and
+    /// the lambdamart models use to refer to the signal, so it must not change.
+    pub fn name(&self) -> &'static str {
+        &SIGNAL_NAMES[SignalEnumDiscriminants::from(*self) as usize]
+    }
+
+    /// The signal with the canonical `name` (see [`SignalEnum::name`]).
+    pub fn from_name(name: &str) -> Option<SignalEnum> {
+        SignalEnumDiscriminants::from_str(name)
+            .ok()
+            .map(SignalEnum::from)
+    }
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct SignalCoefficient {
     map: EnumMap<SignalEnum, f64>,
@@ -62,6 +102,11 @@ impl SignalCoefficient {
             .unwrap_or(signal.default_coefficient())
     }
 
+    /// The coefficient explicitly set for the signal, if any.
+    pub fn try_get(&self, signal: &SignalEnum) -> Option<f64> {
+        self.map.get(*signal).copied()
+    }
+
     pub fn new(coefficients: impl Iterator<Item = (SignalEnum, f64)>) -> Self {
         let mut map = EnumMap::default();
 
@@ -72,14 +117,36 @@ impl SignalCoefficient {
         Self { map }
     }
 
+    /// The coefficients set by the `Ranking(Signal("..."), coefficient)` directives
+    /// of the optic. Directives for unknown signals are ignored.
     pub fn from_optic(optic: &Optic) -> Self {
-        SignalCoefficient::new(optic.rankings.iter().filter_map(|coeff| {
-            match &coeff.target {
-                RankingTarget::Signal(signal) => SignalEnumDiscriminants::from_str(signal)
-                    .ok()
-                    .map(|signal| (signal.into(), coeff.value)),
+        SignalCoefficient::new(
+            optic
+                .rankings
+                .iter()
+                .filter_map(|coeff| match &coeff.target {
+                    RankingTarget::Signal(name) => match SignalEnum::from_name(name) {
+                        Some(signal) => Some((signal, coeff.value)),
+                        None if UNAVAILABLE_SIGNALS.contains(&name.as_str()) => {
+                            tracing::warn!("optic sets coefficient of signal '{name}' which is not compiled into this build");
+                            None
+                        }
+                        None => {
+                            tracing::warn!("optic sets coefficient of unknown signal '{name}'");
+                            None
+                        }
+                    },
+                }),
+        )
+    }
+
+    /// The coefficients set in `coeffs` replace the coefficients of `self`.
+    pub fn override_with(&mut self, coeffs: SignalCoefficient) {
+        for signal in SignalEnum::all() {
+            if let Some(coeff) = coeffs.map.get(signal).copied() {
+                self.map.insert(signal, coeff);
             }
-        }))
+        }
     }
 
     pub fn merge_into(&mut self, coeffs: SignalCoefficient) {
@@ -108,3 +175,157 @@ pub struct SignalScore {
     pub coefficient: f64,
     pub value: f64,
 }
+
+/// The unweighted value of every signal for a single result. The vector has one
+/// entry per signal in the order of [`SignalVector::layout`]. Signals that were not
+/// computed for the result are `0.0`.
+///
+/// The layout changes when signals are added or removed, so clients should map the
+/// positions to signals with the layout returned along with the vectors.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct SignalVector(Vec<f64>);
+
+impl SignalVector {
+    /// The signal of each position in the vector.
+    pub fn layout() -> impl Iterator<Item = SignalEnumDiscriminants> {
+        SignalEnum::all().map(SignalEnumDiscriminants::from)
+    }
+
+    /// The name (see [`SignalEnum::name`]) of the signal of each position in the vector.
+    /// Signals behind a disabled cargo feature are not part of the layout.
+    pub fn layout_names() -> Vec<String> {
+        SignalEnum::all()
+            .map(|signal| signal.name().to_string())
+            .collect()
+    }
+
+    pub fn as_slice(&self) -> &[f64] {
+        &self.0
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl From<&EnumMap<SignalEnum, SignalScore>> for SignalVector {
+    fn from(signals: &EnumMap<SignalEnum, SignalScore>) -> Self {
+        Self(
+            SignalEnum::all()
+                .map(|signal| signals.get(signal).map(|score| score.value).unwrap_or(0.0))
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn name_round_trip() {
+        for signal in SignalEnum::all() {
+            assert_eq!(SignalEnum::from_name(signal.name()), Some(signal));
+        }
+
+        assert_eq!(SignalEnum::from(HostCentrality).name(), "host_centrality");
+        assert_eq!(
+            SignalEnum::from_name("bm25_title"),
+            Some(SignalEnum::from(Bm25Title))
+        );
+        assert_eq!(SignalEnum::from_name("unknown_signal"), None);
+    }
+
+    #[test]
+    fn webgraph_signals_follow_feature() {
+        let compiled = cfg!(feature = "webgraph");
+
+        for name in ["query_centrality", "inbound_similarity"] {
+            assert_eq!(SignalEnum::from_name(name).is_some(), compiled);
+            assert_eq!(
+                SignalEnum::all().any(|signal| signal.name() == name),
+                compiled
+            );
+            assert_eq!(UNAVAILABLE_SIGNALS.contains(&name), !compiled);
+        }
+    }
+
+    /// Rerankers trained on the signal vectors depend on the position of each signal,
+    /// so changes to the layout must be deliberate.
+    #[test]
+    fn signal_vector_layout() {
+        #[cfg(feature = "webgraph")]
+        let webgraph_signals = vec!["query_centrality", "inbound_similarity"];
+        #[cfg(not(feature = "webgraph"))]
+        let webgraph_signals: Vec<&str> = Vec::new();
+
+        let expected: Vec<&str> = [
+            "bm25_title",
+            "bm25_title_bigrams",
+            "bm25_title_trigrams",
+            "bm25_clean_body",
+            "bm25_clean_body_bigrams",
+            "bm25_clean_body_trigrams",
+            "bm25_stemmed_title",
+            "bm25_stemmed_clean_body",
+            "bm25_all_body",
+            "bm25_keywords",
+            "bm25_backlink_text",
+            "idf_sum_url",
+            "idf_sum_site",
+            "idf_sum_domain",
+            "idf_sum_site_no_tokenizer",
+            "idf_sum_domain_no_tokenizer",
+            "idf_sum_domain_name_no_tokenizer",
+            "idf_sum_domain_if_homepage",
+            "idf_sum_domain_name_if_homepage_no_tokenizer",
+            "idf_sum_domain_if_homepage_no_tokenizer",
+            "idf_sum_title_if_homepage",
+            "term_coverage",
+            "anchor_text_match",
+            "cross_encoder_snippet",
+            "cross_encoder_title",
+            "host_centrality",
+            "host_centrality_rank",
+            "page_centrality",
+            "page_centrality_rank",
+            "is_homepage",
+            "fetch_time_ms",
+            "update_timestamp",
+            "tracker_score",
+            "region",
+        ]
+        .into_iter()
+        .chain(webgraph_signals)
+        .chain([
+            "lambda_mart",
+            "url_digits",
+            "url_slashes",
+            "link_density",
+            "title_embedding_similarity",
+            "keyword_embedding_similarity",
+            "domain_name_match",
+            "inbound_link_count",
+            "page_byte_size",
+            "url_term_match",
+            "majority_region",
+            "proximity",
+            "content_freshness",
+            "content_length",
+            "tld_reputation",
+            "mobile_friendly",
+            "has_structured_data",
+            "homepage_boost",
+            "heading_match",
+            "keyword_stuffing",
+            "readability",
+        ])
+        .collect();
+
+        assert_eq!(SignalVector::layout_names(), expected);
+    }
+}
@@ -14,29 +14,166 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>
 
+use std::{collections::HashSet, ops::RangeInclusive};
+
+use itertools::Itertools;
 use tantivy::DocId;
 
 use super::{Signal, SignalComputer};
+#[cfg(feature = "webgraph")]
+use crate::{fastfield_reader, webgraph::NodeID};
 use crate::{
-    fastfield_reader::FieldReader,
-    schema::{self, Field, FLOAT_SCALING},
-    webgraph::NodeID,
-    webpage::Webpage,
+    ranking::tld_reputation::NEUTRAL_REPUTATION,
+    schema::{self, Field},
+    webpage::{url_ext, url_ext::UrlExt, Webpage},
 };
 
+/// A query term is the domain name itself (e.g. `wikipedia` for `en.wikipedia.org`).
+const DOMAIN_NAME_FULL_MATCH: f64 = 1.0;
+/// A query term is one of the subdomain labels (e.g. `docs` for `docs.example.com`).
+const DOMAIN_NAME_SUBDOMAIN_MATCH: f64 = 0.5;
+/// A query term is contained in the domain name (e.g. `wiki` for `wikipedia.org`).
+const DOMAIN_NAME_PARTIAL_MATCH: f64 = 0.25;
+const DOMAIN_NAME_PARTIAL_MIN_LEN: usize = 3;
+
+/// Queries with more terms than this are treated as informational, even if
+/// one of the terms matches the domain name.
+const NAVIGATIONAL_MAX_TERMS: usize = 2;
+
+/// Characters that separate the words of a url path, e.g. `/best-rust_books.html`.
+const URL_PATH_SEPARATORS: [char; 6] = ['/', '-', '_', '.', '+', '~'];
+
+/// Pages up to this size are not penalized.
+const PAGE_BYTE_SIZE_NO_PENALTY: f64 = 16.0 * 1024.0;
+/// Pages of this size and larger get the full penalty.
+const PAGE_BYTE_SIZE_FULL_PENALTY: f64 = 16.0 * 1024.0 * 1024.0;
+/// Pages of unknown size are scored halfway between no and full penalty,
+/// so they are neither rewarded nor punished for the missing data.
+const PAGE_BYTE_SIZE_UNKNOWN: f64 = 0.5;
+
+/// Pages with up to this many outgoing links per word are not penalized.
+const LINK_DENSITY_NO_PENALTY: f64 = 0.1;
+/// Pages with this many outgoing links per word or more get the full penalty.
+const LINK_DENSITY_FULL_PENALTY: f64 = 0.5;
+/// Pages without a link density are scored halfway between no and full penalty.
+const LINK_DENSITY_UNKNOWN: f64 = 0.5;
+
+/// Average number of words per sentence that gets the highest [`Readability`] score.
+const READABILITY_IDEAL_WORDS_PER_SENTENCE: f64 = 15.0;
+/// Width of the [`Readability`] curve in log space. Pages with `e` times longer or
+/// shorter sentences than the ideal get a score of `exp(-0.5 / WIDTH^2)`.
+const READABILITY_WIDTH: f64 = 0.5;
+/// Pages without text are scored as if they had average readability.
+const READABILITY_UNKNOWN: f64 = 0.5;
+
+/// Pages are scored 0.0, the same as pages without a timestamp, if the computer
+/// has no current timestamp.
 fn score_timestamp(page_timestamp: usize, signal_computer: &SignalComputer) -> f64 {
-    if page_timestamp >= signal_computer.current_timestamp().unwrap_or(0) {
+    let Some(now) = signal_computer.current_timestamp() else {
+        return 0.0;
+    };
+
+    if page_timestamp >= now {
+        return 0.0;
+    }
+
+    let hours_since_update = (now - page_timestamp).max(1) / 3600;
+
+    signal_computer.update_time_score(hours_since_update)
+}
+
+/// Score the freshness of a page by its content date, or by the time it was crawled
+/// if the page doesn't declare a date. Dates after the current time, e.g. because of
+/// clock skew, are treated as the current time.
+fn score_content_freshness(
+    content_timestamp: u64,
+    crawl_timestamp: u64,
+    signal_computer: &SignalComputer,
+) -> Option<f64> {
+    let now = signal_computer.current_timestamp()?;
+
+    let timestamp = if content_timestamp > 0 {
+        content_timestamp
+    } else if crawl_timestamp > 0 {
+        crawl_timestamp
+    } else {
+        return None;
+    };
+
+    let timestamp = (timestamp as usize).min(now);
+    let hours_since_update = ((now - timestamp) / 3600).max(1);
+
+    Some(signal_computer.update_time_score(hours_since_update))
+}
+
+/// Selected regions are boosted by [`Region`], so the majority region is
+/// only used when no region is selected.
+fn score_majority_region(
+    webpage_region: crate::webpage::Region,
+    computer: &SignalComputer,
+) -> Option<f64> {
+    let selected_region = computer.query_data().and_then(|q| q.selected_region());
+
+    if selected_region.is_some_and(|region| region != crate::webpage::Region::All) {
+        return None;
+    }
+
+    let top_region = computer.region_count()?.top_region()?;
+
+    if webpage_region == top_region {
+        Some(1.0)
+    } else {
+        Some(0.0)
+    }
+}
+
+/// The fraction of the distinct query terms that are words of the url path.
+fn score_url_term_match(path: &str, terms: &[String]) -> f64 {
+    let terms: Vec<String> = terms
+        .iter()
+        .map(|term| term.to_lowercase())
+        .unique()
+        .collect();
+
+    if terms.is_empty() {
         return 0.0;
     }
 
-    let hours_since_update =
-        (signal_computer.current_timestamp().unwrap() - page_timestamp).max(1) / 3600;
+    let words: HashSet<String> = path
+        .split(|c: char| URL_PATH_SEPARATORS.contains(&c) || c.is_whitespace())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_lowercase())
+        .collect();
+
+    let num_matches = terms.iter().filter(|term| words.contains(*term)).count();
+
+    num_matches as f64 / terms.len() as f64
+}
+
+fn score_page_byte_size(byte_size: Option<u64>) -> f64 {
+    let Some(byte_size) = byte_size.filter(|size| *size > 0) else {
+        return PAGE_BYTE_SIZE_UNKNOWN;
+    };
 
+    let penalty = (byte_size as f64 / PAGE_BYTE_SIZE_NO_PENALTY).log2()
+        / (PAGE_BYTE_SIZE_FULL_PENALTY / PAGE_BYTE_SIZE_NO_PENALTY).log2();
+
+    1.0 - penalty.clamp(0.0, 1.0)
+}
+
+fn score_tld_reputation(host: &str, signal_computer: &SignalComputer) -> f64 {
     signal_computer
-        .update_time_cache()
-        .get(hours_since_update)
+        .tld_reputation()
+        .map(|table| table.score(host))
+        .unwrap_or(NEUTRAL_REPUTATION)
+}
+
+fn score_inbound_link_count(num_links: usize, signal_computer: &SignalComputer) -> f64 {
+    signal_computer
+        .inbound_link_count_cache()
+        .get(num_links)
         .copied()
-        .unwrap_or(0.0)
+        .unwrap_or(1.0)
 }
 
 #[inline]
@@ -59,13 +196,29 @@ fn score_slashes(num_slashes: f64) -> f64 {
     1.0 / (num_slashes + 1.0)
 }
 
-#[inline]
-fn score_link_density(link_density: f64) -> f64 {
-    if link_density > 0.5 {
-        0.0
-    } else {
-        1.0 - link_density
-    }
+fn score_link_density(link_density: Option<f64>) -> f64 {
+    let Some(link_density) = link_density else {
+        return LINK_DENSITY_UNKNOWN;
+    };
+
+    let penalty = (link_density - LINK_DENSITY_NO_PENALTY)
+        / (LINK_DENSITY_FULL_PENALTY - LINK_DENSITY_NO_PENALTY);
+
+    1.0 - penalty.clamp(0.0, 1.0)
+}
+
+/// Bell shaped score of the average sentence length centered on
+/// [`READABILITY_IDEAL_WORDS_PER_SENTENCE`] in log space, so both fragmented text
+/// and run-on sentences get a low score.
+fn score_readability(words_per_sentence: Option<f64>) -> f64 {
+    let Some(words_per_sentence) = words_per_sentence.filter(|wps| *wps > 0.0) else {
+        return READABILITY_UNKNOWN;
+    };
+
+    let distance =
+        (words_per_sentence / READABILITY_IDEAL_WORDS_PER_SENTENCE).ln() / READABILITY_WIDTH;
+
+    (-0.5 * distance * distance).exp()
 }
 
 fn score_region(webpage_region: crate::webpage::Region, computer: &SignalComputer) -> f64 {
@@ -88,11 +241,82 @@ fn score_region(webpage_region: crate::webpage::Region, computer: &SignalCompute
     }
 }
 
-fn host_id(fastfield_reader: &FieldReader<'_>) -> Option<NodeID> {
-    let node_id = fastfield_reader
-        .get(schema::fast_field::HostNodeID.into())
-        .and_then(|n| n.as_u64())
-        .unwrap();
+/// Checks whether the terms concatenated (e.g. `stack overflow`) spell out `name`.
+fn concatenated_terms_eq(terms: &[String], name: &str) -> bool {
+    let mut rest = name;
+
+    for term in terms {
+        match rest.get(..term.len()) {
+            Some(prefix) if prefix.eq_ignore_ascii_case(term) => rest = &rest[term.len()..],
+            _ => return false,
+        }
+    }
+
+    rest.is_empty()
+}
+
+fn score_domain_name_match(host: &str, terms: &[String]) -> f64 {
+    if terms.is_empty() {
+        return 0.0;
+    }
+
+    let root_domain = match url_ext::root_domain(host) {
+        Some(root_domain) => root_domain,
+        None => return 0.0,
+    };
+
+    let domain_name = root_domain.split('.').next().unwrap_or_default();
+
+    if terms
+        .iter()
+        .any(|term| term.eq_ignore_ascii_case(domain_name))
+        || concatenated_terms_eq(terms, domain_name)
+    {
+        return DOMAIN_NAME_FULL_MATCH;
+    }
+
+    let subdomain = host
+        .strip_suffix(root_domain)
+        .map(|s| s.trim_end_matches('.'))
+        .unwrap_or_default();
+
+    if subdomain
+        .split('.')
+        .filter(|label| !label.is_empty() && *label != "www")
+        .any(|label| terms.iter().any(|term| term.eq_ignore_ascii_case(label)))
+    {
+        return DOMAIN_NAME_SUBDOMAIN_MATCH;
+    }
+
+    if terms
+        .iter()
+        .filter(|term| term.len() >= DOMAIN_NAME_PARTIAL_MIN_LEN)
+        .any(|term| domain_name.contains(&term.to_ascii_lowercase()))
+    {
+        return DOMAIN_NAME_PARTIAL_MATCH;
+    }
+
+    0.0
+}
+
+/// Homepages are scored 1.0 when the query looks navigational, i.e. it is short
+/// and fully matches the domain name of the page (e.g. `github` for `github.com`).
+/// Every other page is scored 0.0, so homepages are not boosted for informational queries.
+fn score_homepage_boost(is_homepage: bool, host: &str, terms: &[String]) -> f64 {
+    if !is_homepage || terms.is_empty() || terms.len() > NAVIGATIONAL_MAX_TERMS {
+        return 0.0;
+    }
+
+    if score_domain_name_match(host, terms) == DOMAIN_NAME_FULL_MATCH {
+        1.0
+    } else {
+        0.0
+    }
+}
+
+#[cfg(feature = "webgraph")]
+fn host_id(fastfield_reader: &fastfield_reader::SegmentReader, doc: DocId) -> Option<NodeID> {
+    let node_id = fastfield_reader.get_u64(doc, schema::fast_field::HostNodeID)?;
 
     if node_id == u64::MAX {
         None
@@ -112,19 +336,20 @@ impl Signal for HostCentrality {
         Some(Field::Fast(schema::fast_field::HostCentrality.into()))
     }
 
+    fn value_range(&self) -> Option<RangeInclusive<f64>> {
+        Some(0.0..=1.0)
+    }
+
     fn precompute(self, webpage: &Webpage, _: &SignalComputer) -> Option<f64> {
         Some(webpage.host_centrality)
     }
 
     fn compute(&self, doc: DocId, signal_computer: &SignalComputer) -> Option<f64> {
-        let seg_reader = signal_computer.segment_reader().unwrap().borrow_mut();
-        let fastfield_reader = seg_reader.fastfield_reader().get_field_reader(doc);
+        let seg_reader = signal_computer.segment_reader().unwrap();
 
-        let val = fastfield_reader
-            .get(self.as_fastfield().unwrap())
-            .and_then(|v| v.as_u64())
-            .unwrap();
-        Some(val as f64 / FLOAT_SCALING as f64)
+        seg_reader
+            .fastfield_reader()
+            .get_f64(doc, self.as_fastfield().unwrap())
     }
 }
 
@@ -139,18 +364,20 @@ impl Signal for HostCentralityRank {
         Some(Field::Fast(schema::fast_field::HostCentralityRank.into()))
     }
 
+    fn max_value(&self) -> Option<f64> {
+        Some(1.0)
+    }
+
     fn precompute(self, webpage: &Webpage, _: &SignalComputer) -> Option<f64> {
         Some(score_rank(webpage.host_centrality_rank as f64))
     }
 
     fn compute(&self, doc: DocId, signal_computer: &SignalComputer) -> Option<f64> {
-        let seg_reader = signal_computer.segment_reader().unwrap().borrow_mut();
-        let fastfield_reader = seg_reader.fastfield_reader().get_field_reader(doc);
+        let seg_reader = signal_computer.segment_reader().unwrap();
 
-        let val = fastfield_reader
-            .get(self.as_fastfield().unwrap())
-            .and_then(|v| v.as_u64())
-            .unwrap();
+        let val = seg_reader
+            .fastfield_reader()
+            .get_u64(doc, self.as_fastfield().unwrap())?;
         Some(score_rank(val as f64))
     }
 }
@@ -166,19 +393,20 @@ impl Signal for PageCentrality {
         Some(Field::Fast(schema::fast_field::PageCentrality.into()))
     }
 
+    fn value_range(&self) -> Option<RangeInclusive<f64>> {
+        Some(0.0..=1.0)
+    }
+
     fn precompute(self, webpage: &Webpage, _: &SignalComputer) -> Option<f64> {
         Some(webpage.page_centrality)
     }
 
     fn compute(&self, doc: DocId, signal_computer: &SignalComputer) -> Option<f64> {
-        let seg_reader = signal_computer.segment_reader().unwrap().borrow_mut();
-        let fastfield_reader = seg_reader.fastfield_reader().get_field_reader(doc);
+        let seg_reader = signal_computer.segment_reader().unwrap();
 
-        let val = fastfield_reader
-            .get(self.as_fastfield().unwrap())
-            .and_then(|v| v.as_u64())
-            .unwrap();
-        Some(val as f64 / FLOAT_SCALING as f64)
+        seg_reader
+            .fastfield_reader()
+            .get_f64(doc, self.as_fastfield().unwrap())
     }
 }
 
@@ -193,18 +421,20 @@ impl Signal for PageCentralityRank {
         Some(Field::Fast(schema::fast_field::PageCentralityRank.into()))
     }
 
+    fn max_value(&self) -> Option<f64> {
+        Some(1.0)
+    }
+
     fn precompute(self, webpage: &Webpage, _: &SignalComputer) -> Option<f64> {
         Some(score_rank(webpage.page_centrality_rank as f64))
     }
 
     fn compute(&self, doc: DocId, signal_computer: &SignalComputer) -> Option<f64> {
-        let seg_reader = signal_computer.segment_reader().unwrap().borrow_mut();
-        let fastfield_reader = seg_reader.fastfield_reader().get_field_reader(doc);
+        let seg_reader = signal_computer.segment_reader().unwrap();
 
-        let val = fastfield_reader
-            .get(self.as_fastfield().unwrap())
-            .and_then(|v| v.as_u64())
-            .unwrap();
+        let val = seg_reader
+            .fastfield_reader()
+            .get_u64(doc, self.as_fastfield().unwrap())?;
         Some(score_rank(val as f64))
     }
 }
@@ -220,18 +450,20 @@ impl Signal for IsHomepage {
         Some(Field::Fast(schema::fast_field::IsHomepage.into()))
     }
 
+    fn max_value(&self) -> Option<f64> {
+        Some(1.0)
+    }
+
     fn precompute(self, webpage: &Webpage, _: &SignalComputer) -> Option<f64> {
         Some(webpage.html.is_homepage().into())
     }
 
     fn compute(&self, doc: DocId, signal_computer: &SignalComputer) -> Option<f64> {
-        let seg_reader = signal_computer.segment_reader().unwrap().borrow_mut();
-        let fastfield_reader = seg_reader.fastfield_reader().get_field_reader(doc);
+        let seg_reader = signal_computer.segment_reader().unwrap();
 
-        let val = fastfield_reader
-            .get(self.as_fastfield().unwrap())
-            .and_then(|v| v.as_u64())
-            .unwrap();
+        let val = seg_reader
+            .fastfield_reader()
+            .get_u64(doc, self.as_fastfield().unwrap())?;
         Some(val as f64)
     }
 }
@@ -247,29 +479,22 @@ impl Signal for FetchTimeMs {
         Some(Field::Fast(schema::fast_field::FetchTimeMs.into()))
     }
 
+    fn max_value(&self) -> Option<f64> {
+        Some(1.0)
+    }
+
     fn precompute(self, webpage: &Webpage, signal_computer: &SignalComputer) -> Option<f64> {
-        let fetch_time_ms = webpage.fetch_time_ms as usize;
-        if fetch_time_ms >= signal_computer.fetch_time_ms_cache().len() {
-            Some(0.0)
-        } else {
-            Some(signal_computer.fetch_time_ms_cache()[fetch_time_ms])
-        }
+        Some(signal_computer.fetch_time_score(webpage.fetch_time_ms as f64))
     }
 
     fn compute(&self, doc: DocId, signal_computer: &SignalComputer) -> Option<f64> {
-        let seg_reader = signal_computer.segment_reader().unwrap().borrow_mut();
-        let fastfield_reader = seg_reader.fastfield_reader().get_field_reader(doc);
-
-        let fetch_time_ms = fastfield_reader
-            .get(self.as_fastfield().unwrap())
-            .and_then(|v| v.as_u64())
-            .unwrap() as usize;
-
-        if fetch_time_ms >= signal_computer.fetch_time_ms_cache().len() {
-            Some(0.0)
-        } else {
-            Some(signal_computer.fetch_time_ms_cache()[fetch_time_ms])
-        }
+        let seg_reader = signal_computer.segment_reader().unwrap();
+
+        let fetch_time_ms = seg_reader
+            .fastfield_reader()
+            .get_u64(doc, self.as_fastfield().unwrap())?;
+
+        Some(signal_computer.fetch_time_score(fetch_time_ms as f64))
     }
 }
 
@@ -295,18 +520,54 @@ impl Signal for UpdateTimestamp {
     }
 
     fn compute(&self, doc: DocId, signal_computer: &SignalComputer) -> Option<f64> {
-        let seg_reader = signal_computer.segment_reader().unwrap().borrow_mut();
-        let fastfield_reader = seg_reader.fastfield_reader().get_field_reader(doc);
+        let seg_reader = signal_computer.segment_reader().unwrap();
 
-        let val = fastfield_reader
-            .get(self.as_fastfield().unwrap())
-            .and_then(|v| v.as_u64())
-            .unwrap() as usize;
+        let val = seg_reader
+            .fastfield_reader()
+            .get_u64(doc, self.as_fastfield().unwrap())? as usize;
 
         Some(score_timestamp(val, signal_computer))
     }
 }
 
+/// Like [`UpdateTimestamp`], but pages without a content date are scored
+/// by their crawl time instead of being treated as very old.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct ContentFreshness;
+impl Signal for ContentFreshness {
+    fn default_coefficient(&self) -> f64 {
+        0.0
+    }
+
+    fn as_field(&self) -> Option<Field> {
+        Some(Field::Fast(schema::fast_field::LastUpdated.into()))
+    }
+
+    fn precompute(self, webpage: &Webpage, signal_computer: &SignalComputer) -> Option<f64> {
+        let content_timestamp = webpage
+            .html
+            .updated_time()
+            .map_or(0, |date| date.timestamp().max(0) as u64);
+        let crawl_timestamp = webpage.inserted_at.timestamp().max(0) as u64;
+
+        score_content_freshness(content_timestamp, crawl_timestamp, signal_computer)
+    }
+
+    fn compute(&self, doc: DocId, signal_computer: &SignalComputer) -> Option<f64> {
+        let seg_reader = signal_computer.segment_reader().unwrap();
+        let fastfield_reader = seg_reader.fastfield_reader();
+
+        let content_timestamp = fastfield_reader
+            .get_u64(doc, self.as_fastfield().unwrap())
+            .unwrap_or(0);
+        let crawl_timestamp = fastfield_reader
+            .get_u64(doc, schema::fast_field::CrawlTimestamp.into())
+            .unwrap_or(0);
+
+        score_content_freshness(content_timestamp, crawl_timestamp, signal_computer)
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
 pub struct TrackerScore;
 impl Signal for TrackerScore {
@@ -318,19 +579,21 @@ impl Signal for TrackerScore {
         Some(Field::Fast(schema::fast_field::TrackerScore.into()))
     }
 
+    fn max_value(&self) -> Option<f64> {
+        Some(1.0)
+    }
+
     fn precompute(self, webpage: &Webpage, _: &SignalComputer) -> Option<f64> {
         let num_trackers = webpage.html.trackers().len() as f64;
         Some(score_trackers(num_trackers))
     }
 
     fn compute(&self, doc: DocId, signal_computer: &SignalComputer) -> Option<f64> {
-        let seg_reader = signal_computer.segment_reader().unwrap().borrow_mut();
-        let fastfield_reader = seg_reader.fastfield_reader().get_field_reader(doc);
+        let seg_reader = signal_computer.segment_reader().unwrap();
 
-        let val = fastfield_reader
-            .get(self.as_fastfield().unwrap())
-            .and_then(|v| v.as_u64())
-            .unwrap();
+        let val = seg_reader
+            .fastfield_reader()
+            .get_u64(doc, self.as_fastfield().unwrap())?;
         Some(score_trackers(val as f64))
     }
 }
@@ -353,20 +616,54 @@ impl Signal for Region {
     }
 
     fn compute(&self, doc: DocId, signal_computer: &SignalComputer) -> Option<f64> {
-        let seg_reader = signal_computer.segment_reader().unwrap().borrow_mut();
-        let fastfield_reader = seg_reader.fastfield_reader().get_field_reader(doc);
+        let seg_reader = signal_computer.segment_reader().unwrap();
 
-        let val = fastfield_reader
-            .get(self.as_fastfield().unwrap())
-            .and_then(|v| v.as_u64())
-            .unwrap();
+        let val = seg_reader
+            .fastfield_reader()
+            .get_u64(doc, self.as_fastfield().unwrap())?;
         let region = crate::webpage::Region::from_id(val);
         Some(score_region(region, signal_computer))
     }
 }
 
+/// Favors pages from the region with the most pages in the index when the search
+/// has no selected region. The signal is disabled by default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct MajorityRegion;
+impl Signal for MajorityRegion {
+    fn default_coefficient(&self) -> f64 {
+        0.0
+    }
+
+    fn as_field(&self) -> Option<Field> {
+        Some(Field::Fast(schema::fast_field::Region.into()))
+    }
+
+    fn max_value(&self) -> Option<f64> {
+        Some(1.0)
+    }
+
+    fn precompute(self, webpage: &Webpage, signal_computer: &SignalComputer) -> Option<f64> {
+        let region =
+            crate::webpage::Region::guess_from(webpage).unwrap_or(crate::webpage::Region::All);
+        score_majority_region(region, signal_computer)
+    }
+
+    fn compute(&self, doc: DocId, signal_computer: &SignalComputer) -> Option<f64> {
+        let seg_reader = signal_computer.segment_reader().unwrap();
+
+        let val = seg_reader
+            .fastfield_reader()
+            .get_u64(doc, self.as_fastfield().unwrap())?;
+        let region = crate::webpage::Region::from_id(val);
+        score_majority_region(region, signal_computer)
+    }
+}
+
+#[cfg(feature = "webgraph")]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
 pub struct QueryCentrality;
+#[cfg(feature = "webgraph")]
 impl Signal for QueryCentrality {
     fn default_coefficient(&self) -> f64 {
         0.0
@@ -376,17 +673,22 @@ impl Signal for QueryCentrality {
         None
     }
 
+    fn is_query_dependent(&self) -> bool {
+        true
+    }
+
     fn compute(&self, doc: DocId, signal_computer: &SignalComputer) -> Option<f64> {
-        let seg_reader = signal_computer.segment_reader().unwrap().borrow_mut();
-        let fastfield_reader = seg_reader.fastfield_reader().get_field_reader(doc);
-        let host_id = host_id(&fastfield_reader);
+        let seg_reader = signal_computer.segment_reader().unwrap();
+        let host_id = host_id(seg_reader.fastfield_reader(), doc);
 
         host_id.and_then(|host_id| signal_computer.query_centrality(host_id))
     }
 }
 
+#[cfg(feature = "webgraph")]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
 pub struct InboundSimilarity;
+#[cfg(feature = "webgraph")]
 impl Signal for InboundSimilarity {
     fn default_coefficient(&self) -> f64 {
         0.25
@@ -397,9 +699,8 @@ impl Signal for InboundSimilarity {
     }
 
     fn compute(&self, doc: DocId, signal_computer: &SignalComputer) -> Option<f64> {
-        let seg_reader = signal_computer.segment_reader().unwrap().borrow_mut();
-        let fastfield_reader = seg_reader.fastfield_reader().get_field_reader(doc);
-        let host_id = host_id(&fastfield_reader);
+        let seg_reader = signal_computer.segment_reader().unwrap();
+        let host_id = host_id(seg_reader.fastfield_reader(), doc);
 
         host_id.map(|host_id| signal_computer.inbound_similarity(host_id))
     }
@@ -416,6 +717,10 @@ impl Signal for LambdaMart {
         None
     }
 
+    fn is_query_dependent(&self) -> bool {
+        true
+    }
+
     fn compute(&self, _: DocId, _: &SignalComputer) -> Option<f64> {
         None // computed in later ranking stage
     }
@@ -434,6 +739,10 @@ impl Signal for UrlDigits {
         ))
     }
 
+    fn max_value(&self) -> Option<f64> {
+        Some(1.0)
+    }
+
     fn precompute(self, webpage: &Webpage, _: &SignalComputer) -> Option<f64> {
         let num_digits = (webpage
             .html
@@ -455,13 +764,11 @@ impl Signal for UrlDigits {
     }
 
     fn compute(&self, doc: DocId, signal_computer: &SignalComputer) -> Option<f64> {
-        let seg_reader = signal_computer.segment_reader().unwrap().borrow_mut();
-        let fastfield_reader = seg_reader.fastfield_reader().get_field_reader(doc);
+        let seg_reader = signal_computer.segment_reader().unwrap();
 
-        let val = fastfield_reader
-            .get(self.as_fastfield().unwrap())
-            .and_then(|v| v.as_u64())
-            .unwrap();
+        let val = seg_reader
+            .fastfield_reader()
+            .get_u64(doc, self.as_fastfield().unwrap())?;
         Some(score_digits(val as f64))
     }
 }
@@ -479,6 +786,10 @@ impl Signal for UrlSlashes {
         ))
     }
 
+    fn max_value(&self) -> Option<f64> {
+        Some(1.0)
+    }
+
     fn precompute(self, webpage: &Webpage, _: &SignalComputer) -> Option<f64> {
         let num_slashes = webpage
             .html
@@ -491,17 +802,18 @@ impl Signal for UrlSlashes {
     }
 
     fn compute(&self, doc: DocId, signal_computer: &SignalComputer) -> Option<f64> {
-        let seg_reader = signal_computer.segment_reader().unwrap().borrow_mut();
-        let fastfield_reader = seg_reader.fastfield_reader().get_field_reader(doc);
+        let seg_reader = signal_computer.segment_reader().unwrap();
 
-        let val = fastfield_reader
-            .get(self.as_fastfield().unwrap())
-            .and_then(|v| v.as_u64())
-            .unwrap();
+        let val = seg_reader
+            .fastfield_reader()
+            .get_u64(doc, self.as_fastfield().unwrap())?;
         Some(score_slashes(val as f64))
     }
 }
 
+/// Penalizes pages that are mostly links with little prose. The link density is the
+/// number of outgoing links per word of the page. Documents indexed before the
+/// `links_per_word` fast field existed have no value and are scored as unknown.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
 pub struct LinkDensity;
 impl Signal for LinkDensity {
@@ -510,22 +822,367 @@ impl Signal for LinkDensity {
     }
 
     fn as_field(&self) -> Option<Field> {
-        Some(Field::Fast(schema::fast_field::LinkDensity.into()))
+        Some(Field::Fast(schema::fast_field::LinksPerWord.into()))
+    }
+
+    fn max_value(&self) -> Option<f64> {
+        Some(1.0)
     }
 
     fn precompute(self, webpage: &Webpage, _: &SignalComputer) -> Option<f64> {
-        let link_density = webpage.html.link_density();
+        let link_density = webpage.html.links_per_word();
+        Some(score_link_density(Some(link_density)))
+    }
+
+    fn compute(&self, doc: DocId, signal_computer: &SignalComputer) -> Option<f64> {
+        let seg_reader = signal_computer.segment_reader().unwrap();
+
+        let link_density = seg_reader
+            .fastfield_reader()
+            .get_f64(doc, self.as_fastfield().unwrap());
+
         Some(score_link_density(link_density))
     }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct DomainNameMatch;
+impl Signal for DomainNameMatch {
+    fn default_coefficient(&self) -> f64 {
+        0.05
+    }
+
+    fn as_field(&self) -> Option<Field> {
+        Some(Field::Fast(schema::fast_field::Host.into()))
+    }
+
+    fn is_query_dependent(&self) -> bool {
+        true
+    }
+
+    fn max_value(&self) -> Option<f64> {
+        Some(DOMAIN_NAME_FULL_MATCH)
+    }
+
+    fn precompute(self, webpage: &Webpage, signal_computer: &SignalComputer) -> Option<f64> {
+        let query = signal_computer.query_data()?;
+        let host = webpage.html.url().normalized_host().unwrap_or_default();
+
+        Some(score_domain_name_match(host, query.simple_terms()))
+    }
 
     fn compute(&self, doc: DocId, signal_computer: &SignalComputer) -> Option<f64> {
-        let seg_reader = signal_computer.segment_reader().unwrap().borrow_mut();
-        let fastfield_reader = seg_reader.fastfield_reader().get_field_reader(doc);
-
-        let val = fastfield_reader
-            .get(self.as_fastfield().unwrap())
-            .and_then(|v| v.as_u64())
-            .unwrap();
-        Some(score_link_density(val as f64 / FLOAT_SCALING as f64))
+        let query = signal_computer.query_data()?;
+        let seg_reader = signal_computer.segment_reader().unwrap();
+
+        let host = seg_reader
+            .fastfield_reader()
+            .get_bytes(doc, self.as_fastfield().unwrap())
+            .and_then(|host| String::from_utf8(host).ok())
+            .unwrap_or_default();
+
+        Some(score_domain_name_match(&host, query.simple_terms()))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct InboundLinkCount;
+impl Signal for InboundLinkCount {
+    fn default_coefficient(&self) -> f64 {
+        0.01
+    }
+
+    fn as_field(&self) -> Option<Field> {
+        Some(Field::Fast(schema::fast_field::NumInboundLinks.into()))
+    }
+
+    fn max_value(&self) -> Option<f64> {
+        Some(1.0)
+    }
+
+    fn precompute(self, webpage: &Webpage, signal_computer: &SignalComputer) -> Option<f64> {
+        Some(score_inbound_link_count(
+            webpage.num_inbound_links as usize,
+            signal_computer,
+        ))
+    }
+
+    fn compute(&self, doc: DocId, signal_computer: &SignalComputer) -> Option<f64> {
+        let seg_reader = signal_computer.segment_reader().unwrap();
+
+        let val = seg_reader
+            .fastfield_reader()
+            .get_u64(doc, self.as_fastfield().unwrap())? as usize;
+
+        Some(score_inbound_link_count(val, signal_computer))
+    }
+}
+
+/// Prefers lighter pages. The score decreases with the logarithm of the page size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct PageByteSize;
+impl Signal for PageByteSize {
+    fn default_coefficient(&self) -> f64 {
+        0.005
+    }
+
+    fn as_field(&self) -> Option<Field> {
+        Some(Field::Fast(schema::fast_field::PageByteSize.into()))
+    }
+
+    fn max_value(&self) -> Option<f64> {
+        Some(1.0)
+    }
+
+    fn precompute(self, webpage: &Webpage, _: &SignalComputer) -> Option<f64> {
+        Some(score_page_byte_size(webpage.byte_size))
+    }
+
+    fn compute(&self, doc: DocId, signal_computer: &SignalComputer) -> Option<f64> {
+        let seg_reader = signal_computer.segment_reader().unwrap();
+
+        let byte_size = seg_reader
+            .fastfield_reader()
+            .get_u64(doc, self.as_fastfield().unwrap());
+
+        Some(score_page_byte_size(byte_size))
+    }
+}
+
+/// Matches the query terms against the words of the url path, so a page at
+/// `/best-rust-books` matches the query `rust books`. Unlike [`DomainNameMatch`],
+/// only the path is considered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct UrlTermMatch;
+impl Signal for UrlTermMatch {
+    fn default_coefficient(&self) -> f64 {
+        0.01
+    }
+
+    fn as_field(&self) -> Option<Field> {
+        Some(Field::Fast(schema::fast_field::UrlPath.into()))
+    }
+
+    fn is_query_dependent(&self) -> bool {
+        true
+    }
+
+    fn max_value(&self) -> Option<f64> {
+        Some(1.0)
+    }
+
+    fn precompute(self, webpage: &Webpage, signal_computer: &SignalComputer) -> Option<f64> {
+        let query = signal_computer.query_data()?;
+
+        Some(score_url_term_match(
+            webpage.html.url().path(),
+            query.simple_terms(),
+        ))
+    }
+
+    fn compute(&self, doc: DocId, signal_computer: &SignalComputer) -> Option<f64> {
+        let query = signal_computer.query_data()?;
+        let seg_reader = signal_computer.segment_reader().unwrap();
+
+        let path = seg_reader
+            .fastfield_reader()
+            .get_bytes(doc, self.as_fastfield().unwrap())
+            .and_then(|path| String::from_utf8(path).ok())
+            .unwrap_or_default();
+
+        Some(score_url_term_match(&path, query.simple_terms()))
+    }
+}
+
+/// Reputation of the top level domain of the page from the table set with
+/// [`SignalComputer::set_tld_reputation`]. Pages get a neutral score if their
+/// TLD is not in the table or no table is set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct TldReputation;
+impl Signal for TldReputation {
+    fn default_coefficient(&self) -> f64 {
+        0.01
+    }
+
+    fn as_field(&self) -> Option<Field> {
+        Some(Field::Fast(schema::fast_field::Host.into()))
+    }
+
+    fn value_range(&self) -> Option<RangeInclusive<f64>> {
+        Some(0.0..=1.0)
+    }
+
+    fn precompute(self, webpage: &Webpage, signal_computer: &SignalComputer) -> Option<f64> {
+        let host = webpage.html.url().normalized_host().unwrap_or_default();
+
+        Some(score_tld_reputation(host, signal_computer))
+    }
+
+    fn compute(&self, doc: DocId, signal_computer: &SignalComputer) -> Option<f64> {
+        let seg_reader = signal_computer.segment_reader().unwrap();
+
+        let host = seg_reader
+            .fastfield_reader()
+            .get_bytes(doc, self.as_fastfield().unwrap())
+            .and_then(|host| String::from_utf8(host).ok())
+            .unwrap_or_default();
+
+        Some(score_tld_reputation(&host, signal_computer))
+    }
+}
+
+/// Boosts pages that adapt to the screen of mobile devices. The signal is only
+/// enabled for searches from mobile devices, so desktop searches are unaffected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct MobileFriendly;
+impl Signal for MobileFriendly {
+    fn default_coefficient(&self) -> f64 {
+        0.01
+    }
+
+    fn as_field(&self) -> Option<Field> {
+        Some(Field::Fast(schema::fast_field::MobileFriendly.into()))
+    }
+
+    fn is_query_dependent(&self) -> bool {
+        true
+    }
+
+    fn max_value(&self) -> Option<f64> {
+        Some(1.0)
+    }
+
+    fn precompute(self, webpage: &Webpage, _: &SignalComputer) -> Option<f64> {
+        Some(webpage.html.is_mobile_friendly() as u64 as f64)
+    }
+
+    fn compute(&self, doc: DocId, signal_computer: &SignalComputer) -> Option<f64> {
+        let seg_reader = signal_computer.segment_reader().unwrap();
+
+        seg_reader
+            .fastfield_reader()
+            .get_u64(doc, self.as_fastfield().unwrap())
+            .map(|val| val as f64)
+    }
+}
+
+/// Small boost for pages with schema.org data, as these can be shown with richer
+/// results. Pages without structured data are scored 0.0 and are not penalized.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct HasStructuredData;
+impl Signal for HasStructuredData {
+    fn default_coefficient(&self) -> f64 {
+        0.005
+    }
+
+    fn as_field(&self) -> Option<Field> {
+        Some(Field::Fast(schema::fast_field::HasStructuredData.into()))
+    }
+
+    fn max_value(&self) -> Option<f64> {
+        Some(1.0)
+    }
+
+    fn precompute(self, webpage: &Webpage, _: &SignalComputer) -> Option<f64> {
+        Some(!webpage.html.schema_org().is_empty() as u64 as f64)
+    }
+
+    fn compute(&self, doc: DocId, signal_computer: &SignalComputer) -> Option<f64> {
+        let seg_reader = signal_computer.segment_reader().unwrap();
+
+        let val = seg_reader
+            .fastfield_reader()
+            .get_u64(doc, self.as_fastfield().unwrap())
+            .unwrap_or(0);
+
+        Some(val as f64)
+    }
+}
+
+/// Mild boost for readable prose. The readability is the average number of words
+/// per sentence, see [`score_readability`] for how it is scored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct Readability;
+impl Signal for Readability {
+    fn default_coefficient(&self) -> f64 {
+        0.005
+    }
+
+    fn as_field(&self) -> Option<Field> {
+        Some(Field::Fast(schema::fast_field::WordsPerSentence.into()))
+    }
+
+    fn max_value(&self) -> Option<f64> {
+        Some(1.0)
+    }
+
+    fn precompute(self, webpage: &Webpage, _: &SignalComputer) -> Option<f64> {
+        Some(score_readability(webpage.html.words_per_sentence()))
+    }
+
+    fn compute(&self, doc: DocId, signal_computer: &SignalComputer) -> Option<f64> {
+        let seg_reader = signal_computer.segment_reader().unwrap();
+
+        let words_per_sentence = seg_reader
+            .fastfield_reader()
+            .get_f64(doc, self.as_fastfield().unwrap());
+
+        Some(score_readability(words_per_sentence))
+    }
+}
+
+/// Lets the official homepage win navigational queries. See [`score_homepage_boost`]
+/// for when a query is considered navigational.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct HomepageBoost;
+impl Signal for HomepageBoost {
+    fn default_coefficient(&self) -> f64 {
+        0.05
+    }
+
+    fn as_field(&self) -> Option<Field> {
+        Some(Field::Fast(schema::fast_field::Host.into()))
+    }
+
+    fn is_query_dependent(&self) -> bool {
+        true
+    }
+
+    fn max_value(&self) -> Option<f64> {
+        Some(1.0)
+    }
+
+    fn precompute(self, webpage: &Webpage, signal_computer: &SignalComputer) -> Option<f64> {
+        let query = signal_computer.query_data()?;
+        let host = webpage.html.url().normalized_host().unwrap_or_default();
+
+        Some(score_homepage_boost(
+            webpage.html.is_homepage(),
+            host,
+            query.simple_terms(),
+        ))
+    }
+
+    fn compute(&self, doc: DocId, signal_computer: &SignalComputer) -> Option<f64> {
+        let query = signal_computer.query_data()?;
+        let seg_reader = signal_computer.segment_reader().unwrap();
+
+        let is_homepage = seg_reader
+            .fastfield_reader()
+            .get_u64(doc, schema::fast_field::IsHomepage)
+            .unwrap_or(0);
+
+        // avoid reading the host for the vast majority of pages
+        if is_homepage == 0 {
+            return Some(0.0);
+        }
+
+        let host = seg_reader
+            .fastfield_reader()
+            .get_bytes(doc, self.as_fastfield().unwrap())
+            .and_then(|host| String::from_utf8(host).ok())
+            .unwrap_or_default();
+
+        Some(score_homepage_boost(true, &host, query.simple_terms()))
     }
 }
@@ -15,6 +15,8 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>
 use crate::enum_map::InsertEnumMapKey;
 
+use std::ops::RangeInclusive;
+
 use crate::enum_dispatch_from_discriminant;
 use crate::schema::Field;
 use crate::{
@@ -42,10 +44,34 @@ pub trait Signal:
         None
     }
 
+    /// Range that the values of `compute` and `precompute` are clamped into before
+    /// they are weighted, or `None` if the values are used as they are.
+    fn value_range(&self) -> Option<RangeInclusive<f64>> {
+        None
+    }
+
+    /// Upper bound of the value returned by `compute`, or `None` if the signal is unbounded.
+    fn max_value(&self) -> Option<f64> {
+        self.value_range().map(|range| *range.end())
+    }
+
+    fn clamp_value(&self, value: f64) -> f64 {
+        match self.value_range() {
+            Some(range) => value.clamp(*range.start(), *range.end()),
+            None => value,
+        }
+    }
+
     fn as_textfield(&self) -> Option<TextFieldEnum> {
         self.as_field().and_then(|field| field.as_text())
     }
 
+    /// Whether the signal depends on the query. Query dependent signals are not computed
+    /// when the signal computer is in browse mode (see [`SignalComputer::browse`]).
+    fn is_query_dependent(&self) -> bool {
+        self.as_textfield().is_some()
+    }
+
     fn as_fastfield(&self) -> Option<FastFieldEnum> {
         self.as_field().and_then(|field| field.as_fast())
     }
@@ -79,6 +105,8 @@ pub enum SignalEnum {
     IdfSumDomainNameIfHomepageNoTokenizer,
     IdfSumDomainIfHomepageNoTokenizer,
     IdfSumTitleIfHomepage,
+    TermCoverage,
+    AnchorTextMatch,
     CrossEncoderSnippet,
     CrossEncoderTitle,
     HostCentrality,
@@ -90,7 +118,9 @@ pub enum SignalEnum {
     UpdateTimestamp,
     TrackerScore,
     Region,
+    #[cfg(feature = "webgraph")]
     QueryCentrality,
+    #[cfg(feature = "webgraph")]
     InboundSimilarity,
     LambdaMart,
     UrlDigits,
@@ -98,6 +128,21 @@ pub enum SignalEnum {
     LinkDensity,
     TitleEmbeddingSimilarity,
     KeywordEmbeddingSimilarity,
+    DomainNameMatch,
+    InboundLinkCount,
+    PageByteSize,
+    UrlTermMatch,
+    MajorityRegion,
+    Proximity,
+    ContentFreshness,
+    ContentLength,
+    TldReputation,
+    MobileFriendly,
+    HasStructuredData,
+    HomepageBoost,
+    HeadingMatch,
+    KeywordStuffing,
+    Readability,
 }
 
 enum_dispatch_from_discriminant!(SignalEnumDiscriminants => SignalEnum,
@@ -123,6 +168,8 @@ enum_dispatch_from_discriminant!(SignalEnumDiscriminants => SignalEnum,
     IdfSumDomainNameIfHomepageNoTokenizer,
     IdfSumDomainIfHomepageNoTokenizer,
     IdfSumTitleIfHomepage,
+    TermCoverage,
+    AnchorTextMatch,
     CrossEncoderSnippet,
     CrossEncoderTitle,
     HostCentrality,
@@ -134,7 +181,9 @@ enum_dispatch_from_discriminant!(SignalEnumDiscriminants => SignalEnum,
     UpdateTimestamp,
     TrackerScore,
     Region,
+    #[cfg(feature = "webgraph")]
     QueryCentrality,
+    #[cfg(feature = "webgraph")]
     InboundSimilarity,
     LambdaMart,
     UrlDigits,
@@ -142,6 +191,21 @@ enum_dispatch_from_discriminant!(SignalEnumDiscriminants => SignalEnum,
     LinkDensity,
     TitleEmbeddingSimilarity,
     KeywordEmbeddingSimilarity,
+    DomainNameMatch,
+    InboundLinkCount,
+    PageByteSize,
+    UrlTermMatch,
+    MajorityRegion,
+    Proximity,
+    ContentFreshness,
+    ContentLength,
+    TldReputation,
+    MobileFriendly,
+    HasStructuredData,
+    HomepageBoost,
+    HeadingMatch,
+    KeywordStuffing,
+    Readability,
 ]);
 
 impl SignalEnum {
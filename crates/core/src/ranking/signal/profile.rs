@@ -0,0 +1,88 @@
+// Stract is an open source web search engine.
+// Copyright (C) 2024 Stract ApS
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Named presets of signal coefficients, so searches can select a coherent
+//! ranking without setting the coefficient of every signal.
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::{
+    ContentFreshness, HostCentrality, InboundLinkCount, PageCentrality, SignalCoefficient,
+    SignalEnum, TrackerScore, UpdateTimestamp,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum CoefficientProfile {
+    /// The default coefficients.
+    Balanced,
+    /// Favors recently updated pages over well-linked pages.
+    Freshness,
+    /// Favors pages from central and well-linked hosts regardless of their age.
+    Authority,
+}
+
+impl CoefficientProfile {
+    /// The coefficients set by the profile. Signals that are not mentioned
+    /// keep their default coefficient.
+    pub fn coefficients(&self) -> SignalCoefficient {
+        let coefficients: Vec<(SignalEnum, f64)> = match self {
+            CoefficientProfile::Balanced => Vec::new(),
+            CoefficientProfile::Freshness => vec![
+                (UpdateTimestamp.into(), 0.5),
+                (ContentFreshness.into(), 0.5),
+                (HostCentrality.into(), 0.25),
+                (PageCentrality.into(), 0.1),
+            ],
+            CoefficientProfile::Authority => vec![
+                (HostCentrality.into(), 1.0),
+                (PageCentrality.into(), 0.5),
+                (InboundLinkCount.into(), 0.05),
+                (TrackerScore.into(), 0.1),
+                (UpdateTimestamp.into(), 0.0),
+                (ContentFreshness.into(), 0.0),
+            ],
+        };
+
+        SignalCoefficient::new(coefficients.into_iter())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn balanced_is_default() {
+        let coefficients = CoefficientProfile::Balanced.coefficients();
+
+        for signal in SignalEnum::all() {
+            assert_eq!(coefficients.try_get(&signal), None);
+        }
+    }
+
+    #[test]
+    fn profiles_differ() {
+        let freshness = CoefficientProfile::Freshness.coefficients();
+        let authority = CoefficientProfile::Authority.coefficients();
+        let update_timestamp = UpdateTimestamp.into();
+        let host_centrality = HostCentrality.into();
+
+        assert!(freshness.get(&update_timestamp) > authority.get(&update_timestamp));
+        assert!(authority.get(&host_centrality) > freshness.get(&host_centrality));
+    }
+}
@@ -23,6 +23,18 @@ use tantivy::{DocId, Postings};
 use super::computer::TextFieldData;
 use super::{Signal, SignalComputer};
 
+/// Number of body tokens that gets the highest [`ContentLength`] score.
+const IDEAL_CONTENT_LENGTH: f64 = 800.0;
+
+/// Width of the [`ContentLength`] curve in log space. A page with `e` times more or
+/// fewer tokens than the ideal length gets a score of `exp(-0.5 / WIDTH^2)`.
+const CONTENT_LENGTH_WIDTH: f64 = 1.5;
+
+/// Titles where up to this fraction of the tokens are repeated query terms are not penalized.
+const KEYWORD_STUFFING_NO_PENALTY: f64 = 0.1;
+/// Titles where this fraction of the tokens or more are repeated query terms get the full penalty.
+const KEYWORD_STUFFING_FULL_PENALTY: f64 = 0.5;
+
 fn bm25(field: &mut TextFieldData, doc: DocId) -> f64 {
     if field.postings.is_empty() {
         return 0.0;
@@ -61,6 +73,144 @@ fn idf_sum(field: &mut TextFieldData, doc: DocId) -> f64 {
         .sum::<f32>() as f64
 }
 
+/// Fraction of the distinct query terms that occur in `doc`.
+fn term_coverage(field: &mut TextFieldData, doc: DocId) -> f64 {
+    if field.num_query_terms == 0 {
+        return 0.0;
+    }
+
+    let num_present = field
+        .postings
+        .iter_mut()
+        .zip_eq(field.terms.iter())
+        .filter_map(|(posting, term)| {
+            if posting.doc() == doc || (posting.doc() < doc && posting.seek(doc) == doc) {
+                Some(term)
+            } else {
+                None
+            }
+        })
+        .unique()
+        .count();
+
+    num_present as f64 / field.num_query_terms as f64
+}
+
+/// How close together the query terms are in `doc`. The score is the number of distinct
+/// query terms divided by the length of the shortest span that contains all of them,
+/// so it is 1.0 when the terms are next to each other. Documents that don't contain
+/// all the terms get a score of 0.0.
+fn proximity(field: &mut TextFieldData, doc: DocId) -> Option<f64> {
+    if field.num_query_terms < 2 {
+        return None;
+    }
+
+    // positions of the terms in the document, tagged with the index of the first
+    // occurrence of the term so repeated query terms are only counted once.
+    let mut positions = Vec::new();
+    let mut term_positions = Vec::new();
+    let mut present = Vec::new();
+
+    for (posting, term) in field.postings.iter_mut().zip_eq(field.terms.iter()) {
+        if posting.doc() == doc || (posting.doc() < doc && posting.seek(doc) == doc) {
+            let term_id = field.terms.iter().position(|t| t == term).unwrap();
+
+            if present.contains(&term_id) {
+                continue;
+            }
+            present.push(term_id);
+
+            term_positions.clear();
+            posting.positions(&mut term_positions);
+            positions.extend(term_positions.iter().map(|pos| (*pos, term_id)));
+        }
+    }
+
+    if present.len() < field.num_query_terms {
+        return Some(0.0);
+    }
+
+    positions.sort_unstable();
+
+    let mut counts = vec![0; field.terms.len()];
+    let mut num_covered = 0;
+    let mut start = 0;
+    let mut shortest_span = u32::MAX;
+
+    for (end_pos, term_id) in &positions {
+        if counts[*term_id] == 0 {
+            num_covered += 1;
+        }
+        counts[*term_id] += 1;
+
+        while num_covered == present.len() {
+            let (start_pos, start_term) = positions[start];
+            shortest_span = shortest_span.min(end_pos - start_pos + 1);
+
+            counts[start_term] -= 1;
+            if counts[start_term] == 0 {
+                num_covered -= 1;
+            }
+            start += 1;
+        }
+    }
+
+    if shortest_span == u32::MAX {
+        return Some(0.0);
+    }
+
+    Some(present.len() as f64 / shortest_span as f64)
+}
+
+/// Penalizes fields that repeat the query terms. Only the occurrences after the
+/// first of each distinct query term count as repetitions, so a field that simply
+/// contains the query is not penalized regardless of its length. The score is 1.0
+/// for natural densities and decreases linearly to 0.0 as the fraction of repeated
+/// tokens goes from [`KEYWORD_STUFFING_NO_PENALTY`] to [`KEYWORD_STUFFING_FULL_PENALTY`].
+fn keyword_stuffing(field: &mut TextFieldData, doc: DocId) -> f64 {
+    let num_tokens = field.fieldnorm_reader.fieldnorm(doc);
+
+    if num_tokens == 0 {
+        return 1.0;
+    }
+
+    let num_repeated: u32 = field
+        .postings
+        .iter_mut()
+        .zip_eq(field.terms.iter())
+        .filter_map(|(posting, term)| {
+            if posting.doc() == doc || (posting.doc() < doc && posting.seek(doc) == doc) {
+                Some((term, posting.term_freq()))
+            } else {
+                None
+            }
+        })
+        .unique_by(|(term, _)| *term)
+        .map(|(_, term_freq)| term_freq.saturating_sub(1))
+        .sum();
+
+    let density = num_repeated as f64 / num_tokens as f64;
+    let penalty = (density - KEYWORD_STUFFING_NO_PENALTY)
+        / (KEYWORD_STUFFING_FULL_PENALTY - KEYWORD_STUFFING_NO_PENALTY);
+
+    1.0 - penalty.clamp(0.0, 1.0)
+}
+
+/// Bell shaped score of the number of tokens in the body of `doc`. The curve is
+/// centered on [`IDEAL_CONTENT_LENGTH`] in log space, so both thin pages and huge
+/// pages get a low score.
+fn content_length(field: &TextFieldData, doc: DocId) -> f64 {
+    let num_tokens = field.fieldnorm_reader.fieldnorm(doc);
+
+    if num_tokens == 0 {
+        return 0.0;
+    }
+
+    let distance = (num_tokens as f64 / IDEAL_CONTENT_LENGTH).ln() / CONTENT_LENGTH_WIDTH;
+
+    (-0.5 * distance * distance).exp()
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
 pub struct Bm25Title;
 impl Signal for Bm25Title {
@@ -73,7 +223,7 @@ impl Signal for Bm25Title {
     }
 
     fn compute(&self, doc: DocId, signal_computer: &SignalComputer) -> Option<f64> {
-        let mut seg_reader = signal_computer.segment_reader().unwrap().borrow_mut();
+        let mut seg_reader = signal_computer.segment_reader().unwrap();
 
         seg_reader
             .text_fields_mut()
@@ -94,7 +244,7 @@ impl Signal for Bm25TitleBigrams {
     }
 
     fn compute(&self, doc: DocId, signal_computer: &SignalComputer) -> Option<f64> {
-        let mut seg_reader = signal_computer.segment_reader().unwrap().borrow_mut();
+        let mut seg_reader = signal_computer.segment_reader().unwrap();
 
         seg_reader
             .text_fields_mut()
@@ -115,7 +265,7 @@ impl Signal for Bm25TitleTrigrams {
     }
 
     fn compute(&self, doc: DocId, signal_computer: &SignalComputer) -> Option<f64> {
-        let mut seg_reader = signal_computer.segment_reader().unwrap().borrow_mut();
+        let mut seg_reader = signal_computer.segment_reader().unwrap();
 
         seg_reader
             .text_fields_mut()
@@ -136,7 +286,7 @@ impl Signal for Bm25CleanBody {
     }
 
     fn compute(&self, doc: DocId, signal_computer: &SignalComputer) -> Option<f64> {
-        let mut seg_reader = signal_computer.segment_reader().unwrap().borrow_mut();
+        let mut seg_reader = signal_computer.segment_reader().unwrap();
 
         seg_reader
             .text_fields_mut()
@@ -157,7 +307,7 @@ impl Signal for Bm25CleanBodyBigrams {
     }
 
     fn compute(&self, doc: DocId, signal_computer: &SignalComputer) -> Option<f64> {
-        let mut seg_reader = signal_computer.segment_reader().unwrap().borrow_mut();
+        let mut seg_reader = signal_computer.segment_reader().unwrap();
 
         seg_reader
             .text_fields_mut()
@@ -178,7 +328,7 @@ impl Signal for Bm25CleanBodyTrigrams {
     }
 
     fn compute(&self, doc: DocId, signal_computer: &SignalComputer) -> Option<f64> {
-        let mut seg_reader = signal_computer.segment_reader().unwrap().borrow_mut();
+        let mut seg_reader = signal_computer.segment_reader().unwrap();
 
         seg_reader
             .text_fields_mut()
@@ -199,7 +349,7 @@ impl Signal for Bm25StemmedTitle {
     }
 
     fn compute(&self, doc: DocId, signal_computer: &SignalComputer) -> Option<f64> {
-        let mut seg_reader = signal_computer.segment_reader().unwrap().borrow_mut();
+        let mut seg_reader = signal_computer.segment_reader().unwrap();
 
         seg_reader
             .text_fields_mut()
@@ -220,7 +370,7 @@ impl Signal for Bm25StemmedCleanBody {
     }
 
     fn compute(&self, doc: DocId, signal_computer: &SignalComputer) -> Option<f64> {
-        let mut seg_reader = signal_computer.segment_reader().unwrap().borrow_mut();
+        let mut seg_reader = signal_computer.segment_reader().unwrap();
 
         seg_reader
             .text_fields_mut()
@@ -241,7 +391,7 @@ impl Signal for Bm25AllBody {
     }
 
     fn compute(&self, doc: DocId, signal_computer: &SignalComputer) -> Option<f64> {
-        let mut seg_reader = signal_computer.segment_reader().unwrap().borrow_mut();
+        let mut seg_reader = signal_computer.segment_reader().unwrap();
 
         seg_reader
             .text_fields_mut()
@@ -262,7 +412,7 @@ impl Signal for Bm25Keywords {
     }
 
     fn compute(&self, doc: DocId, signal_computer: &SignalComputer) -> Option<f64> {
-        let mut seg_reader = signal_computer.segment_reader().unwrap().borrow_mut();
+        let mut seg_reader = signal_computer.segment_reader().unwrap();
 
         seg_reader
             .text_fields_mut()
@@ -283,7 +433,7 @@ impl Signal for Bm25BacklinkText {
     }
 
     fn compute(&self, doc: DocId, signal_computer: &SignalComputer) -> Option<f64> {
-        let mut seg_reader = signal_computer.segment_reader().unwrap().borrow_mut();
+        let mut seg_reader = signal_computer.segment_reader().unwrap();
 
         seg_reader
             .text_fields_mut()
@@ -304,7 +454,7 @@ impl Signal for IdfSumUrl {
     }
 
     fn compute(&self, doc: DocId, signal_computer: &SignalComputer) -> Option<f64> {
-        let mut seg_reader = signal_computer.segment_reader().unwrap().borrow_mut();
+        let mut seg_reader = signal_computer.segment_reader().unwrap();
 
         seg_reader
             .text_fields_mut()
@@ -325,7 +475,7 @@ impl Signal for IdfSumSite {
     }
 
     fn compute(&self, doc: DocId, signal_computer: &SignalComputer) -> Option<f64> {
-        let mut seg_reader = signal_computer.segment_reader().unwrap().borrow_mut();
+        let mut seg_reader = signal_computer.segment_reader().unwrap();
 
         seg_reader
             .text_fields_mut()
@@ -346,7 +496,7 @@ impl Signal for IdfSumDomain {
     }
 
     fn compute(&self, doc: DocId, signal_computer: &SignalComputer) -> Option<f64> {
-        let mut seg_reader = signal_computer.segment_reader().unwrap().borrow_mut();
+        let mut seg_reader = signal_computer.segment_reader().unwrap();
 
         seg_reader
             .text_fields_mut()
@@ -367,7 +517,7 @@ impl Signal for IdfSumSiteNoTokenizer {
     }
 
     fn compute(&self, doc: DocId, signal_computer: &SignalComputer) -> Option<f64> {
-        let mut seg_reader = signal_computer.segment_reader().unwrap().borrow_mut();
+        let mut seg_reader = signal_computer.segment_reader().unwrap();
 
         seg_reader
             .text_fields_mut()
@@ -388,7 +538,7 @@ impl Signal for IdfSumDomainNoTokenizer {
     }
 
     fn compute(&self, doc: DocId, signal_computer: &SignalComputer) -> Option<f64> {
-        let mut seg_reader = signal_computer.segment_reader().unwrap().borrow_mut();
+        let mut seg_reader = signal_computer.segment_reader().unwrap();
 
         seg_reader
             .text_fields_mut()
@@ -411,7 +561,7 @@ impl Signal for IdfSumDomainNameNoTokenizer {
     }
 
     fn compute(&self, doc: DocId, signal_computer: &SignalComputer) -> Option<f64> {
-        let mut seg_reader = signal_computer.segment_reader().unwrap().borrow_mut();
+        let mut seg_reader = signal_computer.segment_reader().unwrap();
 
         seg_reader
             .text_fields_mut()
@@ -432,7 +582,7 @@ impl Signal for IdfSumDomainIfHomepage {
     }
 
     fn compute(&self, doc: DocId, signal_computer: &SignalComputer) -> Option<f64> {
-        let mut seg_reader = signal_computer.segment_reader().unwrap().borrow_mut();
+        let mut seg_reader = signal_computer.segment_reader().unwrap();
 
         seg_reader
             .text_fields_mut()
@@ -455,7 +605,7 @@ impl Signal for IdfSumDomainNameIfHomepageNoTokenizer {
     }
 
     fn compute(&self, doc: DocId, signal_computer: &SignalComputer) -> Option<f64> {
-        let mut seg_reader = signal_computer.segment_reader().unwrap().borrow_mut();
+        let mut seg_reader = signal_computer.segment_reader().unwrap();
 
         seg_reader
             .text_fields_mut()
@@ -478,7 +628,7 @@ impl Signal for IdfSumDomainIfHomepageNoTokenizer {
     }
 
     fn compute(&self, doc: DocId, signal_computer: &SignalComputer) -> Option<f64> {
-        let mut seg_reader = signal_computer.segment_reader().unwrap().borrow_mut();
+        let mut seg_reader = signal_computer.segment_reader().unwrap();
 
         seg_reader
             .text_fields_mut()
@@ -499,7 +649,7 @@ impl Signal for IdfSumTitleIfHomepage {
     }
 
     fn compute(&self, doc: DocId, signal_computer: &SignalComputer) -> Option<f64> {
-        let mut seg_reader = signal_computer.segment_reader().unwrap().borrow_mut();
+        let mut seg_reader = signal_computer.segment_reader().unwrap();
 
         seg_reader
             .text_fields_mut()
@@ -508,6 +658,206 @@ impl Signal for IdfSumTitleIfHomepage {
     }
 }
 
+/// The fraction of the distinct query terms that occur in the body of the page.
+///
+/// The signal reuses the `AllBody` postings that are prepared for [`Bm25AllBody`], but it
+/// is not a text field signal itself as it should not be dampened by the ngram
+/// compute order of the field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct TermCoverage;
+impl Signal for TermCoverage {
+    fn default_coefficient(&self) -> f64 {
+        0.01
+    }
+
+    fn as_field(&self) -> Option<Field> {
+        None
+    }
+
+    fn is_query_dependent(&self) -> bool {
+        true
+    }
+
+    fn max_value(&self) -> Option<f64> {
+        Some(1.0)
+    }
+
+    fn compute(&self, doc: DocId, signal_computer: &SignalComputer) -> Option<f64> {
+        let mut seg_reader = signal_computer.segment_reader().unwrap();
+
+        seg_reader
+            .text_fields_mut()
+            .get_mut(schema::text_field::AllBody.into())
+            .map(|field| term_coverage(field, doc))
+    }
+}
+
+/// Rewards pages where the query terms are close together in the body, which bm25
+/// does not take into account. Uses the positions of the postings prepared for
+/// [`Bm25CleanBody`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct Proximity;
+impl Signal for Proximity {
+    fn default_coefficient(&self) -> f64 {
+        0.01
+    }
+
+    fn as_field(&self) -> Option<Field> {
+        None
+    }
+
+    fn is_query_dependent(&self) -> bool {
+        true
+    }
+
+    fn max_value(&self) -> Option<f64> {
+        Some(1.0)
+    }
+
+    fn compute(&self, doc: DocId, signal_computer: &SignalComputer) -> Option<f64> {
+        let mut seg_reader = signal_computer.segment_reader().unwrap();
+
+        seg_reader
+            .text_fields_mut()
+            .get_mut(schema::text_field::CleanBody.into())
+            .and_then(|field| proximity(field, doc))
+    }
+}
+
+/// Rewards pages with enough content in the body, so thin pages that happen to contain
+/// all the query terms don't outrank more substantial pages. Very long pages are also
+/// rewarded less to avoid favouring keyword stuffing. Uses the fieldnorms of the
+/// field prepared for [`Bm25CleanBody`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct ContentLength;
+impl Signal for ContentLength {
+    fn default_coefficient(&self) -> f64 {
+        0.005
+    }
+
+    fn as_field(&self) -> Option<Field> {
+        None
+    }
+
+    fn is_query_dependent(&self) -> bool {
+        true
+    }
+
+    fn max_value(&self) -> Option<f64> {
+        Some(1.0)
+    }
+
+    fn compute(&self, doc: DocId, signal_computer: &SignalComputer) -> Option<f64> {
+        let mut seg_reader = signal_computer.segment_reader().unwrap();
+
+        seg_reader
+            .text_fields_mut()
+            .get_mut(schema::text_field::CleanBody.into())
+            .map(|field| content_length(field, doc))
+    }
+}
+
+/// The fraction of the distinct query terms that occur in the anchor text of the
+/// links pointing to the page. The anchor text is added to the `BacklinkText` field
+/// from the page webgraph when the page is indexed.
+///
+/// Like [`TermCoverage`], the signal reuses the postings that are prepared for
+/// [`Bm25BacklinkText`] so it is not dampened by the compute order of the field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct AnchorTextMatch;
+impl Signal for AnchorTextMatch {
+    fn default_coefficient(&self) -> f64 {
+        0.01
+    }
+
+    fn as_field(&self) -> Option<Field> {
+        None
+    }
+
+    fn is_query_dependent(&self) -> bool {
+        true
+    }
+
+    fn max_value(&self) -> Option<f64> {
+        Some(1.0)
+    }
+
+    fn compute(&self, doc: DocId, signal_computer: &SignalComputer) -> Option<f64> {
+        if signal_computer.query_data()?.simple_terms().is_empty() {
+            return None;
+        }
+
+        let mut seg_reader = signal_computer.segment_reader().unwrap();
+
+        seg_reader
+            .text_fields_mut()
+            .get_mut(schema::text_field::BacklinkText.into())
+            .map(|field| term_coverage(field, doc))
+    }
+}
+
+/// Fraction of the query terms that occur in the `h1` and `h2` headings of the page.
+/// Indexes without the headings field get no score.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct HeadingMatch;
+impl Signal for HeadingMatch {
+    fn default_coefficient(&self) -> f64 {
+        0.01
+    }
+
+    fn as_field(&self) -> Option<Field> {
+        Some(Field::Text(schema::text_field::Headings.into()))
+    }
+
+    fn max_value(&self) -> Option<f64> {
+        Some(1.0)
+    }
+
+    fn compute(&self, doc: DocId, signal_computer: &SignalComputer) -> Option<f64> {
+        let mut seg_reader = signal_computer.segment_reader().unwrap();
+
+        seg_reader
+            .text_fields_mut()
+            .get_mut(self.as_textfield().unwrap())
+            .map(|field| term_coverage(field, doc))
+    }
+}
+
+/// Penalizes titles that repeat the query terms excessively, which is common for spam.
+/// See [`keyword_stuffing`] for how the density is scored.
+///
+/// Like [`TermCoverage`], the signal reuses the `Title` postings prepared for [`Bm25Title`]
+/// without being a text field signal, so it doesn't take part in the ngram dampening of
+/// the title signals.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct KeywordStuffing;
+impl Signal for KeywordStuffing {
+    fn default_coefficient(&self) -> f64 {
+        0.01
+    }
+
+    fn as_field(&self) -> Option<Field> {
+        None
+    }
+
+    fn is_query_dependent(&self) -> bool {
+        true
+    }
+
+    fn max_value(&self) -> Option<f64> {
+        Some(1.0)
+    }
+
+    fn compute(&self, doc: DocId, signal_computer: &SignalComputer) -> Option<f64> {
+        let mut seg_reader = signal_computer.segment_reader().unwrap();
+
+        seg_reader
+            .text_fields_mut()
+            .get_mut(schema::text_field::Title.into())
+            .map(|field| keyword_stuffing(field, doc))
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
 pub struct CrossEncoderSnippet;
 impl Signal for CrossEncoderSnippet {
@@ -519,6 +869,10 @@ impl Signal for CrossEncoderSnippet {
         None
     }
 
+    fn is_query_dependent(&self) -> bool {
+        true
+    }
+
     fn compute(&self, _: DocId, _: &SignalComputer) -> Option<f64> {
         None // computed in later ranking stage
     }
@@ -535,6 +889,10 @@ impl Signal for CrossEncoderTitle {
         None
     }
 
+    fn is_query_dependent(&self) -> bool {
+        true
+    }
+
     fn compute(&self, _: DocId, _: &SignalComputer) -> Option<f64> {
         None // computed in later ranking stage
     }
@@ -551,6 +909,10 @@ impl Signal for TitleEmbeddingSimilarity {
         Some(Field::Fast(schema::fast_field::TitleEmbeddings.into()))
     }
 
+    fn is_query_dependent(&self) -> bool {
+        true
+    }
+
     fn compute(&self, _: DocId, _: &SignalComputer) -> Option<f64> {
         None // computed in later ranking stage
     }
@@ -567,6 +929,10 @@ impl Signal for KeywordEmbeddingSimilarity {
         Some(Field::Fast(schema::fast_field::KeywordEmbeddings.into()))
     }
 
+    fn is_query_dependent(&self) -> bool {
+        true
+    }
+
     fn compute(&self, _: DocId, _: &SignalComputer) -> Option<f64> {
         None // computed in later ranking stage
     }
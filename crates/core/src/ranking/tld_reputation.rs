@@ -0,0 +1,82 @@
+// Stract is an open source web search engine.
+// Copyright (C) 2024 Stract ApS
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Reputation of top level domains. Pages on some TLDs are much more likely to be spam,
+//! so the reputation table lets operators tune how much each TLD is trusted.
+
+use std::collections::HashMap;
+
+/// Reputation of TLDs that are not in the table.
+pub const NEUTRAL_REPUTATION: f64 = 0.5;
+
+/// Maps TLDs to a reputation between 0.0 (spam) and 1.0 (trusted).
+#[derive(Debug, Clone, Default)]
+pub struct TldReputation {
+    scores: HashMap<String, f64>,
+}
+
+impl TldReputation {
+    /// Build the table from (tld, reputation) pairs. The TLDs are given without
+    /// the leading dot and can span several labels, like `co.uk`.
+    pub fn new(scores: impl IntoIterator<Item = (String, f64)>) -> Self {
+        Self {
+            scores: scores
+                .into_iter()
+                .map(|(tld, score)| {
+                    (
+                        tld.trim_start_matches('.').to_lowercase(),
+                        score.clamp(0.0, 1.0),
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.scores.is_empty()
+    }
+
+    /// The reputation of the TLD of `host`. The most specific TLD in the table is used,
+    /// so `example.co.uk` gets the reputation of `co.uk` before the one of `uk`.
+    pub fn score(&self, host: &str) -> f64 {
+        let host = host.trim_end_matches('.').to_lowercase();
+
+        host.match_indices('.')
+            .map(|(i, _)| &host[i + 1..])
+            .find_map(|tld| self.scores.get(tld).copied())
+            .unwrap_or(NEUTRAL_REPUTATION)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn most_specific_tld() {
+        let table = TldReputation::new([
+            ("uk".to_string(), 0.7),
+            (".co.uk".to_string(), 0.9),
+            ("XYZ".to_string(), 0.1),
+        ]);
+
+        assert_eq!(table.score("example.co.uk"), 0.9);
+        assert_eq!(table.score("example.org.uk"), 0.7);
+        assert_eq!(table.score("spam.xyz"), 0.1);
+        assert_eq!(table.score("example.com"), NEUTRAL_REPUTATION);
+        assert_eq!(table.score("localhost"), NEUTRAL_REPUTATION);
+    }
+}
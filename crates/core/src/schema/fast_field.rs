@@ -25,7 +25,7 @@ use crate::{
     enum_dispatch_from_discriminant,
     enum_map::InsertEnumMapKey,
     simhash,
-    webpage::{html::FnCache, Html, Webpage},
+    webpage::{html::FnCache, url_ext::UrlExt, Html, Webpage},
     Result,
 };
 
@@ -129,6 +129,15 @@ pub enum FastFieldEnum {
     LinkDensity,
     TitleEmbeddings,
     KeywordEmbeddings,
+    Host,
+    NumInboundLinks,
+    PageByteSize,
+    UrlPath,
+    CrawlTimestamp,
+    MobileFriendly,
+    HasStructuredData,
+    WordsPerSentence,
+    LinksPerWord,
 }
 
 enum_dispatch_from_discriminant!(FastFieldEnumDiscriminants => FastFieldEnum,
@@ -172,6 +181,15 @@ enum_dispatch_from_discriminant!(FastFieldEnumDiscriminants => FastFieldEnum,
     LinkDensity,
     TitleEmbeddings,
     KeywordEmbeddings,
+    Host,
+    NumInboundLinks,
+    PageByteSize,
+    UrlPath,
+    CrawlTimestamp,
+    MobileFriendly,
+    HasStructuredData,
+    WordsPerSentence,
+    LinksPerWord,
 ]);
 
 impl FastFieldEnum {
@@ -1232,3 +1250,258 @@ impl FastField for KeywordEmbeddings {
         Ok(())
     }
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Host;
+impl FastField for Host {
+    fn name(&self) -> &str {
+        "host"
+    }
+
+    fn data_type(&self) -> DataType {
+        DataType::Bytes
+    }
+
+    fn indexing_option(&self) -> IndexingOption {
+        IndexingOption::Bytes(BytesOptions::default().set_fast())
+    }
+
+    fn add_html_tantivy(
+        &self,
+        html: &Html,
+        _cache: &mut FnCache,
+        doc: &mut TantivyDocument,
+        schema: &tantivy::schema::Schema,
+    ) -> Result<()> {
+        let host = html.url().normalized_host().unwrap_or_default();
+        doc.add_bytes(self.tantivy_field(schema), host.as_bytes().to_vec());
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NumInboundLinks;
+impl FastField for NumInboundLinks {
+    fn name(&self) -> &str {
+        "num_inbound_links"
+    }
+
+    fn add_html_tantivy(
+        &self,
+        _html: &Html,
+        _cache: &mut FnCache,
+        _doc: &mut TantivyDocument,
+        _schema: &tantivy::schema::Schema,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    fn add_webpage_tantivy(
+        &self,
+        webpage: &Webpage,
+        doc: &mut TantivyDocument,
+        schema: &tantivy::schema::Schema,
+    ) -> Result<()> {
+        doc.add_u64(self.tantivy_field(schema), webpage.num_inbound_links);
+
+        Ok(())
+    }
+}
+
+/// Size of the page in bytes. Pages with an unknown size are stored as 0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PageByteSize;
+impl FastField for PageByteSize {
+    fn name(&self) -> &str {
+        "page_byte_size"
+    }
+
+    fn add_html_tantivy(
+        &self,
+        _html: &Html,
+        _cache: &mut FnCache,
+        _doc: &mut TantivyDocument,
+        _schema: &tantivy::schema::Schema,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    fn add_webpage_tantivy(
+        &self,
+        webpage: &Webpage,
+        doc: &mut TantivyDocument,
+        schema: &tantivy::schema::Schema,
+    ) -> Result<()> {
+        doc.add_u64(
+            self.tantivy_field(schema),
+            webpage.byte_size.unwrap_or_default(),
+        );
+
+        Ok(())
+    }
+}
+
+/// Path of the page url, used to match the query terms against the url slug.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct UrlPath;
+impl FastField for UrlPath {
+    fn name(&self) -> &str {
+        "url_path"
+    }
+
+    fn data_type(&self) -> DataType {
+        DataType::Bytes
+    }
+
+    fn indexing_option(&self) -> IndexingOption {
+        IndexingOption::Bytes(BytesOptions::default().set_fast())
+    }
+
+    fn add_html_tantivy(
+        &self,
+        html: &Html,
+        _cache: &mut FnCache,
+        doc: &mut TantivyDocument,
+        schema: &tantivy::schema::Schema,
+    ) -> Result<()> {
+        doc.add_bytes(
+            self.tantivy_field(schema),
+            html.url().path().as_bytes().to_vec(),
+        );
+
+        Ok(())
+    }
+}
+
+/// Unix timestamp of when the page was crawled and inserted into the index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CrawlTimestamp;
+impl FastField for CrawlTimestamp {
+    fn name(&self) -> &str {
+        "crawl_timestamp"
+    }
+
+    fn add_html_tantivy(
+        &self,
+        _html: &Html,
+        _cache: &mut FnCache,
+        _doc: &mut TantivyDocument,
+        _schema: &tantivy::schema::Schema,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    fn add_webpage_tantivy(
+        &self,
+        webpage: &Webpage,
+        doc: &mut TantivyDocument,
+        schema: &tantivy::schema::Schema,
+    ) -> Result<()> {
+        doc.add_u64(
+            self.tantivy_field(schema),
+            webpage.inserted_at.timestamp().max(0) as u64,
+        );
+
+        Ok(())
+    }
+}
+
+/// Whether the page adapts to the screen of mobile devices.
+/// See [`Html::is_mobile_friendly`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MobileFriendly;
+impl FastField for MobileFriendly {
+    fn name(&self) -> &str {
+        "mobile_friendly"
+    }
+
+    fn add_html_tantivy(
+        &self,
+        html: &Html,
+        _cache: &mut FnCache,
+        doc: &mut TantivyDocument,
+        schema: &tantivy::schema::Schema,
+    ) -> Result<()> {
+        doc.add_u64(self.tantivy_field(schema), html.is_mobile_friendly() as u64);
+
+        Ok(())
+    }
+}
+
+/// Whether the page has schema.org data as JSON-LD or microdata.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct HasStructuredData;
+impl FastField for HasStructuredData {
+    fn name(&self) -> &str {
+        "has_structured_data"
+    }
+
+    fn add_html_tantivy(
+        &self,
+        _html: &Html,
+        cache: &mut FnCache,
+        doc: &mut TantivyDocument,
+        schema: &tantivy::schema::Schema,
+    ) -> Result<()> {
+        doc.add_u64(
+            self.tantivy_field(schema),
+            !cache.schema_org().is_empty() as u64,
+        );
+
+        Ok(())
+    }
+}
+
+/// Average number of words per sentence of the clean text, or 0 for pages without text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WordsPerSentence;
+impl FastField for WordsPerSentence {
+    fn name(&self) -> &str {
+        "words_per_sentence"
+    }
+
+    fn add_html_tantivy(
+        &self,
+        html: &Html,
+        _cache: &mut FnCache,
+        doc: &mut TantivyDocument,
+        schema: &tantivy::schema::Schema,
+    ) -> Result<()> {
+        doc.add_u64(
+            self.tantivy_field(schema),
+            (html.words_per_sentence().unwrap_or(0.0) * FLOAT_SCALING as f64) as u64,
+        );
+
+        Ok(())
+    }
+}
+
+/// Number of outgoing links per word of the clean text. Replaces [`LinkDensity`] for
+/// ranking, which is kept so existing indexes keep their meaning.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LinksPerWord;
+impl FastField for LinksPerWord {
+    fn name(&self) -> &str {
+        "links_per_word"
+    }
+
+    fn is_stored(&self) -> bool {
+        true
+    }
+
+    fn add_html_tantivy(
+        &self,
+        html: &Html,
+        _cache: &mut FnCache,
+        doc: &mut TantivyDocument,
+        schema: &tantivy::schema::Schema,
+    ) -> Result<()> {
+        doc.add_u64(
+            self.tantivy_field(schema),
+            (html.links_per_word() * FLOAT_SCALING as f64) as u64,
+        );
+
+        Ok(())
+    }
+}
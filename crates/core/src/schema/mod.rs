@@ -25,10 +25,37 @@ use tantivy::{
 pub use fast_field::{DataType, FastFieldEnum};
 pub use text_field::TextFieldEnum;
 
+use crate::tokenizer::TokenizerRegistry;
+
 use self::{fast_field::FastField, text_field::TextField};
 
 pub const FLOAT_SCALING: u64 = 1_000_000_000;
 
+/// Version of the schema created by [`create_schema`].
+///
+/// Indexes missing newly added fields can still be searched, so the version
+/// only needs to be bumped when existing fields are removed or change type.
+pub const SCHEMA_VERSION: u64 = 1;
+
+/// Oldest schema version that can still be opened.
+pub const MIN_SUPPORTED_SCHEMA_VERSION: u64 = 1;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error(
+        "Index has schema version {found}, but only versions {MIN_SUPPORTED_SCHEMA_VERSION} to {SCHEMA_VERSION} are supported. The index needs to be rebuilt"
+    )]
+    UnsupportedVersion { found: u64 },
+
+    #[error(
+        "Index contains the unknown field '{0}'. It was most likely created by a newer version"
+    )]
+    UnknownField(String),
+
+    #[error("Field '{0}' in the index has a different type than expected")]
+    MismatchedFieldType(String),
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Field {
     Fast(FastFieldEnum),
@@ -102,10 +129,29 @@ impl Field {
 }
 
 pub fn create_schema() -> tantivy::schema::Schema {
+    create_schema_with_tokenizers(&TokenizerRegistry::default())
+}
+
+pub fn create_schema_with_tokenizers(tokenizers: &TokenizerRegistry) -> tantivy::schema::Schema {
+    build_schema(Field::all(), tokenizers)
+}
+
+pub(crate) fn build_schema(
+    fields: impl Iterator<Item = Field>,
+    tokenizers: &TokenizerRegistry,
+) -> tantivy::schema::Schema {
     let mut builder = tantivy::schema::Schema::builder();
 
-    for field in Field::all() {
-        match field.indexing_option() {
+    for field in fields {
+        let indexing_option = match field
+            .as_text()
+            .and_then(|text| tokenizers.get(text).map(|tokenizer| (text, tokenizer)))
+        {
+            Some((text, tokenizer)) => text.indexing_option_with_tokenizer(tokenizer),
+            None => field.indexing_option(),
+        };
+
+        match indexing_option {
             IndexingOption::Text(options) => builder.add_text_field(field.name(), options),
             IndexingOption::Integer(options) => builder.add_u64_field(field.name(), options),
             IndexingOption::DateTime(options) => builder.add_date_field(field.name(), options),
@@ -116,6 +162,31 @@ pub fn create_schema() -> tantivy::schema::Schema {
     builder.build()
 }
 
+/// Checks whether an index written with `schema` at schema `version` can be opened.
+///
+/// Fields missing from the index are allowed, so indexes created before a field was
+/// added keep working. Unknown fields or fields whose type has changed are rejected.
+pub fn check_compatibility(schema: &tantivy::schema::Schema, version: u64) -> Result<(), Error> {
+    if !(MIN_SUPPORTED_SCHEMA_VERSION..=SCHEMA_VERSION).contains(&version) {
+        return Err(Error::UnsupportedVersion { found: version });
+    }
+
+    let expected = create_schema();
+
+    for (_, entry) in schema.fields() {
+        let expected_entry = expected
+            .get_field(entry.name())
+            .map(|field| expected.get_field_entry(field))
+            .map_err(|_| Error::UnknownField(entry.name().to_string()))?;
+
+        if expected_entry.field_type().value_type() != entry.field_type().value_type() {
+            return Err(Error::MismatchedFieldType(entry.name().to_string()));
+        }
+    }
+
+    Ok(())
+}
+
 pub enum IndexingOption {
     Text(TextOptions),
     Integer(NumericOptions),
@@ -68,6 +68,41 @@ pub trait TextField:
         self.indexing_tokenizer()
     }
 
+    /// The tokenizer the field is indexed with in `schema`.
+    ///
+    /// This is the tokenizer from the [`crate::tokenizer::TokenizerRegistry`] if one was registered
+    /// for the field when the index was created, and otherwise the
+    /// [`TextField::indexing_tokenizer`].
+    fn tokenizer(&self, schema: &tantivy::schema::Schema) -> Tokenizer {
+        self.registered_tokenizer(schema)
+            .unwrap_or_else(|| self.indexing_tokenizer())
+    }
+
+    /// The tokenizer used for query terms searching the field in `schema`.
+    /// Fields with a registered tokenizer must be queried with the same tokenizer
+    /// they were indexed with.
+    fn query_tokenizer_for(&self, schema: &tantivy::schema::Schema) -> Tokenizer {
+        self.registered_tokenizer(schema)
+            .unwrap_or_else(|| self.query_tokenizer())
+    }
+
+    /// The tokenizer stored for the field in `schema`, if it differs from
+    /// the default [`TextField::indexing_tokenizer`].
+    fn registered_tokenizer(&self, schema: &tantivy::schema::Schema) -> Option<Tokenizer> {
+        let field = schema.get_field(self.name()).ok()?;
+
+        let name = match schema.get_field_entry(field).field_type() {
+            tantivy::schema::FieldType::Str(options) => options.get_indexing_options()?.tokenizer(),
+            _ => return None,
+        };
+
+        if name == self.indexing_tokenizer().as_str() {
+            return None;
+        }
+
+        Tokenizer::from_name(name)
+    }
+
     fn ngram_size(&self) -> usize {
         1
     }
@@ -103,7 +138,10 @@ pub trait TextField:
     }
 
     fn indexing_option(&self) -> IndexingOption {
-        let tokenizer = self.indexing_tokenizer();
+        self.indexing_option_with_tokenizer(&self.indexing_tokenizer())
+    }
+
+    fn indexing_option_with_tokenizer(&self, tokenizer: &Tokenizer) -> IndexingOption {
         let option = self.record_option();
 
         let mut opt = TextOptions::default().set_indexing_options(
@@ -168,6 +206,8 @@ pub enum TextFieldEnum {
     InsertionTimestamp,
     RecipeFirstIngredientTagId,
     Keywords,
+    /// text of the `h1` and `h2` headings
+    Headings,
 }
 
 enum_dispatch_from_discriminant!(TextFieldEnumDiscriminants => TextFieldEnum,
@@ -204,6 +244,7 @@ enum_dispatch_from_discriminant!(TextFieldEnumDiscriminants => TextFieldEnum,
     InsertionTimestamp,
     RecipeFirstIngredientTagId,
     Keywords,
+    Headings,
 ]);
 
 impl TextFieldEnum {
@@ -1425,3 +1466,23 @@ impl TextField for Keywords {
         Ok(())
     }
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Headings;
+impl TextField for Headings {
+    fn name(&self) -> &str {
+        "headings"
+    }
+
+    fn add_html_tantivy(
+        &self,
+        html: &Html,
+        _cache: &mut FnCache,
+        doc: &mut TantivyDocument,
+        schema: &tantivy::schema::Schema,
+    ) -> Result<()> {
+        doc.add_text(self.tantivy_field(schema), html.headings().join("\n"));
+
+        Ok(())
+    }
+}
@@ -27,7 +27,7 @@ use utoipa::ToSchema;
 use crate::{
     highlighted::HighlightedFragment,
     inverted_index::RetrievedWebpage,
-    ranking::{SignalEnumDiscriminants, SignalScore},
+    ranking::{SignalEnumDiscriminants, SignalScore, SignalVector},
     snippet::TextSnippet,
     web_spell::{self, CorrectionTerm},
     webpage::url_ext::UrlExt,
@@ -180,7 +180,11 @@ pub struct DisplayedWebpage {
     pub snippet: Snippet,
     pub rich_snippet: Option<RichSnippet>,
     pub ranking_signals: Option<HashMap<SignalEnumDiscriminants, SignalScore>>,
+    /// Signal values in the layout of [`SignalVector`].
+    pub features: Option<SignalVector>,
     pub score: Option<f64>,
+    /// `score` rescaled to 0.0-1.0 relative to the other results if requested.
+    pub normalized_score: Option<f64>,
     pub likely_has_ads: bool,
     pub likely_has_paywall: bool,
 }
@@ -212,7 +216,9 @@ impl From<RetrievedWebpage> for DisplayedWebpage {
             domain,
             snippet,
             ranking_signals: None,
+            features: None,
             score: None,
+            normalized_score: None,
             likely_has_ads: webpage.likely_has_ads,
             likely_has_paywall: webpage.likely_has_paywall,
             rich_snippet,
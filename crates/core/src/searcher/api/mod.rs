@@ -14,6 +14,7 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+mod ranking_log;
 mod sidebar;
 mod widget;
 
@@ -22,31 +23,36 @@ use std::sync::Arc;
 use std::time::Instant;
 
 use itertools::{intersperse, Itertools};
+use tokio::sync::mpsc;
 use url::Url;
 
-use crate::bangs::{Bang, BangHit};
+use crate::bangs::{Bang, BangContext, BangHit};
 use crate::collector::{self, Doc};
-use crate::config::{ApiConfig, CollectorConfig};
+use crate::config::{ApiConfig, CollectorConfig, OpticLimitsConfig};
 use crate::image_store::Image;
 use crate::inverted_index::RetrievedWebpage;
 use crate::models::dual_encoder::DualEncoder;
 use crate::ranking::models::cross_encoder::CrossEncoderModel;
 use crate::ranking::pipeline::{PrecisionRankingWebpage, RankableWebpage, RecallRankingWebpage};
-use crate::ranking::SignalEnum;
+use crate::ranking::{SignalEnum, SignalVector};
+use crate::schema::TextFieldEnum;
 use crate::search_prettifier::{DisplayedSidebar, DisplayedWebpage, HighlightedSpellCorrection};
 use crate::web_spell::SpellChecker;
+use crate::webgraph::NodeID;
 use crate::widgets::{Widget, Widgets};
 use crate::{
-    bangs::Bangs,
+    bangs::SharedBangs,
     collector::BucketCollector,
     ranking::{models::lambdamart::LambdaMART, pipeline::RankingPipeline},
 };
 use crate::{query, Result};
 
+pub use self::ranking_log::{RankingLogEntry, RankingLogResult, RankingLogger};
 use self::sidebar::SidebarManager;
 use self::widget::WidgetManager;
 
-use super::{distributed, live, SearchQuery, SearchResult, WebsitesResult};
+use super::correction::{self, QueryCorrection};
+use super::{distributed, live, SearchQuery, SearchResult, TermStat, WebsitesResult};
 
 #[derive(Clone)]
 pub enum ScoredWebpagePointer {
@@ -78,6 +84,10 @@ impl RankableWebpage for ScoredWebpagePointer {
     fn boost(&self) -> Option<f64> {
         self.as_ranking().boost()
     }
+
+    fn host_id(&self) -> Option<NodeID> {
+        self.as_ranking().host_id
+    }
 }
 
 impl collector::Doc for ScoredWebpagePointer {
@@ -88,6 +98,10 @@ impl collector::Doc for ScoredWebpagePointer {
     fn hashes(&self) -> collector::Hashes {
         self.as_ranking().hashes()
     }
+
+    fn host_centrality(&self) -> f64 {
+        self.as_ranking().host_centrality()
+    }
 }
 
 pub fn combine_results(
@@ -157,6 +171,71 @@ pub fn add_ranking_signals(websites: &mut [DisplayedWebpage], pointers: &[Scored
     }
 }
 
+pub fn add_features(websites: &mut [DisplayedWebpage], pointers: &[ScoredWebpagePointer]) {
+    for (website, pointer) in websites.iter_mut().zip(pointers.iter()) {
+        website.features = Some(SignalVector::from(&pointer.as_ranking().signals));
+    }
+}
+
+/// Splits the pointers into the pointers to the index and the pointers to the live index,
+/// keeping the rank of each pointer.
+#[allow(clippy::type_complexity)]
+fn split_pointers(
+    top_websites: &[ScoredWebpagePointer],
+) -> (
+    Vec<(usize, distributed::ScoredWebpagePointer)>,
+    Vec<(usize, live::ScoredWebpagePointer)>,
+) {
+    let mut normal = Vec::new();
+    let mut live = Vec::new();
+
+    for (i, pointer) in top_websites.iter().enumerate() {
+        match pointer {
+            ScoredWebpagePointer::Normal(p) => normal.push((i, p.clone())),
+            ScoredWebpagePointer::Live(p) => live.push((i, p.clone())),
+        }
+    }
+
+    (normal, live)
+}
+
+fn displayed_webpage(
+    query: &SearchQuery,
+    webpage: RetrievedWebpage,
+    pointer: &ScoredWebpagePointer,
+) -> DisplayedWebpage {
+    let mut webpage = DisplayedWebpage::from(webpage);
+
+    if query.return_ranking_signals {
+        add_ranking_signals(
+            std::slice::from_mut(&mut webpage),
+            std::slice::from_ref(pointer),
+        );
+    }
+
+    if query.return_features {
+        add_features(
+            std::slice::from_mut(&mut webpage),
+            std::slice::from_ref(pointer),
+        );
+    }
+
+    webpage.score = Some(pointer.score());
+
+    webpage
+}
+
+/// The best results for a query before their webpages are retrieved.
+struct RankedWebsites {
+    start: Instant,
+    search_query: SearchQuery,
+    top_websites: Vec<ScoredWebpagePointer>,
+    num_hits: Option<usize>,
+    discarded_count: Option<usize>,
+    has_more_results: bool,
+    partial: bool,
+}
+
 pub struct ApiSearcher<S, L> {
     distributed_searcher: Arc<S>,
     sidebar_manager: SidebarManager<S>,
@@ -164,10 +243,13 @@ pub struct ApiSearcher<S, L> {
     cross_encoder: Option<Arc<CrossEncoderModel>>,
     lambda_model: Option<Arc<LambdaMART>>,
     dual_encoder: Option<Arc<DualEncoder>>,
-    bangs: Bangs,
+    bangs: SharedBangs,
     collector_config: CollectorConfig,
     widget_manager: WidgetManager,
     spell_checker: Option<SpellChecker>,
+    ranking_logger: Option<RankingLogger>,
+    max_timeout_ms: u64,
+    optic_limits: OpticLimitsConfig,
 }
 
 impl<S, L> ApiSearcher<S, L>
@@ -181,7 +263,7 @@ where
         cross_encoder: Option<CrossEncoderModel>,
         lambda_model: Option<LambdaMART>,
         dual_encoder: Option<DualEncoder>,
-        bangs: Bangs,
+        bangs: impl Into<SharedBangs>,
         config: ApiConfig,
     ) -> Self {
         let dist_searcher = Arc::new(dist_searcher);
@@ -200,33 +282,40 @@ where
             cross_encoder: cross_encoder.map(Arc::new),
             lambda_model,
             dual_encoder,
-            bangs,
+            bangs: bangs.into(),
             collector_config: config.collector,
             widget_manager,
             spell_checker: config
                 .spell_checker_path
                 .map(|c| SpellChecker::open(c, config.correction_config).unwrap()),
+            max_timeout_ms: config.max_search_timeout_ms,
+            optic_limits: config.optic_limits,
+            ranking_logger: config
+                .ranking_log
+                .as_ref()
+                .map(|c| RankingLogger::open(c).unwrap()),
         }
     }
 
     async fn check_bangs(&self, query: &SearchQuery) -> Result<Option<BangHit>> {
-        let parsed_terms = query::parser::parse(&query.query)?;
+        let bangs = self.bangs.load();
+        let parsed_terms = query::parser::parse_with_bang_prefixes(&query.query, bangs.prefixes())?;
 
         if parsed_terms.iter().any(|term| match term {
-            query::parser::Term::PossibleBang(t) => t.is_empty(),
+            query::parser::Term::PossibleBang { tag, .. } => tag.is_empty(),
             _ => false,
         }) {
             let q: String = intersperse(
                 parsed_terms
                     .iter()
-                    .filter(|term| !matches!(term, query::parser::Term::PossibleBang(_)))
+                    .filter(|term| !matches!(term, query::parser::Term::PossibleBang { .. }))
                     .map(|term| term.to_string()),
                 " ".to_string(),
             )
             .collect();
 
             let mut query = query.clone();
-            query.query = q;
+            query.query = q.clone();
 
             let res = self.search_websites(&query).await?;
 
@@ -239,12 +328,17 @@ where
                     site: None,
                     tag: String::new(),
                     url: webpage.url.clone(),
+                    params: Vec::new(),
                 },
+                tag: String::new(),
+                category: None,
+                query: q,
                 redirect_to: Url::parse(&webpage.url).unwrap().into(),
+                params: Vec::new(),
             }));
         }
 
-        Ok(self.bangs.get(&parsed_terms))
+        Ok(bangs.get(&parsed_terms, &BangContext::from(query)))
     }
 
     pub async fn widget(&self, query: &str) -> Option<Widget> {
@@ -319,29 +413,7 @@ where
         query: &str,
         top_websites: &[ScoredWebpagePointer],
     ) -> Vec<PrecisionRankingWebpage> {
-        let normal: Vec<_> = top_websites
-            .iter()
-            .enumerate()
-            .filter_map(|(i, pointer)| {
-                if let ScoredWebpagePointer::Normal(p) = pointer {
-                    Some((i, p.clone()))
-                } else {
-                    None
-                }
-            })
-            .collect();
-
-        let live: Vec<_> = top_websites
-            .iter()
-            .enumerate()
-            .filter_map(|(i, pointer)| {
-                if let ScoredWebpagePointer::Live(p) = pointer {
-                    Some((i, p.clone()))
-                } else {
-                    None
-                }
-            })
-            .collect();
+        let (normal, live) = split_pointers(top_websites);
 
         let (retrieved_normal, retrieved_live) = tokio::join!(
             self.distributed_searcher.retrieve_webpages(&normal, query),
@@ -358,6 +430,30 @@ where
             .collect::<Vec<_>>()
     }
 
+    /// Sends the webpages on `tx` as batches where each batch holds the webpages
+    /// of a single shard (or all the live webpages) along with their rank.
+    async fn retrieve_webpages_by_shard(
+        &self,
+        query: &str,
+        top_websites: &[ScoredWebpagePointer],
+        tx: mpsc::UnboundedSender<Vec<(usize, PrecisionRankingWebpage)>>,
+    ) {
+        let (normal, live) = split_pointers(top_websites);
+        let live_tx = tx.clone();
+
+        tokio::join!(
+            self.distributed_searcher
+                .retrieve_webpages_by_shard(&normal, query, tx),
+            async {
+                let retrieved = self.retrieve_webpages_from_live(&live, query).await;
+
+                if !retrieved.is_empty() {
+                    live_tx.send(retrieved).ok();
+                }
+            },
+        );
+    }
+
     async fn search_initial_from_live(
         &self,
         query: &SearchQuery,
@@ -379,7 +475,8 @@ where
         }
     }
 
-    async fn search_websites(&self, query: &SearchQuery) -> Result<WebsitesResult> {
+    /// Finds the best results for the query without retrieving the webpages.
+    async fn rank_websites(&self, query: &SearchQuery) -> Result<RankedWebsites> {
         let start = Instant::now();
 
         if query.is_empty() {
@@ -387,6 +484,10 @@ where
         }
 
         let mut search_query = query.clone();
+        search_query.timeout_ms = query
+            .timeout_ms
+            .map(|timeout_ms| timeout_ms.min(self.max_timeout_ms));
+        search_query.bang_prefixes = self.bangs.load().prefixes().to_vec();
         let top_n = search_query.num_results;
 
         // This pipeline should be created before the first search is performed
@@ -405,11 +506,27 @@ where
             self.search_initial_from_live(&search_query),
         );
 
-        let num_docs = initial_results
+        let num_hits = initial_results
             .iter()
             .map(|result| result.local_result.num_websites)
             .sum();
 
+        let discarded_count = initial_results
+            .iter()
+            .map(|result| result.local_result.discarded_count)
+            .sum();
+
+        let partial = initial_results
+            .iter()
+            .map(|result| &result.local_result)
+            .chain(
+                live_results
+                    .iter()
+                    .flatten()
+                    .map(|result| &result.local_result),
+            )
+            .any(|result| result.partial);
+
         let (top_websites, has_more_results) = combine_results(
             self.collector_config.clone(),
             initial_results,
@@ -417,8 +534,42 @@ where
             recall_pipeline,
         );
 
+        Ok(RankedWebsites {
+            start,
+            search_query,
+            top_websites,
+            num_hits,
+            discarded_count,
+            has_more_results,
+            partial,
+        })
+    }
+
+    /// The result of the ranked websites. The webpages are left empty and are
+    /// expected to be filled in by the caller.
+    fn websites_result(&self, query: &SearchQuery, ranked: &RankedWebsites) -> WebsitesResult {
+        if let Some(logger) = &self.ranking_logger {
+            logger.log(query, &ranked.top_websites);
+        }
+
+        WebsitesResult {
+            num_hits: ranked.num_hits,
+            discarded_count: ranked.discarded_count,
+            webpages: Vec::new(),
+            search_duration_ms: ranked.start.elapsed().as_millis(),
+            has_more_results: ranked.has_more_results,
+            partial: ranked.partial,
+            optic_rules: query.optic_rule_outcomes(),
+            features_layout: query.features_layout(),
+        }
+    }
+
+    async fn search_websites(&self, query: &SearchQuery) -> Result<WebsitesResult> {
+        let ranked = self.rank_websites(query).await?;
+        let top_websites = &ranked.top_websites;
+
         let retrieved_webpages = self
-            .retrieve_webpages(&search_query.query, &top_websites)
+            .retrieve_webpages(&ranked.search_query.query, top_websites)
             .await;
 
         let mut search_query = SearchQuery {
@@ -437,46 +588,154 @@ where
 
         let retrieved_webpages = reranking_pipeline.apply(retrieved_webpages);
 
-        let mut retrieved_webpages: Vec<_> = retrieved_webpages
-            .into_iter()
-            .map(|webpage| webpage.into_retrieved_webpage())
-            .map(DisplayedWebpage::from)
-            .collect();
-
         if retrieved_webpages.len() != top_websites.len() {
             return Err(distributed::Error::SearchFailed.into());
         }
 
-        if query.return_ranking_signals {
-            add_ranking_signals(&mut retrieved_webpages, &top_websites);
-        }
+        let mut webpages: Vec<_> = retrieved_webpages
+            .into_iter()
+            .zip(top_websites.iter())
+            .map(|(webpage, pointer)| {
+                displayed_webpage(query, webpage.into_retrieved_webpage(), pointer)
+            })
+            .collect();
 
-        for (website, pointer) in retrieved_webpages.iter_mut().zip(top_websites.iter()) {
-            website.score = Some(pointer.score());
+        if query.normalize_scores {
+            super::normalize_scores(&mut webpages);
         }
 
-        let search_duration_ms = start.elapsed().as_millis();
-
         Ok(WebsitesResult {
-            num_hits: num_docs,
-            webpages: retrieved_webpages,
-            search_duration_ms,
-            has_more_results,
+            webpages,
+            ..self.websites_result(query, &ranked)
         })
     }
 
+    /// Whether the reranking stage can change the order of the results found by the
+    /// recall stage. Without a cross encoder the results are only rescored by the
+    /// seen and explore multipliers.
+    fn reranking_reorders(&self, query: &SearchQuery) -> bool {
+        self.cross_encoder.is_some() || !query.seen_urls.is_empty() || query.explore_seed.is_some()
+    }
+
+    /// Same as `search_websites`, but the webpages are sent on `tx` in ranked order instead of
+    /// being returned. When the reranking can't change the order of the results, each webpage is
+    /// sent as soon as the shards holding it and every result ranked above it have responded.
+    async fn search_websites_stream(
+        &self,
+        query: &SearchQuery,
+        tx: mpsc::Sender<DisplayedWebpage>,
+    ) -> Result<WebsitesResult> {
+        if self.reranking_reorders(query) || query.normalize_scores {
+            let mut result = self.search_websites(query).await?;
+
+            for webpage in result.webpages.drain(..) {
+                if tx.send(webpage).await.is_err() {
+                    break;
+                }
+            }
+
+            return Ok(result);
+        }
+
+        let ranked = self.rank_websites(query).await?;
+        let top_websites = &ranked.top_websites;
+
+        let (batch_tx, mut batch_rx) = mpsc::unbounded_channel();
+
+        let send = async {
+            let mut retrieved: Vec<Option<RetrievedWebpage>> =
+                top_websites.iter().map(|_| None).collect();
+            let mut num_sent = 0;
+
+            while let Some(batch) = batch_rx.recv().await {
+                for (i, webpage) in batch {
+                    retrieved[i] = Some(webpage.into_retrieved_webpage());
+                }
+
+                while let Some(webpage) = retrieved.get_mut(num_sent).and_then(Option::take) {
+                    let webpage = displayed_webpage(query, webpage, &top_websites[num_sent]);
+
+                    if tx.send(webpage).await.is_err() {
+                        return num_sent;
+                    }
+
+                    num_sent += 1;
+                }
+            }
+
+            num_sent
+        };
+
+        let ((), num_sent) = tokio::join!(
+            self.retrieve_webpages_by_shard(&ranked.search_query.query, top_websites, batch_tx),
+            send,
+        );
+
+        if num_sent != top_websites.len() && !tx.is_closed() {
+            return Err(distributed::Error::SearchFailed.into());
+        }
+
+        Ok(self.websites_result(query, &ranked))
+    }
+
+    /// Bangs are resolved before anything else, so a query with a bang is redirected
+    /// without being sent to the search servers. Only a bang without a name (`!`)
+    /// searches, to redirect to the best result.
     pub async fn search(&self, query: &SearchQuery) -> Result<SearchResult> {
         if let Some(bang) = self.check_bangs(query).await? {
             return Ok(SearchResult::Bang(Box::new(bang)));
         }
 
+        if let Some(optic) = &query.optic {
+            crate::query::optic::check_limits(optic, &self.optic_limits)?;
+        }
+
         Ok(SearchResult::Websites(self.search_websites(query).await?))
     }
 
+    /// Same as `search`, but the webpages are sent on `tx` as they become available. The
+    /// returned result therefore never contains any webpages.
+    pub async fn search_stream(
+        &self,
+        query: &SearchQuery,
+        tx: mpsc::Sender<DisplayedWebpage>,
+    ) -> Result<SearchResult> {
+        if let Some(bang) = self.check_bangs(query).await? {
+            return Ok(SearchResult::Bang(Box::new(bang)));
+        }
+
+        if let Some(optic) = &query.optic {
+            crate::query::optic::check_limits(optic, &self.optic_limits)?;
+        }
+
+        Ok(SearchResult::Websites(
+            self.search_websites_stream(query, tx).await?,
+        ))
+    }
+
     pub async fn get_webpage(&self, url: &str) -> Result<Option<RetrievedWebpage>> {
         self.distributed_searcher.get_webpage(url).await
     }
 
+    /// Correct likely misspellings in `query` based on the term frequencies in the index.
+    pub async fn correct(&self, query: &str) -> QueryCorrection {
+        let freqs = self.distributed_searcher.term_frequencies(query).await;
+
+        correction::correct(query, &freqs, correction::MIN_CONFIDENCE)
+    }
+
+    /// The `limit` terms of `field` that start with `prefix` and are found in the most documents.
+    pub async fn top_terms(
+        &self,
+        field: TextFieldEnum,
+        prefix: &str,
+        limit: usize,
+    ) -> Vec<TermStat> {
+        self.distributed_searcher
+            .top_terms(field, prefix, limit)
+            .await
+    }
+
     pub async fn get_entity_image(
         &self,
         image_id: &str,
@@ -488,3 +747,185 @@ where
             .await
     }
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use crate::{
+        bangs::Bangs,
+        config::{ApiThresholds, LLMConfig, OpticLimitsConfig, WidgetsConfig},
+        entity_index::EntityMatch,
+        searcher::{
+            correction::TermFrequencies,
+            distributed::{InitialSearchResultShard, ScoredWebpagePointer},
+            live::InitialSearchResultSplit,
+        },
+    };
+
+    use super::*;
+
+    /// Searcher without any indexed pages. It counts the searches that reach
+    /// the index, so tests can check when the pages are not scored at all.
+    #[derive(Clone, Default)]
+    pub(crate) struct EmptySearcher {
+        num_searches: Arc<AtomicUsize>,
+    }
+
+    impl distributed::SearchClient for EmptySearcher {
+        async fn search_initial(&self, _: &SearchQuery) -> Vec<InitialSearchResultShard> {
+            self.num_searches.fetch_add(1, Ordering::SeqCst);
+            Vec::new()
+        }
+
+        async fn retrieve_webpages(
+            &self,
+            _: &[(usize, ScoredWebpagePointer)],
+            _: &str,
+        ) -> Vec<(usize, PrecisionRankingWebpage)> {
+            Vec::new()
+        }
+
+        async fn retrieve_webpages_by_shard(
+            &self,
+            _: &[(usize, ScoredWebpagePointer)],
+            _: &str,
+            _: mpsc::UnboundedSender<Vec<(usize, PrecisionRankingWebpage)>>,
+        ) {
+        }
+
+        async fn search_entity(&self, _: &str) -> Option<EntityMatch> {
+            None
+        }
+
+        async fn get_webpage(&self, _: &str) -> crate::Result<Option<RetrievedWebpage>> {
+            Ok(None)
+        }
+
+        async fn get_homepage_descriptions(&self, _: &[Url]) -> HashMap<Url, String> {
+            HashMap::new()
+        }
+
+        async fn term_frequencies(&self, _: &str) -> TermFrequencies {
+            TermFrequencies::default()
+        }
+
+        async fn top_terms(&self, _: TextFieldEnum, _: &str, _: usize) -> Vec<TermStat> {
+            Vec::new()
+        }
+
+        async fn get_entity_image(
+            &self,
+            _: &str,
+            _: Option<u64>,
+            _: Option<u64>,
+        ) -> crate::Result<Option<Image>> {
+            Ok(None)
+        }
+    }
+
+    impl live::SearchClient for EmptySearcher {
+        async fn search_initial(&self, _: &SearchQuery) -> Vec<InitialSearchResultSplit> {
+            Vec::new()
+        }
+
+        async fn retrieve_webpages(
+            &self,
+            _: &[(usize, ScoredWebpagePointer)],
+            _: &str,
+        ) -> Vec<(usize, PrecisionRankingWebpage)> {
+            Vec::new()
+        }
+    }
+
+    fn config() -> ApiConfig {
+        ApiConfig {
+            summarizer_path: String::new(),
+            queries_csv_path: String::new(),
+            host: ([127, 0, 0, 1], 0).into(),
+            prometheus_host: ([127, 0, 0, 1], 0).into(),
+            grpc_host: None,
+            crossencoder_model_path: None,
+            lambda_model_path: None,
+            dual_encoder_model_path: None,
+            spell_checker_path: None,
+            bangs_path: String::new(),
+            bang_prefixes: crate::bangs::BANG_PREFIXES.to_vec(),
+            query_store_db_host: None,
+            cluster_id: "test".to_string(),
+            gossip_seed_nodes: None,
+            gossip_addr: ([127, 0, 0, 1], 0).into(),
+            llm: LLMConfig {
+                api_base: String::new(),
+                model: String::new(),
+                api_key: None,
+            },
+            collector: Default::default(),
+            thresholds: ApiThresholds::default(),
+            widgets: WidgetsConfig {
+                thesaurus_paths: Vec::new(),
+                calculator_fetch_currencies_exchange: false,
+            },
+            correction_config: Default::default(),
+            max_concurrent_searches: None,
+            max_search_timeout_ms: 5_000,
+            ranking_log: None,
+            optic_limits: OpticLimitsConfig {
+                max_rules: 2,
+                max_complexity: 100,
+            },
+            query_log: None,
+            admin_token: None,
+            model_buckets: None,
+        }
+    }
+
+    pub(crate) fn api_searcher(
+        searcher: EmptySearcher,
+    ) -> ApiSearcher<EmptySearcher, EmptySearcher> {
+        let bangs = Bangs::from_json(
+            r#"[{
+            "t": "ty",
+            "u": "https://www.youtube.com/results?search_query={{{s}}}"
+        }]"#,
+        );
+
+        ApiSearcher::new(
+            searcher,
+            None::<EmptySearcher>,
+            None,
+            None,
+            None,
+            bangs,
+            config(),
+        )
+    }
+
+    #[tokio::test]
+    async fn bang_is_not_scored() {
+        let empty = EmptySearcher::default();
+        let searcher = api_searcher(empty.clone());
+
+        let res = searcher
+            .search(&SearchQuery {
+                query: "!ty test".to_string(),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        assert!(matches!(res, SearchResult::Bang(_)));
+        assert_eq!(empty.num_searches.load(Ordering::SeqCst), 0);
+
+        let res = searcher
+            .search(&SearchQuery {
+                query: "test".to_string(),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        assert!(matches!(res, SearchResult::Websites(_)));
+        assert!(empty.num_searches.load(Ordering::SeqCst) > 0);
+    }
+}
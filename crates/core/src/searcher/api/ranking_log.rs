@@ -0,0 +1,324 @@
+// Stract is an open source web search engine.
+// Copyright (C) 2024 Stract ApS
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Logs the ranking decisions of sampled queries as json lines
+//! so they can be analysed offline.
+
+use std::{
+    collections::HashMap,
+    fs::OpenOptions,
+    io::{BufWriter, Write},
+    sync::atomic::{AtomicU64, Ordering},
+    thread::JoinHandle,
+};
+
+use crossbeam_channel::{Receiver, Sender, TrySendError};
+use optics::{HostRankings, Optic};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    collector::Doc,
+    config::RankingLogConfig,
+    ranking::{signal::SignalEnumDiscriminants, SignalEnum, SignalScore},
+    searcher::SearchQuery,
+    Result,
+};
+
+use super::ScoredWebpagePointer;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RankingLogResult {
+    pub shard: Option<u64>,
+    pub segment: u32,
+    pub doc_id: u32,
+    pub score: f64,
+    pub signals: HashMap<SignalEnumDiscriminants, SignalScore>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RankingLogEntry {
+    pub query: String,
+    pub optic: Option<Optic>,
+    pub host_rankings: Option<HostRankings>,
+    pub results: Vec<RankingLogResult>,
+}
+
+impl RankingLogEntry {
+    fn new(query: &SearchQuery, websites: &[ScoredWebpagePointer]) -> Self {
+        let results = websites
+            .iter()
+            .map(|website| {
+                let ranking = website.as_ranking();
+
+                let shard = match website {
+                    ScoredWebpagePointer::Normal(pointer) => Some(pointer.shard.as_u64()),
+                    ScoredWebpagePointer::Live(_) => None,
+                };
+
+                RankingLogResult {
+                    shard,
+                    segment: ranking.pointer.address.segment,
+                    doc_id: ranking.pointer.address.doc_id,
+                    score: website.score(),
+                    signals: SignalEnum::all()
+                        .filter_map(|signal| {
+                            ranking
+                                .signals
+                                .get(signal)
+                                .map(|score| (signal.into(), *score))
+                        })
+                        .collect(),
+                }
+            })
+            .collect();
+
+        Self {
+            query: query.query.clone(),
+            optic: query.optic.clone(),
+            host_rankings: query.host_rankings.clone(),
+            results,
+        }
+    }
+}
+
+/// Maximum number of entries waiting to be written before new entries are dropped.
+const MAX_PENDING_ENTRIES: usize = 1024;
+
+/// Samples queries and hands their ranking to a background thread that writes
+/// them to the sink, so logging never blocks the search path on IO.
+pub struct RankingLogger {
+    sender: Option<Sender<RankingLogEntry>>,
+    writer: Option<JoinHandle<()>>,
+    sample_rate: u64,
+    num_queries: AtomicU64,
+}
+
+impl RankingLogger {
+    pub fn new<W: Write + Send + 'static>(sink: W, sample_rate: u64) -> Self {
+        let (sender, receiver) = crossbeam_channel::bounded(MAX_PENDING_ENTRIES);
+        let writer = std::thread::spawn(move || write_entries(BufWriter::new(sink), receiver));
+
+        Self {
+            sender: Some(sender),
+            writer: Some(writer),
+            sample_rate: sample_rate.max(1),
+            num_queries: AtomicU64::new(0),
+        }
+    }
+
+    pub fn open(config: &RankingLogConfig) -> Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&config.path)?;
+
+        Ok(Self::new(file, config.sample_rate))
+    }
+
+    fn should_log(&self) -> bool {
+        self.num_queries.fetch_add(1, Ordering::Relaxed) % self.sample_rate == 0
+    }
+
+    /// Queue the ranking of `websites` for writing if the query is sampled.
+    /// The entry is dropped if the writer has fallen too far behind.
+    pub fn log(&self, query: &SearchQuery, websites: &[ScoredWebpagePointer]) {
+        if !self.should_log() {
+            return;
+        }
+
+        let Some(sender) = &self.sender else {
+            return;
+        };
+
+        match sender.try_send(RankingLogEntry::new(query, websites)) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => {
+                tracing::warn!("ranking log writer is behind, dropping entry")
+            }
+            Err(TrySendError::Disconnected(_)) => {
+                tracing::error!("ranking log writer has stopped, dropping entry")
+            }
+        }
+    }
+}
+
+impl Drop for RankingLogger {
+    fn drop(&mut self) {
+        // closing the channel makes the writer flush the remaining entries and exit
+        self.sender.take();
+
+        if let Some(writer) = self.writer.take() {
+            let _ = writer.join();
+        }
+    }
+}
+
+/// Write entries as json lines until the channel is closed. The sink is flushed
+/// whenever there are no more entries waiting.
+fn write_entries<W: Write>(mut sink: BufWriter<W>, receiver: Receiver<RankingLogEntry>) {
+    while let Ok(entry) = receiver.recv() {
+        for entry in std::iter::once(entry).chain(receiver.try_iter()) {
+            if let Err(err) = serde_json::to_writer(&mut sink, &entry)
+                .map_err(std::io::Error::from)
+                .and_then(|_| sink.write_all(b"\n"))
+            {
+                tracing::error!("failed to write ranking log: {:?}", err);
+            }
+        }
+
+        if let Err(err) = sink.flush() {
+            tracing::error!("failed to flush ranking log: {:?}", err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use crate::{
+        collector::Hashes,
+        distributed::member::ShardId,
+        enum_map::EnumMap,
+        inverted_index::{DocAddress, WebpagePointer},
+        prehashed::Prehashed,
+        ranking::{initial::Score, pipeline::RecallRankingWebpage, signal::HostCentrality},
+        searcher::distributed,
+    };
+
+    use super::*;
+
+    #[derive(Clone, Default)]
+    struct SharedBuf(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuf {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl SharedBuf {
+        fn lines(&self) -> Vec<serde_json::Value> {
+            String::from_utf8(self.0.lock().unwrap().clone())
+                .unwrap()
+                .lines()
+                .map(|line| serde_json::from_str(line).unwrap())
+                .collect()
+        }
+    }
+
+    fn website(doc_id: u32, score: f64) -> ScoredWebpagePointer {
+        let mut signals = EnumMap::new();
+        signals.insert(
+            HostCentrality.into(),
+            SignalScore {
+                coefficient: 2.0,
+                value: score,
+            },
+        );
+
+        ScoredWebpagePointer::Normal(distributed::ScoredWebpagePointer {
+            website: RecallRankingWebpage {
+                pointer: WebpagePointer {
+                    score: Score { total: score },
+                    hashes: Hashes {
+                        site: Prehashed(0),
+                        title: Prehashed(0),
+                        url: Prehashed(0),
+                        url_without_tld: Prehashed(0),
+                        simhash: 0,
+                    },
+                    host_centrality: 0.0,
+                    address: DocAddress { segment: 0, doc_id },
+                },
+                signals,
+                optic_boost: None,
+                title_embedding: None,
+                keyword_embedding: None,
+                host_id: None,
+                score,
+            },
+            shard: ShardId::new(3),
+        })
+    }
+
+    #[test]
+    fn log_entry() {
+        let buf = SharedBuf::default();
+        let logger = RankingLogger::new(buf.clone(), 1);
+
+        let query = SearchQuery {
+            query: "test query".to_string(),
+            optic: Some(Optic::parse("DiscardNonMatching;").unwrap()),
+            ..Default::default()
+        };
+
+        logger.log(&query, &[website(1, 0.5), website(7, 0.25)]);
+        drop(logger);
+
+        let lines = buf.lines();
+        assert_eq!(lines.len(), 1);
+
+        let entry = &lines[0];
+        assert_eq!(entry["query"], "test query");
+        assert_eq!(entry["optic"]["discard_non_matching"], true);
+
+        let results = entry["results"].as_array().unwrap();
+        assert_eq!(results.len(), 2);
+
+        assert_eq!(results[0]["shard"], 3);
+        assert_eq!(results[0]["docId"], 1);
+        assert_eq!(results[0]["score"], 0.5);
+        assert_eq!(
+            results[0]["signals"]["host_centrality"],
+            serde_json::json!({"coefficient": 2.0, "value": 0.5})
+        );
+
+        assert_eq!(results[1]["docId"], 7);
+        assert_eq!(results[1]["score"], 0.25);
+    }
+
+    #[test]
+    fn sampling() {
+        let buf = SharedBuf::default();
+        let logger = RankingLogger::new(buf.clone(), 3);
+
+        for i in 0..7 {
+            let query = SearchQuery {
+                query: format!("query {i}"),
+                ..Default::default()
+            };
+
+            logger.log(&query, &[website(i, 1.0)]);
+        }
+
+        drop(logger);
+
+        let queries: Vec<_> = buf
+            .lines()
+            .into_iter()
+            .map(|entry| entry["query"].as_str().unwrap().to_string())
+            .collect();
+
+        assert_eq!(queries, vec!["query 0", "query 3", "query 6"]);
+    }
+}
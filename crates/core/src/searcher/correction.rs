@@ -0,0 +1,282 @@
+// Stract is an open source web search engine.
+// Copyright (C) 2024 Stract ApS
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! "Did you mean" correction of full queries based on the document frequencies
+//! of the query tokens in the index. Each token is compared against all terms
+//! within an edit distance of one, and replaced by the candidate that is
+//! found in considerably more documents.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Corrections with a lower confidence are not suggested.
+pub const MIN_CONFIDENCE: f64 = 0.8;
+
+/// Shorter tokens have too many candidates within a single edit
+/// to be corrected reliably.
+const MIN_TOKEN_LEN: usize = 3;
+
+/// How much more we trust the token the user typed than a candidate found in the same number of documents.
+const ORIGINAL_WEIGHT: f64 = 10.0;
+
+const ALPHABET: &str = "abcdefghijklmnopqrstuvwxyz";
+
+/// The tokens of a query together with the document frequencies of
+/// the tokens and their spelling candidates. Terms that are not found
+/// in any document are left out.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TermFrequencies {
+    pub tokens: Vec<String>,
+    pub doc_freqs: HashMap<String, u64>,
+}
+
+impl TermFrequencies {
+    /// Sum the frequencies from another shard. All shards tokenize
+    /// the query the same way, so the tokens are only kept once.
+    pub fn merge(&mut self, other: Self) {
+        if self.tokens.is_empty() {
+            self.tokens = other.tokens;
+        }
+
+        for (term, freq) in other.doc_freqs {
+            *self.doc_freqs.entry(term).or_default() += freq;
+        }
+    }
+
+    fn freq(&self, term: &str) -> u64 {
+        self.doc_freqs.get(term).copied().unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryCorrection {
+    /// The corrected query, or the original query if there was no confident correction.
+    pub query: String,
+    pub corrected: bool,
+    /// Confidence in the best correction that was found between 0 and 1.
+    pub confidence: f64,
+}
+
+impl QueryCorrection {
+    pub fn unchanged(query: &str) -> Self {
+        Self {
+            query: query.to_string(),
+            corrected: false,
+            confidence: 0.0,
+        }
+    }
+}
+
+/// All terms within an edit distance of one from `token`.
+pub fn candidates(token: &str) -> Vec<String> {
+    if token.len() < MIN_TOKEN_LEN || !token.chars().all(|c| c.is_ascii_lowercase()) {
+        return Vec::new();
+    }
+
+    let chars: Vec<char> = token.chars().collect();
+    let mut res = Vec::new();
+
+    for i in 0..chars.len() {
+        let mut deleted = chars.clone();
+        deleted.remove(i);
+        res.push(deleted.into_iter().collect());
+
+        if i + 1 < chars.len() {
+            let mut transposed = chars.clone();
+            transposed.swap(i, i + 1);
+            res.push(transposed.into_iter().collect());
+        }
+
+        for c in ALPHABET.chars() {
+            let mut replaced = chars.clone();
+            replaced[i] = c;
+            res.push(replaced.into_iter().collect());
+        }
+    }
+
+    for i in 0..=chars.len() {
+        for c in ALPHABET.chars() {
+            let mut inserted = chars.clone();
+            inserted.insert(i, c);
+            res.push(inserted.into_iter().collect());
+        }
+    }
+
+    res.sort();
+    res.dedup();
+    res.retain(|candidate| candidate != token);
+
+    res
+}
+
+/// The most frequent candidate for `token` and the confidence that it is the intended term.
+fn correct_token(token: &str, freqs: &TermFrequencies) -> Option<(String, f64)> {
+    let orig_freq = freqs.freq(token);
+
+    let mut total = 0;
+    let mut best: Option<(String, u64)> = None;
+
+    for candidate in candidates(token) {
+        let freq = freqs.freq(&candidate);
+
+        if freq == 0 {
+            continue;
+        }
+
+        total += freq;
+
+        if best.as_ref().map(|(_, best)| freq > *best).unwrap_or(true) {
+            best = Some((candidate, freq));
+        }
+    }
+
+    let (best, best_freq) = best?;
+
+    if best_freq <= orig_freq {
+        return None;
+    }
+
+    let confidence = best_freq as f64 / (total as f64 + ORIGINAL_WEIGHT * orig_freq as f64);
+
+    Some((best, confidence))
+}
+
+/// Correct the misspelled tokens of `query`. The original query is returned
+/// unchanged if the confidence of the correction is below `min_confidence`.
+pub fn correct(query: &str, freqs: &TermFrequencies, min_confidence: f64) -> QueryCorrection {
+    let mut corrections = HashMap::new();
+    let mut confidence = 1.0;
+
+    for token in &freqs.tokens {
+        if corrections.contains_key(token) {
+            continue;
+        }
+
+        if let Some((correction, token_confidence)) = correct_token(token, freqs) {
+            confidence *= token_confidence;
+            corrections.insert(token.clone(), correction);
+        }
+    }
+
+    if corrections.is_empty() {
+        return QueryCorrection::unchanged(query);
+    }
+
+    if confidence < min_confidence {
+        return QueryCorrection {
+            confidence,
+            ..QueryCorrection::unchanged(query)
+        };
+    }
+
+    let corrected = query
+        .split_whitespace()
+        .map(|word| {
+            corrections
+                .get(&word.to_lowercase())
+                .cloned()
+                .unwrap_or_else(|| word.to_string())
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    QueryCorrection {
+        query: corrected,
+        corrected: true,
+        confidence,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn freqs(tokens: &[&str], doc_freqs: &[(&str, u64)]) -> TermFrequencies {
+        TermFrequencies {
+            tokens: tokens.iter().map(|t| t.to_string()).collect(),
+            doc_freqs: doc_freqs
+                .iter()
+                .map(|(term, freq)| (term.to_string(), *freq))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn edit_distance_candidates() {
+        let candidates = candidates("helo");
+
+        assert!(candidates.contains(&"hello".to_string()));
+        assert!(candidates.contains(&"help".to_string()));
+        assert!(candidates.contains(&"hlo".to_string()));
+        assert!(candidates.contains(&"ehlo".to_string()));
+        assert!(!candidates.contains(&"helo".to_string()));
+
+        assert!(super::candidates("ab").is_empty());
+        assert!(super::candidates("c++").is_empty());
+    }
+
+    #[test]
+    fn obvious_typo() {
+        let freqs = freqs(
+            &["best", "exampel", "website"],
+            &[
+                ("best", 500),
+                ("example", 300),
+                ("website", 400),
+                ("exampel", 1),
+            ],
+        );
+
+        let correction = correct("best Exampel website", &freqs, MIN_CONFIDENCE);
+
+        assert!(correction.corrected);
+        assert_eq!(correction.query, "best example website");
+        assert!(correction.confidence >= MIN_CONFIDENCE);
+    }
+
+    #[test]
+    fn correctly_spelled() {
+        let freqs = freqs(
+            &["best", "website"],
+            &[
+                ("best", 500),
+                ("website", 400),
+                ("websites", 350),
+                ("rest", 20),
+            ],
+        );
+
+        assert_eq!(
+            correct("best website", &freqs, MIN_CONFIDENCE),
+            QueryCorrection::unchanged("best website")
+        );
+    }
+
+    #[test]
+    fn low_confidence() {
+        // two equally likely corrections
+        let freqs = freqs(&["tast"], &[("test", 100), ("task", 100)]);
+
+        let correction = correct("tast", &freqs, MIN_CONFIDENCE);
+
+        assert!(!correction.corrected);
+        assert_eq!(correction.query, "tast");
+        assert!(correction.confidence < MIN_CONFIDENCE);
+    }
+}
@@ -31,19 +31,21 @@ use crate::{
     image_store::Image,
     inverted_index::{RetrievedWebpage, WebpagePointer},
     ranking::pipeline::{PrecisionRankingWebpage, RecallRankingWebpage},
+    schema::{text_field::TextField, TextFieldEnum},
     Result,
 };
 
 use std::{collections::HashMap, sync::Arc};
 
 use fnv::FnvHashMap;
-use futures::future::join_all;
+use futures::{future::join_all, stream::FuturesUnordered, StreamExt};
 use itertools::Itertools;
 use std::future::Future;
 use thiserror::Error;
+use tokio::sync::mpsc;
 use url::Url;
 
-use super::{InitialWebsiteResult, SearchQuery};
+use super::{correction::TermFrequencies, InitialWebsiteResult, SearchQuery, TermStat};
 
 #[derive(Error, Debug)]
 pub enum Error {
@@ -144,6 +146,29 @@ impl DistributedSearcher {
     }
 }
 
+/// The pointers to retrieve from each shard along with the ranking of each result.
+#[allow(clippy::type_complexity)]
+fn group_by_shard(
+    top_websites: &[(usize, ScoredWebpagePointer)],
+) -> (
+    FnvHashMap<usize, RecallRankingWebpage>,
+    HashMap<ShardId, Vec<(usize, WebpagePointer)>>,
+) {
+    let mut rankings = FnvHashMap::default();
+    let mut pointers: HashMap<_, Vec<_>> = HashMap::new();
+
+    for (i, pointer) in top_websites {
+        pointers
+            .entry(pointer.shard)
+            .or_default()
+            .push((*i, pointer.website.pointer.clone()));
+
+        rankings.insert(*i, pointer.website.clone());
+    }
+
+    (rankings, pointers)
+}
+
 impl SearchClient for DistributedSearcher {
     async fn search_initial(&self, query: &SearchQuery) -> Vec<InitialSearchResultShard> {
         let client = self.client().await;
@@ -177,17 +202,7 @@ impl SearchClient for DistributedSearcher {
         top_websites: &[(usize, ScoredWebpagePointer)],
         query: &str,
     ) -> Vec<(usize, PrecisionRankingWebpage)> {
-        let mut rankings = FnvHashMap::default();
-        let mut pointers: HashMap<_, Vec<_>> = HashMap::new();
-
-        for (i, pointer) in top_websites {
-            pointers
-                .entry(pointer.shard)
-                .or_default()
-                .push((*i, pointer.website.pointer.clone()));
-
-            rankings.insert(*i, pointer.website.clone());
-        }
+        let (rankings, pointers) = group_by_shard(top_websites);
 
         let client = self.client().await;
         let mut futures = Vec::new();
@@ -210,6 +225,34 @@ impl SearchClient for DistributedSearcher {
         retrieved_webpages
     }
 
+    async fn retrieve_webpages_by_shard(
+        &self,
+        top_websites: &[(usize, ScoredWebpagePointer)],
+        query: &str,
+        tx: mpsc::UnboundedSender<Vec<(usize, PrecisionRankingWebpage)>>,
+    ) {
+        let (rankings, pointers) = group_by_shard(top_websites);
+
+        let client = self.client().await;
+        let mut futures: FuturesUnordered<_> = pointers
+            .into_iter()
+            .map(|(shard, pointers)| {
+                self.retrieve_webpages_from_shard(shard, &client, query, pointers)
+            })
+            .collect();
+
+        while let Some(pages) = futures.next().await {
+            let pages = pages
+                .into_iter()
+                .map(|(i, page)| (i, PrecisionRankingWebpage::new(page, rankings[&i].clone())))
+                .collect();
+
+            if tx.send(pages).is_err() {
+                break;
+            }
+        }
+    }
+
     async fn get_webpage(&self, url: &str) -> Result<Option<RetrievedWebpage>> {
         let client = self.client().await;
 
@@ -250,6 +293,56 @@ impl SearchClient for DistributedSearcher {
         }
     }
 
+    async fn term_frequencies(&self, query: &str) -> TermFrequencies {
+        let client = self.client().await;
+
+        let res = client
+            .send(
+                &search_server::GetTermFrequencies {
+                    query: query.to_string(),
+                },
+                &AllShardsSelector,
+                &RandomReplicaSelector,
+            )
+            .await;
+
+        let mut freqs = TermFrequencies::default();
+
+        if let Ok(res) = res {
+            for shard_freqs in res.into_iter().flat_map(|(_, v)| v).flatten() {
+                freqs.merge(shard_freqs);
+            }
+        }
+
+        freqs
+    }
+
+    async fn top_terms(&self, field: TextFieldEnum, prefix: &str, limit: usize) -> Vec<TermStat> {
+        let client = self.client().await;
+
+        let res = client
+            .send(
+                &search_server::GetTopTerms {
+                    field: field.name().to_string(),
+                    prefix: prefix.to_string(),
+                    limit,
+                },
+                &AllShardsSelector,
+                &RandomReplicaSelector,
+            )
+            .await;
+
+        let mut doc_freqs: HashMap<String, u64> = HashMap::new();
+
+        if let Ok(res) = res {
+            for stat in res.into_iter().flat_map(|(_, v)| v).flatten().flatten() {
+                *doc_freqs.entry(stat.term).or_default() += stat.doc_freq;
+            }
+        }
+
+        TermStat::top(doc_freqs, limit)
+    }
+
     async fn get_entity_image(
         &self,
         image_id: &str,
@@ -302,6 +395,15 @@ pub trait SearchClient {
         query: &str,
     ) -> impl Future<Output = Vec<(usize, PrecisionRankingWebpage)>> + Send;
 
+    /// Same as `retrieve_webpages`, but the webpages of each shard are sent on `tx`
+    /// as soon as that shard responds. The webpages within a batch are not sorted.
+    fn retrieve_webpages_by_shard(
+        &self,
+        top_websites: &[(usize, ScoredWebpagePointer)],
+        query: &str,
+        tx: mpsc::UnboundedSender<Vec<(usize, PrecisionRankingWebpage)>>,
+    ) -> impl Future<Output = ()> + Send;
+
     fn search_entity(&self, query: &str) -> impl Future<Output = Option<EntityMatch>> + Send;
 
     fn get_webpage(
@@ -314,6 +416,18 @@ pub trait SearchClient {
         urls: &[Url],
     ) -> impl Future<Output = HashMap<Url, String>> + Send;
 
+    /// Document frequencies of the query tokens and their spelling candidates summed across all shards.
+    fn term_frequencies(&self, query: &str) -> impl Future<Output = TermFrequencies> + Send;
+
+    /// The `limit` terms of `field` that start with `prefix` and are found in the most
+    /// documents across all shards.
+    fn top_terms(
+        &self,
+        field: TextFieldEnum,
+        prefix: &str,
+        limit: usize,
+    ) -> impl Future<Output = Vec<TermStat>> + Send;
+
     fn get_entity_image(
         &self,
         image_id: &str,
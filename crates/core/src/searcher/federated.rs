@@ -0,0 +1,137 @@
+// Stract is an open source web search engine.
+// Copyright (C) 2024 Stract ApS
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Merging of the results of the same query from several independent indexes.
+//! Every index ranks its results with the same signals and coefficients, so the
+//! scores are comparable across the indexes.
+
+use std::collections::{hash_map::Entry, HashMap};
+
+use crate::search_prettifier::DisplayedWebpage;
+
+pub trait RankedDoc {
+    fn url(&self) -> &str;
+    fn score(&self) -> f64;
+}
+
+impl RankedDoc for DisplayedWebpage {
+    fn url(&self) -> &str {
+        &self.url
+    }
+
+    fn score(&self) -> f64 {
+        self.score.unwrap_or_default()
+    }
+}
+
+/// Merge the ranked results from each index into a single ranking by score.
+/// When the same url is returned by several indexes, only the copy with the
+/// highest score is kept. Results with equal scores are ordered by url.
+pub fn merge_ranked<T: RankedDoc>(results: Vec<Vec<T>>) -> Vec<T> {
+    let mut by_url: HashMap<String, T> = HashMap::new();
+
+    for doc in results.into_iter().flatten() {
+        match by_url.entry(doc.url().to_string()) {
+            Entry::Occupied(mut existing) => {
+                if doc.score() > existing.get().score() {
+                    existing.insert(doc);
+                }
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(doc);
+            }
+        }
+    }
+
+    let mut merged: Vec<_> = by_url.into_values().collect();
+    merged.sort_by(|a, b| {
+        b.score()
+            .total_cmp(&a.score())
+            .then_with(|| a.url().cmp(b.url()))
+    });
+
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Doc {
+        url: &'static str,
+        score: f64,
+        shard: usize,
+    }
+
+    impl RankedDoc for Doc {
+        fn url(&self) -> &str {
+            self.url
+        }
+
+        fn score(&self) -> f64 {
+            self.score
+        }
+    }
+
+    fn doc(url: &'static str, score: f64, shard: usize) -> Doc {
+        Doc { url, score, shard }
+    }
+
+    #[test]
+    fn overlapping_shards() {
+        let a = vec![
+            doc("https://a.com", 3.0, 0),
+            doc("https://c.com", 2.0, 0),
+            doc("https://d.com", 1.0, 0),
+        ];
+        let b = vec![
+            doc("https://c.com", 2.5, 1),
+            doc("https://b.com", 2.0, 1),
+            doc("https://e.com", 0.5, 1),
+        ];
+
+        let merged = merge_ranked(vec![a, b]);
+
+        assert_eq!(
+            merged,
+            vec![
+                doc("https://a.com", 3.0, 0),
+                doc("https://c.com", 2.5, 1),
+                doc("https://b.com", 2.0, 1),
+                doc("https://d.com", 1.0, 0),
+                doc("https://e.com", 0.5, 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn ties_are_deterministic() {
+        let a = vec![doc("https://b.com", 1.0, 0), doc("https://c.com", 1.0, 0)];
+        let b = vec![doc("https://a.com", 1.0, 1), doc("https://b.com", 1.0, 1)];
+
+        let merged = merge_ranked(vec![a, b]);
+
+        assert_eq!(
+            merged,
+            vec![
+                doc("https://a.com", 1.0, 1),
+                doc("https://b.com", 1.0, 0),
+                doc("https://c.com", 1.0, 0),
+            ]
+        );
+    }
+}
@@ -16,27 +16,42 @@
 
 use std::collections::HashMap;
 use std::sync::{Arc, RwLockReadGuard};
+use std::time::Duration;
 
 use itertools::Itertools;
 use url::Url;
+use whatlang::Lang;
 
-use crate::config::{CollectorConfig, SnippetConfig};
+use crate::collector::{Deadline, SearchCursor};
+use crate::config::{CollectorConfig, SignalCacheConfig, SnippetConfig};
 use crate::index::Index;
 use crate::inverted_index::{InvertedIndex, RetrievedWebpage};
 use crate::models::dual_encoder::DualEncoder;
 use crate::query::Query;
+use crate::ranking::bm25::{Bm25Params, Bm25Variant};
+#[cfg(feature = "webgraph")]
+use crate::ranking::host_signal_cache::{HostSignalCache, QueryFingerprint};
+#[cfg(feature = "webgraph")]
 use crate::ranking::inbound_similarity::InboundSimilarity;
 use crate::ranking::models::lambdamart::LambdaMART;
 use crate::ranking::models::linear::LinearRegression;
+use crate::ranking::models::registry::ModelRegistry;
+use crate::ranking::optic_rule_cache::OpticRuleCache;
 use crate::ranking::pipeline::{PrecisionRankingWebpage, RankingPipeline, RecallRankingWebpage};
-use crate::ranking::{self, query_centrality, Ranker, SignalComputer, SignalEnum};
+use crate::ranking::tld_reputation::TldReputation;
+#[cfg(feature = "webgraph")]
+use crate::ranking::{self, query_centrality};
+use crate::ranking::{Ranker, SignalComputer, SignalEnum, SignalVector, SystemClock};
+use crate::schema::TextFieldEnum;
 use crate::search_ctx::Ctx;
 use crate::search_prettifier::DisplayedWebpage;
+#[cfg(feature = "webgraph")]
 use crate::webgraph::Node;
 use crate::{inverted_index, live_index, Error, Result};
 
-use super::WebsitesResult;
+use super::correction::TermFrequencies;
 use super::{InitialWebsiteResult, SearchQuery};
+use super::{TermStat, WebsitesResult};
 
 pub trait SearchableIndex {
     type SearchGuard<'a>: SearchGuard<'a>
@@ -102,11 +117,23 @@ impl<'a> SearchGuard<'a> for LiveIndexSearchGuard<'a> {
 
 pub struct LocalSearcher<I: SearchableIndex> {
     index: I,
+    #[cfg(feature = "webgraph")]
     inbound_similarity: Option<InboundSimilarity>,
     linear_regression: Option<Arc<LinearRegression>>,
+    model_registry: Option<Arc<ModelRegistry>>,
     lambda_model: Option<Arc<LambdaMART>>,
     dual_encoder: Option<Arc<DualEncoder>>,
     collector_config: CollectorConfig,
+    #[cfg(feature = "webgraph")]
+    query_centrality_fallback: Option<f64>,
+    ranking_stopwords: Vec<Lang>,
+    #[cfg(feature = "webgraph")]
+    host_signal_cache: Option<Arc<HostSignalCache>>,
+    optic_rule_cache: Option<Arc<OpticRuleCache>>,
+    tld_reputation: Option<Arc<TldReputation>>,
+    bm25_params: Bm25Params,
+    bm25_variant: Bm25Variant,
+    signal_cache_config: SignalCacheConfig,
 }
 
 impl<I> From<I> for LocalSearcher<I>
@@ -121,7 +148,10 @@ where
 struct InvertedIndexResult {
     webpages: Vec<RecallRankingWebpage>,
     num_hits: Option<usize>,
+    discarded_count: Option<usize>,
     has_more: bool,
+    next_cursor: Option<SearchCursor>,
+    partial: bool,
 }
 
 impl<I> LocalSearcher<I>
@@ -131,22 +161,45 @@ where
     pub fn new(index: I) -> Self {
         LocalSearcher {
             index,
+            #[cfg(feature = "webgraph")]
             inbound_similarity: None,
             linear_regression: None,
+            model_registry: None,
             lambda_model: None,
             dual_encoder: None,
             collector_config: CollectorConfig::default(),
+            #[cfg(feature = "webgraph")]
+            query_centrality_fallback: None,
+            ranking_stopwords: Vec::new(),
+            #[cfg(feature = "webgraph")]
+            host_signal_cache: None,
+            optic_rule_cache: None,
+            tld_reputation: None,
+            bm25_params: Bm25Params::default(),
+            bm25_variant: Bm25Variant::default(),
+            signal_cache_config: SignalCacheConfig::default(),
         }
     }
 
+    #[cfg(feature = "webgraph")]
     pub fn set_inbound_similarity(&mut self, inbound: InboundSimilarity) {
         self.inbound_similarity = Some(inbound);
+
+        if let Some(cache) = &self.host_signal_cache {
+            cache.invalidate();
+        }
     }
 
     pub fn set_linear_model(&mut self, model: LinearRegression) {
         self.linear_regression = Some(Arc::new(model));
     }
 
+    /// Models that searches can select instead of the model set with
+    /// [`LocalSearcher::set_linear_model`].
+    pub fn set_model_registry(&mut self, registry: ModelRegistry) {
+        self.model_registry = Some(Arc::new(registry));
+    }
+
     pub fn set_lambda_model(&mut self, model: LambdaMART) {
         self.lambda_model = Some(Arc::new(model));
     }
@@ -163,13 +216,66 @@ where
         self.index.set_snippet_config(config);
     }
 
+    /// Query centrality of hosts that are not in the webgraph.
+    #[cfg(feature = "webgraph")]
+    pub fn set_query_centrality_fallback(&mut self, fallback: Option<f64>) {
+        self.query_centrality_fallback = fallback;
+    }
+
+    /// Reputation of the TLDs used by the [`ranking::TldReputation`] signal.
+    pub fn set_tld_reputation(&mut self, tld_reputation: TldReputation) {
+        self.tld_reputation = Some(Arc::new(tld_reputation));
+    }
+
+    /// The k1 and b parameters of BM25 used by the text signals.
+    pub fn set_bm25_params(&mut self, params: Bm25Params) {
+        self.bm25_params = params;
+    }
+
+    pub fn set_bm25_variant(&mut self, variant: Bm25Variant) {
+        self.bm25_variant = variant;
+    }
+
+    /// Size of the fetch time and freshness lookup tables used by the signals.
+    pub fn set_signal_cache_config(&mut self, config: SignalCacheConfig) {
+        self.signal_cache_config = config;
+    }
+
+    /// Remove the stopwords of `langs` from the terms used for ranking.
+    pub fn set_ranking_stopwords(&mut self, langs: Vec<Lang>) {
+        self.ranking_stopwords = langs;
+    }
+
+    /// Cache the host signals of up to `capacity` (query, host) pairs across requests.
+    /// The cache is invalidated when the inbound similarity is replaced.
+    #[cfg(feature = "webgraph")]
+    pub fn set_host_signal_cache(&mut self, capacity: usize) {
+        self.host_signal_cache = Some(Arc::new(HostSignalCache::new(capacity)));
+    }
+
+    #[cfg(feature = "webgraph")]
+    pub fn host_signal_cache(&self) -> Option<&HostSignalCache> {
+        self.host_signal_cache.as_deref()
+    }
+
+    /// Cache the compiled ranking rules of up to `capacity` optics across requests.
+    /// Rules compiled before the index changed are compiled again.
+    pub fn set_optic_rule_cache(&mut self, capacity: usize) {
+        self.optic_rule_cache = Some(Arc::new(OpticRuleCache::new(capacity)));
+    }
+
+    pub fn optic_rule_cache(&self) -> Option<&OpticRuleCache> {
+        self.optic_rule_cache.as_deref()
+    }
+
     fn parse_query<'a, G: SearchGuard<'a>>(
         &'a self,
         ctx: &Ctx,
         guard: &G,
         query: &SearchQuery,
     ) -> Result<Query> {
-        let parsed_query = Query::parse(ctx, query, guard.inverted_index())?;
+        let mut parsed_query = Query::parse(ctx, query, guard.inverted_index())?;
+        parsed_query.remove_stopwords(&self.ranking_stopwords);
 
         if parsed_query.is_empty() {
             Err(Error::EmptyQuery.into())
@@ -178,6 +284,7 @@ where
         }
     }
 
+    #[cfg_attr(not(feature = "webgraph"), allow(unused_variables))]
     fn ranker<'a, G: SearchGuard<'a>>(
         &'a self,
         query: &Query,
@@ -186,8 +293,8 @@ where
         de_rank_similar: bool,
         computer: SignalComputer,
     ) -> Result<Ranker> {
-        let query_centrality_coeff =
-            computer.coefficient(&ranking::signal::QueryCentrality.into());
+        #[cfg(feature = "webgraph")]
+        let query_centrality_coeff = computer.coefficient(&ranking::signal::QueryCentrality.into());
 
         let mut ranker = Ranker::new(
             computer,
@@ -197,6 +304,36 @@ where
 
         ranker.de_rank_similar(de_rank_similar);
 
+        #[cfg(feature = "webgraph")]
+        let ranker =
+            self.with_query_centrality(query, ctx, guard, ranker, query_centrality_coeff)?;
+
+        let mut ranker = ranker
+            .with_max_docs(
+                self.collector_config.max_docs_considered,
+                guard.inverted_index().num_segments(),
+            )
+            .with_num_results(query.num_results())
+            .with_offset(query.offset());
+
+        if let Some(cursor) = query.cursor() {
+            ranker = ranker.with_cursor(*cursor);
+        }
+
+        Ok(ranker)
+    }
+
+    /// Find the hosts of the best results for the query, so the query centrality
+    /// of each host can be computed as its similarity to those hosts.
+    #[cfg(feature = "webgraph")]
+    fn with_query_centrality<'a, G: SearchGuard<'a>>(
+        &'a self,
+        query: &Query,
+        ctx: &Ctx,
+        guard: &G,
+        mut ranker: Ranker,
+        query_centrality_coeff: f64,
+    ) -> Result<Ranker> {
         if query_centrality_coeff > 0.0 {
             if let Some(inbound_sim) = self.inbound_similarity.as_ref() {
                 ranker = ranker
@@ -211,20 +348,49 @@ where
                 if !top_host_nodes.is_empty() {
                     let inbound = inbound_sim.scorer(&top_host_nodes, &[], false);
 
-                    let query_centrality = query_centrality::Scorer::new(inbound);
+                    let query_centrality = query_centrality::Scorer::new(inbound)
+                        .with_fallback(self.query_centrality_fallback);
 
                     ranker.set_query_centrality(query_centrality);
                 }
             }
         }
 
-        Ok(ranker
-            .with_max_docs(
-                self.collector_config.max_docs_considered,
-                guard.inverted_index().num_segments(),
-            )
-            .with_num_results(query.num_results())
-            .with_offset(query.offset()))
+        Ok(ranker)
+    }
+
+    /// Score the hosts by their similarity to the liked and disliked hosts of the query.
+    #[cfg(feature = "webgraph")]
+    fn set_webgraph_signals(
+        &self,
+        computer: &mut SignalComputer,
+        parsed_query: &Query,
+        query: &SearchQuery,
+    ) {
+        if let Some(inbound_sim) = &self.inbound_similarity {
+            let liked_hosts: Vec<_> = parsed_query
+                .host_rankings()
+                .liked
+                .iter()
+                .map(|site| Node::from(site.clone()).into_host())
+                .map(|node| node.id())
+                .collect();
+
+            let disliked_hosts: Vec<_> = parsed_query
+                .host_rankings()
+                .disliked
+                .iter()
+                .map(|site| Node::from(site.clone()).into_host())
+                .map(|node| node.id())
+                .collect();
+
+            let scorer = inbound_sim.scorer(&liked_hosts, &disliked_hosts, false);
+            computer.set_inbound_similarity(scorer);
+        }
+
+        if let Some(cache) = &self.host_signal_cache {
+            computer.set_host_signal_cache(cache.clone(), QueryFingerprint::new(query));
+        }
     }
 
     fn search_inverted_index<'a, G: SearchGuard<'a>>(
@@ -243,31 +409,36 @@ where
                 self.collector_config.clone(),
                 100,
             );
+
+        if query.cursor.is_some() {
+            // the cursor points into the order of the collector, so only the next page
+            // (and one extra result to know if there are more) is collected and re-ranked.
+            query.num_results = pipeline.top_n + 1;
+        }
+
+        let deadline = query
+            .timeout_ms
+            .map(|timeout_ms| Deadline::new(Duration::from_millis(timeout_ms)));
+
         let parsed_query = self.parse_query(ctx, guard, &query)?;
 
-        let mut computer = SignalComputer::new(Some(&parsed_query));
+        let mut computer = SignalComputer::new_with_clock(Some(&parsed_query), &SystemClock);
 
-        if let Some(inbound_sim) = &self.inbound_similarity {
-            let liked_hosts: Vec<_> = parsed_query
-                .host_rankings()
-                .liked
-                .iter()
-                .map(|site| Node::from(site.clone()).into_host())
-                .map(|node| node.id())
-                .collect();
+        #[cfg(feature = "webgraph")]
+        self.set_webgraph_signals(&mut computer, &parsed_query, &query);
 
-            let disliked_hosts: Vec<_> = parsed_query
-                .host_rankings()
-                .disliked
-                .iter()
-                .map(|site| Node::from(site.clone()).into_host())
-                .map(|node| node.id())
-                .collect();
+        if let Some(cache) = &self.optic_rule_cache {
+            computer.set_optic_rule_cache(cache.clone());
+        }
 
-            let scorer = inbound_sim.scorer(&liked_hosts, &disliked_hosts, false);
-            computer.set_inbound_similarity(scorer);
+        if let Some(tld_reputation) = &self.tld_reputation {
+            computer.set_tld_reputation(tld_reputation.clone());
         }
 
+        computer.set_bm25_variant(self.bm25_variant.clone());
+        computer.set_bm25_params(self.bm25_params);
+        computer.set_cache_config(self.signal_cache_config);
+
         computer.set_region_count(
             guard
                 .search_index()
@@ -277,11 +448,22 @@ where
                 .clone(),
         );
 
-        if let Some(model) = self.linear_regression.as_ref() {
-            computer.set_linear_model(model.clone());
+        let model = self
+            .model_registry
+            .as_ref()
+            .zip(query.model.as_ref())
+            .and_then(|(registry, selection)| registry.select(selection))
+            .or_else(|| self.linear_regression.clone());
+
+        if let Some(model) = model {
+            computer.set_linear_model(model);
         }
 
-        let ranker = self.ranker(&parsed_query, ctx, guard, de_rank_similar, computer)?;
+        let mut ranker = self.ranker(&parsed_query, ctx, guard, de_rank_similar, computer)?;
+
+        if let Some(deadline) = &deadline {
+            ranker = ranker.with_deadline(deadline.clone());
+        }
 
         let res = guard.inverted_index().search_initial(
             &parsed_query,
@@ -291,7 +473,7 @@ where
 
         let fastfield_reader = guard.inverted_index().fastfield_reader();
 
-        let ranking_websites = guard.inverted_index().retrieve_ranking_websites(
+        let mut ranking_websites = guard.inverted_index().retrieve_ranking_websites(
             ctx,
             res.top_websites,
             ranker.computer(),
@@ -299,14 +481,37 @@ where
         )?;
 
         let pipe_top_n = pipeline.top_n;
-        let has_more = ranking_websites.len() > pipe_top_n;
+        let mut has_more = ranking_websites.len() > pipe_top_n;
+
+        let mut next_cursor = None;
+        if query.cursor.is_some() {
+            ranking_websites.truncate(pipe_top_n);
+
+            if has_more {
+                next_cursor = ranking_websites
+                    .last()
+                    .map(|website| SearchCursor::from(&website.pointer));
+            }
+        }
 
+        let has_min_score = pipeline.min_score().is_some();
+        let num_ranked = ranking_websites.len().min(pipe_top_n);
         let ranking_websites = pipeline.apply(ranking_websites);
 
+        // the results are ranked by score, so when some results on this page are below
+        // the minimum score, the results on the following pages would be as well.
+        if has_min_score && ranking_websites.len() < num_ranked {
+            has_more = false;
+            next_cursor = None;
+        }
+
         Ok(InvertedIndexResult {
             webpages: ranking_websites,
             num_hits: res.num_websites,
+            discarded_count: res.discarded_count,
             has_more,
+            next_cursor,
+            partial: deadline.map_or(false, |deadline| deadline.is_reached()),
         })
     }
 
@@ -323,7 +528,10 @@ where
         Ok(InitialWebsiteResult {
             websites: inverted_index_result.webpages,
             num_websites: inverted_index_result.num_hits,
+            discarded_count: inverted_index_result.discarded_count,
             has_more: inverted_index_result.has_more,
+            next_cursor: inverted_index_result.next_cursor,
+            partial: inverted_index_result.partial,
         })
     }
 
@@ -349,6 +557,15 @@ where
 
     /// This function is mainly used for tests and benchmarks
     pub fn search(&self, query: &SearchQuery) -> Result<WebsitesResult> {
+        self.search_page(query).map(|(result, _)| result)
+    }
+
+    /// Like [`LocalSearcher::search`], but also returns the cursor of the next page
+    /// when the query has a cursor and there are more results after this page.
+    pub fn search_page(
+        &self,
+        query: &SearchQuery,
+    ) -> Result<(WebsitesResult, Option<SearchCursor>)> {
         use std::time::Instant;
 
         let start = Instant::now();
@@ -374,6 +591,10 @@ where
             }
         };
 
+        if query.cursor.is_some() {
+            search_query.num_results = query.num_results;
+        }
+
         let search_result = self.search_initial(&search_query, true)?;
 
         let pointers: Vec<_> = search_result
@@ -393,7 +614,10 @@ where
 
         let top_websites = pipeline.apply(websites);
 
-        let has_more_results = search_len != top_websites.len();
+        let has_more_results = match query.cursor {
+            Some(_) => search_result.next_cursor.is_some(),
+            None => search_len != top_websites.len(),
+        };
 
         let pointers: Vec<_> = top_websites
             .iter()
@@ -417,14 +641,29 @@ where
             }
 
             webpage.ranking_signals = Some(ranking_signals);
+            webpage.score = Some(ranking.ranking.score);
+
+            if search_query.return_features {
+                webpage.features = Some(SignalVector::from(&ranking.ranking.signals));
+            }
         }
 
-        Ok(WebsitesResult {
+        if search_query.normalize_scores {
+            super::normalize_scores(&mut webpages);
+        }
+
+        let result = WebsitesResult {
             num_hits: search_result.num_websites,
+            discarded_count: search_result.discarded_count,
             webpages,
             search_duration_ms: start.elapsed().as_millis(),
             has_more_results,
-        })
+            partial: search_result.partial,
+            optic_rules: search_query.optic_rule_outcomes(),
+            features_layout: search_query.features_layout(),
+        };
+
+        Ok((result, search_result.next_cursor))
     }
 
     pub fn get_webpage(&self, url: &str) -> Option<RetrievedWebpage> {
@@ -434,12 +673,35 @@ where
     pub fn get_homepage(&self, url: &Url) -> Option<RetrievedWebpage> {
         self.index.guard().inverted_index().get_homepage(url)
     }
+
+    pub fn term_frequencies(&self, query: &str) -> Result<TermFrequencies> {
+        self.index.guard().inverted_index().term_frequencies(query)
+    }
+
+    /// The `limit` terms of `field` that start with `prefix` and are found in the most documents.
+    pub fn top_terms(
+        &self,
+        field: TextFieldEnum,
+        prefix: &str,
+        limit: usize,
+    ) -> Result<Vec<TermStat>> {
+        self.index
+            .guard()
+            .inverted_index()
+            .top_terms(field, prefix, limit)
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    #[cfg(feature = "webgraph")]
+    use optics::HostRankings;
+
+    #[cfg(feature = "webgraph")]
+    use crate::webgraph::WebgraphWriter;
     use crate::{
-        searcher::NUM_RESULTS_PER_PAGE,
+        ranking::models::registry::ModelSelection,
+        searcher::{correction, NUM_RESULTS_PER_PAGE},
         webpage::{Html, Webpage},
     };
 
@@ -503,4 +765,736 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn cursor_pagination() {
+        const NUM_WEBSITES: usize = 60;
+        const PAGE_SIZE: usize = 7;
+
+        let mut index = Index::temporary().expect("Unable to open index");
+
+        for i in 0..NUM_WEBSITES {
+            index
+                .insert(&Webpage {
+                    html: Html::parse(
+                        r#"
+            <html>
+                <head>
+                    <title>Example website</title>
+                </head>
+                <body>
+                    test
+                </body>
+            </html>
+            "#,
+                        &format!("https://www.{i}.com"),
+                    )
+                    .unwrap(),
+                    // several websites share the same score to test tie-breaking
+                    host_centrality: (i / 3) as f64,
+                    fetch_time_ms: 500,
+                    ..Default::default()
+                })
+                .expect("failed to insert webpage");
+        }
+
+        index.commit().unwrap();
+
+        let searcher = LocalSearcher::new(index);
+
+        let mut all_urls: Vec<_> = searcher
+            .search(&SearchQuery {
+                query: "test".to_string(),
+                num_results: 100,
+                ..Default::default()
+            })
+            .unwrap()
+            .webpages
+            .into_iter()
+            .map(|page| page.url)
+            .collect();
+        all_urls.sort();
+
+        assert_eq!(all_urls.len(), NUM_WEBSITES);
+
+        let mut cursor = SearchCursor::default();
+        let mut paginated_urls = Vec::new();
+        let mut num_pages = 0;
+
+        loop {
+            let query = SearchQuery {
+                query: "test".to_string(),
+                num_results: PAGE_SIZE,
+                cursor: Some(cursor),
+                ..Default::default()
+            };
+
+            let (res, next_cursor) = searcher.search_page(&query).unwrap();
+            assert!(res.webpages.len() <= PAGE_SIZE);
+
+            // the cursor is stable for identical queries
+            assert_eq!(searcher.search_page(&query).unwrap().1, next_cursor);
+
+            paginated_urls.extend(res.webpages.into_iter().map(|page| page.url));
+            num_pages += 1;
+
+            match next_cursor {
+                Some(next) => {
+                    assert!(res.has_more_results);
+                    cursor = next;
+                }
+                None => {
+                    assert!(!res.has_more_results);
+                    break;
+                }
+            }
+        }
+
+        assert_eq!(num_pages, NUM_WEBSITES.div_ceil(PAGE_SIZE));
+
+        let num_paginated = paginated_urls.len();
+        paginated_urls.sort();
+        paginated_urls.dedup();
+
+        assert_eq!(paginated_urls.len(), num_paginated);
+        assert_eq!(paginated_urls, all_urls);
+    }
+
+    #[test]
+    fn cursor_pages_with_max_docs() {
+        const NUM_WEBSITES: usize = 60;
+        const PAGE_SIZE: usize = 7;
+
+        let mut index = Index::temporary().expect("Unable to open index");
+
+        for i in 0..NUM_WEBSITES {
+            index
+                .insert(&Webpage {
+                    html: Html::parse(
+                        r#"
+            <html>
+                <head>
+                    <title>Example website</title>
+                </head>
+                <body>
+                    test
+                </body>
+            </html>
+            "#,
+                        &format!("https://www.{i}.com"),
+                    )
+                    .unwrap(),
+                    host_centrality: (i % 5) as f64,
+                    fetch_time_ms: 500,
+                    ..Default::default()
+                })
+                .expect("failed to insert webpage");
+        }
+
+        index.commit().unwrap();
+
+        let mut searcher = LocalSearcher::new(index);
+        searcher.set_collector_config(CollectorConfig {
+            max_docs_considered: 10,
+            ..Default::default()
+        });
+
+        let mut cursor = SearchCursor::default();
+        let mut pages = Vec::new();
+
+        loop {
+            let (res, next_cursor) = searcher
+                .search_page(&SearchQuery {
+                    query: "test".to_string(),
+                    num_results: PAGE_SIZE,
+                    cursor: Some(cursor),
+                    ..Default::default()
+                })
+                .unwrap();
+
+            pages.push(
+                res.webpages
+                    .into_iter()
+                    .map(|page| page.url)
+                    .collect::<Vec<_>>(),
+            );
+
+            match next_cursor {
+                Some(next) => cursor = next,
+                None => break,
+            }
+        }
+
+        assert!(pages.len() > 1);
+
+        // the documents before the cursor don't count towards the documents
+        // considered, so every page but the last is full.
+        for page in &pages[..pages.len() - 1] {
+            assert_eq!(page.len(), PAGE_SIZE);
+        }
+
+        let urls: Vec<_> = pages.iter().flatten().collect();
+        assert_eq!(urls.iter().unique().count(), urls.len());
+    }
+
+    #[test]
+    fn correct_with_term_frequencies() {
+        let mut index = Index::temporary().expect("Unable to open index");
+
+        for i in 0..5 {
+            index
+                .insert(&Webpage {
+                    html: Html::parse(
+                        r#"
+            <html>
+                <head>
+                    <title>Example website</title>
+                </head>
+                <body>
+                    this is the best example website ever
+                </body>
+            </html>
+            "#,
+                        &format!("https://www.{i}.com"),
+                    )
+                    .unwrap(),
+                    fetch_time_ms: 500,
+                    ..Default::default()
+                })
+                .expect("failed to insert webpage");
+        }
+
+        index.commit().unwrap();
+
+        let searcher = LocalSearcher::new(index);
+
+        let freqs = searcher.term_frequencies("best exampel website").unwrap();
+        assert_eq!(freqs.tokens, vec!["best", "exampel", "website"]);
+        assert_eq!(freqs.doc_freqs.get("example"), Some(&5));
+        assert_eq!(freqs.doc_freqs.get("exampel"), None);
+
+        let correction =
+            correction::correct("best exampel website", &freqs, correction::MIN_CONFIDENCE);
+        assert!(correction.corrected);
+        assert_eq!(correction.query, "best example website");
+
+        let freqs = searcher.term_frequencies("best example website").unwrap();
+        let correction =
+            correction::correct("best example website", &freqs, correction::MIN_CONFIDENCE);
+        assert!(!correction.corrected);
+        assert_eq!(correction.query, "best example website");
+    }
+
+    #[test]
+    fn top_terms() {
+        let mut index = Index::temporary().expect("Unable to open index");
+
+        let bodies = [
+            "rust is a systems language",
+            "learning rust the hard way",
+            "rust and ruby",
+            "a rusty old bike",
+            "ruby on rails",
+        ];
+
+        for (i, body) in bodies.iter().enumerate() {
+            index
+                .insert(&Webpage {
+                    html: Html::parse(
+                        &format!(
+                            r#"
+            <html>
+                <head>
+                    <title>Example website</title>
+                </head>
+                <body>
+                    {body}
+                </body>
+            </html>
+            "#
+                        ),
+                        &format!("https://www.{i}.com"),
+                    )
+                    .unwrap(),
+                    fetch_time_ms: 500,
+                    ..Default::default()
+                })
+                .expect("failed to insert webpage");
+        }
+
+        index.commit().unwrap();
+
+        let searcher = LocalSearcher::new(index);
+
+        let terms = searcher
+            .top_terms(crate::schema::text_field::CleanBody.into(), "rus", 10)
+            .unwrap();
+        assert_eq!(
+            terms,
+            vec![
+                TermStat {
+                    term: "rust".to_string(),
+                    doc_freq: 3,
+                },
+                TermStat {
+                    term: "rusty".to_string(),
+                    doc_freq: 1,
+                },
+            ]
+        );
+
+        let terms = searcher
+            .top_terms(crate::schema::text_field::CleanBody.into(), "ru", 1)
+            .unwrap();
+        assert_eq!(
+            terms,
+            vec![TermStat {
+                term: "rust".to_string(),
+                doc_freq: 3,
+            }]
+        );
+
+        assert!(searcher
+            .top_terms(crate::schema::text_field::CleanBody.into(), "xyz", 10)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "webgraph")]
+    fn host_signal_cache() {
+        let mut wrt = WebgraphWriter::new(
+            crate::gen_temp_path(),
+            crate::executor::Executor::single_thread(),
+            crate::webgraph::Compression::default(),
+        );
+
+        wrt.insert(Node::from("a.com"), Node::from("b.com"), String::new());
+        wrt.insert(Node::from("a.com"), Node::from("c.com"), String::new());
+        wrt.insert(Node::from("d.com"), Node::from("c.com"), String::new());
+
+        let graph = wrt.finalize();
+
+        let mut index = Index::temporary().expect("Unable to open index");
+
+        for host in ["b.com", "c.com"] {
+            index
+                .insert(&Webpage {
+                    html: Html::parse(
+                        r#"
+            <html>
+                <head>
+                    <title>Example website</title>
+                </head>
+                <body>
+                    test
+                </body>
+            </html>
+            "#,
+                        &format!("https://{host}"),
+                    )
+                    .unwrap(),
+                    node_id: Some(Node::from(host).id()),
+                    ..Default::default()
+                })
+                .expect("failed to insert webpage");
+        }
+
+        index.commit().unwrap();
+
+        let mut searcher = LocalSearcher::new(index);
+        searcher.set_inbound_similarity(InboundSimilarity::build(&graph));
+        searcher.set_host_signal_cache(100);
+
+        let query = SearchQuery {
+            query: "test".to_string(),
+            host_rankings: Some(HostRankings {
+                liked: vec!["b.com".to_string()],
+                disliked: vec![],
+                blocked: vec![],
+            }),
+            ..Default::default()
+        };
+
+        let first = searcher.search(&query).unwrap();
+
+        let cache = searcher.host_signal_cache().unwrap();
+        let misses = cache.misses().get();
+        let hits = cache.hits().get();
+        assert!(misses > 0);
+
+        let second = searcher.search(&query).unwrap();
+
+        let cache = searcher.host_signal_cache().unwrap();
+        assert_eq!(cache.misses().get(), misses);
+        assert!(cache.hits().get() > hits);
+
+        assert_eq!(
+            first
+                .webpages
+                .iter()
+                .map(|webpage| (webpage.url.clone(), webpage.score))
+                .collect::<Vec<_>>(),
+            second
+                .webpages
+                .iter()
+                .map(|webpage| (webpage.url.clone(), webpage.score))
+                .collect::<Vec<_>>()
+        );
+
+        searcher.set_inbound_similarity(InboundSimilarity::build(&graph));
+        assert!(searcher.host_signal_cache().unwrap().is_empty());
+    }
+
+    #[test]
+    fn optic_rule_cache() {
+        let mut index = Index::temporary().expect("Unable to open index");
+
+        for host in ["a.com", "b.com"] {
+            index
+                .insert(&Webpage {
+                    html: Html::parse(
+                        r#"
+            <html>
+                <head>
+                    <title>Example website</title>
+                </head>
+                <body>
+                    test
+                </body>
+            </html>
+            "#,
+                        &format!("https://{host}"),
+                    )
+                    .unwrap(),
+                    ..Default::default()
+                })
+                .expect("failed to insert webpage");
+        }
+
+        index.commit().unwrap();
+
+        let mut searcher = LocalSearcher::new(index);
+        searcher.set_optic_rule_cache(10);
+
+        let query = SearchQuery {
+            query: "test".to_string(),
+            optic: Some(
+                optics::Optic::parse(
+                    r#"
+                    Rule {
+                        Matches {
+                            Site("b.com")
+                        },
+                        Action(Boost(10))
+                    };
+                "#,
+                )
+                .unwrap(),
+            ),
+            ..Default::default()
+        };
+
+        let first = searcher.search(&query).unwrap();
+        assert_eq!(&first.webpages[0].url, "https://b.com/");
+
+        let cache = searcher.optic_rule_cache().unwrap();
+        let misses = cache.misses().get();
+        let hits = cache.hits().get();
+        assert!(misses > 0);
+        assert_eq!(cache.len(), 1);
+
+        let second = searcher.search(&query).unwrap();
+
+        let cache = searcher.optic_rule_cache().unwrap();
+        assert_eq!(cache.misses().get(), misses);
+        assert!(cache.hits().get() > hits);
+        assert_eq!(cache.len(), 1);
+
+        assert_eq!(
+            first
+                .webpages
+                .iter()
+                .map(|webpage| (webpage.url.clone(), webpage.score))
+                .collect::<Vec<_>>(),
+            second
+                .webpages
+                .iter()
+                .map(|webpage| (webpage.url.clone(), webpage.score))
+                .collect::<Vec<_>>()
+        );
+
+        // queries without an optic don't use the cache
+        searcher
+            .search(&SearchQuery {
+                query: "test".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(searcher.optic_rule_cache().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn select_model() {
+        let mut index = Index::temporary().expect("Unable to open index");
+
+        for (host, host_centrality, fetch_time_ms) in [("a.com", 1.0, 5000), ("b.com", 0.0, 1)] {
+            index
+                .insert(&Webpage {
+                    html: Html::parse(
+                        r#"
+            <html>
+                <head>
+                    <title>Example website</title>
+                </head>
+                <body>
+                    test
+                </body>
+            </html>
+            "#,
+                        &format!("https://{host}"),
+                    )
+                    .unwrap(),
+                    host_centrality,
+                    fetch_time_ms,
+                    ..Default::default()
+                })
+                .expect("failed to insert webpage");
+        }
+
+        index.commit().unwrap();
+
+        let model = |signal: ranking::SignalEnum| {
+            let mut weights = crate::enum_map::EnumMap::new();
+            for signal in ranking::SignalEnum::all() {
+                weights.insert(signal, 0.0);
+            }
+            weights.insert(signal, 1.0);
+
+            LinearRegression { weights }
+        };
+
+        let mut registry = ModelRegistry::new();
+        registry.insert("centrality", model(ranking::signal::HostCentrality.into()));
+        registry.insert("speed", model(ranking::signal::FetchTimeMs.into()));
+
+        let mut searcher = LocalSearcher::new(index);
+        searcher.set_model_registry(registry);
+
+        let top = |model: ModelSelection| {
+            searcher
+                .search(&SearchQuery {
+                    query: "test".to_string(),
+                    model: Some(model),
+                    ..Default::default()
+                })
+                .unwrap()
+                .webpages[0]
+                .url
+                .clone()
+        };
+
+        assert_eq!(
+            top(ModelSelection::Name("centrality".to_string())),
+            "https://a.com/"
+        );
+        assert_eq!(
+            top(ModelSelection::Name("speed".to_string())),
+            "https://b.com/"
+        );
+
+        // buckets are spread over the models in the order of their names
+        assert_eq!(top(ModelSelection::Bucket(0)), "https://a.com/");
+        assert_eq!(top(ModelSelection::Bucket(1)), "https://b.com/");
+    }
+
+    #[test]
+    fn explore_seed() {
+        let mut index = Index::temporary().expect("Unable to open index");
+
+        for i in 0..10 {
+            index
+                .insert(&Webpage {
+                    html: Html::parse(
+                        r#"
+            <html>
+                <head>
+                    <title>Example website</title>
+                </head>
+                <body>
+                    test
+                </body>
+            </html>
+            "#,
+                        &format!("https://www.{i}.com"),
+                    )
+                    .unwrap(),
+                    host_centrality: 1.0,
+                    fetch_time_ms: 500,
+                    ..Default::default()
+                })
+                .expect("failed to insert webpage");
+        }
+
+        index.commit().unwrap();
+
+        let searcher = LocalSearcher::new(index);
+        let urls = |explore_seed: Option<u64>| -> Vec<_> {
+            searcher
+                .search(&SearchQuery {
+                    query: "test".to_string(),
+                    explore_seed,
+                    ..Default::default()
+                })
+                .unwrap()
+                .webpages
+                .into_iter()
+                .map(|page| page.url)
+                .collect()
+        };
+
+        let baseline = urls(None);
+        assert_eq!(baseline.len(), 10);
+
+        let first = urls(Some(1));
+        assert_eq!(first, urls(Some(1)));
+        assert_ne!(first, urls(Some(2)));
+        assert_ne!(first, baseline);
+
+        let mut sorted = first.clone();
+        sorted.sort();
+        let mut sorted_baseline = baseline.clone();
+        sorted_baseline.sort();
+        assert_eq!(sorted, sorted_baseline);
+    }
+
+    #[test]
+    fn seen_urls() {
+        let mut index = Index::temporary().expect("Unable to open index");
+
+        for url in ["https://www.a.com", "https://www.b.com"] {
+            index
+                .insert(&Webpage {
+                    html: Html::parse(
+                        r#"
+            <html>
+                <head>
+                    <title>Example website</title>
+                </head>
+                <body>
+                    test
+                </body>
+            </html>
+            "#,
+                        url,
+                    )
+                    .unwrap(),
+                    host_centrality: 1.0,
+                    fetch_time_ms: 500,
+                    ..Default::default()
+                })
+                .expect("failed to insert webpage");
+        }
+
+        index.commit().unwrap();
+
+        let searcher = LocalSearcher::new(index);
+        let search = |seen_urls: Vec<String>| {
+            searcher
+                .search(&SearchQuery {
+                    query: "test".to_string(),
+                    seen_urls,
+                    ..Default::default()
+                })
+                .unwrap()
+                .webpages
+        };
+
+        let unseen = search(Vec::new());
+        assert_eq!(unseen.len(), 2);
+        assert_eq!(unseen[0].score, unseen[1].score);
+
+        let first = unseen[0].url.clone();
+        let second = unseen[1].url.clone();
+
+        let res = search(vec![first.clone()]);
+        assert_eq!(res[0].url, second);
+        assert_eq!(res[1].url, first);
+        assert!(res[1].score < res[0].score);
+    }
+
+    #[test]
+    fn normalize_scores() {
+        let mut index = Index::temporary().expect("Unable to open index");
+
+        for (i, host_centrality) in [1.0, 0.5, 0.1].into_iter().enumerate() {
+            index
+                .insert(&Webpage {
+                    html: Html::parse(
+                        &format!(
+                            r#"
+            <html>
+                <head>
+                    <title>Example website</title>
+                </head>
+                <body>
+                    test {}
+                </body>
+            </html>
+            "#,
+                            crate::rand_words(100)
+                        ),
+                        &format!("https://www.{i}.com"),
+                    )
+                    .unwrap(),
+                    host_centrality,
+                    ..Default::default()
+                })
+                .expect("failed to insert webpage");
+        }
+
+        index.commit().unwrap();
+
+        let searcher = LocalSearcher::new(index);
+        let result = searcher
+            .search(&SearchQuery {
+                query: "test".to_string(),
+                normalize_scores: true,
+                ..Default::default()
+            })
+            .unwrap();
+
+        assert_eq!(result.webpages.len(), 3);
+
+        let scores: Vec<_> = result
+            .webpages
+            .iter()
+            .map(|webpage| webpage.score.unwrap())
+            .collect();
+        let normalized: Vec<_> = result
+            .webpages
+            .iter()
+            .map(|webpage| webpage.normalized_score.unwrap())
+            .collect();
+
+        assert!(scores.iter().all(|score| *score > 0.0));
+        assert_eq!(normalized[0], 1.0);
+        assert!(normalized.windows(2).all(|w| w[0] >= w[1]));
+
+        for (score, normalized) in scores.iter().zip(&normalized) {
+            assert!((normalized - score / scores[0]).abs() < 1e-9);
+        }
+
+        let result = searcher
+            .search(&SearchQuery {
+                query: "test".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+
+        assert!(result
+            .webpages
+            .iter()
+            .all(|webpage| webpage.normalized_score.is_none()));
+    }
 }
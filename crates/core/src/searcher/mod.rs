@@ -15,23 +15,45 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 pub mod api;
+pub mod correction;
 pub mod distributed;
+pub mod federated;
 pub mod live;
 pub mod local;
 
 pub use distributed::*;
 pub use local::*;
+use std::collections::HashMap;
+
+use itertools::Itertools;
 use optics::{HostRankings, Optic};
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
+use whatlang::Lang;
 
 use crate::{
-    bangs::BangHit, config::defaults, ranking::pipeline::RecallRankingWebpage,
-    search_prettifier::DisplayedWebpage, webpage::region::Region,
+    bangs::BangHit,
+    collector::SearchCursor,
+    config::defaults,
+    ranking::{
+        models::registry::ModelSelection, pipeline::RecallRankingWebpage, CoefficientProfile,
+        OpticRuleOutcome, SignalCoefficient, SignalEnum, SignalVector,
+    },
+    search_prettifier::DisplayedWebpage,
+    webpage::region::Region,
 };
 
 pub const NUM_RESULTS_PER_PAGE: usize = 20;
 
+/// The kind of device the search is made from.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Device {
+    #[default]
+    Desktop,
+    Mobile,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub enum SearchResult {
     Websites(WebsitesResult),
@@ -43,8 +65,20 @@ pub enum SearchResult {
 pub struct WebsitesResult {
     pub webpages: Vec<DisplayedWebpage>,
     pub num_hits: Option<usize>,
+    /// Number of documents removed by `Discard` rules in the optics.
+    /// Only counted when requested with `show_filtered`.
+    pub discarded_count: Option<usize>,
     pub search_duration_ms: u128,
     pub has_more_results: bool,
+    /// Whether the search timed out before all documents were scored.
+    /// The results are then the best among the documents scored before the timeout.
+    pub partial: bool,
+    /// Whether each rule of the optic was applied, in the order of the rules.
+    /// Only set when requested with `debug_optic`.
+    pub optic_rules: Option<Vec<OpticRuleOutcome>>,
+    /// Name of the signal at each position of the `features` of the webpages.
+    /// Only set when the features are requested.
+    pub features_layout: Option<Vec<String>>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -56,15 +90,86 @@ pub struct SearchQuery {
     pub optic: Option<Optic>,
     pub host_rankings: Option<HostRankings>,
     pub return_ranking_signals: bool,
+    pub return_features: bool,
     pub safe_search: bool,
     pub count_results: bool,
+    /// Only collect the documents ranked after the cursor. The document addresses of a
+    /// cursor are only meaningful within a single index, so only the local searcher
+    /// pages with cursors.
+    pub cursor: Option<SearchCursor>,
+    /// Stop scoring documents after this many milliseconds and return the
+    /// best results found so far.
+    pub timeout_ms: Option<u64>,
+    /// Signals that are not computed for this query.
+    pub disabled_signals: Vec<SignalEnum>,
+    /// Maximum number of results from the same host before the remaining
+    /// results of the host are moved down.
+    pub per_host_limit: Option<usize>,
+    /// Results with a final score below this are not returned.
+    pub min_score: Option<f64>,
+    /// Count the documents that matched the query but were removed by
+    /// a `Discard` rule in the optics.
+    pub show_filtered: bool,
+    /// Language of the query. Stemmed fields are searched with the stemmer of
+    /// this language instead of the language detected from the query.
+    pub lang: Option<Lang>,
+    /// The linear model used to rank the results if the search servers have
+    /// several models registered.
+    pub model: Option<ModelSelection>,
+    /// Attach the scores of the results rescaled to 0.0-1.0.
+    pub normalize_scores: bool,
+    /// Device the search is made from. Mobile friendly pages are only
+    /// boosted for searches from mobile devices.
+    pub device: Device,
+    /// Perturb the final scores by a small jitter that only depends on this
+    /// seed and the webpage, so alternative orderings can be explored reproducibly.
+    pub explore_seed: Option<u64>,
+    /// Preset of signal coefficients. Coefficients set in the optic take
+    /// precedence over the coefficients of the profile.
+    pub profile: Option<CoefficientProfile>,
+    /// Urls the user has already seen. Their final scores are multiplied by
+    /// [`CollectorConfig::seen_multiplier`](crate::config::CollectorConfig).
+    pub seen_urls: Vec<String>,
+    /// Report whether each rule of the optic was applied to the search.
+    pub debug_optic: bool,
+    /// Characters that start a bang in the query. The api sets these to its configured
+    /// prefixes, so the search servers parse the query the same way as the api.
+    pub bang_prefixes: Vec<char>,
+}
+
+/// The number of documents in the collection that contain a term.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TermStat {
+    pub term: String,
+    pub doc_freq: u64,
+}
+
+impl TermStat {
+    /// The `limit` terms found in the most documents. Ties are ordered by the term
+    /// so the result is the same on every shard.
+    pub fn top(doc_freqs: HashMap<String, u64>, limit: usize) -> Vec<TermStat> {
+        doc_freqs
+            .into_iter()
+            .map(|(term, doc_freq)| TermStat { term, doc_freq })
+            .sorted_by(|a, b| {
+                b.doc_freq
+                    .cmp(&a.doc_freq)
+                    .then_with(|| a.term.cmp(&b.term))
+            })
+            .take(limit)
+            .collect()
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct InitialWebsiteResult {
     pub num_websites: Option<usize>,
+    pub discarded_count: Option<usize>,
     pub websites: Vec<RecallRankingWebpage>,
     pub has_more: bool,
+    pub next_cursor: Option<SearchCursor>,
+    pub partial: bool,
 }
 
 impl Default for SearchQuery {
@@ -80,8 +185,24 @@ impl Default for SearchQuery {
             optic: Default::default(),
             host_rankings: Default::default(),
             return_ranking_signals: defaults::SearchQuery::return_ranking_signals(),
+            return_features: defaults::SearchQuery::return_features(),
             safe_search: defaults::SearchQuery::safe_search(),
             count_results: defaults::SearchQuery::count_results(),
+            cursor: Default::default(),
+            timeout_ms: Default::default(),
+            disabled_signals: Default::default(),
+            per_host_limit: Default::default(),
+            min_score: Default::default(),
+            show_filtered: defaults::SearchQuery::show_filtered(),
+            lang: Default::default(),
+            model: Default::default(),
+            normalize_scores: defaults::SearchQuery::normalize_scores(),
+            device: Default::default(),
+            explore_seed: Default::default(),
+            profile: Default::default(),
+            seen_urls: Default::default(),
+            debug_optic: defaults::SearchQuery::debug_optic(),
+            bang_prefixes: crate::bangs::BANG_PREFIXES.to_vec(),
         }
     }
 }
@@ -90,4 +211,70 @@ impl SearchQuery {
     pub fn is_empty(&self) -> bool {
         self.query.is_empty()
     }
+
+    /// The coefficients of the profile overridden by the `Ranking` directives
+    /// of the optic, or `None` if the query sets neither.
+    pub fn signal_coefficients(&self) -> Option<SignalCoefficient> {
+        if self.profile.is_none() && self.optic.is_none() {
+            return None;
+        }
+
+        let mut coefficients = self
+            .profile
+            .map(|profile| profile.coefficients())
+            .unwrap_or_default();
+
+        if let Some(optic) = &self.optic {
+            coefficients.override_with(SignalCoefficient::from_optic(optic));
+        }
+
+        Some(coefficients)
+    }
+
+    /// The layout of the signal vectors if requested with `return_features`.
+    fn features_layout(&self) -> Option<Vec<String>> {
+        self.return_features.then(SignalVector::layout_names)
+    }
+
+    /// The outcome of each rule of the optic if requested with `debug_optic`.
+    fn optic_rule_outcomes(&self) -> Option<Vec<OpticRuleOutcome>> {
+        if !self.debug_optic {
+            return None;
+        }
+
+        Some(
+            self.optic
+                .iter()
+                .flat_map(|optic| optic.rules.iter())
+                .map(OpticRuleOutcome::of)
+                .collect(),
+        )
+    }
+}
+
+/// Rescale the scores of the webpages to 0.0-1.0 so they can be displayed.
+/// The highest score becomes 1.0 and the differences between the scores keep
+/// their proportions, so the order of the webpages is not changed. Scores are
+/// scaled relative to 0.0 unless some are negative, in which case the lowest
+/// score becomes 0.0.
+pub fn normalize_scores(webpages: &mut [DisplayedWebpage]) {
+    let scores = || webpages.iter().filter_map(|webpage| webpage.score);
+
+    let Some(max) = scores().max_by(|a, b| a.total_cmp(b)) else {
+        return;
+    };
+    let min = scores()
+        .min_by(|a, b| a.total_cmp(b))
+        .unwrap_or(max)
+        .min(0.0);
+
+    for webpage in webpages.iter_mut() {
+        webpage.normalized_score = webpage.score.map(|score| {
+            if max > min {
+                (score - min) / (max - min)
+            } else {
+                1.0
+            }
+        });
+    }
 }
@@ -14,18 +14,21 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use std::{cmp::Reverse, collections::BinaryHeap, sync::Arc};
+use std::{cmp::Reverse, collections::BinaryHeap, sync::Arc, time::Duration};
 
 use fnv::{FnvHashMap, FnvHashSet};
 use hashbrown::HashSet;
 use url::Url;
 
 use crate::{
-    ranking::inbound_similarity::InboundSimilarity,
+    ranking::{host_resolver::HostResolver, inbound_similarity::InboundSimilarity},
     webgraph::{Node, NodeID, Webgraph},
     webpage::url_ext::UrlExt,
 };
 
+const HOST_RESOLVER_CAPACITY: usize = 100_000;
+const UNKNOWN_HOST_TTL: Duration = Duration::from_secs(10 * 60);
+
 #[derive(serde::Serialize, serde::Deserialize, Debug)]
 pub struct ScoredNode {
     pub node: Node,
@@ -60,6 +63,7 @@ impl Eq for ScoredNodeID {}
 pub struct SimilarHostsFinder {
     webgraph: Arc<Webgraph>,
     inbound_similarity: InboundSimilarity,
+    host_resolver: HostResolver,
     max_similar_hosts: usize,
 }
 
@@ -72,6 +76,7 @@ impl SimilarHostsFinder {
         Self {
             webgraph,
             inbound_similarity,
+            host_resolver: HostResolver::new(HOST_RESOLVER_CAPACITY, UNKNOWN_HOST_TTL),
             max_similar_hosts,
         }
     }
@@ -95,7 +100,10 @@ impl SimilarHostsFinder {
             })
             .collect::<HashSet<_>>();
 
-        let nodes = nodes.into_iter().map(|node| node.id()).collect::<Vec<_>>();
+        let nodes = nodes
+            .iter()
+            .filter_map(|node| self.resolve(node.as_str()))
+            .collect::<Vec<_>>();
 
         let mut scorer = self.inbound_similarity.scorer(&nodes, &[], true);
 
@@ -180,6 +188,81 @@ impl SimilarHostsFinder {
     }
 
     pub fn knows_about(&self, node: &Node) -> bool {
-        self.inbound_similarity.knows_about(node.id())
+        self.resolve(node.as_str()).is_some()
+    }
+
+    fn resolve(&self, host: &str) -> Option<NodeID> {
+        self.host_resolver
+            .resolve(host, |node| self.inbound_similarity.knows_about(*node))
+    }
+
+    /// The hosts most similar to `host` based on their inbound links,
+    /// or `None` if the host is not in the webgraph.
+    pub fn related_hosts(&self, host: &str, limit: usize) -> Option<Vec<ScoredNode>> {
+        let host = host
+            .trim_start_matches("http://")
+            .trim_start_matches("https://");
+        let node = Node::from(host.to_string()).into_host();
+
+        if !self.knows_about(&node) {
+            return None;
+        }
+
+        Some(self.find_similar_hosts(&[node.as_str().to_string()], limit))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::webgraph::WebgraphWriter;
+
+    use super::*;
+
+    fn finder() -> SimilarHostsFinder {
+        let mut wrt = WebgraphWriter::new(
+            crate::gen_temp_path(),
+            crate::executor::Executor::single_thread(),
+            crate::webgraph::Compression::default(),
+        );
+
+        for hub in ["hub1.com", "hub2.com"] {
+            for host in ["a.com", "b.com", "c.com"] {
+                wrt.insert(Node::from(hub), Node::from(host), String::new());
+            }
+        }
+        wrt.insert(Node::from("hub3.com"), Node::from("c.com"), String::new());
+        wrt.insert(Node::from("hub3.com"), Node::from("d.com"), String::new());
+
+        for host in ["a.com", "b.com", "c.com", "d.com"] {
+            wrt.insert(Node::from(host), Node::from("x.com"), String::new());
+        }
+
+        let graph = Arc::new(wrt.finalize());
+        let inbound_similarity = InboundSimilarity::build(&graph);
+
+        SimilarHostsFinder::new(graph, inbound_similarity, 100)
+    }
+
+    #[test]
+    fn related_hosts() {
+        let finder = finder();
+
+        let related = finder.related_hosts("https://a.com", 10).unwrap();
+        let hosts: Vec<_> = related.iter().map(|n| n.node.as_str()).collect();
+
+        assert_eq!(hosts, vec!["b.com", "c.com"]);
+        assert!(related[0].score > related[1].score);
+        assert!(related[1].score > 0.0);
+
+        let related = finder.related_hosts("a.com", 1).unwrap();
+        assert_eq!(related.len(), 1);
+        assert_eq!(related[0].node.as_str(), "b.com");
+    }
+
+    #[test]
+    fn unknown_host() {
+        let finder = finder();
+
+        assert!(finder.related_hosts("unknown.com", 10).is_none());
     }
 }
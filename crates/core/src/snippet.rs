@@ -18,7 +18,7 @@ use std::ops::Range;
 
 use crate::config::SnippetConfig;
 use crate::highlighted::{HighlightedFragment, HighlightedKind};
-use crate::query::Query;
+use crate::query::{optic::is_ranking_rule, Query};
 use crate::tokenizer::{BigramTokenizer, Normal, Stemmed, Tokenizer, TrigramTokenizer};
 use crate::web_spell::sentence_ranges;
 use crate::webpage::region::Region;
@@ -26,6 +26,7 @@ use hashbrown::{HashMap, HashSet};
 use utoipa::ToSchema;
 
 use itertools::Itertools;
+use optics::{Action, MatchLocation, PatternPart};
 use whatlang::Lang;
 
 /// For now we use an algorithm similar to the `UnifiedHighlighter` in lucene <https://lucene.apache.org/core/7_3_1/highlighter/org/apache/lucene/search/uhighlight/UnifiedHighlighter.html>.
@@ -40,10 +41,14 @@ use whatlang::Lang;
 const K1: f64 = 1.2;
 const B: f64 = 0.75;
 
+/// The score of a passage is multiplied by `1 + OPTIC_PHRASE_BOOST` for each boosted optic phrase it contains.
+const OPTIC_PHRASE_BOOST: f64 = 2.0;
+
 #[derive(Debug)]
 struct PassageCandidate {
     score: f64,
     text: String,
+    tokens: Vec<String>,
     doc_terms: HashMap<String, u64>,
 }
 
@@ -140,25 +145,34 @@ fn passages(text: &str, mut tokenizer: Tokenizer, config: &SnippetConfig) -> Vec
         .map(|offset| {
             let sentence = text[offset].to_string();
 
-            let mut doc_terms = HashMap::new();
+            let tokens = tokenize(&mut tokenizer, &sentence);
 
-            {
-                let mut stream =
-                    tantivy::tokenizer::Tokenizer::token_stream(&mut tokenizer, &sentence);
-                while let Some(tok) = stream.next() {
-                    *doc_terms.entry(tok.text.clone()).or_insert(0) += 1;
-                }
+            let mut doc_terms = HashMap::new();
+            for tok in &tokens {
+                *doc_terms.entry(tok.clone()).or_insert(0) += 1;
             }
 
             PassageCandidate {
                 score: 0.0,
                 text: sentence,
+                tokens,
                 doc_terms,
             }
         })
         .collect()
 }
 
+fn tokenize(tokenizer: &mut Tokenizer, text: &str) -> Vec<String> {
+    let mut stream = tantivy::tokenizer::Tokenizer::token_stream(tokenizer, text);
+
+    let mut res = Vec::new();
+    while let Some(tok) = stream.next() {
+        res.push(tok.text.clone());
+    }
+
+    res
+}
+
 fn calculate_idf(terms: &HashSet<String>, passages: &[PassageCandidate]) -> HashMap<String, f64> {
     let mut n: HashMap<_, _> = terms.iter().map(|term| (term.to_string(), 0)).collect();
 
@@ -201,25 +215,80 @@ fn score_passages_with_bm25(passages: &mut [PassageCandidate], terms: &HashSet<S
     }
 }
 
+/// Prefer passages that contain the phrases boosted by the optic, so the snippet
+/// shows the content that made the optic surface the result.
+fn boost_optic_phrases(passages: &mut [PassageCandidate], optic_phrases: &[Vec<String>]) {
+    for passage in passages.iter_mut() {
+        let num_matches = optic_phrases
+            .iter()
+            .filter(|phrase| {
+                !phrase.is_empty()
+                    && passage
+                        .tokens
+                        .windows(phrase.len())
+                        .any(|window| window == phrase.as_slice())
+            })
+            .count();
+
+        passage.score *= 1.0 + OPTIC_PHRASE_BOOST * num_matches as f64;
+    }
+}
+
+/// The phrases of the content and description matchers in the rules of the
+/// query optics that boost a result. These are the same ranking rules that
+/// are compiled for the signal computer.
+fn optic_phrases(query: &Query) -> Vec<String> {
+    query
+        .optics()
+        .iter()
+        .flat_map(|optic| optic.rules.iter())
+        .filter(|rule| is_ranking_rule(rule))
+        .filter(|rule| matches!(rule.action, Action::Boost(_) | Action::ScopedBoost(_, _)))
+        .flat_map(|rule| rule.matches.iter().flatten())
+        .filter(|matching| {
+            matches!(
+                matching.location,
+                MatchLocation::Content | MatchLocation::Description
+            )
+        })
+        .flat_map(|matching| {
+            // wildcards and anchors split the pattern into separate phrases
+            matching
+                .pattern
+                .split(|part| !matches!(part, PatternPart::Raw(_)))
+                .map(|parts| {
+                    parts
+                        .iter()
+                        .filter_map(|part| match part {
+                            PatternPart::Raw(text) => Some(text.as_str()),
+                            _ => None,
+                        })
+                        .join(" ")
+                        .trim()
+                        .to_string()
+                })
+                .filter(|phrase| !phrase.is_empty())
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
 fn snippet_string_builder(
     text: &str,
     terms: &[String],
+    optic_phrases: &[String],
     lang: whatlang::Lang,
     config: SnippetConfig,
     mut tokenizer: Tokenizer,
 ) -> SnippetBuilder {
     let terms: HashSet<String> = terms
         .iter()
-        .flat_map(|term| {
-            let mut stream = tantivy::tokenizer::Tokenizer::token_stream(&mut tokenizer, term);
-
-            let mut res = Vec::new();
-            while let Some(tok) = stream.next() {
-                res.push(tok.text.clone());
-            }
+        .flat_map(|term| tokenize(&mut tokenizer, term))
+        .collect();
 
-            res.into_iter()
-        })
+    let optic_phrases: Vec<_> = optic_phrases
+        .iter()
+        .map(|phrase| tokenize(&mut tokenizer, phrase))
         .collect();
 
     let mut passages = passages(text, tokenizer, &config);
@@ -236,6 +305,7 @@ fn snippet_string_builder(
     }
 
     score_passages_with_bm25(&mut passages, &terms);
+    boost_optic_phrases(&mut passages, &optic_phrases);
 
     let best_idx = passages
         .iter()
@@ -275,11 +345,13 @@ fn snippet_string_builder(
 fn snippet_string(
     text: &str,
     terms: &[String],
+    optic_phrases: &[String],
     lang: whatlang::Lang,
     config: SnippetConfig,
 ) -> TextSnippet {
     let tokenizer = Tokenizer::Normal(Normal::default());
-    let snip = snippet_string_builder(text, terms, lang, config.clone(), tokenizer).build();
+    let snip =
+        snippet_string_builder(text, terms, optic_phrases, lang, config.clone(), tokenizer).build();
 
     if !snip.fragments.is_empty()
         && snip
@@ -291,7 +363,7 @@ fn snippet_string(
     }
 
     let tokenizer = Tokenizer::Stemmed(Stemmed::with_forced_language(lang));
-    snippet_string_builder(text, terms, lang, config, tokenizer).build()
+    snippet_string_builder(text, terms, optic_phrases, lang, config, tokenizer).build()
 }
 
 pub fn generate(query: &Query, text: &str, region: &Region, config: SnippetConfig) -> TextSnippet {
@@ -318,17 +390,21 @@ pub fn generate(query: &Query, text: &str, region: &Region, config: SnippetConfi
         };
     }
 
+    let optic_phrases = optic_phrases(query);
+
     match config.max_considered_words {
         Some(num_words) => {
             let text = text.split_whitespace().take(num_words).join(" ");
-            snippet_string(&text, query.simple_terms(), lang, config)
+            snippet_string(&text, query.simple_terms(), &optic_phrases, lang, config)
         }
-        None => snippet_string(text, query.simple_terms(), lang, config),
+        None => snippet_string(text, query.simple_terms(), &optic_phrases, lang, config),
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use optics::Optic;
+
     use super::*;
     use crate::{
         index::Index,
@@ -494,6 +570,7 @@ Survey in 2016, 2017, and 2018."#;
                 text: snippet_string(
                     "this is a test",
                     &[],
+                    &[],
                     whatlang::Lang::Eng,
                     SnippetConfig::default()
                 )
@@ -511,6 +588,7 @@ Survey in 2016, 2017, and 2018."#;
                 text: snippet_string(
                     "",
                     &["test".to_string()],
+                    &[],
                     whatlang::Lang::Eng,
                     SnippetConfig::default()
                 )
@@ -522,18 +600,102 @@ Survey in 2016, 2017, and 2018."#;
         assert_eq!(
             highlight(Snippet {
                 date: None,
-                text: snippet_string("", &[], whatlang::Lang::Eng, SnippetConfig::default())
+                text: snippet_string("", &[], &[], whatlang::Lang::Eng, SnippetConfig::default())
             })
             .as_str(),
             ""
         );
     }
 
+    #[test]
+    fn optic_phrase_selects_passage() {
+        let text = "Rust is a nice language. Rust programs are written for memory safety. The Rust compiler is fast and reliable overall.";
+        let config = SnippetConfig {
+            desired_num_chars: 20,
+            delta_num_chars: 5,
+            ..Default::default()
+        };
+
+        let snip = snippet_string(
+            text,
+            &["rust".to_string()],
+            &[],
+            whatlang::Lang::Eng,
+            config.clone(),
+        );
+        assert!(snip.unhighlighted_string().starts_with("Rust is a nice"));
+
+        let snip = snippet_string(
+            text,
+            &["rust".to_string()],
+            &["memory safety".to_string()],
+            whatlang::Lang::Eng,
+            config,
+        );
+        assert!(snip.unhighlighted_string().starts_with("Rust programs"));
+    }
+
+    #[test]
+    fn optic_phrases_from_rules() {
+        let optic = Optic::parse(
+            r#"
+            Rule {
+                Matches {
+                    Content("memory safety")
+                },
+                Action(Boost(2))
+            };
+            Rule {
+                Matches {
+                    Content("garbage * collector")
+                },
+                Action(Boost(2))
+            };
+            Rule {
+                Matches {
+                    Content("unsafe")
+                },
+                Action(Downrank(2))
+            };
+            Rule {
+                Matches {
+                    Site("example.com")
+                },
+                Action(Boost(2))
+            };
+        "#,
+        )
+        .unwrap();
+
+        let index = Index::temporary().expect("Unable to open index");
+        let ctx = index.inverted_index.local_search_ctx();
+        let query = Query::parse(
+            &ctx,
+            &SearchQuery {
+                query: "rust".to_string(),
+                optic: Some(optic),
+                ..Default::default()
+            },
+            &index.inverted_index,
+        )
+        .unwrap();
+
+        assert_eq!(
+            optic_phrases(&query),
+            vec![
+                "memory safety".to_string(),
+                "garbage".to_string(),
+                "collector".to_string()
+            ]
+        );
+    }
+
     #[test]
     fn compounded_terms() {
         let snip = snippet_string_builder(
             "this is a test",
             &["thisis".to_string()],
+            &[],
             whatlang::Lang::Eng,
             SnippetConfig::default(),
             Tokenizer::Normal(Normal::default()),
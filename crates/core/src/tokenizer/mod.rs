@@ -14,7 +14,11 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use std::{array, collections::VecDeque, str::CharIndices};
+use std::{
+    array,
+    collections::{HashMap, VecDeque},
+    str::CharIndices,
+};
 
 use logos::{Lexer, Logos};
 use tantivy::tokenizer::{
@@ -23,7 +27,7 @@ use tantivy::tokenizer::{
 
 use whatlang::Lang;
 
-use crate::{ceil_char_boundary, floor_char_boundary};
+use crate::{ceil_char_boundary, floor_char_boundary, schema::TextFieldEnum};
 
 use self::{add_space_last::AddSpaceLast, split_preserve::StrSplitPreserve};
 
@@ -62,6 +66,7 @@ pub enum Tokenizer {
     Stemmed(Stemmed),
     Bigram(BigramTokenizer),
     Trigram(TrigramTokenizer),
+    CjkBigram(CjkBigramTokenizer),
     Json(JsonField),
     SiteOperator(SiteOperatorUrlTokenizer),
 }
@@ -71,6 +76,34 @@ impl Tokenizer {
         Self::Stemmed(Stemmed::default())
     }
 
+    /// Default configured tokenizer with the given name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let tokenizer = match name {
+            _ if name == Normal::as_str() => Tokenizer::Normal(Normal::default()),
+            _ if name == Stemmed::as_str() => Tokenizer::new_stemmed(),
+            _ if name == Identity::as_str() => Tokenizer::Identity(Identity::default()),
+            _ if name == BigramTokenizer::as_str() => Tokenizer::Bigram(Default::default()),
+            _ if name == TrigramTokenizer::as_str() => Tokenizer::Trigram(Default::default()),
+            _ if name == CjkBigramTokenizer::as_str() => Tokenizer::CjkBigram(Default::default()),
+            _ if name == JsonField::as_str() => Tokenizer::Json(JsonField),
+            _ if name == SiteOperatorUrlTokenizer::as_str() => {
+                Tokenizer::SiteOperator(SiteOperatorUrlTokenizer)
+            }
+            _ => return None,
+        };
+
+        Some(tokenizer)
+    }
+
+    /// Stem with the stemmer of `lang` instead of the stemmer of the language
+    /// detected from the text. Tokenizers that don't stem are returned unchanged.
+    pub fn with_stemming_lang(self, lang: Lang) -> Self {
+        match self {
+            Tokenizer::Stemmed(_) => Tokenizer::Stemmed(Stemmed::with_forced_language(lang)),
+            tokenizer => tokenizer,
+        }
+    }
+
     pub fn as_str(&self) -> &'static str {
         match self {
             Tokenizer::Normal(_) => Normal::as_str(),
@@ -78,12 +111,36 @@ impl Tokenizer {
             Tokenizer::Identity(_) => Identity::as_str(),
             Tokenizer::Bigram(_) => BigramTokenizer::as_str(),
             Tokenizer::Trigram(_) => TrigramTokenizer::as_str(),
+            Tokenizer::CjkBigram(_) => CjkBigramTokenizer::as_str(),
             Tokenizer::Json(_) => JsonField::as_str(),
             Tokenizer::SiteOperator(_) => SiteOperatorUrlTokenizer::as_str(),
         }
     }
 }
 
+/// Tokenizers to use for specific text fields instead of their default
+/// [`crate::schema::text_field::TextField::indexing_tokenizer`], e.g. for
+/// deployments indexing CJK content.
+///
+/// The registry is only needed when the index is created. The name of the tokenizer
+/// is stored in the index schema, from where it is resolved both when documents are
+/// indexed and when query terms are generated, so the two always stay in sync.
+#[derive(Clone, Default)]
+pub struct TokenizerRegistry {
+    fields: HashMap<TextFieldEnum, Tokenizer>,
+}
+
+impl TokenizerRegistry {
+    pub fn with_tokenizer(mut self, field: impl Into<TextFieldEnum>, tokenizer: Tokenizer) -> Self {
+        self.fields.insert(field.into(), tokenizer);
+        self
+    }
+
+    pub fn get(&self, field: TextFieldEnum) -> Option<&Tokenizer> {
+        self.fields.get(&field)
+    }
+}
+
 impl Default for Tokenizer {
     fn default() -> Self {
         Self::Normal(Normal::default())
@@ -147,6 +204,20 @@ impl TrigramTokenizer {
     }
 }
 
+/// Splits runs of CJK characters into overlapping character bigrams,
+/// as CJK text is usually not separated by whitespace. Other words are
+/// tokenized as with the [`Normal`] tokenizer.
+#[derive(Clone, Default)]
+pub struct CjkBigramTokenizer {
+    inner_tokenizer: Normal,
+}
+
+impl CjkBigramTokenizer {
+    pub fn as_str() -> &'static str {
+        "cjk_bigram_tokenizer"
+    }
+}
+
 #[derive(Clone, Default)]
 pub struct Stemmed {
     force_language: Option<Lang>,
@@ -185,6 +256,7 @@ impl tantivy::tokenizer::Tokenizer for Tokenizer {
             Tokenizer::Json(tokenizer) => tokenizer.token_stream(text),
             Tokenizer::Bigram(tokenizer) => tokenizer.token_stream(text),
             Tokenizer::Trigram(tokenizer) => tokenizer.token_stream(text),
+            Tokenizer::CjkBigram(tokenizer) => tokenizer.token_stream(text),
             Tokenizer::SiteOperator(tokenizer) => tokenizer.token_stream(text),
         }
     }
@@ -258,6 +330,95 @@ impl tantivy::tokenizer::Tokenizer for TrigramTokenizer {
     }
 }
 
+impl tantivy::tokenizer::Tokenizer for CjkBigramTokenizer {
+    type TokenStream<'a> = BoxTokenStream<'a>;
+
+    fn token_stream<'a>(&'a mut self, text: &'a str) -> Self::TokenStream<'a> {
+        let mut tokens = Vec::new();
+        let mut inner = self.inner_tokenizer.token_stream(text);
+
+        while let Some(token) = inner.next() {
+            split_cjk_bigrams(token, &mut tokens);
+        }
+
+        for (position, token) in tokens.iter_mut().enumerate() {
+            token.position = position;
+        }
+
+        BoxTokenStream::new(VecTokenStream::from(tokens))
+    }
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{3040}'..='\u{30FF}' // hiragana and katakana
+        | '\u{3400}'..='\u{4DBF}' // han extension a
+        | '\u{4E00}'..='\u{9FFF}' // han
+        | '\u{AC00}'..='\u{D7AF}' // hangul
+        | '\u{F900}'..='\u{FAFF}' // han compatibility
+    )
+}
+
+fn split_cjk_bigrams(token: &tantivy::tokenizer::Token, res: &mut Vec<tantivy::tokenizer::Token>) {
+    let chars: Vec<_> = token.text.char_indices().collect();
+    let mut start = 0;
+
+    while start < chars.len() {
+        let cjk = is_cjk(chars[start].1);
+        let end = chars[start..]
+            .iter()
+            .position(|(_, c)| is_cjk(*c) != cjk)
+            .map_or(chars.len(), |len| start + len);
+
+        let byte_offset = |i: usize| chars.get(i).map_or(token.text.len(), |(offset, _)| *offset);
+
+        let mut push = |from: usize, to: usize| {
+            res.push(tantivy::tokenizer::Token {
+                offset_from: token.offset_from + byte_offset(from),
+                offset_to: token.offset_from + byte_offset(to),
+                text: token.text[byte_offset(from)..byte_offset(to)].to_string(),
+                ..Default::default()
+            })
+        };
+
+        if cjk && end - start > 1 {
+            for i in start..end - 1 {
+                push(i, i + 2);
+            }
+        } else {
+            push(start, end);
+        }
+
+        start = end;
+    }
+}
+
+struct VecTokenStream {
+    tokens: Vec<tantivy::tokenizer::Token>,
+    next: usize,
+}
+
+impl From<Vec<tantivy::tokenizer::Token>> for VecTokenStream {
+    fn from(tokens: Vec<tantivy::tokenizer::Token>) -> Self {
+        Self { tokens, next: 0 }
+    }
+}
+
+impl tantivy::tokenizer::TokenStream for VecTokenStream {
+    fn advance(&mut self) -> bool {
+        self.next += 1;
+        self.next <= self.tokens.len()
+    }
+
+    fn token(&self) -> &tantivy::tokenizer::Token {
+        &self.tokens[self.next - 1]
+    }
+
+    fn token_mut(&mut self) -> &mut tantivy::tokenizer::Token {
+        &mut self.tokens[self.next - 1]
+    }
+}
+
 pub struct IdentityTokenStream {
     num_advances: usize,
     token: Option<tantivy::tokenizer::Token>,
@@ -792,6 +953,18 @@ mod tests {
         res
     }
 
+    fn tokenize_cjk_bigram(s: &str) -> Vec<String> {
+        let mut res = Vec::new();
+        let mut tokenizer = Tokenizer::CjkBigram(CjkBigramTokenizer::default());
+        let mut stream = tokenizer.token_stream(s);
+
+        while let Some(token) = stream.next() {
+            res.push(token.text.clone());
+        }
+
+        res
+    }
+
     fn tokenize_url(s: &str) -> Vec<String> {
         let mut res = Vec::new();
         let mut tokenizer = SiteOperatorUrlTokenizer;
@@ -1075,4 +1248,18 @@ key1.key2="this\" is @ a # test""#;
         assert_eq!(tokenize_identity("this is a test"), vec!["this is a test"]);
         assert_eq!(tokenize_identity("a-b"), vec!["a-b"]);
     }
+
+    #[test]
+    fn cjk_bigram() {
+        assert_eq!(
+            tokenize_cjk_bigram("東京都庁"),
+            vec!["東京", "京都", "都庁"]
+        );
+        assert_eq!(tokenize_cjk_bigram("東"), vec!["東"]);
+        assert_eq!(
+            tokenize_cjk_bigram("hello 東京 world"),
+            vec!["hello", "東京", "world"]
+        );
+        assert_eq!(tokenize_cjk_bigram("abc東京"), vec!["abc", "東京"]);
+    }
 }
@@ -117,6 +117,18 @@ impl Html {
                     .unwrap_or_default() as f64)
     }
 
+    /// Number of outgoing links per word of the clean text. Pages that are mostly links
+    /// with little prose, like link farms, have many links per word.
+    pub fn links_per_word(&self) -> f64 {
+        let num_words = self
+            .clean_text
+            .as_ref()
+            .map(|text| text.split_whitespace().count())
+            .unwrap_or_default();
+
+        self.anchor_links().len() as f64 / (1.0 + num_words as f64)
+    }
+
     pub fn anchor_links(&self) -> Vec<Link> {
         if self.is_no_follow() {
             return Vec::new();
@@ -148,6 +148,36 @@ impl Html {
         }
     }
 
+    /// The text of the `h1` and `h2` headings of the page in document order.
+    pub fn headings(&self) -> Vec<String> {
+        self.root
+            .select("h1, h2")
+            .unwrap()
+            .map(|heading| heading.text_contents().trim().to_string())
+            .filter(|heading| !heading.is_empty())
+            .collect()
+    }
+
+    /// Average number of words per sentence of the clean text, where sentences end
+    /// with `.`, `!` or `?`. Returns `None` for pages without text.
+    pub fn words_per_sentence(&self) -> Option<f64> {
+        let text = self.clean_text.as_ref()?;
+
+        let (num_sentences, num_words) = text
+            .split(['.', '!', '?'])
+            .map(|sentence| sentence.split_whitespace().count())
+            .filter(|num_words| *num_words > 0)
+            .fold((0, 0), |(sentences, words), num_words| {
+                (sentences + 1, words + num_words)
+            });
+
+        if num_sentences == 0 {
+            return None;
+        }
+
+        Some(num_words as f64 / num_sentences as f64)
+    }
+
     pub fn url(&self) -> &Url {
         &self.url
     }
@@ -292,6 +322,30 @@ impl Html {
         false
     }
 
+    /// Whether the page adapts to the screen of mobile devices. This is the case
+    /// for pages that set their viewport to the width of the device, and for AMP pages.
+    pub fn is_mobile_friendly(&self) -> bool {
+        let viewport_follows_device = self.metadata().into_iter().any(|meta| {
+            meta.get("name")
+                .is_some_and(|name| name.eq_ignore_ascii_case("viewport"))
+                && meta.get("content").is_some_and(|content| {
+                    content
+                        .to_ascii_lowercase()
+                        .replace(' ', "")
+                        .contains("width=device-width")
+                })
+        });
+
+        if viewport_follows_device {
+            return true;
+        }
+
+        self.root.select_first("html").is_ok_and(|html| {
+            let attributes = html.attributes.borrow();
+            attributes.contains("amp") || attributes.contains("⚡")
+        })
+    }
+
     pub fn likely_has_paywall(&self) -> bool {
         for item in self.schema_org().into_iter().filter(|item| {
             item.types_contains("NewsArticle")
@@ -546,6 +600,66 @@ mod tests {
         assert_eq!(webpage.title(), None);
     }
 
+    #[test]
+    fn headings() {
+        let raw = format!(
+            r#"
+            <html>
+                <head>
+                    <title>Title</title>
+                </head>
+                <body>
+                    <h1>Main heading</h1>
+                    <p>{CONTENT}</p>
+                    <h2> Section </h2>
+                    <h2></h2>
+                    <h3>Subsection</h3>
+                </body>
+            </html>
+        "#
+        );
+
+        let webpage = Html::parse(&raw, "https://www.example.com/whatever").unwrap();
+
+        assert_eq!(
+            webpage.headings(),
+            vec!["Main heading".to_string(), "Section".to_string()]
+        );
+    }
+
+    #[test]
+    fn words_per_sentence() {
+        let page = |body: &str| {
+            Html::parse(
+                &format!(
+                    r#"
+                    <html>
+                        <head>
+                            <title>Title</title>
+                        </head>
+                        <body>
+                            <p>{body}</p>
+                        </body>
+                    </html>
+                "#
+                ),
+                "https://www.example.com/",
+            )
+            .unwrap()
+        };
+
+        assert_eq!(
+            page(&"This is a short sentence. ".repeat(50)).words_per_sentence(),
+            Some(5.0)
+        );
+        assert_eq!(
+            page(&"This is a longer sentence with a few more words in it! ".repeat(50))
+                .words_per_sentence(),
+            Some(11.0)
+        );
+        assert_eq!(page("").words_per_sentence(), None);
+    }
+
     #[test]
     fn text_raw_body() {
         let raw = format!(
@@ -1117,4 +1231,36 @@ mod tests {
 
         assert!(html.likely_has_paywall());
     }
+
+    #[test]
+    fn mobile_friendly() {
+        let html = |head: &str, html_attrs: &str| {
+            Html::parse(
+                &format!(r#"<html {html_attrs}><head>{head}</head><body>test</body></html>"#),
+                "https://www.example.com/",
+            )
+            .unwrap()
+        };
+
+        assert!(html(
+            r#"<meta name="viewport" content="width=device-width, initial-scale=1">"#,
+            ""
+        )
+        .is_mobile_friendly());
+        assert!(html(
+            r#"<meta name="Viewport" content="initial-scale=1, width = device-width">"#,
+            ""
+        )
+        .is_mobile_friendly());
+        assert!(html("", "amp").is_mobile_friendly());
+        assert!(html("", "⚡").is_mobile_friendly());
+
+        assert!(!html(r#"<meta name="viewport" content="width=1024">"#, "").is_mobile_friendly());
+        assert!(!html(
+            r#"<meta name="description" content="width=device-width">"#,
+            ""
+        )
+        .is_mobile_friendly());
+        assert!(!html("", r#"lang="en""#).is_mobile_friendly());
+    }
 }
@@ -16,6 +16,7 @@
 
 use crate::{
     schema::{fast_field::FastField, text_field::TextField, Field},
+    tokenizer::Tokenizer,
     webgraph::NodeID,
     Result,
 };
@@ -23,7 +24,11 @@ use candle_core::Tensor;
 use chrono::{DateTime, Utc};
 
 use std::collections::HashMap;
-use tantivy::TantivyDocument;
+use tantivy::{
+    schema::OwnedValue,
+    tokenizer::{PreTokenizedString, Tokenizer as _},
+    TantivyDocument,
+};
 use url::Url;
 
 mod adservers;
@@ -46,6 +51,9 @@ pub struct Webpage {
     pub page_centrality: f64,
     pub page_centrality_rank: u64,
     pub fetch_time_ms: u64,
+    pub num_inbound_links: u64,
+    /// Size of the html in bytes, if known.
+    pub byte_size: Option<u64>,
     pub pre_computed_score: f64,
     pub node_id: Option<NodeID>,
     pub dmoz_description: Option<String>,
@@ -67,6 +75,8 @@ impl Default for Webpage {
             page_centrality: Default::default(),
             page_centrality_rank: u64::MAX,
             fetch_time_ms: Default::default(),
+            num_inbound_links: Default::default(),
+            byte_size: Default::default(),
             pre_computed_score: Default::default(),
             node_id: Default::default(),
             dmoz_description: Default::default(),
@@ -89,6 +99,8 @@ impl From<Html> for Webpage {
             page_centrality: Default::default(),
             page_centrality_rank: u64::MAX,
             fetch_time_ms: Default::default(),
+            num_inbound_links: Default::default(),
+            byte_size: Default::default(),
             pre_computed_score: Default::default(),
             node_id: Default::default(),
             dmoz_description: Default::default(),
@@ -138,8 +150,58 @@ impl Webpage {
             }
         }
 
-        Ok(doc)
+        Ok(retokenize_registered_fields(doc, schema))
+    }
+}
+
+/// Text fields are pretokenized with their default tokenizer. Fields that had another
+/// tokenizer registered when the index was created must be tokenized with that instead.
+fn retokenize_registered_fields(
+    doc: TantivyDocument,
+    schema: &tantivy::schema::Schema,
+) -> TantivyDocument {
+    let registered: HashMap<tantivy::schema::Field, Tokenizer> = Field::all()
+        .filter_map(|field| field.as_text())
+        .filter_map(|field| {
+            let tokenizer = field.registered_tokenizer(schema)?;
+            Some((schema.get_field(field.name()).ok()?, tokenizer))
+        })
+        .collect();
+
+    if registered.is_empty() {
+        return doc;
+    }
+
+    let mut res = TantivyDocument::new();
+
+    for field_value in doc.field_values() {
+        let field = field_value.field;
+
+        match (registered.get(&field), field_value.value()) {
+            (Some(tokenizer), OwnedValue::PreTokStr(pretokenized)) => {
+                let mut tokenizer = tokenizer.clone();
+                let mut tokens = Vec::new();
+
+                {
+                    let mut stream = tokenizer.token_stream(&pretokenized.text);
+                    while let Some(token) = stream.next() {
+                        tokens.push(token.clone());
+                    }
+                }
+
+                res.add_pre_tokenized_text(
+                    field,
+                    PreTokenizedString {
+                        text: pretokenized.text.clone(),
+                        tokens,
+                    },
+                );
+            }
+            (_, value) => res.add_field_value(field, value.clone()),
+        }
     }
+
+    res
 }
 
 struct Script {
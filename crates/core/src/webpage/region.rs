@@ -202,6 +202,17 @@ impl RegionCount {
         self.commit()
     }
 
+    /// The region with the most pages. Ties are broken by the region id.
+    pub fn top_region(&self) -> Option<Region> {
+        self.map
+            .iter()
+            .filter(|(region, count)| **region != Region::All && **count > 0)
+            .max_by(|(a, a_count), (b, b_count)| {
+                a_count.cmp(b_count).then_with(|| b.id().cmp(&a.id()))
+            })
+            .map(|(region, _)| *region)
+    }
+
     pub fn score(&self, region: &Region) -> f64 {
         self.fast_count
             .get(region.id() as usize)
@@ -241,4 +252,25 @@ mod tests {
         assert_eq!(a.score(&Region::Denmark), 0.4);
         assert_eq!(a.score(&Region::France), 0.0);
     }
+
+    #[test]
+    fn top_region() {
+        let mut count = RegionCount::open(gen_temp_path().join("region_count.json"));
+        assert_eq!(count.top_region(), None);
+
+        count.increment(&Region::Germany);
+        count.increment(&Region::Denmark);
+        count.increment(&Region::Denmark);
+        assert_eq!(count.top_region(), Some(Region::Denmark));
+
+        count.increment(&Region::Germany);
+        assert_eq!(
+            count.top_region(),
+            Some(if Region::Denmark.id() < Region::Germany.id() {
+                Region::Denmark
+            } else {
+                Region::Germany
+            })
+        );
+    }
 }
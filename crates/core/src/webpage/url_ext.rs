@@ -28,6 +28,14 @@ static ICANN_LIST: once_cell::sync::Lazy<publicsuffix::List> = once_cell::sync::
         .expect("Failed to parse public icann suffix list")
 });
 
+/// The registrable domain of `host` according to the public suffix list,
+/// e.g. `example.co.uk` for `docs.example.co.uk`.
+pub fn root_domain(host: &str) -> Option<&str> {
+    let suffix =
+        std::str::from_utf8(PUBLIC_SUFFIX_LIST.domain(host.as_bytes())?.as_bytes()).ok()?;
+    Some(suffix)
+}
+
 pub trait UrlExt {
     fn icann_domain(&self) -> Option<&str>;
     fn root_domain(&self) -> Option<&str>;
@@ -46,10 +54,7 @@ impl UrlExt for url::Url {
     }
 
     fn root_domain(&self) -> Option<&str> {
-        let host = self.host_str()?;
-        let suffix =
-            std::str::from_utf8(PUBLIC_SUFFIX_LIST.domain(host.as_bytes())?.as_bytes()).ok()?;
-        Some(suffix)
+        root_domain(self.host_str()?)
     }
 
     fn normalized_host(&self) -> Option<&str> {
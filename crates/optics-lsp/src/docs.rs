@@ -64,6 +64,10 @@ pub fn token_docs(token: &optics::Token) -> Option<&'static str> {
         As an example, `Schema(\"BlogPosting\")` matches all pages that contains the https://schema.org/BlogPosting entity. Note that `Schema` \
         does not support the pattern syntax, but only simple strings.",
 
+        optics::Token::InboundCount => "`InboundCount > ...` or `InboundCount < ...` matches any search result where the number of links pointing to the page \
+        is strictly above or below the number specified in `...`. As an example, `Rule { Matches { InboundCount < 10 }, Action(Downrank(2)) }` \
+        downranks all pages with fewer than 10 inbound links.",
+
         optics::Token::Ranking => "When results are ranked we take a weighted sum of various signals to give each webpage a score for the specific query. \
         The top scored results are then presented to the user. `Ranking` allows you to alter the weight of all the `Signal`s and text `Field`s.",
 
@@ -77,7 +81,9 @@ pub fn token_docs(token: &optics::Token) -> Option<&'static str> {
 
         optics::Token::Action => "`Action` defines which action should be applied to the matching search result. The result can either be boosted, downranked or discarded.",
 
-        optics::Token::Boost => "`Boost(...)` boosts the search result by the number specified in `...`.",
+        optics::Token::Boost => "`Boost(...)` boosts the search result by the number specified in `...`. \
+        The boost can be limited to a single signal or text field with e.g. `Boost(3, Signal(\"host_centrality\"))` or `Boost(3, Field(\"title\"))`, \
+        in which case only that part of the score is boosted.",
 
         optics::Token::Downrank => "`Downrank(...)` downranks the search result by the number specified in `...`. A higher number further downranks the search result.",
 
@@ -33,6 +33,40 @@ pub enum RankingTarget {
     Signal(String),
 }
 
+/// The part of the document score a scoped boost applies to.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub enum BoostTarget {
+    /// The contribution of a single ranking signal.
+    Signal(String),
+    /// The contributions of all the ranking signals computed on a text field.
+    Field(String),
+}
+
+impl Display for BoostTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BoostTarget::Signal(signal) => write!(f, "Signal(\"{signal}\")"),
+            BoostTarget::Field(field) => write!(f, "Field(\"{field}\")"),
+        }
+    }
+}
+
+/// How a numeric property of the document is compared to a threshold.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum Comparison {
+    Greater,
+    Less,
+}
+
+impl Display for Comparison {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Comparison::Greater => write!(f, ">"),
+            Comparison::Less => write!(f, "<"),
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct RankingCoeff {
     pub target: RankingTarget,
@@ -119,11 +153,13 @@ pub enum RawMatchPart {
     Content(String),
     MicroformatTag(String),
     Schema(String),
+    InboundCount(Comparison, u64),
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum RawAction {
     Boost(u64),
+    ScopedBoost(u64, BoostTarget),
     Downrank(u64),
     Discard,
 }
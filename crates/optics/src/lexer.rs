@@ -28,6 +28,8 @@ pub enum Token<'a> {
     CloseBracket,
     OpenParenthesis,
     CloseParenthesis,
+    Greater,
+    Less,
 
     DiscardNonMatching,
     Rule,
@@ -45,6 +47,7 @@ pub enum Token<'a> {
     Content,
     MicroformatTag,
     Schema,
+    InboundCount,
     Action,
     Boost,
     Downrank,
@@ -65,6 +68,8 @@ impl<'a> Display for Token<'a> {
             Token::CloseBracket => f.write_str("}"),
             Token::OpenParenthesis => f.write_str("("),
             Token::CloseParenthesis => f.write_str(")"),
+            Token::Greater => f.write_str(">"),
+            Token::Less => f.write_str("<"),
             Token::DiscardNonMatching => f.write_str("DiscardNonMatching"),
             Token::Rule => f.write_str("Rule"),
             Token::RankingPipeline => f.write_str("RankingPipeline"),
@@ -81,6 +86,7 @@ impl<'a> Display for Token<'a> {
             Token::Content => f.write_str("Content"),
             Token::MicroformatTag => f.write_str("MicroformatTag"),
             Token::Schema => f.write_str("Schema"),
+            Token::InboundCount => f.write_str("InboundCount"),
             Token::Action => f.write_str("Action"),
             Token::Boost => f.write_str("Boost"),
             Token::Downrank => f.write_str("Downrank"),
@@ -117,6 +123,10 @@ enum Outer<'a> {
     OpenParenthesis,
     #[token(")")]
     CloseParenthesis,
+    #[token(">")]
+    Greater,
+    #[token("<")]
+    Less,
 
     #[token("DiscardNonMatching")]
     DiscardNonMatching,
@@ -150,6 +160,8 @@ enum Outer<'a> {
     MicroformatTag,
     #[token("Schema")]
     Schema,
+    #[token("InboundCount")]
+    InboundCount,
     #[token("Action")]
     Action,
     #[token("Boost")]
@@ -303,6 +315,8 @@ impl<'source> Iterator for LexerBridge<'source> {
                 Outer::CloseBracket => Some(Ok((s.start, Token::CloseBracket, s.end))),
                 Outer::OpenParenthesis => Some(Ok((s.start, Token::OpenParenthesis, s.end))),
                 Outer::CloseParenthesis => Some(Ok((s.start, Token::CloseParenthesis, s.end))),
+                Outer::Greater => Some(Ok((s.start, Token::Greater, s.end))),
+                Outer::Less => Some(Ok((s.start, Token::Less, s.end))),
                 Outer::Rule => Some(Ok((s.start, Token::Rule, s.end))),
                 Outer::Ranking => Some(Ok((s.start, Token::Ranking, s.end))),
                 Outer::Stage => Some(Ok((s.start, Token::Stage, s.end))),
@@ -318,6 +332,7 @@ impl<'source> Iterator for LexerBridge<'source> {
                 Outer::Content => Some(Ok((s.start, Token::Content, s.end))),
                 Outer::MicroformatTag => Some(Ok((s.start, Token::MicroformatTag, s.end))),
                 Outer::Schema => Some(Ok((s.start, Token::Schema, s.end))),
+                Outer::InboundCount => Some(Ok((s.start, Token::InboundCount, s.end))),
                 Outer::Action => Some(Ok((s.start, Token::Action, s.end))),
                 Outer::Boost => Some(Ok((s.start, Token::Boost, s.end))),
                 Outer::Downrank => Some(Ok((s.start, Token::Downrank, s.end))),
@@ -17,7 +17,7 @@
 pub mod ast;
 mod lexer;
 
-use ast::RankingCoeff;
+use ast::{BoostTarget, Comparison, RankingCoeff};
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 use std::fmt::Display;
@@ -139,6 +139,7 @@ impl From<RawAction> for Action {
     fn from(value: RawAction) -> Self {
         match value {
             RawAction::Boost(boost) => Action::Boost(boost),
+            RawAction::ScopedBoost(boost, target) => Action::ScopedBoost(boost, target),
             RawAction::Downrank(down_boost) => Action::Downrank(down_boost),
             RawAction::Discard => Action::Discard,
         }
@@ -154,6 +155,9 @@ pub struct Matching {
 impl Display for Matching {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let s = match self.location {
+            MatchLocation::InboundCount(comparison, count) => {
+                return write!(f, "InboundCount {comparison} {count}")
+            }
             MatchLocation::Site => "Site",
             MatchLocation::Url => "Url",
             MatchLocation::Domain => "Domain",
@@ -186,6 +190,12 @@ impl TryFrom<RawMatchPart> for Matching {
             RawMatchPart::Content(s) => (s, MatchLocation::Content),
             RawMatchPart::MicroformatTag(s) => (s, MatchLocation::MicroformatTag),
             RawMatchPart::Schema(s) => (s, MatchLocation::Schema),
+            RawMatchPart::InboundCount(comparison, count) => {
+                return Ok(Self {
+                    location: MatchLocation::InboundCount(comparison, count),
+                    pattern: Vec::new(),
+                })
+            }
         };
 
         let mut pattern = Vec::new();
@@ -295,11 +305,17 @@ pub enum MatchLocation {
     Content,
     MicroformatTag,
     Schema,
+    /// Compares the number of links pointing to the page against a threshold.
+    /// The pattern of such a matching is always empty.
+    InboundCount(Comparison, u64),
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub enum Action {
     Boost(u64),
+    /// Boost only the part of the score that comes from the target
+    /// instead of the score of the whole document.
+    ScopedBoost(u64, BoostTarget),
     Downrank(u64),
     Discard,
 }
@@ -310,6 +326,7 @@ impl Display for Action {
 
         match self {
             Action::Boost(b) => write!(f, "Boost({b})")?,
+            Action::ScopedBoost(b, target) => write!(f, "Boost({b}, {target})")?,
             Action::Downrank(d) => write!(f, "Downrank({d})")?,
             Action::Discard => write!(f, "Discard")?,
         }
@@ -569,4 +586,75 @@ mod tests {
 
         assert_eq!(optic, parsed);
     }
+
+    #[test]
+    fn scoped_boost() {
+        let optic = Optic::parse(
+            r#"
+            Rule {
+                Matches {
+                    Site("example.com")
+                },
+                Action(Boost(3, Field("title")))
+            };
+            Rule {
+                Matches {
+                    Site("example.org")
+                },
+                Action(Boost(2, Signal("host_centrality")))
+            };
+        "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            optic.rules[0].action,
+            Action::ScopedBoost(3, BoostTarget::Field("title".to_string()))
+        );
+        assert_eq!(
+            optic.rules[1].action,
+            Action::ScopedBoost(2, BoostTarget::Signal("host_centrality".to_string()))
+        );
+
+        let parsed = Optic::parse(&optic.to_string()).unwrap();
+        assert_eq!(optic, parsed);
+    }
+
+    #[test]
+    fn inbound_count() {
+        let optic = Optic::parse(
+            r#"
+            Rule {
+                Matches {
+                    InboundCount < 10
+                },
+                Action(Downrank(2))
+            };
+            Rule {
+                Matches {
+                    Site("example.com"),
+                    InboundCount > 100
+                },
+                Action(Boost(3))
+            };
+        "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            optic.rules[0].matches[0][0].location,
+            MatchLocation::InboundCount(Comparison::Less, 10)
+        );
+        assert!(optic.rules[0].matches[0][0].pattern.is_empty());
+        assert_eq!(
+            optic.rules[1].matches[0][1].location,
+            MatchLocation::InboundCount(Comparison::Greater, 100)
+        );
+
+        let parsed = Optic::parse(&optic.to_string()).unwrap();
+        assert_eq!(optic, parsed);
+
+        assert!(Optic::parse("Rule { Matches { InboundCount > 1.5 } };").is_err());
+        assert!(Optic::parse("Rule { Matches { InboundCount 10 } };").is_err());
+    }
 }
@@ -110,6 +110,18 @@ export const api = {
       `/beta/api/autosuggest?${new URLSearchParams(params)}`,
       options,
     ),
+  correct: (
+    params: {
+      q: string;
+    },
+    options?: ApiOptions,
+  ) =>
+    requestJson<QueryCorrection>(
+      'GET',
+      `/beta/api/correct?${new URLSearchParams(params)}`,
+      undefined,
+      options,
+    ),
   exploreExport: (body: ExploreExportOpticParams, options?: ApiOptions) =>
     requestPlain('POST', `/beta/api/explore/export`, body, options),
   hostsExport: (body: HostsExportOpticParams, options?: ApiOptions) =>
@@ -189,16 +201,31 @@ export const api = {
 };
 
 export type ApiSearchQuery = {
+  coeff?: string;
   countResults?: boolean;
+  debugOptic?: boolean;
+  device?: Device;
+  disable?: string;
+  exploreSeed?: number;
+  features?: boolean;
   flattenResponse?: boolean;
   hostRankings?: HostRankings;
+  lang?: string;
+  minScore?: number;
+  model?: string;
+  normalizeScores?: boolean;
   numResults?: number;
   optic?: string;
   page?: number;
+  perHostLimit?: number;
+  profile?: CoefficientProfile;
   query: string;
   returnRankingSignals?: boolean;
   safeSearch?: boolean;
+  seen?: string[];
   selectedRegion?: Region;
+  showFiltered?: boolean;
+  timeoutMs?: number;
 };
 export type ApiSearchResult =
   | (WebsitesResult & {
@@ -210,6 +237,7 @@ export type ApiSearchResult =
 export type Bang = {
   c?: string;
   d?: string;
+  p: BangParam[];
   r?: number;
   s?: string;
   sc?: string;
@@ -218,7 +246,20 @@ export type Bang = {
 };
 export type BangHit = {
   bang: Bang;
+  category?: string;
+  params?: BangParamValue[];
+  query: string;
   redirectTo: UrlWrapper;
+  tag: string;
+};
+export type BangParam = {
+  k: BangParamKind;
+  n: string;
+};
+export type BangParamKind = 'safeSearch' | 'region';
+export type BangParamValue = {
+  name: string;
+  value: string;
 };
 export type Calculation = {
   input: string;
@@ -233,7 +274,9 @@ export type CodeOrText =
       type: 'text';
       value: string;
     };
+export type CoefficientProfile = 'balanced' | 'freshness' | 'authority';
 export type Definition = string;
+export type Device = 'desktop' | 'mobile';
 export type DisplayedAnswer = {
   answer: string;
   prettyUrl: string;
@@ -263,8 +306,10 @@ export type DisplayedSidebar =
     };
 export type DisplayedWebpage = {
   domain: string;
+  features?: SignalVector;
   likelyHasAds: boolean;
   likelyHasPaywall: boolean;
+  normalizedScore?: number;
   prettyUrl: string;
   rankingSignals?: {};
   richSnippet?: RichSnippet;
@@ -327,6 +372,7 @@ export type Lemma = string;
 export type Node = {
   name: string;
 };
+export type OpticRuleOutcome = 'applied' | 'noMatchers' | 'zeroBoost' | 'unknownTarget';
 export type PartOfSpeech = 'noun' | 'verb' | 'adjective' | 'adjectiveSatellite' | 'adverb';
 export const PART_OF_SPEECHES = [
   'noun',
@@ -339,6 +385,11 @@ export type PartOfSpeechMeaning = {
   meanings: WordMeaning[];
   pos: PartOfSpeech;
 };
+export type QueryCorrection = {
+  confidence: number;
+  corrected: boolean;
+  query: string;
+};
 export type Region = 'All' | 'Denmark' | 'France' | 'Germany' | 'Spain' | 'US';
 export const REGIONS = ['All', 'Denmark', 'France', 'Germany', 'Spain', 'US'] satisfies Region[];
 export type RichSnippet = {
@@ -358,6 +409,7 @@ export type SignalScore = {
   coefficient: number;
   value: number;
 };
+export type SignalVector = number[];
 export type SimilarHostsParams = {
   hosts: string[];
   topN: number;
@@ -380,6 +432,7 @@ export type StackOverflowQuestion = {
   body: CodeOrText[];
 };
 export type Suggestion = {
+  description?: string;
   highlighted: HighlightedFragment[];
   raw: string;
 };
@@ -392,8 +445,12 @@ export type ThesaurusWidget = {
 };
 export type UrlWrapper = string;
 export type WebsitesResult = {
+  discardedCount?: number;
+  featuresLayout?: string[];
   hasMoreResults: boolean;
   numHits?: number;
+  opticRules?: OpticRuleOutcome[];
+  partial: boolean;
   searchDurationMs: number;
   webpages: DisplayedWebpage[];
 };